@@ -0,0 +1,29 @@
+//! Compare the expected-value strategies as the operator count grows.
+//!
+//! Run with `cargo bench --bench expected_values`.
+
+use std::{hint::black_box, time::Instant};
+
+use network_shapley::core_math::{ExpectedValueStrategy, expected_values_with};
+
+const REPEATS: u32 = 3;
+
+fn main() {
+    println!("operators | strategy   | time per run");
+    for n in [8, 12, 14, 16] {
+        let svalue: Vec<Option<f64>> = (0..1u32 << n)
+            .map(|s| (s % 11 != 5).then(|| s.count_ones() as f64 * 3.5))
+            .collect();
+        for strategy in [
+            ExpectedValueStrategy::SubsetSum,
+            ExpectedValueStrategy::Streaming,
+        ] {
+            let start = Instant::now();
+            for _ in 0..REPEATS {
+                black_box(expected_values_with(black_box(&svalue), n, 0.98, strategy));
+            }
+            let elapsed = start.elapsed() / REPEATS;
+            println!("{n:9} | {:10} | {elapsed:?}", format!("{strategy:?}"));
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Time building the routing LP as the network grows, with half of the
+//! demands multicast so the group constraints are built too.
+//!
+//! Run with `cargo bench --bench lp_build`.
+
+use std::{hint::black_box, time::Instant};
+
+use network_shapley::{
+    pipeline::build_lp,
+    shapley::testing::{RandomInputConfig, random_input},
+};
+
+const REPEATS: u32 = 3;
+
+fn main() {
+    println!("private links | demands | columns | time per build");
+    for links in [500, 2000, 5000] {
+        let config = RandomInputConfig {
+            operators: 8,
+            cities: links / 10,
+            private_links: links,
+            demands: 8,
+            ..Default::default()
+        };
+        let mut input = random_input(7, &config);
+        for demand in input.demands.iter_mut().step_by(2) {
+            demand.multicast = true;
+            demand.receivers = 4;
+        }
+
+        let columns = build_lp(&input).expect("random inputs are valid").n_cols();
+        let start = Instant::now();
+        for _ in 0..REPEATS {
+            black_box(build_lp(black_box(&input)).expect("random inputs are valid"));
+        }
+        let elapsed = start.elapsed() / REPEATS;
+        println!(
+            "{links:13} | {:7} | {columns:7} | {elapsed:?}",
+            input.demands.len()
+        );
+    }
+}
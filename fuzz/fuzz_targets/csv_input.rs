@@ -0,0 +1,43 @@
+//! The four input tables as CSV, separated by NUL bytes in the order devices,
+//! private links, public links, demands, through validation and consolidation
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use network_shapley::shapley::ShapleyInput;
+use serde::de::DeserializeOwned;
+
+fn read_table<T: DeserializeOwned>(bytes: &[u8]) -> Option<Vec<T>> {
+    csv::Reader::from_reader(bytes)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .ok()
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut tables = data.split(|&b| b == 0);
+    let (Some(devices), Some(private_links), Some(public_links), Some(demands)) =
+        (tables.next(), tables.next(), tables.next(), tables.next())
+    else {
+        return;
+    };
+    let (Some(devices), Some(private_links), Some(public_links), Some(demands)) = (
+        read_table(devices),
+        read_table(private_links),
+        read_table(public_links),
+        read_table(demands),
+    ) else {
+        return;
+    };
+
+    let input = ShapleyInput {
+        private_links,
+        devices,
+        demands,
+        public_links,
+        operator_uptime: 0.98,
+        contiguity_bonus: 5.0,
+        demand_multiplier: 1.0,
+    };
+    // Errors are fine; panics are not
+    let _ = input.consolidation_report();
+});
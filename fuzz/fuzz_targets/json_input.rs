@@ -0,0 +1,12 @@
+//! A `ShapleyInput` as the CLI reads it, through validation and consolidation
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use network_shapley::shapley::ShapleyInput;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = serde_json::from_slice::<ShapleyInput>(data) {
+        // Errors are fine; panics are not
+        let _ = input.consolidation_report();
+    }
+});
@@ -0,0 +1,9 @@
+//! Compiles `core_math` in a `no_std` crate, so a `std` item slipping into
+//! it fails the build.
+
+#![no_std]
+
+extern crate alloc;
+
+#[path = "../../src/core_math.rs"]
+pub mod core_math;
@@ -0,0 +1,187 @@
+//! Pseudonymization of inputs for sharing them outside the organization.
+//!
+//! [`ShapleyInput::anonymize`](crate::shapley::ShapleyInput::anonymize)
+//! replaces every operator, device and city name by a pseudonym drawn from a
+//! seed and returns the [`Pseudonyms`] it used. Pseudonyms keep the naming
+//! conventions the computation relies on: cities stay three capital letters
+//! and each device name starts with its city's pseudonym, so the anonymized
+//! input gives the same values under the pseudonyms, which
+//! [`Pseudonyms::deanonymize`] maps back.
+//!
+//! Names in [`ShapleyOptions`](crate::options::ShapleyOptions), such as
+//! reservation devices and region cities, are not rewritten.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    shapley::{Operator, ShapleyInput, ShapleyOutput},
+    utils::{LETTER_CODES, SplitMix64, city_of, letter_code},
+};
+
+/// Operator names with a meaning to the computation, kept as they are
+const RESERVED_OPERATORS: [&str; 3] = ["Private", "Public", ""];
+
+/// Original name to pseudonym, per kind of name
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pseudonyms {
+    pub operators: BTreeMap<Operator, Operator>,
+    pub devices: BTreeMap<String, String>,
+    pub cities: BTreeMap<String, String>,
+}
+
+impl Pseudonyms {
+    /// `output` with every operator pseudonym replaced by the original name.
+    /// Operators without a pseudonym keep their name.
+    pub fn deanonymize(&self, output: &ShapleyOutput) -> ShapleyOutput {
+        let originals: BTreeMap<&Operator, &Operator> = self
+            .operators
+            .iter()
+            .map(|(original, pseudonym)| (pseudonym, original))
+            .collect();
+        output
+            .iter()
+            .map(|(operator, value)| {
+                let name = originals.get(operator).copied().unwrap_or(operator);
+                (name.clone(), value.clone())
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn anonymize(input: &ShapleyInput, seed: u64) -> (ShapleyInput, Pseudonyms) {
+    let mut rng = SplitMix64::new(seed);
+
+    let device_names: BTreeSet<&str> = input
+        .devices
+        .iter()
+        .map(|d| d.device.as_str())
+        .chain(
+            input
+                .private_links
+                .iter()
+                .flat_map(|l| [l.device1.as_str(), l.device2.as_str()]),
+        )
+        .collect();
+    let city_names: BTreeSet<&str> = input
+        .public_links
+        .iter()
+        .flat_map(|l| [l.city1.as_str(), l.city2.as_str()])
+        .chain(
+            input
+                .demands
+                .iter()
+                .flat_map(|d| [d.start.as_str(), d.end.as_str()]),
+        )
+        .chain(device_names.iter().map(|d| city_of(d)))
+        .collect();
+    let operator_names: BTreeSet<&str> = input
+        .devices
+        .iter()
+        .map(|d| d.operator.as_str())
+        .filter(|op| !RESERVED_OPERATORS.contains(op))
+        .collect();
+
+    // Cities draw distinct codes at random; operators get a shuffled number
+    assert!(
+        city_names.len() <= LETTER_CODES,
+        "More cities than three-letter codes"
+    );
+    let mut taken = BTreeSet::new();
+    let cities: BTreeMap<String, String> = city_names
+        .iter()
+        .map(|&city| {
+            let code = loop {
+                let code = (rng.next_u64() % LETTER_CODES as u64) as usize;
+                if taken.insert(code) {
+                    break code;
+                }
+            };
+            (city.to_string(), letter_code(code))
+        })
+        .collect();
+
+    let mut numbers: Vec<usize> = (1..=operator_names.len()).collect();
+    for i in (1..numbers.len()).rev() {
+        numbers.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+    }
+    let width = operator_names.len().to_string().len();
+    let operators: BTreeMap<Operator, Operator> = operator_names
+        .iter()
+        .zip(numbers)
+        .map(|(&op, number)| (op.to_string(), format!("Operator{number:0width$}")))
+        .collect();
+
+    // Devices are numbered within their city, in a random order
+    let mut order: Vec<&str> = device_names.into_iter().collect();
+    for i in (1..order.len()).rev() {
+        order.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+    }
+    let mut per_city: BTreeMap<&str, usize> = BTreeMap::new();
+    let devices: BTreeMap<String, String> = order
+        .into_iter()
+        .map(|device| {
+            let city = &cities[city_of(device)];
+            let count = per_city.entry(city_of(device)).or_default();
+            *count += 1;
+            (device.to_string(), format!("{city}{count}"))
+        })
+        .collect();
+
+    let mut anonymized = input.clone();
+    for device in &mut anonymized.devices {
+        device.device = devices[&device.device].clone();
+        if let Some(pseudonym) = operators.get(&device.operator) {
+            device.operator = pseudonym.clone();
+        }
+    }
+    for link in &mut anonymized.private_links {
+        link.device1 = devices[&link.device1].clone();
+        link.device2 = devices[&link.device2].clone();
+    }
+    for link in &mut anonymized.public_links {
+        link.city1 = cities[&link.city1].clone();
+        link.city2 = cities[&link.city2].clone();
+    }
+    for demand in &mut anonymized.demands {
+        demand.start = cities[&demand.start].clone();
+        demand.end = cities[&demand.end].clone();
+    }
+
+    (
+        anonymized,
+        Pseudonyms {
+            operators,
+            devices,
+            cities,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shapley::testing::{RandomInputConfig, random_input};
+
+    #[test]
+    fn test_anonymized_input_gives_same_values() {
+        let input = random_input(9, &RandomInputConfig::default());
+        let (anonymized, pseudonyms) = input.anonymize(42);
+        assert_eq!(pseudonyms.operators.len(), 3);
+        for device in &anonymized.devices {
+            assert!(device.operator.starts_with("Operator"));
+        }
+        assert_eq!(pseudonyms.cities.len(), 5);
+
+        let expected = input.compute().unwrap();
+        let values = pseudonyms.deanonymize(&anonymized.compute().unwrap());
+        for (op, value) in &expected {
+            assert!((values[op].value - value.value).abs() < 1e-9);
+        }
+
+        // Same seed, same pseudonyms
+        assert_eq!(input.anonymize(42).1, pseudonyms);
+    }
+}
@@ -0,0 +1,224 @@
+//! Precision audit of the aggregation stage: the Shapley values computed in
+//! f64 from the LP values, against the same aggregation in exact rational
+//! arithmetic.
+//!
+//! LP values, scenario probabilities and the operator uptime are f64 and
+//! converted exactly, so the only difference between the two is the rounding
+//! of the f64 aggregation. Exact arithmetic is slow; expect seconds from about
+//! 15 operators.
+
+use std::collections::BTreeMap;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive, Zero};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core_math,
+    error::{Result, ShapleyError},
+    shapley::Operator,
+};
+
+/// One operator's Shapley value from both aggregations
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ValueDivergence {
+    /// Value of the f64 aggregation, as [`compute`](crate::shapley::ShapleyInput::compute)
+    /// reports it before co-ownership shares
+    pub value: f64,
+    /// Exact value, rounded to the nearest f64
+    pub exact: f64,
+    /// `|value - exact|`, computed exactly then rounded
+    pub divergence: f64,
+}
+
+/// Divergence of the f64 aggregation from exact arithmetic, see
+/// [`audit`](crate::audit)
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PrecisionAudit {
+    pub operators: BTreeMap<Operator, ValueDivergence>,
+}
+
+impl PrecisionAudit {
+    /// Largest divergence over the operators
+    pub fn max_divergence(&self) -> f64 {
+        self.operators
+            .values()
+            .map(|d| d.divergence)
+            .fold(0.0, f64::max)
+    }
+
+    /// Audit the values `game_values` the f64 pipeline aggregated from the
+    /// LP values `scenarios`, each with its probability
+    pub(crate) fn new(
+        operators: &[Operator],
+        scenarios: &[(f64, Vec<Option<f64>>)],
+        game_values: &[f64],
+        operator_uptime: f64,
+    ) -> Result<Self> {
+        let n_operators = operators.len();
+        let values = core_math::shapley_values(game_values, n_operators);
+        let exact = exact_shapley_values(
+            &exact_game_values(scenarios, n_operators, operator_uptime)?,
+            n_operators,
+        );
+
+        let operators = operators
+            .iter()
+            .zip(values)
+            .zip(exact)
+            .map(|((op, value), exact)| {
+                let divergence = match rational(value, "Shapley value") {
+                    Ok(value) => to_f64(&(value - &exact).abs()),
+                    Err(_) => f64::INFINITY,
+                };
+                let audit = ValueDivergence {
+                    value,
+                    exact: to_f64(&exact),
+                    divergence,
+                };
+                (op.clone(), audit)
+            })
+            .collect();
+        Ok(Self { operators })
+    }
+}
+
+/// Exact counterpart of the coalition values the Shapley stage aggregates:
+/// expected values under operator uptime, weighted over scenarios
+fn exact_game_values(
+    scenarios: &[(f64, Vec<Option<f64>>)],
+    n_operators: usize,
+    operator_uptime: f64,
+) -> Result<Vec<BigRational>> {
+    let uptime = rational(operator_uptime, "operator uptime")?;
+    let downtime = BigRational::from_integer(1.into()) - &uptime;
+    let mut game = vec![BigRational::zero(); 1 << n_operators];
+    let feasible_somewhere = |c: usize| scenarios.iter().any(|(_, svalue)| svalue[c].is_some());
+    for (weight, svalue) in scenarios {
+        let probability = rational(*weight, "scenario probability")?;
+        let mut values = svalue
+            .iter()
+            .enumerate()
+            .map(|(c, v)| match v {
+                Some(v) => rational(*v, "coalition value"),
+                // Infeasible coalitions add nothing to expected values or to
+                // a scenario, unless no scenario can route them: then they
+                // are -inf, which has no exact counterpart
+                None if operator_uptime < 1.0 || feasible_somewhere(c) => Ok(BigRational::zero()),
+                None => rational(f64::NEG_INFINITY, "coalition value"),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if operator_uptime < 1.0 {
+            for i in 0..n_operators {
+                let bit = 1 << i;
+                for s in 0..values.len() {
+                    if s & bit != 0 {
+                        values[s] = &uptime * &values[s] + &downtime * &values[s ^ bit];
+                    }
+                }
+            }
+        }
+        for (total, value) in game.iter_mut().zip(values) {
+            *total += &probability * value;
+        }
+    }
+    Ok(game)
+}
+
+fn exact_shapley_values(coalition_values: &[BigRational], n_operators: usize) -> Vec<BigRational> {
+    let factorial = |n: usize| -> BigInt { (1..=n).map(BigInt::from).product() };
+    let fact_n = factorial(n_operators);
+    let weights: Vec<BigRational> = (0..n_operators)
+        .map(|size| {
+            BigRational::new(
+                factorial(size) * factorial(n_operators - size - 1),
+                fact_n.clone(),
+            )
+        })
+        .collect();
+
+    (0..n_operators)
+        .map(|k| {
+            let mut value = BigRational::zero();
+            for (without, without_value) in coalition_values.iter().enumerate() {
+                if (without >> k) & 1 == 0 {
+                    let size = (without as u32).count_ones() as usize;
+                    value +=
+                        &weights[size] * (&coalition_values[without | (1 << k)] - without_value);
+                }
+            }
+            value
+        })
+        .collect()
+}
+
+fn rational(value: f64, what: &str) -> Result<BigRational> {
+    BigRational::from_float(value).ok_or_else(|| {
+        ShapleyError::NumericalError(format!("The {what} {value} has no exact value"))
+    })
+}
+
+fn to_f64(value: &BigRational) -> f64 {
+    value.to_f64().unwrap_or(f64::NAN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{options::ShapleyOptions, shapley::testing};
+
+    fn ops(n: usize) -> Vec<Operator> {
+        ["A", "B", "C"][..n].iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_exact_aggregation() {
+        // 0.1 and 0.2 are not exact in binary: the f64 sums round
+        let svalue: Vec<Option<f64>> = (0..8u32)
+            .map(|s| Some(0.1 * s.count_ones() as f64 + 0.2 * (s & 1) as f64))
+            .collect();
+        let scenarios = vec![(1.0, svalue.clone())];
+        let uptime = 0.9;
+        let game = core_math::uptime_adjusted_values(&svalue, 3, uptime);
+        let audit = PrecisionAudit::new(&ops(3), &scenarios, &game, uptime).unwrap();
+
+        let expected = core_math::aggregate(&svalue, 3, uptime);
+        for (op, value) in ops(3).iter().zip(expected) {
+            let divergence = &audit.operators[op];
+            assert_eq!(divergence.value, value);
+            assert!(divergence.divergence < 1e-15);
+        }
+        // Every operator adds 0.1 × 0.9, and operator A 0.2 × 0.9 more
+        assert!((audit.operators["A"].exact - 0.27).abs() < 1e-15);
+        assert!((audit.operators["B"].exact - 0.09).abs() < 1e-15);
+        assert!(audit.max_divergence() < 1e-15);
+
+        // Without uptime an infeasible coalition is -inf, which has no exact value
+        let mut infeasible = svalue;
+        infeasible[0] = None;
+        let game = core_math::uptime_adjusted_values(&infeasible, 3, 1.0);
+        let scenarios = vec![(1.0, infeasible)];
+        assert!(matches!(
+            PrecisionAudit::new(&ops(3), &scenarios, &game, 1.0),
+            Err(ShapleyError::NumericalError(_))
+        ));
+    }
+
+    #[test]
+    fn test_precision_audit_of_a_computation() {
+        let mut input = testing::random_input(4, &testing::RandomInputConfig::default());
+        input.operator_uptime = 0.95;
+        let options = ShapleyOptions::default();
+        let audit = input.precision_audit(&options).unwrap();
+        let values = input.compute_with(&options).unwrap();
+        assert_eq!(audit.operators.len(), values.len());
+        for (op, divergence) in &audit.operators {
+            assert_eq!(divergence.value, values[op].value);
+        }
+        assert!(audit.max_divergence() < 1e-9);
+    }
+}
@@ -0,0 +1,349 @@
+//! Batched solving of coalition LPs. Public under the `unstable-backend`
+//! feature for experimenting with other solvers; the interface may change in
+//! any release.
+
+use std::fmt::Debug;
+
+pub use crate::solver::{CoalitionBuffers, CoalitionResult, SolveStatus};
+use crate::{
+    error::Result,
+    lp_builder::LpPrimitives,
+    options::SolverOptions,
+    solver::{PrecomputedRows, solve_coalition},
+};
+
+/// Most coalitions submitted to the backend per call
+pub(crate) const BATCH_SIZE: usize = 64;
+
+/// Coalitions per batch when `coalitions` are solved on `threads` threads:
+/// small enough that every thread gets a batch, at most [`BATCH_SIZE`]
+pub(crate) fn batch_len(coalitions: usize, threads: usize) -> usize {
+    (coalitions / threads.max(1)).clamp(1, BATCH_SIZE)
+}
+
+/// The LP structure shared by every coalition. A coalition's LP keeps the
+/// columns and inequality rows whose operator masks both intersect the
+/// coalition mask; everything else is common.
+#[derive(Clone, Copy)]
+pub struct CoalitionLp<'a> {
+    pub(crate) primitives: &'a LpPrimitives,
+    pub(crate) precomputed: &'a PrecomputedRows,
+    pub col_op1_mask: &'a [u32],
+    pub col_op2_mask: &'a [u32],
+    pub row_op1_mask: &'a [u32],
+    pub row_op2_mask: &'a [u32],
+    /// Optional group index per column and the number of groups; solved
+    /// coalitions then report their objective split by group
+    pub col_group: Option<(&'a [usize], usize)>,
+    /// LPs of lower priority classes over the same links. Each is solved in
+    /// turn, in the bandwidth left once the optimal flows of the LPs before it
+    /// are fixed; the coalition's objective is the sum over classes.
+    pub residual: &'a [CoalitionLp<'a>],
+}
+
+impl CoalitionLp<'_> {
+    pub fn n_cols(&self) -> usize {
+        self.col_op1_mask.len()
+    }
+
+    /// Solve a single coalition with the simplex, class by class if there are
+    /// [`residual`](Self::residual) LPs. A coalition is infeasible if any of
+    /// its classes is.
+    pub fn solve(
+        &self,
+        coalition_mask: u32,
+        buffers: &mut CoalitionBuffers,
+        options: &SolverOptions,
+    ) -> Result<CoalitionResult> {
+        let Some((next, rest)) = self.residual.split_first() else {
+            return self.solve_class(coalition_mask, buffers, options);
+        };
+
+        let caller_primal = buffers.primal.replace(Vec::new());
+        let result = self.solve_class(coalition_mask, buffers, options);
+        let primal = std::mem::replace(&mut buffers.primal, caller_primal).unwrap_or_default();
+        let mut result = result?;
+        if result.status != SolveStatus::Solved {
+            return Ok(result);
+        }
+
+        // Fix this class's flows: the bandwidth they use is gone for the rest
+        let mut reserved = buffers
+            .reserved
+            .take()
+            .unwrap_or_else(|| vec![0.0; self.primitives.bandwidth_rows]);
+        let a_ub = &self.primitives.a_ub;
+        for (col, &x) in primal.iter().enumerate() {
+            for idx in a_ub.colptr[col]..a_ub.colptr[col + 1] {
+                if let Some(taken) = reserved.get_mut(a_ub.rowval[idx]) {
+                    *taken += a_ub.nzval[idx] * x;
+                }
+            }
+        }
+        buffers.reserved = Some(reserved);
+        let lower = CoalitionLp {
+            residual: rest,
+            ..*next
+        }
+        .solve(coalition_mask, buffers, options);
+        buffers.reserved = None;
+        let mut lower = lower?;
+        lower.iterations += result.iterations;
+        lower.solve_time += result.solve_time;
+        if lower.status != SolveStatus::Solved {
+            return Ok(lower);
+        }
+
+        let mut class_objectives = vec![result.objective_value];
+        if lower.class_objectives.is_empty() {
+            class_objectives.push(lower.objective_value);
+        } else {
+            class_objectives.extend(&lower.class_objectives);
+        }
+        result.objective_value += lower.objective_value;
+        result.class_objectives = class_objectives;
+        result.iterations = lower.iterations;
+        result.solve_time = lower.solve_time;
+        for (group, lower) in result
+            .group_objectives
+            .iter_mut()
+            .zip(&lower.group_objectives)
+        {
+            *group += lower;
+        }
+        Ok(result)
+    }
+
+    /// Solve the coalition's LP of this class alone
+    fn solve_class(
+        &self,
+        coalition_mask: u32,
+        buffers: &mut CoalitionBuffers,
+        options: &SolverOptions,
+    ) -> Result<CoalitionResult> {
+        let Some((col_group, n_groups)) = self.col_group else {
+            return self.solve_plain(coalition_mask, buffers, options);
+        };
+
+        // Capture the primal solution just for this solve unless the caller wants it
+        let caller_wants_primal = buffers.primal.is_some();
+        buffers.primal.get_or_insert_with(Vec::new);
+        let mut result = self.solve_plain(coalition_mask, buffers, options);
+        if let Ok(result) = &mut result
+            && result.status == SolveStatus::Solved
+            && let Some(primal) = &buffers.primal
+        {
+            result.group_objectives = vec![0.0; n_groups];
+            for ((&group, &cost), &x) in col_group.iter().zip(&self.primitives.cost).zip(primal) {
+                result.group_objectives[group] += cost * x;
+            }
+        }
+        if !caller_wants_primal {
+            buffers.primal = None;
+        }
+        result
+    }
+
+    fn solve_plain(
+        &self,
+        coalition_mask: u32,
+        buffers: &mut CoalitionBuffers,
+        options: &SolverOptions,
+    ) -> Result<CoalitionResult> {
+        solve_coalition(
+            self.primitives,
+            self.precomputed,
+            buffers,
+            coalition_mask,
+            self.col_op1_mask,
+            self.col_op2_mask,
+            self.row_op1_mask,
+            self.row_op2_mask,
+            options,
+        )
+    }
+}
+
+/// Solves a group of coalition LPs that share one [`CoalitionLp`] structure and
+/// differ only in their coalition mask. Submitting whole groups lets a batched
+/// backend (e.g. on a GPU) amortise setup across coalitions. Chosen with
+/// [`ShapleyOptions::backend`](crate::options::ShapleyOptions::backend).
+pub trait BatchLpBackend: Debug + Send + Sync {
+    /// One result per mask, in the order of `masks`. `buffers` belong to the
+    /// calling worker for the duration of one computation, so concurrent
+    /// computations never share solver state.
+    fn solve_batch(
+        &self,
+        lp: CoalitionLp<'_>,
+        masks: &[u32],
+        buffers: &mut CoalitionBuffers,
+        options: &SolverOptions,
+    ) -> Vec<Result<CoalitionResult>>;
+}
+
+/// Solves each LP of a batch in turn with the vendored simplex, reusing the
+/// worker's buffers across batches. The default backend.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimplexBackend;
+
+impl BatchLpBackend for SimplexBackend {
+    fn solve_batch(
+        &self,
+        lp: CoalitionLp<'_>,
+        masks: &[u32],
+        buffers: &mut CoalitionBuffers,
+        options: &SolverOptions,
+    ) -> Vec<Result<CoalitionResult>> {
+        masks
+            .iter()
+            .map(|&mask| lp.solve(mask, buffers, options))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        lp_builder::LpBuilderInput,
+        types::{ConsolidatedDemand, ConsolidatedLink, LinkProvenance},
+    };
+
+    #[test]
+    fn test_batches_spread_over_threads() {
+        // 8 coalitions of a 3-operator game on 4 threads: a batch each
+        assert_eq!(batch_len(8, 4), 2);
+        assert_eq!(batch_len(3, 8), 1);
+        assert_eq!(batch_len(1 << 20, 8), BATCH_SIZE);
+        assert_eq!(batch_len(5, 0), 5);
+    }
+
+    #[test]
+    fn test_simplex_backend_matches_single_solves() {
+        let links = vec![ConsolidatedLink {
+            device1: "A".to_string(),
+            device2: "B".to_string(),
+            latency: 1.0,
+            bandwidth: 10.0,
+            operator1: "Op1".to_string(),
+            operator2: "Op1".to_string(),
+            shared: 1,
+            link_type: 0,
+            provenance: LinkProvenance::OriginalPrivate,
+            multicast_capable: false,
+        }];
+        let demands = vec![ConsolidatedDemand {
+            start: "A".to_string(),
+            end: "B".to_string(),
+            receivers: 1,
+            traffic: 5.0,
+            priority: 1.0,
+            kind: 1,
+            multicast: false,
+            original: 1,
+        }];
+        let primitives = LpBuilderInput::new(&links, &demands)
+            .build()
+            .expect("LP builder should succeed");
+        let precomputed = PrecomputedRows::new(&primitives);
+        let col_masks = vec![1u32; primitives.cost.len()];
+        let row_masks = vec![1u32; primitives.b_ub.len()];
+        let lp = CoalitionLp {
+            primitives: &primitives,
+            precomputed: &precomputed,
+            col_op1_mask: &col_masks,
+            col_op2_mask: &col_masks,
+            row_op1_mask: &row_masks,
+            row_op2_mask: &row_masks,
+            col_group: None,
+            residual: &[],
+        };
+
+        let options = SolverOptions::default();
+        let mut buffers = CoalitionBuffers::new(lp.n_cols());
+        let batch = SimplexBackend.solve_batch(lp, &[1, 0, 1], &mut buffers, &options);
+        assert_eq!(batch.len(), 3);
+
+        let mut buffers = CoalitionBuffers::new(lp.n_cols());
+        let single = lp.solve(1, &mut buffers, &options).unwrap();
+        for i in [0, 2] {
+            let result = batch[i].as_ref().unwrap();
+            assert_eq!(result.status, SolveStatus::Solved);
+            assert_eq!(result.objective_value, single.objective_value);
+        }
+        // No columns survive the empty mask
+        assert!(batch[1].is_err());
+    }
+
+    #[test]
+    fn test_residual_class_uses_leftover_bandwidth() {
+        let links = vec![ConsolidatedLink {
+            device1: "A".to_string(),
+            device2: "B".to_string(),
+            latency: 1.0,
+            bandwidth: 10.0,
+            operator1: "Op1".to_string(),
+            operator2: "Op1".to_string(),
+            shared: 1,
+            link_type: 0,
+            provenance: LinkProvenance::OriginalPrivate,
+            multicast_capable: false,
+        }];
+        let demand = |kind, traffic| ConsolidatedDemand {
+            start: "A".to_string(),
+            end: "B".to_string(),
+            receivers: 1,
+            traffic,
+            priority: 1.0,
+            kind,
+            multicast: false,
+            original: kind,
+        };
+        let build = |demands: &[ConsolidatedDemand]| {
+            LpBuilderInput::new(&links, demands)
+                .build()
+                .expect("LP builder should succeed")
+        };
+        let first = build(&[demand(1, 8.0)]);
+        let precomputed = PrecomputedRows::new(&first);
+        let col_masks = vec![1u32; first.cost.len()];
+        let row_masks = vec![1u32; first.b_ub.len()];
+        let lp = CoalitionLp {
+            primitives: &first,
+            precomputed: &precomputed,
+            col_op1_mask: &col_masks,
+            col_op2_mask: &col_masks,
+            row_op1_mask: &row_masks,
+            row_op2_mask: &row_masks,
+            col_group: None,
+            residual: &[],
+        };
+        let options = SolverOptions::default();
+
+        for (traffic, feasible) in [(2.0, true), (3.0, false)] {
+            let second = build(&[demand(2, traffic)]);
+            let second_rows = PrecomputedRows::new(&second);
+            let residual = [CoalitionLp {
+                primitives: &second,
+                precomputed: &second_rows,
+                ..lp
+            }];
+            let staged = CoalitionLp {
+                residual: &residual,
+                ..lp
+            };
+
+            let mut buffers = CoalitionBuffers::new(staged.n_cols());
+            let result = staged.solve(1, &mut buffers, &options).unwrap();
+            assert!(buffers.reserved.is_none());
+            if feasible {
+                // Only 2 of the 10 units are left once the first class is routed
+                assert_eq!(result.status, SolveStatus::Solved);
+                assert_eq!(result.class_objectives, vec![8.0, 2.0]);
+                assert_eq!(result.objective_value, 10.0);
+            } else {
+                assert_eq!(result.status, SolveStatus::Infeasible);
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::shapley::{Operator, ShapleyOutput};
+
+/// Shapley values under the configured objective and with carbon added to
+/// it, see [`ShapleyInput::carbon_report`](crate::shapley::ShapleyInput::carbon_report)
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct CarbonReport {
+    /// Values under `ShapleyOptions::cost_model`, latency by default
+    pub base: ShapleyOutput,
+    /// Values with each link's weighted carbon intensity added to its cost
+    pub carbon_adjusted: ShapleyOutput,
+    /// Carbon-adjusted minus base value: positive for operators whose links
+    /// save more carbon than the routes they replace
+    pub difference: BTreeMap<Operator, f64>,
+}
+
+impl CarbonReport {
+    pub(crate) fn new(base: ShapleyOutput, carbon_adjusted: ShapleyOutput) -> Self {
+        let difference = base
+            .iter()
+            .map(|(op, value)| {
+                let adjusted = carbon_adjusted.get(op).map_or(0.0, |v| v.value);
+                (op.clone(), adjusted - value.value)
+            })
+            .collect();
+        Self {
+            base,
+            carbon_adjusted,
+            difference,
+        }
+    }
+}
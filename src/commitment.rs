@@ -0,0 +1,134 @@
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    shapley::{Operator, ShapleyOutput},
+    types::{Devices, PrivateLinks},
+};
+
+/// Shapley values on declared and on measured link capacity, and what falling
+/// short of the declared capacity costs each operator
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct CommitmentReport {
+    /// Values with every link at its declared `bandwidth`
+    pub declared: ShapleyOutput,
+    /// Values with every link at its `measured_bandwidth` where given
+    pub measured: ShapleyOutput,
+    /// Declared capacity not delivered, summed over the operator's links. A
+    /// link between two operators counts in full for both.
+    pub shortfall: BTreeMap<Operator, f64>,
+    /// Value the operator loses by its shortfall: the drop from its declared
+    /// to its measured value, or zero if it delivered its commitments or did
+    /// not lose value. Drops caused only by other operators' shortfalls are
+    /// not a penalty.
+    pub penalty: BTreeMap<Operator, f64>,
+}
+
+impl CommitmentReport {
+    pub(crate) fn new(
+        private_links: &PrivateLinks,
+        devices: &Devices,
+        declared: ShapleyOutput,
+        measured: ShapleyOutput,
+    ) -> Self {
+        let operator_of: HashMap<&str, &str> = devices
+            .iter()
+            .map(|d| (d.device.as_str(), d.operator.as_str()))
+            .collect();
+
+        let mut shortfall: BTreeMap<Operator, f64> =
+            declared.keys().map(|op| (op.clone(), 0.0)).collect();
+        for link in private_links {
+            let Some(measured) = link.measured_bandwidth else {
+                continue;
+            };
+            let missing = (link.bandwidth - measured).max(0.0);
+            let op1 = operator_of.get(link.device1.as_str());
+            let op2 = operator_of.get(link.device2.as_str());
+            for op in [op1, op2.filter(|&op2| Some(op2) != op1)]
+                .into_iter()
+                .flatten()
+            {
+                if let Some(total) = shortfall.get_mut(*op) {
+                    *total += missing;
+                }
+            }
+        }
+
+        let penalty = shortfall
+            .iter()
+            .map(|(op, &missing)| {
+                let lost = match measured.get(op) {
+                    Some(m) if missing > 0.0 => (declared[op].value - m.value).max(0.0),
+                    _ => 0.0,
+                };
+                (op.clone(), lost)
+            })
+            .collect();
+
+        Self {
+            declared,
+            measured,
+            shortfall,
+            penalty,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        shapley::ShapleyValue,
+        types::{Device, PrivateLink},
+    };
+
+    fn values(entries: &[(&str, f64)]) -> ShapleyOutput {
+        entries
+            .iter()
+            .map(|&(op, value)| {
+                (
+                    op.to_string(),
+                    ShapleyValue {
+                        value,
+                        proportion: 0.0,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_shortfall_and_penalty() {
+        let devices = vec![
+            Device::new("NYC1".into(), 1, "Alpha".into()),
+            Device::new("LON1".into(), 1, "Alpha".into()),
+            Device::new("LON2".into(), 1, "Beta".into()),
+            Device::new("PAR1".into(), 1, "Gamma".into()),
+        ];
+        let links = vec![
+            PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 10.0, 1.0, None)
+                .with_measured_bandwidth(4.0),
+            PrivateLink::new("LON2".into(), "PAR1".into(), 10.0, 10.0, 1.0, None)
+                .with_measured_bandwidth(12.0),
+            PrivateLink::new("LON1".into(), "LON2".into(), 1.0, 10.0, 1.0, None),
+        ];
+        let report = CommitmentReport::new(
+            &links,
+            &devices,
+            values(&[("Alpha", 5.0), ("Beta", 3.0), ("Gamma", 2.0)]),
+            values(&[("Alpha", 2.0), ("Beta", 1.0), ("Gamma", 2.0)]),
+        );
+        // Alpha owns both ends of its short link but counts it once
+        assert_eq!(report.shortfall["Alpha"], 6.0);
+        // Over-delivering is no shortfall
+        assert_eq!(report.shortfall["Beta"], 0.0);
+        assert_eq!(report.penalty["Alpha"], 3.0);
+        // Beta lost value only through Alpha's shortfall
+        assert_eq!(report.penalty["Beta"], 0.0);
+        assert_eq!(report.penalty["Gamma"], 0.0);
+    }
+}
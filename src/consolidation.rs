@@ -1,14 +1,215 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+};
 
 use crate::{
     error::{Result, ShapleyError},
-    types::{ConsolidatedDemand, ConsolidatedLink, Demands, Devices, PrivateLinks, PublicLinks},
+    options::CapacityReservation,
+    profile::DemandProfile,
+    types::{
+        ConsolidatedDemand, ConsolidatedLink, Demand, Demands, Devices, LinkProvenance,
+        PrivateLink, PrivateLinks, PublicLinks, Regions,
+    },
+    utils::{PRIVATE_OPERATOR, city_of, multicast_capable_node, public_node},
 };
 
-/// Consolidate demand table for LP construction
+/// Original demand type, and share of its traffic, of each type created by
+/// [`expand_regions`]
+pub(crate) type TypeOrigins = BTreeMap<u32, (u32, f64)>;
+
+/// Raise the traffic of every demand of a type in `floors` (type → minimum
+/// traffic) to at least the floor, so that standing connectivity for the
+/// type is valued even while it carries no traffic
+pub(crate) fn apply_traffic_floor<'a>(
+    demands: &'a Demands,
+    floors: &BTreeMap<u32, f64>,
+) -> Result<Cow<'a, Demands>> {
+    if let Some((kind, floor)) = floors.iter().find(|(_, f)| !(f.is_finite() && **f >= 0.0)) {
+        return Err(ShapleyError::Validation(format!(
+            "Traffic floor of type {kind} must be finite and non-negative, got {floor}"
+        )));
+    }
+    if !demands
+        .iter()
+        .any(|d| floors.get(&d.kind).is_some_and(|&floor| d.traffic < floor))
+    {
+        return Ok(Cow::Borrowed(demands));
+    }
+    Ok(Cow::Owned(
+        demands
+            .iter()
+            .map(|d| Demand {
+                traffic: floors.get(&d.kind).map_or(d.traffic, |&f| d.traffic.max(f)),
+                ..d.clone()
+            })
+            .collect(),
+    ))
+}
+
+/// Check each demand's traffic range and, if `robust`, move every demand to
+/// the high end of its range, the worst case for every coalition. A profile
+/// in `profiles` replaces its type's traffic, so robust ranges on a profiled
+/// type are rejected rather than ignored.
+pub(crate) fn apply_traffic_range<'a>(
+    demands: &'a Demands,
+    robust: bool,
+    profiles: &BTreeMap<u32, DemandProfile>,
+) -> Result<Cow<'a, Demands>> {
+    for d in demands {
+        let low = d.traffic_low.unwrap_or(d.traffic);
+        let high = d.traffic_high.unwrap_or(d.traffic);
+        if !(low.is_finite()
+            && high.is_finite()
+            && 0.0 <= low
+            && low <= d.traffic
+            && d.traffic <= high)
+        {
+            return Err(ShapleyError::Validation(format!(
+                "Demand {}-{} of type {} has traffic {} outside its range [{low}, {high}]",
+                d.start, d.end, d.kind, d.traffic
+            )));
+        }
+        let ranged = d.traffic_low.is_some() || d.traffic_high.is_some();
+        if robust && ranged && profiles.contains_key(&d.kind) {
+            return Err(ShapleyError::Validation(format!(
+                "Demand {}-{} of type {} has a traffic range, but a demand profile sets the traffic of its type",
+                d.start, d.end, d.kind
+            )));
+        }
+        if robust && d.priority < 0.0 {
+            return Err(ShapleyError::Validation(format!(
+                "Demand {}-{} of type {} has negative priority {}, so its worst case is not its highest traffic",
+                d.start, d.end, d.kind, d.priority
+            )));
+        }
+    }
+    if !robust || demands.iter().all(|d| d.traffic_high.is_none()) {
+        return Ok(Cow::Borrowed(demands));
+    }
+    Ok(Cow::Owned(
+        demands
+            .iter()
+            .map(|d| Demand {
+                traffic: d.traffic_high.unwrap_or(d.traffic),
+                ..d.clone()
+            })
+            .collect(),
+    ))
+}
+
+/// Replace demand endpoints that name a region in `regions` (region → city →
+/// weight) with its cities. The source region's traffic is split between its
+/// cities by weight, each under a new demand type since a type has a single
+/// origin. Unicast traffic to a region is split the same way, one new type
+/// per city pair; multicast traffic reaches every city of the region in full.
+///
+/// Also returns each new type's original type and the fraction of the
+/// original traffic it carries.
+pub(crate) fn expand_regions<'a>(
+    demands: &'a Demands,
+    regions: &Regions,
+    public_links: &PublicLinks,
+) -> Result<(Cow<'a, Demands>, TypeOrigins)> {
+    let is_region = |name: &str| regions.contains_key(name);
+    if !demands
+        .iter()
+        .any(|d| is_region(&d.start) || is_region(&d.end))
+    {
+        return Ok((Cow::Borrowed(demands), BTreeMap::new()));
+    }
+
+    let cities: HashSet<&str> = public_links
+        .iter()
+        .flat_map(|link| [link.city1.as_str(), link.city2.as_str()])
+        .collect();
+    let mut shares: HashMap<&str, Vec<(&str, f64)>> = HashMap::new();
+    for (region, members) in regions {
+        if cities.contains(region.as_str()) {
+            return Err(ShapleyError::Validation(format!(
+                "Region {region} has the same name as a city"
+            )));
+        }
+        let total: f64 = members.values().sum();
+        if members.is_empty()
+            || members.values().any(|w| !(w.is_finite() && *w >= 0.0))
+            || total <= 0.0
+        {
+            return Err(ShapleyError::Validation(format!(
+                "Region {region} needs at least one city and non-negative weights with a positive sum"
+            )));
+        }
+        shares.insert(
+            region,
+            members
+                .iter()
+                .filter(|(_, w)| **w > 0.0)
+                .map(|(city, w)| (city.as_str(), w / total))
+                .collect(),
+        );
+    }
+    let split = |name: &str| -> Vec<(String, f64)> {
+        match shares.get(name) {
+            Some(cities) => cities
+                .iter()
+                .map(|&(city, share)| (city.to_string(), share))
+                .collect(),
+            None => vec![(name.to_string(), 1.0)],
+        }
+    };
+
+    let mut next_kind = demands.iter().map(|d| d.kind).max().unwrap_or(0);
+    let mut origins = TypeOrigins::new();
+    let mut multicast_kinds: HashMap<(u32, String), u32> = HashMap::new();
+    let mut expanded = Vec::new();
+    for demand in demands {
+        if !is_region(&demand.start) && !is_region(&demand.end) {
+            expanded.push(demand.clone());
+            continue;
+        }
+        for (start, start_share) in split(&demand.start) {
+            for (end, end_share) in split(&demand.end) {
+                let share = if demand.multicast {
+                    start_share
+                } else {
+                    start_share * end_share
+                };
+                let kind = if !demand.multicast {
+                    next_kind += 1;
+                    next_kind
+                } else if is_region(&demand.start) {
+                    *multicast_kinds
+                        .entry((demand.kind, start.clone()))
+                        .or_insert_with(|| {
+                            next_kind += 1;
+                            next_kind
+                        })
+                } else {
+                    demand.kind
+                };
+                if kind != demand.kind {
+                    origins.insert(kind, (demand.kind, share));
+                }
+                expanded.push(Demand {
+                    start: start.clone(),
+                    end,
+                    traffic: demand.traffic * share,
+                    kind,
+                    ..demand.clone()
+                });
+            }
+        }
+    }
+    Ok((Cow::Owned(expanded), origins))
+}
+
+/// Consolidate demand table for LP construction. Traffic is scaled by the
+/// multiplier of the demand's type in `multiplier_by_type`, or by
+/// `demand_multiplier` for types not listed.
 pub(crate) fn consolidate_demand(
     demands: &Demands,
     demand_multiplier: f64,
+    multiplier_by_type: &BTreeMap<u32, f64>,
 ) -> Result<Vec<ConsolidatedDemand>> {
     let mut consolidated = Vec::new();
 
@@ -140,14 +341,132 @@ pub(crate) fn consolidate_demand(
         }
     }
 
-    // Apply demand multiplier
+    // Apply demand multiplier, per input type where one is given
     for demand in &mut consolidated {
-        demand.traffic *= demand_multiplier;
+        demand.traffic *= multiplier_by_type
+            .get(&demand.original)
+            .copied()
+            .unwrap_or(demand_multiplier);
     }
 
     Ok(consolidated)
 }
 
+/// Charge the crossover links of the overridden cities and operators their
+/// own contiguity bonus. A city's override takes precedence over its
+/// operators'.
+pub(crate) fn override_contiguity_bonus(
+    links: &mut [ConsolidatedLink],
+    by_city: &BTreeMap<String, f64>,
+    by_operator: &BTreeMap<String, f64>,
+) -> Result<()> {
+    let overrides = by_city.iter().map(|(city, b)| ("city", city, b));
+    let overrides = overrides.chain(by_operator.iter().map(|(op, b)| ("operator", op, b)));
+    for (what, name, bonus) in overrides {
+        if !bonus.is_finite() {
+            return Err(ShapleyError::Validation(format!(
+                "Contiguity bonus of {what} {name} must be finite, got {bonus}"
+            )));
+        }
+    }
+    for link in links
+        .iter_mut()
+        .filter(|l| l.provenance == LinkProvenance::Crossover)
+    {
+        let bonus = by_city
+            .get(city_of(&link.device1))
+            .or_else(|| by_operator.get(&link.operator1));
+        if let Some(&bonus) = bonus {
+            link.latency = bonus;
+        }
+    }
+    Ok(())
+}
+
+/// Hand the private links in `unrewarded`, in both directions, to
+/// [`PRIVATE_OPERATOR`] so that every coalition has them and none is
+/// credited for them
+pub(crate) fn mark_unrewarded(links: &mut [ConsolidatedLink], unrewarded: &[(String, String)]) {
+    if unrewarded.is_empty() {
+        return;
+    }
+    let unrewarded: HashSet<(&str, &str)> = unrewarded
+        .iter()
+        .flat_map(|(a, b)| [(a.as_str(), b.as_str()), (b.as_str(), a.as_str())])
+        .collect();
+    for link in links.iter_mut().filter(|l| {
+        matches!(
+            l.provenance,
+            LinkProvenance::OriginalPrivate | LinkProvenance::Reverse
+        ) && unrewarded.contains(&(l.device1.as_str(), l.device2.as_str()))
+    }) {
+        link.operator1 = PRIVATE_OPERATOR.to_string();
+        link.operator2 = PRIVATE_OPERATOR.to_string();
+    }
+}
+
+/// Carve each reservation out of its private link, in both directions: the
+/// link keeps the unreserved capacity for all traffic, and a parallel link
+/// with the reserved capacity is added for the protected type (one per
+/// consolidated type derived from it, sharing that capacity). Reservations on
+/// links not in `links`, e.g. down in an outage scenario, are skipped.
+pub(crate) fn reserve_capacity(
+    links: &mut Vec<ConsolidatedLink>,
+    demands: &[ConsolidatedDemand],
+    reservations: &[CapacityReservation],
+) {
+    let mut next_shared = links.iter().map(|l| l.shared).max().unwrap_or(0) + 1;
+    let mut dedicated = Vec::new();
+    for reservation in reservations {
+        let kinds: BTreeSet<u32> = demands
+            .iter()
+            .filter(|d| d.original == reservation.kind)
+            .map(|d| d.kind)
+            .collect();
+        for provenance in [LinkProvenance::OriginalPrivate, LinkProvenance::Reverse] {
+            let Some(link) = links
+                .iter()
+                .find(|l| {
+                    l.provenance == provenance
+                        && ((l.device1 == reservation.device1 && l.device2 == reservation.device2)
+                            || (l.device1 == reservation.device2
+                                && l.device2 == reservation.device1))
+                })
+                .cloned()
+            else {
+                continue;
+            };
+            let reserved = reservation.bandwidth.min(link.bandwidth);
+            for other in links.iter_mut().filter(|l| l.shared == link.shared) {
+                other.bandwidth = (other.bandwidth - reserved).max(0.0);
+            }
+            for &kind in &kinds {
+                dedicated.push(ConsolidatedLink {
+                    bandwidth: reserved,
+                    shared: next_shared,
+                    link_type: kind,
+                    ..link.clone()
+                });
+            }
+            next_shared += 1;
+        }
+    }
+
+    // The LP expects every private link ahead of the public ones
+    let first_public = links
+        .iter()
+        .position(|l| l.operator1 == "Public")
+        .unwrap_or(links.len());
+    links.splice(first_public..first_public, dedicated);
+}
+
+/// Adjust bandwidth using quadratic uptime penalty curve.
+/// Maps raw uptime to effective availability — heavily penalizes below 98%:
+///   100% → 1.0, 99% → ~0.66, 98% → ~0, <98% → 0
+fn uptime_factor(uptime: f64) -> f64 {
+    (-1578.9474 * uptime.powi(2) + 3176.3158 * uptime - 1596.3684).clamp(0.0, 1.0)
+}
+
 /// Consolidate links for LP construction
 pub(crate) fn consolidate_links(
     private_links: &PrivateLinks,
@@ -163,32 +482,62 @@ pub(crate) fn consolidate_links(
         .iter()
         .map(|d| (d.device.as_str(), d.operator.as_str()))
         .collect();
+    let device_multicast: HashMap<&str, bool> = devices
+        .iter()
+        .filter_map(|d| Some((d.device.as_str(), d.multicast_capable?)))
+        .collect();
+    let receives_multicast = |node: &str| {
+        device_multicast
+            .get(node)
+            .copied()
+            .unwrap_or_else(|| multicast_capable_node(node))
+    };
+
+    // Collapse each link bundle (LAG) into its first member: capacities add up
+    // after the uptime penalty and latency is the fastest member's
+    let mut bundled_links: Vec<(&PrivateLink, f64, f64)> = Vec::new(); // (link, latency, bandwidth)
+    let mut bundle_index: HashMap<u32, usize> = HashMap::new();
+    for link in private_links {
+        let bandwidth = link.bandwidth * uptime_factor(link.uptime);
+        match link.bundle.map(|b| (b, bundle_index.get(&b).copied())) {
+            Some((_, Some(i))) => {
+                let (_, latency, total) = &mut bundled_links[i];
+                *latency = latency.min(link.latency);
+                *total += bandwidth;
+            }
+            Some((b, None)) => {
+                bundle_index.insert(b, bundled_links.len());
+                bundled_links.push((link, link.latency, bandwidth));
+            }
+            None => bundled_links.push((link, link.latency, bandwidth)),
+        }
+    }
 
     // Process private links - create bidirectional flows
     let mut max_shared = 0u32;
 
     // First pass: find max shared ID and assign shared IDs to links without them
     let mut private_links_with_shared = Vec::new();
-    for link in private_links {
+    for &(link, latency, bandwidth) in &bundled_links {
         if let Some(shared) = link.shared {
             max_shared = max_shared.max(shared);
-            private_links_with_shared.push((link, shared));
+            private_links_with_shared.push((link, latency, bandwidth, shared));
         } else {
             // Assign new shared ID to links without one
-            private_links_with_shared.push((link, 0)); // Will be assigned later
+            private_links_with_shared.push((link, latency, bandwidth, 0)); // Will be assigned later
         }
     }
 
     let mut next_shared = max_shared + 1;
     for pvt_link in &mut private_links_with_shared {
-        if pvt_link.1 == 0 {
-            pvt_link.1 = next_shared;
+        if pvt_link.3 == 0 {
+            pvt_link.3 = next_shared;
             next_shared += 1
         }
     }
 
     // Add forward direction
-    for (link, shared_id) in &private_links_with_shared {
+    for &(link, latency, bandwidth, shared_id) in &private_links_with_shared {
         let operator1 = device_to_operator
             .get(link.device1.as_str())
             .unwrap_or(&"Unknown");
@@ -196,23 +545,19 @@ pub(crate) fn consolidate_links(
             .get(link.device2.as_str())
             .unwrap_or(&"Unknown");
 
-        // Adjust bandwidth using quadratic uptime penalty curve.
-        // Maps raw uptime to effective availability — heavily penalizes below 98%:
-        //   100% → 1.0, 99% → ~0.66, 98% → ~0, <98% → 0
-        let uptime_factor = (-1578.9474 * link.uptime.powi(2) + 3176.3158 * link.uptime
-            - 1596.3684)
-            .clamp(0.0, 1.0);
-        let adjusted_bandwidth = link.bandwidth * uptime_factor;
-
         consolidated.push(ConsolidatedLink {
             device1: link.device1.clone(),
             device2: link.device2.clone(),
-            latency: link.latency,
-            bandwidth: adjusted_bandwidth,
+            latency,
+            bandwidth,
             operator1: operator1.to_string(),
             operator2: operator2.to_string(),
-            shared: *shared_id,
+            shared: shared_id,
             link_type: 0, // Available to all traffic types
+            provenance: LinkProvenance::OriginalPrivate,
+            multicast_capable: link
+                .multicast_capable
+                .unwrap_or_else(|| receives_multicast(&link.device2)),
         });
     }
 
@@ -223,6 +568,7 @@ pub(crate) fn consolidate_links(
     let forward_count = consolidated.len();
     for i in 0..forward_count {
         let link = consolidated[i].clone();
+        let original = private_links_with_shared[i].0;
         consolidated.push(ConsolidatedLink {
             device1: link.device2,
             device2: link.device1,
@@ -232,6 +578,10 @@ pub(crate) fn consolidate_links(
             operator2: link.operator1,
             shared: link.shared + max_shared,
             link_type: 0,
+            provenance: LinkProvenance::Reverse,
+            multicast_capable: original
+                .multicast_capable
+                .unwrap_or_else(|| receives_multicast(&original.device1)),
         });
     }
 
@@ -264,26 +614,30 @@ pub(crate) fn consolidate_links(
     for link in public_links {
         // Forward direction
         public_links_consolidated.push(ConsolidatedLink {
-            device1: format!("{}00", link.city1),
-            device2: format!("{}00", link.city2),
+            device1: public_node(&link.city1),
+            device2: public_node(&link.city2),
             latency: link.latency,
             bandwidth: 0.0, // Public links have no bandwidth limit
             operator1: "Public".to_string(),
             operator2: "Public".to_string(),
             shared: 0,
             link_type: 0,
+            provenance: LinkProvenance::PublicBackbone,
+            multicast_capable: false,
         });
 
         // Reverse direction
         public_links_consolidated.push(ConsolidatedLink {
-            device1: format!("{}00", link.city2),
-            device2: format!("{}00", link.city1),
+            device1: public_node(&link.city2),
+            device2: public_node(&link.city1),
             latency: link.latency,
             bandwidth: 0.0,
             operator1: "Public".to_string(),
             operator2: "Public".to_string(),
             shared: 0,
             link_type: 0,
+            provenance: LinkProvenance::PublicBackbone,
+            multicast_capable: false,
         });
     }
 
@@ -305,19 +659,21 @@ pub(crate) fn consolidate_links(
             // Public on-ramp for source
             public_links_consolidated.push(ConsolidatedLink {
                 device1: src.clone(),
-                device2: format!("{src}00"),
+                device2: public_node(src),
                 latency: 0.0,
                 bandwidth: 0.0,
                 operator1: "Public".to_string(),
                 operator2: "Public".to_string(),
                 shared: 0,
                 link_type: type_id,
+                provenance: LinkProvenance::OnRamp,
+                multicast_capable: false,
             });
 
             // Public off-ramps for destinations
             for dst in &destinations_vec {
                 public_links_consolidated.push(ConsolidatedLink {
-                    device1: format!("{dst}00"),
+                    device1: public_node(dst),
                     device2: dst.to_string(),
                     latency: 0.0,
                     bandwidth: 0.0,
@@ -325,6 +681,8 @@ pub(crate) fn consolidate_links(
                     operator2: "Public".to_string(),
                     shared: 0,
                     link_type: type_id,
+                    provenance: LinkProvenance::OffRamp,
+                    multicast_capable: false,
                 });
             }
 
@@ -345,6 +703,8 @@ pub(crate) fn consolidate_links(
                         operator2: device.operator.clone(),
                         shared: shared_id,
                         link_type: type_id,
+                        provenance: LinkProvenance::OnRamp,
+                        multicast_capable: receives_multicast(&device.device),
                     });
                 }
             }
@@ -367,6 +727,8 @@ pub(crate) fn consolidate_links(
                             operator2: device.operator.clone(),
                             shared: shared_id,
                             link_type: type_id,
+                            provenance: LinkProvenance::OffRamp,
+                            multicast_capable: false,
                         };
                         consolidated.push(new_link);
                     }
@@ -376,7 +738,7 @@ pub(crate) fn consolidate_links(
     }
 
     // Add crossover points between private and public networks
-    let private_cities: HashSet<&str> = devices.iter().map(|d| &d.device[..3]).collect();
+    let private_cities: HashSet<&str> = devices.iter().map(|d| city_of(&d.device)).collect();
 
     let public_cities: HashSet<&str> = public_links
         .iter()
@@ -399,13 +761,15 @@ pub(crate) fn consolidate_links(
                     .ok_or_else(|| ShapleyError::MissingDevice(device.device.clone()))?;
                 consolidated.push(ConsolidatedLink {
                     device1: device.device.clone(),
-                    device2: format!("{city}00"),
+                    device2: public_node(city),
                     latency: contiguity_bonus,
                     bandwidth: device.edge as f64,
                     operator1: device.operator.clone(),
                     operator2: device.operator.clone(),
                     shared: outbound_shared_id,
                     link_type: 0,
+                    provenance: LinkProvenance::Crossover,
+                    multicast_capable: false,
                 });
 
                 // Public to device (inbound)
@@ -414,7 +778,7 @@ pub(crate) fn consolidate_links(
                     .copied()
                     .ok_or_else(|| ShapleyError::MissingDevice(device.device.clone()))?;
                 consolidated.push(ConsolidatedLink {
-                    device1: format!("{city}00"),
+                    device1: public_node(city),
                     device2: device.device.clone(),
                     latency: contiguity_bonus,
                     bandwidth: device.edge as f64,
@@ -422,6 +786,8 @@ pub(crate) fn consolidate_links(
                     operator2: device.operator.clone(),
                     shared: inbound_shared_id,
                     link_type: 0,
+                    provenance: LinkProvenance::Crossover,
+                    multicast_capable: receives_multicast(&device.device),
                 });
             }
         }
@@ -463,7 +829,7 @@ pub(crate) fn consolidate_links(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::Demand;
+    use crate::types::PublicLink;
 
     #[test]
     fn test_consolidate_demand_basic() {
@@ -472,7 +838,7 @@ mod tests {
             Demand::new("A".to_string(), "C".to_string(), 2, 1.0, 1.0, 1, false),
         ];
 
-        let result = consolidate_demand(&demands, 2.0)
+        let result = consolidate_demand(&demands, 2.0, &BTreeMap::new())
             .expect("Demand consolidation should succeed in tests");
 
         assert_eq!(result.len(), 2);
@@ -480,6 +846,22 @@ mod tests {
         assert_eq!(result[1].traffic, 2.0);
     }
 
+    #[test]
+    fn test_consolidate_demand_multiplier_by_type() {
+        let demands = vec![
+            Demand::new("A".to_string(), "B".to_string(), 1, 1.0, 1.0, 1, false),
+            Demand::new("A".to_string(), "C".to_string(), 1, 1.0, 1.0, 2, true),
+            Demand::new("A".to_string(), "D".to_string(), 1, 1.0, 1.0, 2, true),
+        ];
+
+        let result = consolidate_demand(&demands, 2.0, &BTreeMap::from([(2, 0.5)]))
+            .expect("Demand consolidation should succeed in tests");
+
+        // Multicast rows split into their own types but keep their multiplier
+        let traffic: Vec<(u32, f64)> = result.iter().map(|d| (d.kind, d.traffic)).collect();
+        assert_eq!(traffic, vec![(1, 2.0), (2, 0.5), (3, 0.5)]);
+    }
+
     #[test]
     fn test_consolidate_demand_with_multicast() {
         let demands = vec![
@@ -492,7 +874,7 @@ mod tests {
             Demand::new("X".to_string(), "Z".to_string(), 1, 2.0, 1.0, 2, true),
         ];
 
-        let result = consolidate_demand(&demands, 1.0)
+        let result = consolidate_demand(&demands, 1.0, &BTreeMap::new())
             .expect("Multicast demand consolidation should succeed");
 
         // Check that multicast demands are properly consolidated
@@ -508,10 +890,64 @@ mod tests {
         assert!(unique_types.len() >= 2);
     }
 
+    #[test]
+    fn test_expand_regions() {
+        let regions = BTreeMap::from([
+            (
+                "EU".to_string(),
+                BTreeMap::from([("LON".to_string(), 3.0), ("PAR".to_string(), 1.0)]),
+            ),
+            (
+                "APAC".to_string(),
+                BTreeMap::from([("SIN".to_string(), 1.0), ("TYO".to_string(), 1.0)]),
+            ),
+        ]);
+        let public_links = vec![PublicLink::new("LON".into(), "SIN".into(), 100.0)];
+        let demands = vec![
+            Demand::new("NYC".into(), "LON".into(), 1, 5.0, 1.0, 1, false),
+            Demand::new("EU".into(), "APAC".into(), 1, 8.0, 1.0, 2, false),
+            Demand::new("EU".into(), "NYC".into(), 2, 4.0, 1.0, 3, true),
+            Demand::new("EU".into(), "APAC".into(), 1, 4.0, 1.0, 3, true),
+        ];
+
+        let (expanded, origins) = expand_regions(&demands, &regions, &public_links).unwrap();
+        let rows: Vec<(&str, &str, f64, u32)> = expanded
+            .iter()
+            .map(|d| (d.start.as_str(), d.end.as_str(), d.traffic, d.kind))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("NYC", "LON", 5.0, 1),
+                ("LON", "SIN", 3.0, 4),
+                ("LON", "TYO", 3.0, 5),
+                ("PAR", "SIN", 1.0, 6),
+                ("PAR", "TYO", 1.0, 7),
+                // One multicast stream per source city, reaching every receiver
+                ("LON", "NYC", 3.0, 8),
+                ("PAR", "NYC", 1.0, 9),
+                ("LON", "SIN", 3.0, 8),
+                ("LON", "TYO", 3.0, 8),
+                ("PAR", "SIN", 1.0, 9),
+                ("PAR", "TYO", 1.0, 9),
+            ]
+        );
+        assert_eq!(origins[&4], (2, 0.375));
+        assert_eq!(origins[&9], (3, 0.25));
+
+        let unregioned = demands[..1].to_vec();
+        let untouched = expand_regions(&unregioned, &regions, &public_links).unwrap();
+        assert!(matches!(untouched.0, Cow::Borrowed(_)));
+
+        let clashing = BTreeMap::from([("LON".to_string(), regions["EU"].clone())]);
+        assert!(expand_regions(&demands, &clashing, &public_links).is_err());
+    }
+
     #[test]
     fn test_consolidate_demand_empty() {
         let demands = vec![];
-        let result = consolidate_demand(&demands, 1.0).expect("Empty demands should succeed");
+        let result = consolidate_demand(&demands, 1.0, &BTreeMap::new())
+            .expect("Empty demands should succeed");
 
         assert_eq!(result.len(), 0);
     }
@@ -527,7 +963,8 @@ mod tests {
             Demand::new("E".to_string(), "G".to_string(), 3, 3.0, 1.0, 3, true),
         ];
 
-        let result = consolidate_demand(&demands, 1.0).expect("Mixed demands should succeed");
+        let result = consolidate_demand(&demands, 1.0, &BTreeMap::new())
+            .expect("Mixed demands should succeed");
 
         assert_eq!(result.len(), 4);
 
@@ -585,7 +1022,6 @@ mod tests {
     #[test]
     fn test_uptime_penalty_applied_to_bandwidth() {
         // Verify consolidate_links applies the penalty to bandwidth
-        // Device names must be 3+ chars (code slices [..3] for city prefix)
         let private_links = vec![crate::types::PrivateLink::new(
             "AAA1".to_string(),
             "BBB1".to_string(),
@@ -626,4 +1062,115 @@ mod tests {
             "Bandwidth should be ~66 (penalized), got {bw}"
         );
     }
+
+    #[test]
+    fn test_link_bundle_is_collapsed() {
+        let private_links = vec![
+            PrivateLink::new("AAA1".into(), "BBB1".into(), 12.0, 100.0, 1.0, None).with_bundle(7),
+            PrivateLink::new("BBB1".into(), "AAA1".into(), 10.0, 40.0, 1.0, None).with_bundle(7),
+            PrivateLink::new("AAA1".into(), "BBB1".into(), 30.0, 5.0, 1.0, None),
+        ];
+        let devices = vec![
+            crate::types::Device::new("AAA1".to_string(), 10, "Op1".to_string()),
+            crate::types::Device::new("BBB1".to_string(), 10, "Op1".to_string()),
+        ];
+
+        let result = consolidate_links(&private_links, &devices, &[], &vec![], 5.0)
+            .expect("consolidate_links should succeed");
+        let forward: Vec<_> = result
+            .iter()
+            .filter(|l| l.device1 == "AAA1" && l.device2 == "BBB1")
+            .collect();
+
+        // One link for the bundle, one for the unbundled parallel link
+        assert_eq!(forward.len(), 2);
+        assert_eq!(forward[0].latency, 10.0);
+        assert!((forward[0].bandwidth - 140.0).abs() < 1e-6);
+        assert_eq!(forward[1].latency, 30.0);
+        assert_ne!(forward[0].shared, forward[1].shared);
+    }
+
+    #[test]
+    fn test_link_provenance() {
+        let private_links = vec![PrivateLink::new(
+            "AAA1".into(),
+            "BBB1".into(),
+            10.0,
+            10.0,
+            1.0,
+            None,
+        )];
+        let devices = vec![
+            crate::types::Device::new("AAA1".to_string(), 10, "Op1".to_string()),
+            crate::types::Device::new("BBB1".to_string(), 10, "Op2".to_string()),
+        ];
+        let demands = consolidate_demand(
+            &vec![Demand::new(
+                "AAA".into(),
+                "BBB".into(),
+                1,
+                1.0,
+                1.0,
+                1,
+                false,
+            )],
+            1.0,
+            &BTreeMap::new(),
+        )
+        .unwrap();
+        let public_links = vec![crate::types::PublicLink::new(
+            "AAA".into(),
+            "BBB".into(),
+            50.0,
+        )];
+
+        let links = consolidate_links(&private_links, &devices, &demands, &public_links, 5.0)
+            .expect("consolidate_links should succeed");
+        let count = |p: LinkProvenance| links.iter().filter(|l| l.provenance == p).count();
+
+        assert_eq!(count(LinkProvenance::OriginalPrivate), 1);
+        assert_eq!(count(LinkProvenance::Reverse), 1);
+        assert_eq!(count(LinkProvenance::PublicBackbone), 2);
+        // One public and one private ramp at each end
+        assert_eq!(count(LinkProvenance::OnRamp), 2);
+        assert_eq!(count(LinkProvenance::OffRamp), 2);
+        // Both directions at each device
+        assert_eq!(count(LinkProvenance::Crossover), 4);
+        assert!(
+            links
+                .iter()
+                .filter(|l| l.provenance == LinkProvenance::Crossover)
+                .all(|l| l.latency == 5.0)
+        );
+    }
+
+    #[test]
+    fn test_multicast_capability() {
+        let link = |a: &str, b: &str| PrivateLink::new(a.into(), b.into(), 10.0, 10.0, 1.0, None);
+        let private_links = vec![
+            link("lax-dz001", "LAX00r"),
+            link("LAX00r", "sfo-dz002").with_multicast_capable(false),
+        ];
+        let devices = vec![
+            crate::types::Device::new("lax-dz001".to_string(), 10, "Op1".to_string())
+                .with_multicast_capable(false),
+            crate::types::Device::new("LAX00r".to_string(), 10, "Op1".to_string()),
+            crate::types::Device::new("sfo-dz002".to_string(), 10, "Op2".to_string()),
+        ];
+
+        let links = consolidate_links(&private_links, &devices, &[], &vec![], 5.0)
+            .expect("consolidate_links should succeed");
+        let capable = |device1: &str, device2: &str| {
+            links
+                .iter()
+                .find(|l| l.device1 == device1 && l.device2 == device2)
+                .unwrap()
+                .multicast_capable
+        };
+        // The device's flag overrides its name, the link's overrides both
+        assert!(capable("lax-dz001", "LAX00r"));
+        assert!(!capable("LAX00r", "lax-dz001"));
+        assert!(!capable("LAX00r", "sfo-dz002"));
+        assert!(!capable("sfo-dz002", "LAX00r"));
+    }
 }
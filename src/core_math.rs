@@ -0,0 +1,629 @@
+//! Post-LP aggregation: expected coalition values under operator uptime and
+//! Shapley values from coalition values.
+//!
+//! This module only depends on `core` and `alloc` so it can be lifted into a
+//! `no_std` environment, e.g. an on-chain program that re-runs the aggregation
+//! stage from published coalition values; `just nostd` builds it in a `no_std`
+//! crate to keep it that way. Coalition `i` contains the operators whose bits
+//! are set in `i` (bit k ↔ operator k).
+
+use alloc::{vec, vec::Vec};
+
+const FACTORIAL_LIMIT: usize = 21;
+const FACTORIALS: [u64; FACTORIAL_LIMIT] = {
+    let mut facts = [1u64; FACTORIAL_LIMIT];
+    let mut i = 1;
+    while i < FACTORIAL_LIMIT {
+        facts[i] = facts[i - 1] * (i as u64);
+        i += 1;
+    }
+    facts
+};
+
+/// n! as f64, exact up to 20!
+pub fn factorial(n: usize) -> f64 {
+    if n < FACTORIAL_LIMIT {
+        FACTORIALS[n] as f64
+    } else {
+        (FACTORIAL_LIMIT..=n).fold(FACTORIALS[FACTORIAL_LIMIT - 1] as f64, |acc, k| {
+            acc * k as f64
+        })
+    }
+}
+
+/// `base^exp` by repeated squaring; `f64::powi` lives in std.
+#[cfg(test)]
+fn powi(mut base: f64, mut exp: u32) -> f64 {
+    let mut result = 1.0;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result *= base;
+        }
+        base *= base;
+        exp >>= 1;
+    }
+    result
+}
+
+/// How [`expected_values_with`] sums over subcoalitions. Both give the same
+/// values up to rounding; the choice is kept for benchmarking.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExpectedValueStrategy {
+    /// Subset-sum transform applied one operator at a time, in place:
+    /// O(n·2^n) time
+    #[default]
+    SubsetSum,
+    /// Each coalition sums over its own subcoalitions with coefficients
+    /// built on the fly: O(3^n) time, but coalitions are independent
+    Streaming,
+}
+
+/// Compute expected values considering operator uptime.
+///
+/// For each coalition S, computes:
+///   evalue[S] = Σ_{T⊆S} uptime^|T| × (1-uptime)^(|S\T|) × svalue[T]
+///
+/// Memory stays at the 2^n output values; nothing of size 2^n × 2^n is
+/// materialized. Infeasible subcoalitions (`None`) contribute nothing.
+pub fn expected_values(
+    svalue: &[Option<f64>],
+    n_operators: usize,
+    operator_uptime: f64,
+) -> Vec<f64> {
+    expected_values_with(
+        svalue,
+        n_operators,
+        operator_uptime,
+        ExpectedValueStrategy::default(),
+    )
+}
+
+/// [`expected_values`] with an explicit strategy
+#[doc(hidden)]
+pub fn expected_values_with(
+    svalue: &[Option<f64>],
+    n_operators: usize,
+    operator_uptime: f64,
+    strategy: ExpectedValueStrategy,
+) -> Vec<f64> {
+    match strategy {
+        ExpectedValueStrategy::SubsetSum => {
+            expected_values_subset_sum(svalue, n_operators, operator_uptime)
+        }
+        ExpectedValueStrategy::Streaming => {
+            expected_values_streaming(svalue, n_operators, operator_uptime)
+        }
+    }
+}
+
+/// The weight factors per operator, so for every S containing operator i,
+///   f[S] ← uptime × f[S] + (1-uptime) × f[S∖i]
+/// applied for each operator in turn turns svalue into evalue.
+fn expected_values_subset_sum(
+    svalue: &[Option<f64>],
+    n_operators: usize,
+    operator_uptime: f64,
+) -> Vec<f64> {
+    let downtime = 1.0 - operator_uptime;
+
+    let mut evalue: Vec<f64> = svalue
+        .iter()
+        .map(|&v| v.filter(|v| v.is_finite()).unwrap_or(0.0))
+        .collect();
+
+    for i in 0..n_operators {
+        let bit = 1 << i;
+        for s in 0..evalue.len() {
+            if s & bit != 0 {
+                evalue[s] = operator_uptime * evalue[s] + downtime * evalue[s ^ bit];
+            }
+        }
+    }
+
+    evalue
+}
+
+/// Gosper's subset iteration (`t = (t-1) & s`) over the subcoalitions of
+/// each coalition. The coefficient uptime^|T| × (1-uptime)^(|S∖T|) only
+/// depends on the two sizes, so it comes from powers built recursively up
+/// to n rather than from a 2^n × 2^n matrix.
+fn expected_values_streaming(
+    svalue: &[Option<f64>],
+    n_operators: usize,
+    operator_uptime: f64,
+) -> Vec<f64> {
+    let downtime = 1.0 - operator_uptime;
+    let mut up = vec![1.0; n_operators + 1];
+    let mut down = vec![1.0; n_operators + 1];
+    for k in 1..=n_operators {
+        up[k] = up[k - 1] * operator_uptime;
+        down[k] = down[k - 1] * downtime;
+    }
+
+    (0..svalue.len())
+        .map(|s| {
+            let s_size = (s as u32).count_ones() as usize;
+            let mut sum = 0.0;
+            let mut t = s;
+            loop {
+                if let Some(val) = svalue[t].filter(|v| v.is_finite()) {
+                    let t_size = (t as u32).count_ones() as usize;
+                    sum += up[t_size] * down[s_size - t_size] * val;
+                }
+                if t == 0 {
+                    break;
+                }
+                t = (t - 1) & s;
+            }
+            sum
+        })
+        .collect()
+}
+
+/// Compute Shapley values from coalition values
+pub fn shapley_values(coalition_values: &[f64], n_operators: usize) -> Vec<f64> {
+    let mut shapley_values = vec![0.0; n_operators];
+    let fact_n = factorial(n_operators);
+
+    for (k, sv) in shapley_values.iter_mut().enumerate() {
+        let mut value = 0.0;
+
+        // Find coalitions with this operator
+        for (coalition_idx, &with_value) in coalition_values.iter().enumerate() {
+            if (coalition_idx >> k) & 1 == 1 {
+                // Coalition without operator (remove bit k)
+                let without_idx = coalition_idx ^ (1 << k);
+                let without_value = coalition_values[without_idx];
+
+                // Coalition size
+                let coalition_size = (coalition_idx as u32).count_ones() as usize;
+
+                // Weight calculation
+                let weight = factorial(coalition_size - 1)
+                    * factorial(n_operators - coalition_size)
+                    / fact_n;
+
+                value += weight * (with_value - without_value);
+            }
+        }
+
+        *sv = value;
+    }
+
+    shapley_values
+}
+
+/// One term of operator k's Shapley sum: joining coalition `without` (which
+/// excludes k) adds `delta` to its value, counted with `weight`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarginalTerm {
+    pub without: usize,
+    pub delta: f64,
+    pub weight: f64,
+}
+
+/// The `top` terms of operator `k`'s Shapley sum with the largest marginal
+/// contribution, largest first. Ties keep the smaller coalition first.
+pub fn top_marginal_terms(
+    coalition_values: &[f64],
+    n_operators: usize,
+    k: usize,
+    top: usize,
+) -> Vec<MarginalTerm> {
+    let fact_n = factorial(n_operators);
+    let mut terms: Vec<MarginalTerm> = (0..coalition_values.len())
+        .filter(|&without| (without >> k) & 1 == 0)
+        .map(|without| {
+            let size = (without as u32).count_ones() as usize;
+            MarginalTerm {
+                without,
+                delta: coalition_values[without | (1 << k)] - coalition_values[without],
+                weight: factorial(size) * factorial(n_operators - size - 1) / fact_n,
+            }
+        })
+        .collect();
+    terms.sort_by(|a, b| b.delta.total_cmp(&a.delta).then(a.without.cmp(&b.without)));
+    terms.truncate(top);
+    terms
+}
+
+/// Mean and population variance of operator `k`'s marginal contributions
+/// v(S ∪ k) − v(S) over the coalitions S ∌ k of each size; entry `s` covers
+/// the coalitions of `s` members. Averaging the means over sizes gives the
+/// Shapley value.
+pub fn marginal_moments_by_size(
+    coalition_values: &[f64],
+    n_operators: usize,
+    k: usize,
+) -> Vec<(f64, f64)> {
+    let deltas = || {
+        (0..coalition_values.len())
+            .filter(move |&without| (without >> k) & 1 == 0)
+            .map(move |without| {
+                let size = (without as u32).count_ones() as usize;
+                (
+                    size,
+                    coalition_values[without | (1 << k)] - coalition_values[without],
+                )
+            })
+    };
+    let counts: Vec<f64> = (0..n_operators)
+        .map(|s| factorial(n_operators - 1) / (factorial(s) * factorial(n_operators - 1 - s)))
+        .collect();
+
+    let mut means = vec![0.0; n_operators];
+    for (size, delta) in deltas() {
+        means[size] += delta;
+    }
+    for (mean, count) in means.iter_mut().zip(&counts) {
+        *mean /= count;
+    }
+    let mut variances = vec![0.0; n_operators];
+    for (size, delta) in deltas() {
+        let d = delta - means[size];
+        variances[size] += d * d;
+    }
+    means
+        .into_iter()
+        .zip(variances)
+        .zip(&counts)
+        .map(|((mean, variance), count)| (mean, variance / count))
+        .collect()
+}
+
+/// Owen values for a partition of the operators into blocks, given as one
+/// bitmask per block. Blocks first split the value as players of the game
+/// between blocks, then each block's share is split among its members:
+///   φ_i = Σ_{R ⊆ M∖{k}} Σ_{T ⊆ B_k∖{i}} |R|!(m−|R|−1)!/m! × |T|!(b−|T|−1)!/b!
+///         × (v(Q_R ∪ T ∪ i) − v(Q_R ∪ T))
+/// for i in block B_k of size b, with m blocks and Q_R the union of blocks in R.
+/// Singleton blocks, or a single block of everyone, give the Shapley value.
+pub fn owen_values(coalition_values: &[f64], n_operators: usize, blocks: &[u32]) -> Vec<f64> {
+    let m = blocks.len();
+    let fact_m = factorial(m);
+    let mut owen = vec![0.0; n_operators];
+
+    for (k, &block) in blocks.iter().enumerate() {
+        let b = block.count_ones() as usize;
+        let fact_b = factorial(b);
+        let other_blocks = ((1u32 << m) - 1) & !(1 << k);
+
+        for (i, value) in owen.iter_mut().enumerate() {
+            if block & (1 << i) == 0 {
+                continue;
+            }
+            let rest = block & !(1 << i);
+
+            // R ranges over subsets of the other blocks
+            let mut r = other_blocks;
+            loop {
+                let r_size = r.count_ones() as usize;
+                let r_weight = factorial(r_size) * factorial(m - r_size - 1) / fact_m;
+                let q: u32 = (0..m)
+                    .filter(|&j| r & (1 << j) != 0)
+                    .fold(0, |acc, j| acc | blocks[j]);
+
+                // T ranges over subsets of the rest of i's block
+                let mut t = rest;
+                loop {
+                    let t_size = t.count_ones() as usize;
+                    let t_weight = factorial(t_size) * factorial(b - t_size - 1) / fact_b;
+                    let without = (q | t) as usize;
+                    *value += r_weight
+                        * t_weight
+                        * (coalition_values[without | (1 << i)] - coalition_values[without]);
+                    if t == 0 {
+                        break;
+                    }
+                    t = (t - 1) & rest;
+                }
+
+                if r == 0 {
+                    break;
+                }
+                r = (r - 1) & other_blocks;
+            }
+        }
+    }
+
+    owen
+}
+
+/// Shapley interaction index of every operator pair, row-major n × n with a
+/// zero diagonal:
+///   I[i][j] = Σ_{S ⊆ N∖{i,j}} |S|!(n−|S|−2)!/(n−1)! × (v(S+i+j) − v(S+i) − v(S+j) + v(S))
+///
+/// Positive entries mark pairs that are worth more together than apart.
+pub fn interaction_indices(coalition_values: &[f64], n_operators: usize) -> Vec<f64> {
+    let mut indices = vec![0.0; n_operators * n_operators];
+    if n_operators < 2 {
+        return indices;
+    }
+    let fact_n1 = factorial(n_operators - 1);
+
+    for i in 0..n_operators {
+        for j in i + 1..n_operators {
+            let pair = (1 << i) | (1 << j);
+            let mut value = 0.0;
+            for (s, &vs) in coalition_values.iter().enumerate() {
+                if s & pair != 0 {
+                    continue;
+                }
+                let size = (s as u32).count_ones() as usize;
+                let weight = factorial(size) * factorial(n_operators - size - 2) / fact_n1;
+                let delta = coalition_values[s | pair]
+                    - coalition_values[s | (1 << i)]
+                    - coalition_values[s | (1 << j)]
+                    + vs;
+                value += weight * delta;
+            }
+            indices[i * n_operators + j] = value;
+            indices[j * n_operators + i] = value;
+        }
+    }
+
+    indices
+}
+
+/// What operators `a` and `b` are each credited, in their Shapley values,
+/// for coalitions that need both of them:
+///   J = Σ_{S ⊇ {a,b}} d(S)/|S|,  d(S) = Σ_{T ⊆ S} (−1)^{|S|−|T|} v(T)
+/// with d the Harsanyi dividends. For the owners of a co-owned link this is
+/// half of what their joint ownership is worth.
+pub fn joint_credit(coalition_values: &[f64], n_operators: usize, a: usize, b: usize) -> f64 {
+    // Möbius transform, one operator at a time, in place
+    let mut dividends = coalition_values.to_vec();
+    for k in 0..n_operators {
+        let bit = 1 << k;
+        for mask in 0..dividends.len() {
+            if mask & bit != 0 {
+                dividends[mask] -= dividends[mask ^ bit];
+            }
+        }
+    }
+    let pair = (1 << a) | (1 << b);
+    dividends
+        .iter()
+        .enumerate()
+        .filter(|&(mask, _)| mask & pair == pair)
+        .map(|(mask, &d)| d / (mask as u32).count_ones() as f64)
+        .sum()
+}
+
+/// Share of the positive Shapley mass held by each operator; negative values get 0.
+pub fn proportions(shapley_values: &[f64]) -> Vec<f64> {
+    let total_value: f64 = shapley_values.iter().map(|v| v.max(0.0)).sum();
+    shapley_values
+        .iter()
+        .map(|&value| {
+            if total_value > 0.0 {
+                (value.max(0.0) / total_value * 100.0) / 100.0
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Coalition values fed to the Shapley stage. With `operator_uptime >= 1` the
+/// raw values are used as-is and an infeasible coalition counts as -∞.
+pub fn uptime_adjusted_values(
+    svalue: &[Option<f64>],
+    n_operators: usize,
+    operator_uptime: f64,
+) -> Vec<f64> {
+    if operator_uptime < 1.0 {
+        expected_values(svalue, n_operators, operator_uptime)
+    } else {
+        svalue
+            .iter()
+            .map(|&v| v.unwrap_or(f64::NEG_INFINITY))
+            .collect()
+    }
+}
+
+/// Coalition values of several scenarios (see [`uptime_adjusted_values`]),
+/// weighted by the scenarios' probabilities. An infeasible coalition (-∞)
+/// adds nothing in a scenario, as an infeasible state does to the uptime
+/// average, instead of swamping every other scenario; a coalition infeasible
+/// in every scenario stays infeasible, whatever their probabilities.
+#[derive(Debug, Clone)]
+pub struct ScenarioSum {
+    total: Vec<f64>,
+    feasible: Vec<bool>,
+}
+
+impl ScenarioSum {
+    pub fn new(n_coalitions: usize) -> Self {
+        Self {
+            total: vec![0.0; n_coalitions],
+            feasible: vec![false; n_coalitions],
+        }
+    }
+
+    /// Add the coalition `values` of a scenario with `probability`
+    pub fn add(&mut self, probability: f64, values: &[f64]) {
+        let slots = self.total.iter_mut().zip(&mut self.feasible);
+        for ((total, feasible), &value) in slots.zip(values) {
+            if value.is_finite() {
+                *total += probability * value;
+                *feasible = true;
+            }
+        }
+    }
+
+    /// The weighted values, -∞ for coalitions no scenario could route
+    pub fn finish(self) -> Vec<f64> {
+        self.total
+            .into_iter()
+            .zip(self.feasible)
+            .map(|(total, feasible)| if feasible { total } else { f64::NEG_INFINITY })
+            .collect()
+    }
+}
+
+/// Full aggregation stage: Shapley values from raw per-coalition LP values
+pub fn aggregate(svalue: &[Option<f64>], n_operators: usize, operator_uptime: f64) -> Vec<f64> {
+    shapley_values(
+        &uptime_adjusted_values(svalue, n_operators, operator_uptime),
+        n_operators,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interaction_indices() {
+        // v(S) = |S|²: every pair adds exactly 2 on top of its members
+        let values: Vec<f64> = (0..8u32).map(|s| powi(s.count_ones() as f64, 2)).collect();
+        let indices = interaction_indices(&values, 3);
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = if i == j { 0.0 } else { 2.0 };
+                assert!((indices[i * 3 + j] - expected).abs() < 1e-12);
+            }
+        }
+
+        // Additive game: no synergy
+        let additive = [0.0, 1.0, 2.0, 3.0];
+        assert_eq!(interaction_indices(&additive, 2), vec![0.0; 4]);
+    }
+
+    #[test]
+    fn test_joint_credit() {
+        // Operators 0 and 1 are worth 6 together only, operator 2 adds 3
+        // alone and 3 more with operator 0
+        let values: Vec<f64> = (0..8usize)
+            .map(|c| {
+                let both = if c & 0b011 == 0b011 { 6.0 } else { 0.0 };
+                let two = if c & 0b100 != 0 { 3.0 } else { 0.0 };
+                let with_zero = if c & 0b101 == 0b101 { 3.0 } else { 0.0 };
+                both + two + with_zero
+            })
+            .collect();
+        assert!((joint_credit(&values, 3, 0, 1) - 3.0).abs() < 1e-12);
+        assert!((joint_credit(&values, 3, 0, 2) - 1.5).abs() < 1e-12);
+        assert!(joint_credit(&values, 3, 1, 2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_marginal_moments_by_size() {
+        // v(S) = |S|²: joining s others always adds 2s + 1
+        let values: Vec<f64> = (0..8u32).map(|s| (s.count_ones() as f64).powi(2)).collect();
+        assert_eq!(
+            marginal_moments_by_size(&values, 3, 1),
+            vec![(1.0, 0.0), (3.0, 0.0), (5.0, 0.0)]
+        );
+
+        // Operator 0 adds 1 alone, and 3 or 1 after operator 1 or 2
+        let values = [0.0, 1.0, 0.0, 3.0, 0.0, 1.0, 0.0, 3.0];
+        let moments = marginal_moments_by_size(&values, 3, 0);
+        assert_eq!(moments[1], (2.0, 1.0));
+        let mean = moments.iter().map(|m| m.0).sum::<f64>() / 3.0;
+        assert!((mean - shapley_values(&values, 3)[0]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_owen_values() {
+        // Majority game: any two operators win
+        let values: Vec<f64> = (0..8u32)
+            .map(|s| if s.count_ones() >= 2 { 1.0 } else { 0.0 })
+            .collect();
+
+        // {A, B} negotiating jointly leave C nothing
+        let owen = owen_values(&values, 3, &[0b011, 0b100]);
+        assert!((owen[0] - 0.5).abs() < 1e-12);
+        assert!((owen[1] - 0.5).abs() < 1e-12);
+        assert!(owen[2].abs() < 1e-12);
+
+        // Trivial partitions reduce to the Shapley value
+        let shapley = shapley_values(&values, 3);
+        for blocks in [&[0b001, 0b010, 0b100][..], &[0b111][..]] {
+            let owen = owen_values(&values, 3, blocks);
+            for (o, s) in owen.iter().zip(&shapley) {
+                assert!((o - s).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_scenario_sum() {
+        let mut sum = ScenarioSum::new(3);
+        sum.add(0.8, &[0.0, f64::NEG_INFINITY, 5.0]);
+        sum.add(0.2, &[1.0, f64::NEG_INFINITY, f64::NEG_INFINITY]);
+        // Infeasible in one scenario adds nothing there; in every scenario,
+        // stays infeasible
+        assert_eq!(sum.finish(), vec![0.2, f64::NEG_INFINITY, 4.0]);
+
+        let mut certain = ScenarioSum::new(2);
+        certain.add(1.0, &[-3.0, f64::NEG_INFINITY]);
+        assert_eq!(certain.finish(), vec![-3.0, f64::NEG_INFINITY]);
+    }
+
+    #[test]
+    fn test_factorial() {
+        assert_eq!(factorial(0), 1.0);
+        assert_eq!(factorial(5), 120.0);
+        assert_eq!(factorial(10), 3628800.0);
+        assert_eq!(factorial(22), 21.0 * 22.0 * factorial(20));
+    }
+
+    #[test]
+    fn test_powi_matches_std() {
+        for exp in 0..20 {
+            assert!((powi(0.93, exp) - 0.93f64.powi(exp as i32)).abs() < 1e-15);
+        }
+    }
+
+    #[test]
+    fn test_expected_value_strategies_agree() {
+        let n = 6;
+        let svalue: Vec<Option<f64>> = (0..1u32 << n)
+            .map(|s| (s % 7 != 3).then(|| (s * 37 % 101) as f64 - 20.0))
+            .collect();
+        for uptime in [0.0, 0.5, 0.93, 1.0] {
+            let fast = expected_values_with(&svalue, n, uptime, ExpectedValueStrategy::SubsetSum);
+            let reference =
+                expected_values_with(&svalue, n, uptime, ExpectedValueStrategy::Streaming);
+            for (f, r) in fast.iter().zip(&reference) {
+                assert!((f - r).abs() < 1e-9, "{f} != {r}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_compute_expected_values_simple() {
+        // Test with 2 operators, uptime = 0.9
+        let n_ops = 2;
+        let uptime = 0.9; // 0.9
+
+        // svalue for coalitions: {}, {B}, {A}, {A,B}
+        let svalue = vec![Some(100.0), Some(120.0), Some(150.0), Some(200.0)];
+
+        let evalue = expected_values(&svalue, n_ops, uptime);
+
+        // These expected values are derived from running the reference Python code
+        // with the same inputs.
+        let expected_evalue = vec![100.0, 118.0, 145.0, 187.3];
+
+        for (i, (val, exp)) in evalue.iter().zip(expected_evalue).enumerate() {
+            assert!(
+                (val - exp).abs() < 1e-9,
+                "Mismatch at index {i}: got {val}, expected {exp}",
+            );
+        }
+    }
+
+    #[test]
+    fn test_shapley_values_are_efficient() {
+        let values = vec![0.0, 10.0, 20.0, 50.0];
+        let phi = shapley_values(&values, 2);
+
+        assert!((phi[0] - 20.0).abs() < 1e-12);
+        assert!((phi[1] - 30.0).abs() < 1e-12);
+        assert_eq!(proportions(&phi), vec![0.4, 0.6]);
+    }
+}
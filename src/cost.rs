@@ -0,0 +1,320 @@
+use std::{collections::HashMap, fmt::Debug, sync::Arc, time::Duration};
+
+use crate::types::{BandwidthUnit, LinkProvenance};
+
+/// View of one directed link of the consolidated network, as seen by a [`CostModel`].
+#[derive(Debug, Clone, Copy)]
+pub struct CostedLink<'a> {
+    pub device1: &'a str,
+    pub device2: &'a str,
+    pub operator1: &'a str,
+    pub operator2: &'a str,
+    /// Latency used in the objective (contiguity bonus for crossover links)
+    pub latency: f64,
+    pub provenance: LinkProvenance,
+}
+
+/// Per-unit-of-traffic cost of routing over a link. The LP minimises
+/// Σ cost × priority × flow, so the coalition value is expressed in whatever
+/// unit the model returns.
+pub trait CostModel: Debug + Send + Sync {
+    fn cost(&self, link: &CostedLink<'_>) -> f64;
+}
+
+/// Cost equals latency, the objective of the reference implementation
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LatencyCost;
+
+impl CostModel for LatencyCost {
+    fn cost(&self, link: &CostedLink<'_>) -> f64 {
+        link.latency
+    }
+}
+
+/// Monetary cost: latency converted to money plus a per-link price.
+///
+/// Private link prices are keyed by device pair, public link prices by city
+/// pair; both are direction-agnostic. Links without a price cost only their
+/// converted latency.
+#[derive(Debug, Clone, Default)]
+pub struct MonetaryCost {
+    /// Money per unit of latency per unit of traffic
+    pub latency_to_money: f64,
+    pub private_link_cost: HashMap<(String, String), f64>,
+    pub public_link_cost: HashMap<(String, String), f64>,
+}
+
+impl MonetaryCost {
+    pub fn new(latency_to_money: f64) -> Self {
+        Self {
+            latency_to_money,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_private_link_cost(mut self, device1: &str, device2: &str, cost: f64) -> Self {
+        self.private_link_cost
+            .insert((device1.to_string(), device2.to_string()), cost);
+        self
+    }
+
+    pub fn with_public_link_cost(mut self, city1: &str, city2: &str, cost: f64) -> Self {
+        self.public_link_cost
+            .insert((city1.to_string(), city2.to_string()), cost);
+        self
+    }
+}
+
+impl CostModel for MonetaryCost {
+    fn cost(&self, link: &CostedLink<'_>) -> f64 {
+        let price = match link.provenance {
+            LinkProvenance::OriginalPrivate | LinkProvenance::Reverse => {
+                lookup(&self.private_link_cost, link.device1, link.device2).unwrap_or(0.0)
+            }
+            LinkProvenance::PublicBackbone => public_cities(link)
+                .and_then(|(city1, city2)| lookup(&self.public_link_cost, city1, city2))
+                .unwrap_or(0.0),
+            LinkProvenance::OnRamp | LinkProvenance::OffRamp | LinkProvenance::Crossover => 0.0,
+        };
+        link.latency * self.latency_to_money + price
+    }
+}
+
+/// Monetary cost with public internet transit billed by volume: traffic on
+/// a public link pays its price per GB for the volume one unit of traffic
+/// moves over the billing period, on top of latency converted to money.
+/// Coalition values then include the transit saved by keeping traffic on
+/// private links.
+///
+/// City pair prices are direction-agnostic; public links without one pay
+/// `price_per_gb`.
+#[derive(Debug, Clone, Default)]
+pub struct TransitCost {
+    /// Money per unit of latency per unit of traffic
+    pub latency_to_money: f64,
+    /// GB that one unit of traffic moves over the billing period, see
+    /// [`gb_per_period`](Self::gb_per_period)
+    pub gb_per_traffic_unit: f64,
+    pub price_per_gb: f64,
+    pub city_pair_price_per_gb: HashMap<(String, String), f64>,
+}
+
+impl TransitCost {
+    pub fn new(latency_to_money: f64, gb_per_traffic_unit: f64, price_per_gb: f64) -> Self {
+        Self {
+            latency_to_money,
+            gb_per_traffic_unit,
+            price_per_gb,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_city_pair_price(mut self, city1: &str, city2: &str, price_per_gb: f64) -> Self {
+        self.city_pair_price_per_gb
+            .insert((city1.to_string(), city2.to_string()), price_per_gb);
+        self
+    }
+
+    /// GB moved over `period` by one unit of traffic given in `unit`
+    pub fn gb_per_period(unit: BandwidthUnit, period: Duration) -> f64 {
+        unit.bits_per_second() * period.as_secs_f64() / 8e9
+    }
+}
+
+impl CostModel for TransitCost {
+    fn cost(&self, link: &CostedLink<'_>) -> f64 {
+        let transit = match link.provenance {
+            LinkProvenance::PublicBackbone => {
+                let price = public_cities(link)
+                    .and_then(|(city1, city2)| lookup(&self.city_pair_price_per_gb, city1, city2))
+                    .unwrap_or(self.price_per_gb);
+                price * self.gb_per_traffic_unit
+            }
+            _ => 0.0,
+        };
+        link.latency * self.latency_to_money + transit
+    }
+}
+
+/// Carbon emitted per unit of traffic on each link.
+///
+/// Private link intensities are keyed by device pair, public link
+/// intensities by city pair; both are direction-agnostic. Public links
+/// without one use `public_default`, private links without one emit nothing.
+#[derive(Debug, Clone, Default)]
+pub struct CarbonIntensity {
+    pub private_link: HashMap<(String, String), f64>,
+    pub public_link: HashMap<(String, String), f64>,
+    pub public_default: f64,
+}
+
+impl CarbonIntensity {
+    pub fn new(public_default: f64) -> Self {
+        Self {
+            public_default,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_private_link(mut self, device1: &str, device2: &str, intensity: f64) -> Self {
+        self.private_link
+            .insert((device1.to_string(), device2.to_string()), intensity);
+        self
+    }
+
+    pub fn with_public_link(mut self, city1: &str, city2: &str, intensity: f64) -> Self {
+        self.public_link
+            .insert((city1.to_string(), city2.to_string()), intensity);
+        self
+    }
+
+    pub fn of(&self, link: &CostedLink<'_>) -> f64 {
+        match link.provenance {
+            LinkProvenance::OriginalPrivate | LinkProvenance::Reverse => {
+                lookup(&self.private_link, link.device1, link.device2).unwrap_or(0.0)
+            }
+            LinkProvenance::PublicBackbone => public_cities(link)
+                .and_then(|(city1, city2)| lookup(&self.public_link, city1, city2))
+                .unwrap_or(self.public_default),
+            LinkProvenance::OnRamp | LinkProvenance::OffRamp | LinkProvenance::Crossover => 0.0,
+        }
+    }
+}
+
+/// Cost of a base model plus `weight` per unit of carbon, so that routing
+/// over greener links is valued. `weight` converts carbon into the base
+/// model's unit.
+#[derive(Debug, Clone)]
+pub struct CarbonCost {
+    pub base: Arc<dyn CostModel>,
+    pub intensity: CarbonIntensity,
+    pub weight: f64,
+}
+
+impl CarbonCost {
+    /// Latency plus weighted carbon
+    pub fn new(intensity: CarbonIntensity, weight: f64) -> Self {
+        Self {
+            base: Arc::new(LatencyCost),
+            intensity,
+            weight,
+        }
+    }
+
+    pub fn with_base(mut self, base: Arc<dyn CostModel>) -> Self {
+        self.base = base;
+        self
+    }
+}
+
+impl CostModel for CarbonCost {
+    fn cost(&self, link: &CostedLink<'_>) -> f64 {
+        self.base.cost(link) + self.weight * self.intensity.of(link)
+    }
+}
+
+/// Price of the unordered pair `a`, `b`
+fn lookup(table: &HashMap<(String, String), f64>, a: &str, b: &str) -> Option<f64> {
+    table
+        .get(&(a.to_string(), b.to_string()))
+        .or_else(|| table.get(&(b.to_string(), a.to_string())))
+        .copied()
+}
+
+/// Cities of a public backbone link between `{city}00` nodes
+fn public_cities<'a>(link: &CostedLink<'a>) -> Option<(&'a str, &'a str)> {
+    Some((
+        link.device1.strip_suffix("00")?,
+        link.device2.strip_suffix("00")?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link<'a>(device1: &'a str, device2: &'a str, provenance: LinkProvenance) -> CostedLink<'a> {
+        CostedLink {
+            device1,
+            device2,
+            operator1: "Op",
+            operator2: "Op",
+            latency: 10.0,
+            provenance,
+        }
+    }
+
+    #[test]
+    fn test_monetary_cost_lookup_is_direction_agnostic() {
+        let model = MonetaryCost::new(0.5)
+            .with_private_link_cost("FRA1", "AMS1", 2.0)
+            .with_public_link_cost("FRA", "AMS", 7.0);
+
+        assert_eq!(
+            model.cost(&link("AMS1", "FRA1", LinkProvenance::Reverse)),
+            7.0
+        );
+        assert_eq!(
+            model.cost(&link("AMS00", "FRA00", LinkProvenance::PublicBackbone)),
+            12.0
+        );
+        // Ramps and unpriced links only pay for latency
+        assert_eq!(
+            model.cost(&link("FRA", "FRA00", LinkProvenance::OnRamp)),
+            5.0
+        );
+        assert_eq!(
+            model.cost(&link("FRA1", "LON1", LinkProvenance::OriginalPrivate)),
+            5.0
+        );
+    }
+
+    #[test]
+    fn test_transit_cost_charges_public_volume() {
+        // 1 Gbps for an hour moves 450 GB
+        let gb = TransitCost::gb_per_period(BandwidthUnit::Gbps, Duration::from_secs(3600));
+        assert_eq!(gb, 450.0);
+        let model = TransitCost::new(0.5, gb, 0.01).with_city_pair_price("FRA", "AMS", 0.02);
+
+        assert_eq!(
+            model.cost(&link("AMS00", "FRA00", LinkProvenance::PublicBackbone)),
+            14.0
+        );
+        assert_eq!(
+            model.cost(&link("LON00", "FRA00", LinkProvenance::PublicBackbone)),
+            9.5
+        );
+        // Private links and ramps carry no transit
+        assert_eq!(
+            model.cost(&link("FRA1", "LON1", LinkProvenance::OriginalPrivate)),
+            5.0
+        );
+    }
+
+    #[test]
+    fn test_carbon_cost_adds_weighted_intensity() {
+        let intensity = CarbonIntensity::new(3.0)
+            .with_private_link("FRA1", "AMS1", 1.0)
+            .with_public_link("FRA", "AMS", 2.0);
+        let model = CarbonCost::new(intensity, 0.5);
+
+        assert_eq!(
+            model.cost(&link("AMS1", "FRA1", LinkProvenance::Reverse)),
+            10.5
+        );
+        assert_eq!(
+            model.cost(&link("AMS00", "FRA00", LinkProvenance::PublicBackbone)),
+            11.0
+        );
+        assert_eq!(
+            model.cost(&link("LON00", "FRA00", LinkProvenance::PublicBackbone)),
+            11.5
+        );
+
+        let monetary = model.with_base(Arc::new(MonetaryCost::new(0.1)));
+        assert_eq!(
+            monetary.cost(&link("FRA1", "LON1", LinkProvenance::OriginalPrivate)),
+            1.0
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! The repository's example networks, embedded in the crate so
+//! documentation, tests and demos need no files on disk.
+//!
+//! [`simple`] is the four-device network of the `simple` example;
+//! [`simulated`] and [`simulated_demand2`] are the simulated 17-device
+//! network of the `csv_demand1` and `csv_demand2` examples under their two
+//! demand tables.
+
+use crate::{
+    shapley::ShapleyInput,
+    tables::{TableSchema, read_input_table},
+    types::{Demand, Device, PrivateLink, PublicLink},
+};
+
+const PRIVATE_LINKS: &str = include_str!("../tests/private_links.csv");
+const DEVICES: &str = include_str!("../tests/devices.csv");
+const PUBLIC_LINKS: &str = include_str!("../tests/public_links.csv");
+const DEMAND1: &str = include_str!("../tests/demand1.csv");
+const DEMAND2: &str = include_str!("../tests/demand2.csv");
+
+/// Two operators' four devices between Singapore and Europe, with unicast
+/// and multicast demands
+pub fn simple() -> ShapleyInput {
+    let private = |a: &str, b: &str, latency| {
+        PrivateLink::new(a.to_string(), b.to_string(), latency, 10.0, 1.0, None)
+    };
+    let device =
+        |name: &str, operator: &str| Device::new(name.to_string(), 1, operator.to_string());
+    let public = |a: &str, b: &str, latency| PublicLink::new(a.to_string(), b.to_string(), latency);
+    let demand = |start: &str, end: &str, receivers, traffic, priority, kind, multicast| {
+        Demand::new(
+            start.to_string(),
+            end.to_string(),
+            receivers,
+            traffic,
+            priority,
+            kind,
+            multicast,
+        )
+    };
+    ShapleyInput {
+        private_links: vec![
+            private("SIN1", "FRA1", 50.0),
+            private("FRA1", "AMS1", 3.0),
+            private("FRA1", "LON1", 5.0),
+        ],
+        devices: vec![
+            device("SIN1", "Alpha"),
+            device("FRA1", "Alpha"),
+            device("AMS1", "Beta"),
+            device("LON1", "Beta"),
+        ],
+        demands: vec![
+            demand("SIN", "AMS", 1, 1.0, 1.0, 1, true),
+            demand("SIN", "LON", 5, 1.0, 2.0, 1, true),
+            demand("AMS", "LON", 2, 3.0, 1.0, 2, false),
+            demand("AMS", "FRA", 1, 3.0, 1.0, 2, false),
+        ],
+        public_links: vec![
+            public("SIN", "FRA", 100.0),
+            public("SIN", "AMS", 102.0),
+            public("FRA", "LON", 7.0),
+            public("FRA", "AMS", 5.0),
+        ],
+        operator_uptime: 0.98,
+        contiguity_bonus: 5.0,
+        demand_multiplier: 1.0,
+    }
+}
+
+/// The simulated network with demands out of Singapore
+pub fn simulated() -> ShapleyInput {
+    simulated_with(DEMAND1)
+}
+
+/// The simulated network with demands out of New York
+pub fn simulated_demand2() -> ShapleyInput {
+    simulated_with(DEMAND2)
+}
+
+fn simulated_with(demands: &str) -> ShapleyInput {
+    ShapleyInput {
+        private_links: embedded(PRIVATE_LINKS),
+        devices: embedded(DEVICES),
+        demands: embedded(demands),
+        public_links: embedded(PUBLIC_LINKS),
+        operator_uptime: 0.98,
+        contiguity_bonus: 5.0,
+        demand_multiplier: 1.2,
+    }
+}
+
+fn embedded<T: serde::de::DeserializeOwned + TableSchema>(table: &str) -> Vec<T> {
+    read_input_table(table.as_bytes()).expect("embedded datasets are valid tables")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_datasets() {
+        let output = simple().compute().unwrap();
+        assert_eq!(output.len(), 2);
+
+        let simulated = simulated();
+        assert_eq!(simulated.devices.len(), DEVICES.lines().count() - 1);
+        assert_eq!(
+            simulated.private_links.len(),
+            PRIVATE_LINKS.lines().count() - 1
+        );
+        assert_eq!(simulated.demands[0].start, "SIN");
+        assert_eq!(simulated_demand2().demands[0].start, "NYC");
+    }
+}
@@ -0,0 +1,188 @@
+//! Splitting the coalition LPs of one computation across machines.
+//!
+//! A coordinator builds a [`WorkPartition`] with
+//! [`ShapleyInput::work_partition`](crate::shapley::ShapleyInput::work_partition)
+//! and hands it to every worker. Each worker runs
+//! [`ShapleyInput::compute_shard`](crate::shapley::ShapleyInput::compute_shard)
+//! on the same input and options for its shard, and
+//! [`merge_partial_results`] turns the partial results back into the Shapley
+//! values [`ShapleyInput::compute_with`](crate::shapley::ShapleyInput::compute_with)
+//! would have produced.
+
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core_math,
+    error::{Result, ShapleyError},
+    shapley::{Operator, ShapleyOutput, SolveSummary, to_output},
+};
+
+/// Split of the coalition index space into `shards` contiguous ranges of
+/// nearly equal size. Coalition `i` is the set of operators `operators[k]`
+/// for which bit k of `i` is set.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkPartition {
+    /// Operators, sorted
+    pub operators: Vec<Operator>,
+    pub shards: usize,
+}
+
+impl WorkPartition {
+    /// Between 1 shard and one shard per coalition
+    pub fn new(operators: Vec<Operator>, shards: usize) -> Result<Self> {
+        let n_coalitions = 1usize << operators.len();
+        if shards == 0 || shards > n_coalitions {
+            return Err(ShapleyError::Validation(format!(
+                "Number of shards must be within [1, {n_coalitions}], got {shards}"
+            )));
+        }
+        Ok(Self { operators, shards })
+    }
+
+    pub fn n_coalitions(&self) -> usize {
+        1 << self.operators.len()
+    }
+
+    /// Coalition indices solved by `shard`
+    pub fn range(&self, shard: usize) -> Result<Range<usize>> {
+        if shard >= self.shards {
+            return Err(ShapleyError::Validation(format!(
+                "Shard {shard} is out of range for {} shards",
+                self.shards
+            )));
+        }
+        let n = self.n_coalitions();
+        Ok(shard * n / self.shards..(shard + 1) * n / self.shards)
+    }
+}
+
+/// Raw coalition LP values of one shard, see
+/// [`ShapleyInput::compute_shard`](crate::shapley::ShapleyInput::compute_shard)
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialResult {
+    pub partition: WorkPartition,
+    pub shard: usize,
+    pub operator_uptime: f64,
+    /// One set of values per demand time slot and SRLG outage state
+    pub scenarios: Vec<PartialScenario>,
+    pub solve_summary: SolveSummary,
+}
+
+/// Coalition values of one shard in one scenario
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialScenario {
+    pub probability: f64,
+    /// Negated optimal objective of each coalition in the shard's range;
+    /// `None` when infeasible
+    pub svalue: Vec<Option<f64>>,
+}
+
+/// Shapley values from the partial results of every shard of one partition,
+/// given in any order
+pub fn merge_partial_results(partials: &[PartialResult]) -> Result<ShapleyOutput> {
+    let Some(first) = partials.first() else {
+        return Err(ShapleyError::DataInconsistency(
+            "No partial results to merge".to_string(),
+        ));
+    };
+    let partition = &first.partition;
+    let n_operators = partition.operators.len();
+
+    let mut seen = vec![false; partition.shards];
+    for partial in partials {
+        if partial.partition != *partition
+            || partial.operator_uptime != first.operator_uptime
+            || partial.scenarios.len() != first.scenarios.len()
+        {
+            return Err(ShapleyError::DataInconsistency(format!(
+                "Partial result of shard {} belongs to a different computation",
+                partial.shard
+            )));
+        }
+        let range = partition.range(partial.shard)?;
+        if std::mem::replace(&mut seen[partial.shard], true) {
+            return Err(ShapleyError::DataInconsistency(format!(
+                "Shard {} is given more than once",
+                partial.shard
+            )));
+        }
+        for (scenario, reference) in partial.scenarios.iter().zip(&first.scenarios) {
+            if scenario.probability != reference.probability || scenario.svalue.len() != range.len()
+            {
+                return Err(ShapleyError::DataInconsistency(format!(
+                    "Partial result of shard {} does not match its range",
+                    partial.shard
+                )));
+            }
+        }
+    }
+    if let Some(missing) = seen.iter().position(|&s| !s) {
+        return Err(ShapleyError::DataInconsistency(format!(
+            "Shard {missing} of {} is missing",
+            partition.shards
+        )));
+    }
+
+    // Expected values under operator uptime, weighted by scenario probability
+    let mut values = core_math::ScenarioSum::new(partition.n_coalitions());
+    for (s, scenario) in first.scenarios.iter().enumerate() {
+        let mut svalue = vec![None; partition.n_coalitions()];
+        for partial in partials {
+            let range = partition.range(partial.shard)?;
+            svalue[range].clone_from_slice(&partial.scenarios[s].svalue);
+        }
+        let adjusted =
+            core_math::uptime_adjusted_values(&svalue, n_operators, first.operator_uptime);
+        values.add(scenario.probability, &adjusted);
+    }
+
+    let shapley_values = core_math::shapley_values(&values.finish(), n_operators);
+    Ok(to_output(
+        partition.operators.iter().cloned(),
+        shapley_values,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        options::ShapleyOptions,
+        shapley::testing::{RandomInputConfig, random_input},
+    };
+
+    #[test]
+    fn test_merged_shards_match_compute() {
+        let config = RandomInputConfig {
+            operator_uptime: 0.95,
+            ..Default::default()
+        };
+        let input = random_input(11, &config);
+        let options = ShapleyOptions::default();
+        let expected = input.compute_with(&options).unwrap();
+
+        let partition = input.work_partition(&options, 3).unwrap();
+        assert_eq!(partition.range(0).unwrap(), 0..2);
+        assert_eq!(partition.range(2).unwrap(), 5..8);
+        let mut partials: Vec<PartialResult> = (0..3)
+            .map(|shard| input.compute_shard(&options, &partition, shard).unwrap())
+            .collect();
+        partials.reverse();
+        let merged = merge_partial_results(&partials).unwrap();
+        for (op, value) in &expected {
+            assert!((merged[op].value - value.value).abs() < 1e-9);
+        }
+
+        partials.pop();
+        assert!(merge_partial_results(&partials).is_err());
+        partials.push(partials[0].clone());
+        assert!(merge_partial_results(&partials).is_err());
+        assert!(WorkPartition::new(partition.operators.clone(), 9).is_err());
+    }
+}
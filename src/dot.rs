@@ -0,0 +1,146 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{
+    types::{ConsolidatedLink, ConsolidationReport, LinkProvenance},
+    utils::device_suffix,
+};
+
+/// Edge colors assigned to operators in name order, cycling when exhausted
+const PALETTE: [&str; 10] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+    "#bcbd22", "#7f7f7f",
+];
+const PUBLIC_COLOR: &str = "gray50";
+
+impl ConsolidationReport {
+    /// Graphviz rendering of the consolidated network.
+    ///
+    /// Devices are boxes, public internet nodes (`{city}00`) grey ellipses and
+    /// demand endpoints (`{city}`) diamonds. Private edges take their operator's
+    /// colour; public backbone edges are dashed grey and ramps dotted. Edges are
+    /// labelled with latency and, when capacity-limited, bandwidth.
+    pub fn to_dot(&self) -> String {
+        let operators: BTreeSet<&str> = self
+            .links
+            .iter()
+            .flat_map(|l| [l.operator1.as_str(), l.operator2.as_str()])
+            .filter(|&op| op != "Public")
+            .collect();
+        let colors: BTreeMap<&str, &str> = operators
+            .into_iter()
+            .zip(PALETTE.iter().cycle().copied())
+            .collect();
+
+        let mut nodes: BTreeMap<&str, &str> = BTreeMap::new();
+        for link in &self.links {
+            for (device, operator) in [
+                (&link.device1, &link.operator1),
+                (&link.device2, &link.operator2),
+            ] {
+                let shape = match device_suffix(device) {
+                    "00" if operator == "Public" => "shape=ellipse, style=filled, fillcolor=gray90",
+                    "" => "shape=diamond",
+                    _ => "shape=box",
+                };
+                nodes.entry(device.as_str()).or_insert(shape);
+            }
+        }
+
+        let mut dot = String::from("digraph network {\n    rankdir=LR;\n");
+        for (node, shape) in &nodes {
+            let _ = writeln!(dot, "    {} [{shape}];", quote(node));
+        }
+        for link in &self.links {
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [{}];",
+                quote(&link.device1),
+                quote(&link.device2),
+                edge_attributes(link, &colors)
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn edge_attributes(link: &ConsolidatedLink, colors: &BTreeMap<&str, &str>) -> String {
+    let color = colors
+        .get(link.operator1.as_str())
+        .copied()
+        .unwrap_or(PUBLIC_COLOR);
+    let style = match link.provenance {
+        LinkProvenance::PublicBackbone => "dashed",
+        LinkProvenance::OnRamp | LinkProvenance::OffRamp => "dotted",
+        _ => "solid",
+    };
+    let mut label = format!("{}", link.latency);
+    if link.bandwidth > 0.0 {
+        let _ = write!(label, " / {}", link.bandwidth);
+    }
+    if link.link_type != 0 {
+        let _ = write!(label, " (type {})", link.link_type);
+    }
+    format!("color=\"{color}\", style={style}, label={}", quote(&label))
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(
+        device1: &str,
+        device2: &str,
+        operator: &str,
+        provenance: LinkProvenance,
+    ) -> ConsolidatedLink {
+        ConsolidatedLink {
+            device1: device1.to_string(),
+            device2: device2.to_string(),
+            latency: 5.0,
+            bandwidth: if operator == "Public" { 0.0 } else { 10.0 },
+            operator1: operator.to_string(),
+            operator2: operator.to_string(),
+            shared: 0,
+            link_type: 0,
+            provenance,
+            multicast_capable: false,
+        }
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let report = ConsolidationReport {
+            links: vec![
+                link("FRA1", "AMS1", "Alpha", LinkProvenance::OriginalPrivate),
+                link("FRA00", "AMS00", "Public", LinkProvenance::PublicBackbone),
+                link("FRA", "FRA00", "Public", LinkProvenance::OnRamp),
+                link("ZÜR", "ZÜR00", "Public", LinkProvenance::OnRamp),
+            ],
+            demands: vec![],
+        };
+        let dot = report.to_dot();
+
+        assert!(dot.starts_with("digraph network {"));
+        assert!(dot.contains("\"FRA1\" [shape=box];"));
+        assert!(dot.contains("\"FRA00\" [shape=ellipse"));
+        assert!(dot.contains("\"FRA\" [shape=diamond];"));
+        // City codes are three characters, not three bytes
+        assert!(dot.contains("\"ZÜR\" [shape=diamond];"));
+        assert!(dot.contains("\"ZÜR00\" [shape=ellipse"));
+        assert!(
+            dot.contains(
+                "\"FRA1\" -> \"AMS1\" [color=\"#1f77b4\", style=solid, label=\"5 / 10\"];"
+            )
+        );
+        assert!(dot.contains("\"FRA00\" -> \"AMS00\" [color=\"gray50\", style=dashed"));
+        assert!(dot.contains("\"FRA\" -> \"FRA00\" [color=\"gray50\", style=dotted"));
+    }
+}
@@ -1,3 +1,7 @@
+use std::fmt::{Display, Formatter};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, ShapleyError>;
@@ -30,4 +34,280 @@ pub enum ShapleyError {
 
     #[error("Matrix construction error: {0}")]
     MatrixConstructionError(String),
+
+    #[error("Thread pool error: {0}")]
+    ThreadPool(String),
+
+    #[error("Computation cancelled")]
+    Cancelled,
+
+    #[error("I/O error: {0}")]
+    Io(String),
+
+    /// Another error with where it arose: returned by the table readers, by
+    /// [`ShapleyInput::validate`](crate::shapley::ShapleyInput::validate) and
+    /// for coalition LPs that could not be solved. Input checks inside a
+    /// computation return the bare error; see [`ShapleyError::root`].
+    #[error("{context}: {source}")]
+    WithContext {
+        context: ErrorContext,
+        #[source]
+        source: Box<ShapleyError>,
+    },
+}
+
+impl ShapleyError {
+    /// The error without any [`ErrorContext`] around it, for matching on the
+    /// kind of failure
+    pub fn root(&self) -> &ShapleyError {
+        match self {
+            Self::WithContext { source, .. } => source.root(),
+            other => other,
+        }
+    }
+
+    /// The error without any [`ErrorContext`] around it
+    pub fn into_root(self) -> ShapleyError {
+        match self {
+            Self::WithContext { source, .. } => source.into_root(),
+            other => other,
+        }
+    }
+
+    /// Where the error arose, if known
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Stable identifier of the kind of failure
+    pub fn code(&self) -> ErrorCode {
+        match self.root() {
+            Self::Validation(_) => ErrorCode::Validation,
+            Self::LpSolver(_) => ErrorCode::LpSolver,
+            Self::DataInconsistency(_) => ErrorCode::DataInconsistency,
+            Self::TooManyOperators { .. } => ErrorCode::TooManyOperators,
+            Self::InvalidCityLabel(_) => ErrorCode::InvalidCityLabel,
+            Self::MissingDevice(_) => ErrorCode::MissingDevice,
+            Self::UnreachableDemandNode(_) => ErrorCode::UnreachableDemandNode,
+            Self::NumericalError(_) => ErrorCode::Numerical,
+            Self::MatrixConstructionError(_) => ErrorCode::MatrixConstruction,
+            Self::ThreadPool(_) => ErrorCode::ThreadPool,
+            Self::Cancelled => ErrorCode::Cancelled,
+            Self::Io(_) => ErrorCode::Io,
+            Self::WithContext { .. } => unreachable!("root() strips context"),
+        }
+    }
+
+    /// Attach `context`, filling in fields missing from any context the
+    /// error already carries
+    pub(crate) fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            Self::WithContext {
+                context: inner,
+                source,
+            } => Self::WithContext {
+                context: inner.or(context),
+                source,
+            },
+            source => Self::WithContext {
+                context,
+                source: Box::new(source),
+            },
+        }
+    }
+}
+
+/// Stable, machine-readable kind of a [`ShapleyError`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    Validation,
+    LpSolver,
+    DataInconsistency,
+    TooManyOperators,
+    InvalidCityLabel,
+    MissingDevice,
+    UnreachableDemandNode,
+    Numerical,
+    MatrixConstruction,
+    ThreadPool,
+    Cancelled,
+    Io,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Validation => "validation",
+            Self::LpSolver => "lp_solver",
+            Self::DataInconsistency => "data_inconsistency",
+            Self::TooManyOperators => "too_many_operators",
+            Self::InvalidCityLabel => "invalid_city_label",
+            Self::MissingDevice => "missing_device",
+            Self::UnreachableDemandNode => "unreachable_demand_node",
+            Self::Numerical => "numerical",
+            Self::MatrixConstruction => "matrix_construction",
+            Self::ThreadPool => "thread_pool",
+            Self::Cancelled => "cancelled",
+            Self::Io => "io",
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Input table an error points into
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputTable {
+    PrivateLinks,
+    Devices,
+    Demands,
+    PublicLinks,
+}
+
+impl Display for InputTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::PrivateLinks => "private links",
+            Self::Devices => "devices",
+            Self::Demands => "demands",
+            Self::PublicLinks => "public links",
+        })
+    }
+}
+
+/// How a coalition LP ended when its solve failed
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SolverStatus {
+    Infeasible,
+    /// Iteration or time limit reached before optimality
+    LimitReached,
+    /// The solver gave up, e.g. numerically
+    Failed,
+}
+
+/// Where in the inputs or in the computation an error arose. Fields that do
+/// not apply are `None`.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    pub table: Option<InputTable>,
+    /// Zero-based row in `table`
+    pub row: Option<usize>,
+    /// Field of the row, named as in the input types
+    pub column: Option<&'static str>,
+    pub device: Option<String>,
+    /// Coalition bitmask (bit k ↔ k-th operator by name)
+    pub coalition: Option<usize>,
+    pub solver_status: Option<SolverStatus>,
+}
+
+impl ErrorContext {
+    /// Row `row` of `table`
+    pub(crate) fn row(table: InputTable, row: usize) -> Self {
+        Self {
+            table: Some(table),
+            row: Some(row),
+            ..Default::default()
+        }
+    }
+
+    /// Field `column` of row `row` of `table`
+    pub(crate) fn cell(table: InputTable, row: usize, column: &'static str) -> Self {
+        Self {
+            column: Some(column),
+            ..Self::row(table, row)
+        }
+    }
+
+    /// The solve of coalition `coalition`
+    pub(crate) fn coalition(coalition: usize, solver_status: SolverStatus) -> Self {
+        Self {
+            coalition: Some(coalition),
+            solver_status: Some(solver_status),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn with_device(mut self, device: &str) -> Self {
+        self.device = Some(device.to_string());
+        self
+    }
+
+    /// Fields of `self`, with those missing taken from `other`
+    fn or(self, other: Self) -> Self {
+        Self {
+            table: self.table.or(other.table),
+            row: self.row.or(other.row),
+            column: self.column.or(other.column),
+            device: self.device.or(other.device),
+            coalition: self.coalition.or(other.coalition),
+            solver_status: self.solver_status.or(other.solver_status),
+        }
+    }
+}
+
+impl Display for ErrorContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(table) = self.table {
+            parts.push(match self.row {
+                Some(row) => format!("{table} row {row}"),
+                None => table.to_string(),
+            });
+        }
+        if let Some(column) = self.column {
+            parts.push(format!("column {column}"));
+        }
+        if let Some(device) = &self.device {
+            parts.push(format!("device {device}"));
+        }
+        if let Some(coalition) = self.coalition {
+            parts.push(format!("coalition {coalition:#b}"));
+        }
+        if let Some(status) = self.solver_status {
+            parts.push(format!("solver {status:?}"));
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use super::*;
+
+    #[test]
+    fn test_context_wraps_source() {
+        let err = ShapleyError::MissingDevice("LON1".into()).with_context(
+            ErrorContext::cell(InputTable::PrivateLinks, 2, "device2").with_device("LON1"),
+        );
+        assert_eq!(err.code(), ErrorCode::MissingDevice);
+        assert!(matches!(err.root(), ShapleyError::MissingDevice(d) if d == "LON1"));
+        assert_eq!(err.context().unwrap().row, Some(2));
+        assert_eq!(err.source().unwrap().to_string(), "Missing device: LON1");
+        assert_eq!(
+            err.to_string(),
+            "private links row 2, column device2, device LON1: Missing device: LON1"
+        );
+
+        // Wrapping again fills in the gaps instead of nesting
+        let err = err.with_context(ErrorContext::row(InputTable::Demands, 7).with_device("PAR1"));
+        let context = err.context().unwrap();
+        assert_eq!(context.table, Some(InputTable::PrivateLinks));
+        assert_eq!(context.row, Some(2));
+        assert_eq!(context.device.as_deref(), Some("LON1"));
+        assert!(matches!(err.root(), ShapleyError::MissingDevice(_)));
+    }
 }
@@ -0,0 +1,200 @@
+//! Running a computation off the caller's thread, for async services
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+use crate::{
+    error::{Result, ShapleyError},
+    options::{CancellationToken, ShapleyOptions},
+    shapley::{ShapleyInput, ShapleyOutput},
+};
+
+/// A computation running on its own thread. Resolves to its result; dropping
+/// it before then cancels the computation at the next batch of solves.
+///
+/// The thread only drives the computation: coalition solves run in the pool
+/// chosen by `options.resources`, and starting one thread is negligible next
+/// to the solves it waits on. A fixed pool of driving threads would instead
+/// queue computations behind each other, which is
+/// [`ComputeManager`](crate::manager::ComputeManager)'s job.
+pub struct ComputeFuture<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    cancellation: CancellationToken,
+}
+
+struct Shared<T> {
+    result: Option<Result<T>>,
+    finished: bool,
+    waker: Option<Waker>,
+}
+
+impl<T: Send + 'static> ComputeFuture<T> {
+    /// Run `f` on a new thread with `options`, under a token of the future's
+    /// own that also follows the caller's
+    fn spawn(
+        options: &ShapleyOptions,
+        f: impl FnOnce(&ShapleyOptions) -> Result<T> + Send + 'static,
+    ) -> Self {
+        let cancellation = match &options.cancellation {
+            Some(caller) => caller.child(),
+            None => CancellationToken::new(),
+        };
+        let options = ShapleyOptions {
+            cancellation: Some(cancellation.clone()),
+            ..options.clone()
+        };
+        let shared = Arc::new(Mutex::new(Shared {
+            result: None,
+            finished: false,
+            waker: None,
+        }));
+
+        let finish = {
+            let shared = Arc::clone(&shared);
+            move |result: Result<T>| {
+                let mut shared = shared.lock().unwrap_or_else(|e| e.into_inner());
+                shared.result = Some(result);
+                shared.finished = true;
+                if let Some(waker) = shared.waker.take() {
+                    waker.wake();
+                }
+            }
+        };
+        let finish_on_error = finish.clone();
+        let spawned = thread::Builder::new()
+            .name("network-shapley".to_string())
+            .spawn(move || finish(f(&options)));
+        if let Err(e) = spawned {
+            finish_on_error(Err(ShapleyError::ThreadPool(e.to_string())));
+        }
+
+        Self {
+            shared,
+            cancellation,
+        }
+    }
+}
+
+impl<T> Future for ComputeFuture<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        match shared.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for ComputeFuture<T> {
+    fn drop(&mut self) {
+        let shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        if !shared.finished {
+            self.cancellation.cancel();
+        }
+    }
+}
+
+impl ShapleyInput {
+    /// [`compute_with`](Self::compute_with) on a thread of its own, so an
+    /// async executor is never blocked. Coalition solves run in the pool
+    /// chosen by `options.resources`; cancelling `options.cancellation` or
+    /// dropping the future stops them at the next batch.
+    pub fn compute_async(&self, options: &ShapleyOptions) -> ComputeFuture<ShapleyOutput> {
+        let input = self.clone();
+        ComputeFuture::spawn(options, move |options| input.compute_with(options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::Wake;
+
+    use super::*;
+    use crate::types::{Demand, Device, PrivateLink, PublicLink};
+
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn test_compute_async() {
+        let input = ShapleyInput {
+            private_links: vec![PrivateLink::new(
+                "NYC1".into(),
+                "LON1".into(),
+                10.0,
+                100.0,
+                1.0,
+                None,
+            )],
+            devices: vec![
+                Device::new("NYC1".into(), 100, "Alpha".into()),
+                Device::new("LON1".into(), 100, "Beta".into()),
+            ],
+            demands: vec![Demand::new(
+                "NYC".into(),
+                "LON".into(),
+                1,
+                5.0,
+                1.0,
+                1,
+                false,
+            )],
+            public_links: vec![PublicLink::new("NYC".into(), "LON".into(), 100.0)],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let options = ShapleyOptions::default();
+        let values = block_on(input.compute_async(&options)).unwrap();
+        assert_eq!(values, input.compute_with(&options).unwrap());
+
+        // The future's own token is cancelled on drop, never the caller's
+        block_on(input.compute_async(&options)).unwrap();
+        let shared = ShapleyOptions {
+            cancellation: Some(CancellationToken::new()),
+            ..Default::default()
+        };
+        drop(input.compute_async(&shared));
+        assert!(!shared.cancellation.as_ref().unwrap().is_cancelled());
+        assert_eq!(block_on(input.compute_async(&shared)).unwrap(), values);
+        assert_eq!(block_on(input.compute_async(&shared)).unwrap(), values);
+
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        let cancelled = ShapleyOptions {
+            cancellation: Some(cancellation),
+            ..Default::default()
+        };
+        assert!(matches!(
+            block_on(input.compute_async(&cancelled)),
+            Err(ShapleyError::Cancelled)
+        ));
+    }
+}
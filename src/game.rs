@@ -0,0 +1,571 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{core_math, shapley::Operator};
+
+/// Cap on the number of violations retained per property. Counting continues
+/// past the cap so `violation_count` is always exact.
+const MAX_REPORTED_VIOLATIONS: usize = 100;
+
+/// Relative tolerance below which a shortfall is attributed to LP round-off
+/// rather than a genuine violation.
+const VIOLATION_TOLERANCE: f64 = 1e-9;
+
+/// A pair of coalitions for which a game property fails.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub first: Vec<Operator>,
+    pub second: Vec<Operator>,
+    /// How far the left-hand side falls below the right-hand side of the
+    /// property's inequality (always positive).
+    pub shortfall: f64,
+}
+
+/// Outcome of checking one game property over all relevant coalition pairs.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropertyCheck {
+    /// Number of coalition pairs checked (pairs with an infeasible member are skipped).
+    pub pairs_checked: usize,
+    /// Total number of violating pairs.
+    pub violation_count: usize,
+    /// The violating pairs with the largest shortfall, at most 100 of them.
+    pub violations: Vec<Violation>,
+}
+
+impl PropertyCheck {
+    pub fn holds(&self) -> bool {
+        self.violation_count == 0
+    }
+
+    fn record(&mut self, lhs: f64, rhs: f64, first: usize, second: usize, operators: &[Operator]) {
+        self.pairs_checked += 1;
+        let shortfall = rhs - lhs;
+        if shortfall <= VIOLATION_TOLERANCE * (1.0 + lhs.abs().max(rhs.abs())) {
+            return;
+        }
+        self.violation_count += 1;
+        self.violations.push(Violation {
+            first: members(first, operators),
+            second: members(second, operators),
+            shortfall,
+        });
+        if self.violations.len() > 2 * MAX_REPORTED_VIOLATIONS {
+            self.truncate();
+        }
+    }
+
+    fn truncate(&mut self) {
+        self.violations
+            .sort_by(|a, b| b.shortfall.total_cmp(&a.shortfall));
+        self.violations.truncate(MAX_REPORTED_VIOLATIONS);
+    }
+}
+
+/// Superadditivity and convexity of the coalition game induced by the LP values
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameProperties {
+    /// v(S ∪ T) + v(∅) ≥ v(S) + v(T) for all disjoint, non-empty S and T.
+    pub superadditivity: PropertyCheck,
+    /// v(S ∪ T) + v(S ∩ T) ≥ v(S) + v(T) for all S and T. Checked through the
+    /// equivalent local condition on S ∪ {i} and S ∪ {j}, which are the pairs reported.
+    pub convexity: PropertyCheck,
+}
+
+impl GameProperties {
+    pub fn is_superadditive(&self) -> bool {
+        self.superadditivity.holds()
+    }
+
+    pub fn is_convex(&self) -> bool {
+        self.convexity.holds()
+    }
+}
+
+/// Pairwise Shapley interaction indices between operators.
+///
+/// `interaction[i][j]` is the average extra value operators i and j create
+/// together over what they add separately, across all coalitions of the
+/// others. Positive means super-additive (complementary networks), negative
+/// means the pair largely duplicates each other. With `operator_uptime = 1`,
+/// an infeasible coalition makes the pairs it touches non-finite.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SynergyMatrix {
+    pub operators: Vec<Operator>,
+    pub interaction: Vec<Vec<f64>>,
+}
+
+impl SynergyMatrix {
+    pub(crate) fn new(operators: Vec<Operator>, coalition_values: &[f64]) -> Self {
+        let n = operators.len();
+        let flat = core_math::interaction_indices(coalition_values, n);
+        let interaction = flat.chunks(n.max(1)).map(<[f64]>::to_vec).collect();
+        Self {
+            operators,
+            interaction: if n == 0 { Vec::new() } else { interaction },
+        }
+    }
+
+    /// Interaction index of two operators, `None` if either is unknown
+    pub fn get(&self, first: &str, second: &str) -> Option<f64> {
+        let i = self.operators.iter().position(|op| op == first)?;
+        let j = self.operators.iter().position(|op| op == second)?;
+        Some(self.interaction[i][j])
+    }
+
+    /// Every unordered pair with its index, most synergistic first
+    pub fn ranked_pairs(&self) -> Vec<(Operator, Operator, f64)> {
+        let n = self.operators.len();
+        let mut pairs: Vec<(Operator, Operator, f64)> = (0..n)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .map(|(i, j)| {
+                (
+                    self.operators[i].clone(),
+                    self.operators[j].clone(),
+                    self.interaction[i][j],
+                )
+            })
+            .collect();
+        pairs.sort_by(|a, b| b.2.total_cmp(&a.2));
+        pairs
+    }
+}
+
+/// Check superadditivity and convexity from the coalition value vector.
+///
+/// `svalue[i]` is the value of the coalition whose members are the set bits of
+/// `i` (bit k ↔ `operators[k]`); `None` marks an infeasible coalition, and any
+/// pair touching one is skipped. Superadditivity enumerates every disjoint pair,
+/// so this is O(3^n); convexity is O(n² 2^n).
+pub(crate) fn game_properties(operators: &[Operator], svalue: &[Option<f64>]) -> GameProperties {
+    let n = operators.len();
+    let n_coal = 1usize << n;
+    let full = n_coal - 1;
+    let mut properties = GameProperties::default();
+
+    let Some(empty) = svalue[0] else {
+        return properties;
+    };
+
+    // Superadditivity: every non-empty T ⊆ complement(S) with T > S, so each
+    // unordered pair is visited once.
+    for s in 1..n_coal {
+        let Some(vs) = svalue[s] else { continue };
+        let rest = full & !s;
+        let mut t = rest;
+        while t > s {
+            if let (Some(vt), Some(vu)) = (svalue[t], svalue[s | t]) {
+                properties
+                    .superadditivity
+                    .record(vu + empty, vs + vt, s, t, operators);
+            }
+            t = (t - 1) & rest;
+        }
+    }
+
+    // Convexity (supermodularity): v(S+i+j) + v(S) ≥ v(S+i) + v(S+j)
+    for s in 0..n_coal {
+        let Some(vs) = svalue[s] else { continue };
+        for i in (0..n).filter(|&i| s & (1 << i) == 0) {
+            for j in (i + 1..n).filter(|&j| s & (1 << j) == 0) {
+                let (si, sj) = (s | 1 << i, s | 1 << j);
+                if let (Some(vi), Some(vj), Some(vij)) = (svalue[si], svalue[sj], svalue[si | sj]) {
+                    properties
+                        .convexity
+                        .record(vij + vs, vi + vj, si, sj, operators);
+                }
+            }
+        }
+    }
+
+    properties.superadditivity.truncate();
+    properties.convexity.truncate();
+    properties
+}
+
+pub(crate) fn members(coalition: usize, operators: &[Operator]) -> Vec<Operator> {
+    Coalition::from_index(coalition)
+        .names(operators)
+        .cloned()
+        .collect()
+}
+
+/// Every coalition of `n` operators, enumerated lazily in index order.
+/// Coalition `i` holds operator k when bit k of `i` is set, the layout of
+/// every coalition-indexed vector in this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Coalitions {
+    n_operators: usize,
+}
+
+impl Coalitions {
+    /// At most 31 operators, the most a bitmask can address
+    pub fn new(n_operators: usize) -> Self {
+        assert!(
+            n_operators < 32,
+            "At most 31 operators fit a coalition bitmask"
+        );
+        Self { n_operators }
+    }
+
+    pub fn n_operators(&self) -> usize {
+        self.n_operators
+    }
+
+    /// Number of coalitions, 2^n
+    pub fn len(&self) -> usize {
+        1 << self.n_operators
+    }
+
+    /// Always false: the empty coalition is one
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Every coalition with its index, from the empty coalition to the grand one
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (usize, Coalition)> + use<> {
+        (0..self.len()).map(|i| (i, Coalition::from_index(i)))
+    }
+
+    /// The coalitions of `size` members, in index order
+    pub fn of_size(&self, size: usize) -> impl Iterator<Item = (usize, Coalition)> + use<> {
+        self.iter().filter(move |(_, c)| c.size() == size)
+    }
+
+    pub fn grand(&self) -> Coalition {
+        Coalition::from_index(self.len() - 1)
+    }
+}
+
+/// Members of one coalition as a bitset over operator indices
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Coalition(pub u32);
+
+impl Coalition {
+    pub fn from_index(index: usize) -> Self {
+        Self(index as u32)
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub fn contains(self, operator: usize) -> bool {
+        self.0 & (1 << operator) != 0
+    }
+
+    pub fn size(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn with(self, operator: usize) -> Self {
+        Self(self.0 | 1 << operator)
+    }
+
+    pub fn without(self, operator: usize) -> Self {
+        Self(self.0 & !(1 << operator))
+    }
+
+    /// Indices of the members, ascending
+    pub fn members(self) -> impl Iterator<Item = usize> {
+        (0..u32::BITS as usize).filter(move |&k| self.contains(k))
+    }
+
+    /// Names of the members, with `operators[k]` the name of operator k
+    pub fn names(self, operators: &[Operator]) -> impl Iterator<Item = &Operator> {
+        operators
+            .iter()
+            .enumerate()
+            .filter(move |&(k, _)| self.contains(k))
+            .map(|(_, op)| op)
+    }
+}
+
+/// A coalition an operator joined and what joining it was worth
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarginalContribution {
+    /// The coalition before the operator joined
+    pub coalition: Vec<Operator>,
+    /// Value the operator added
+    pub delta: f64,
+    /// Weight of this term in the operator's Shapley value
+    pub weight: f64,
+}
+
+/// For each operator, the coalitions where its marginal contribution was
+/// largest: the terms that drive its Shapley value
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MarginalReport {
+    pub top: BTreeMap<Operator, Vec<MarginalContribution>>,
+}
+
+impl MarginalReport {
+    pub(crate) fn new(operators: &[Operator], coalition_values: &[f64], k: usize) -> Self {
+        let n = operators.len();
+        let top = operators
+            .iter()
+            .enumerate()
+            .map(|(i, op)| {
+                let terms = core_math::top_marginal_terms(coalition_values, n, i, k)
+                    .into_iter()
+                    .map(|term| MarginalContribution {
+                        coalition: members(term.without, operators),
+                        delta: term.delta,
+                        weight: term.weight,
+                    })
+                    .collect();
+                (op.clone(), terms)
+            })
+            .collect();
+        Self { top }
+    }
+}
+
+/// Spread of an operator's marginal contributions among the coalitions of
+/// one size
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SizeMoments {
+    /// Members of the coalitions joined
+    pub size: usize,
+    pub mean: f64,
+    pub variance: f64,
+}
+
+/// An operator's marginal contributions weighted as in its Shapley value,
+/// so `mean` is the Shapley value. A small variance means the value holds in
+/// most coalitions; a large one that it comes from a few.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContributionStats {
+    pub mean: f64,
+    pub variance: f64,
+    /// Mean and variance by the size of the coalition joined, smallest first
+    pub by_size: Vec<SizeMoments>,
+}
+
+/// [`ContributionStats`] of every operator
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContributionReport {
+    pub operators: BTreeMap<Operator, ContributionStats>,
+}
+
+impl ContributionReport {
+    pub(crate) fn new(operators: &[Operator], coalition_values: &[f64]) -> Self {
+        let n = operators.len();
+        let operators = operators
+            .iter()
+            .enumerate()
+            .map(|(i, op)| {
+                let by_size: Vec<SizeMoments> =
+                    core_math::marginal_moments_by_size(coalition_values, n, i)
+                        .into_iter()
+                        .enumerate()
+                        .map(|(size, (mean, variance))| SizeMoments {
+                            size,
+                            mean,
+                            variance,
+                        })
+                        .collect();
+                // Every size is equally likely, then every coalition of it
+                let mean = by_size.iter().map(|m| m.mean).sum::<f64>() / n as f64;
+                let second_moment = by_size
+                    .iter()
+                    .map(|m| m.variance + m.mean * m.mean)
+                    .sum::<f64>()
+                    / n as f64;
+                let stats = ContributionStats {
+                    mean,
+                    variance: (second_moment - mean * mean).max(0.0),
+                    by_size,
+                };
+                (op.clone(), stats)
+            })
+            .collect();
+        Self { operators }
+    }
+}
+
+/// Tightest bounds on every coalition value implied by the values known so
+/// far and monotonicity, i.e. v(S) <= v(T) whenever S is a subset of T.
+/// Coalition LP values are monotone because a larger coalition only adds
+/// links. Infeasible coalitions are known as -inf. Returns the lower and upper
+/// bound of each coalition.
+pub(crate) fn monotone_bounds(known: &[Option<f64>], n: usize) -> (Vec<f64>, Vec<f64>) {
+    let size = 1usize << n;
+    let mut lower = vec![f64::NEG_INFINITY; size];
+    for s in 0..size {
+        lower[s] = known[s].unwrap_or_else(|| {
+            (0..n)
+                .filter(|i| s & (1 << i) != 0)
+                .map(|i| lower[s ^ (1 << i)])
+                .fold(f64::NEG_INFINITY, f64::max)
+        });
+    }
+    let mut upper = vec![f64::INFINITY; size];
+    for s in (0..size).rev() {
+        upper[s] = known[s].unwrap_or_else(|| {
+            (0..n)
+                .filter(|i| s & (1 << i) == 0)
+                .map(|i| upper[s | (1 << i)])
+                .fold(f64::INFINITY, f64::min)
+        });
+    }
+    (lower, upper)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coalitions_iter() {
+        let coalitions = Coalitions::new(3);
+        assert_eq!(coalitions.len(), 8);
+        let all: Vec<(usize, Coalition)> = coalitions.iter().collect();
+        assert_eq!(all[5], (5, Coalition(0b101)));
+        assert_eq!(all[5].1.members().collect::<Vec<_>>(), vec![0, 2]);
+        let pairs: Vec<usize> = coalitions.of_size(2).map(|(i, _)| i).collect();
+        assert_eq!(pairs, vec![3, 5, 6]);
+
+        let operators: Vec<Operator> = vec!["A".into(), "B".into(), "C".into()];
+        let grand = coalitions.grand();
+        assert_eq!(
+            grand.without(1).names(&operators).collect::<Vec<_>>(),
+            ["A", "C"]
+        );
+        assert_eq!(Coalition::default().with(1), Coalition(0b010));
+        assert_eq!(members(6, &operators), vec!["B", "C"]);
+    }
+
+    #[test]
+    fn test_monotone_bounds() {
+        // Only the empty and grand coalitions of three operators are known
+        let mut known = vec![None; 8];
+        known[0] = Some(f64::NEG_INFINITY);
+        known[7] = Some(4.0);
+        let (lower, upper) = monotone_bounds(&known, 3);
+        assert_eq!((lower[3], upper[3]), (f64::NEG_INFINITY, 4.0));
+
+        known[3] = Some(2.0);
+        known[6] = Some(f64::NEG_INFINITY);
+        let (lower, upper) = monotone_bounds(&known, 3);
+        assert_eq!((lower[7], lower[3], upper[1]), (4.0, 2.0, 2.0));
+        // A subset of an infeasible coalition is infeasible
+        assert_eq!(upper[2], f64::NEG_INFINITY);
+    }
+
+    fn ops(n: usize) -> Vec<Operator> {
+        ["A", "B", "C"][..n].iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_convex_game_has_no_violations() {
+        // v(S) = |S|², which is supermodular
+        let svalue: Vec<Option<f64>> = (0..8u32)
+            .map(|s| Some((s.count_ones() as f64).powi(2)))
+            .collect();
+        let props = game_properties(&ops(3), &svalue);
+
+        assert!(props.is_superadditive());
+        assert!(props.is_convex());
+        assert_eq!(props.superadditivity.pairs_checked, 6);
+        assert_eq!(props.convexity.pairs_checked, 6);
+    }
+
+    #[test]
+    fn test_subadditive_pair_is_reported() {
+        // {A} and {B} are each worth 10 but together only 12
+        let svalue = vec![Some(0.0), Some(10.0), Some(10.0), Some(12.0)];
+        let props = game_properties(&ops(2), &svalue);
+
+        assert!(!props.is_superadditive());
+        assert!(!props.is_convex());
+        let violation = &props.superadditivity.violations[0];
+        assert_eq!(violation.first, vec!["A".to_string()]);
+        assert_eq!(violation.second, vec!["B".to_string()]);
+        assert!((violation.shortfall - 8.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_synergy_matrix() {
+        // A and B complement each other, C duplicates A
+        let values = [0.0, 5.0, 5.0, 20.0, 5.0, 5.0, 20.0, 20.0];
+        let synergy = SynergyMatrix::new(ops(3), &values);
+
+        assert_eq!(synergy.get("A", "B"), synergy.get("B", "A"));
+        assert!(synergy.get("A", "D").is_none());
+        let ranked = synergy.ranked_pairs();
+        assert_eq!(ranked.len(), 3);
+        assert_eq!((ranked[0].0.as_str(), ranked[0].1.as_str()), ("A", "B"));
+        assert!(synergy.get("A", "C").unwrap() < 0.0);
+    }
+
+    #[test]
+    fn test_marginal_report() {
+        let values = [0.0, 5.0, 5.0, 20.0, 5.0, 5.0, 20.0, 20.0];
+        let report = MarginalReport::new(&ops(3), &values, 2);
+
+        // A adds most when joining B, then when starting alone
+        let top = &report.top["A"];
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].coalition, vec!["B".to_string()]);
+        assert_eq!(top[0].delta, 15.0);
+        assert_eq!(top[0].weight, 1.0 / 6.0);
+        assert!(top[1].coalition.is_empty());
+        assert_eq!(top[1].delta, 5.0);
+
+        // The weighted terms of all coalitions sum to the Shapley value
+        let all = MarginalReport::new(&ops(3), &values, usize::MAX);
+        let shapley = core_math::shapley_values(&values, 3);
+        for (k, op) in ops(3).iter().enumerate() {
+            let sum: f64 = all.top[op].iter().map(|c| c.weight * c.delta).sum();
+            assert!((sum - shapley[k]).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_contribution_report() {
+        // v(S) = |S|²: contributions grow with the coalition joined
+        let values: Vec<f64> = (0..8u32).map(|s| (s.count_ones() as f64).powi(2)).collect();
+        let report = ContributionReport::new(&ops(3), &values);
+        let stats = &report.operators["B"];
+        assert_eq!(
+            stats.by_size.iter().map(|m| m.mean).collect::<Vec<_>>(),
+            [1.0, 3.0, 5.0]
+        );
+        assert!(stats.by_size.iter().all(|m| m.variance == 0.0));
+        assert!((stats.mean - 3.0).abs() < 1e-12);
+        assert!((stats.variance - 8.0 / 3.0).abs() < 1e-12);
+
+        // An additive game gives every operator a fixed contribution
+        let values: Vec<f64> = (0..8usize)
+            .map(|s| (s & 1) as f64 * 2.0 + (s >> 1) as f64)
+            .collect();
+        let report = ContributionReport::new(&ops(3), &values);
+        assert_eq!(report.operators["A"].mean, 2.0);
+        assert_eq!(report.operators["A"].variance, 0.0);
+    }
+
+    #[test]
+    fn test_infeasible_coalitions_are_skipped() {
+        let svalue = vec![Some(0.0), None, Some(10.0), Some(12.0)];
+        let props = game_properties(&ops(2), &svalue);
+
+        assert_eq!(props.superadditivity.pairs_checked, 0);
+        assert_eq!(props.convexity.pairs_checked, 0);
+    }
+}
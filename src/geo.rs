@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Value, json};
+
+use crate::{
+    error::{Result, ShapleyError},
+    utilization::UtilizationReport,
+    utils::city_of,
+};
+
+impl UtilizationReport {
+    /// GeoJSON `FeatureCollection` with one `LineString` per link, carrying its
+    /// operators, provenance, flow and earned value as properties.
+    ///
+    /// `coordinates` maps device names to `[longitude, latitude]`. Public nodes
+    /// (`{city}00`) and demand endpoints (`{city}`) fall back to an entry for
+    /// their three-letter city code.
+    pub fn to_geojson(&self, coordinates: &BTreeMap<String, [f64; 2]>) -> Result<String> {
+        let locate = |node: &str| -> Result<[f64; 2]> {
+            coordinates
+                .get(node)
+                .or_else(|| coordinates.get(city_of(node)))
+                .copied()
+                .ok_or_else(|| ShapleyError::MissingDevice(format!("No coordinates for {node}")))
+        };
+
+        let features = self
+            .links
+            .iter()
+            .map(|l| {
+                Ok(json!({
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "LineString",
+                        "coordinates": [locate(&l.link.device1)?, locate(&l.link.device2)?],
+                    },
+                    "properties": {
+                        "device1": l.link.device1,
+                        "device2": l.link.device2,
+                        "operator1": l.link.operator1,
+                        "operator2": l.link.operator2,
+                        "provenance": format!("{:?}", l.link.provenance),
+                        "latency": l.link.latency,
+                        "bandwidth": l.link.bandwidth,
+                        "flow": l.flow,
+                        "utilization": l.utilization,
+                        "earned_value": l.earned_value,
+                    },
+                }))
+            })
+            .collect::<Result<Vec<Value>>>()?;
+
+        Ok(json!({ "type": "FeatureCollection", "features": features }).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        types::{ConsolidatedLink, LinkProvenance},
+        utilization::LinkUtilization,
+    };
+
+    #[test]
+    fn test_to_geojson() {
+        let report = UtilizationReport {
+            links: vec![LinkUtilization {
+                link: ConsolidatedLink {
+                    device1: "FRA1".to_string(),
+                    device2: "AMS00".to_string(),
+                    latency: 1.0,
+                    bandwidth: 0.0,
+                    operator1: "Alpha".to_string(),
+                    operator2: "Alpha".to_string(),
+                    shared: 0,
+                    link_type: 0,
+                    provenance: LinkProvenance::OriginalPrivate,
+                    multicast_capable: false,
+                },
+                flow: 3.0,
+                utilization: None,
+                earned_value: 1.5,
+            }],
+        };
+        let coordinates = BTreeMap::from([
+            ("FRA1".to_string(), [8.68, 50.11]),
+            ("AMS".to_string(), [4.90, 52.37]),
+        ]);
+
+        let geojson: Value =
+            serde_json::from_str(&report.to_geojson(&coordinates).unwrap()).unwrap();
+        let feature = &geojson["features"][0];
+        assert_eq!(
+            feature["geometry"]["coordinates"],
+            json!([[8.68, 50.11], [4.90, 52.37]])
+        );
+        assert_eq!(feature["properties"]["earned_value"], json!(1.5));
+        assert_eq!(feature["properties"]["utilization"], Value::Null);
+
+        assert!(report.to_geojson(&BTreeMap::new()).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! How far the LP's fractional routing is from routing real networks can
+//! follow. The LP may split a demand over any number of paths in any
+//! proportion; a router sends a flow down one path. The assessment rounds
+//! the grand coalition's optimal routing to one path per demand where
+//! capacity allows, re-solves with every commodity confined to the rounded
+//! paths and reports the cost of doing so.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    lp_builder::LpPrimitives,
+    sla::{decompose, outgoing_links},
+    types::{ConsolidatedDemand, ConsolidatedLink},
+};
+
+/// Relative slack allowed when checking that a rounded path fits
+const CAPACITY_TOLERANCE: f64 = 1e-9;
+
+/// How one demand is split in the grand coalition's optimal routing
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DemandSplit {
+    pub start: String,
+    pub end: String,
+    /// Traffic type as given (`Demand::kind`)
+    pub kind: u32,
+    /// Traffic delivered to `end`: traffic times receivers
+    pub traffic: f64,
+    /// Traffic on each path of the fractional routing, largest first
+    pub paths: Vec<f64>,
+    /// Whether the rounded routing sends the demand down a single path.
+    /// Demands that fit on none of their paths, and multicast demands, keep
+    /// all of them.
+    pub unsplit: bool,
+}
+
+/// Gap between the fractional optimum and a near-integral routing of the
+/// grand coalition, see
+/// [`ShapleyInput::integrality_report`](crate::shapley::ShapleyInput::integrality_report).
+/// Demands are listed after consolidation, as in
+/// [`SlaReport`](crate::sla::SlaReport).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IntegralityReport {
+    pub demands: Vec<DemandSplit>,
+    /// Objective of the grand coalition's LP
+    pub fractional_objective: f64,
+    /// Objective of the same LP with every commodity confined to its rounded
+    /// paths; `None` if those paths cannot carry the traffic
+    pub rounded_objective: Option<f64>,
+}
+
+impl IntegralityReport {
+    /// Extra objective of the rounded routing, relative to the fractional
+    /// one; `None` if the rounding is infeasible
+    pub fn gap(&self) -> Option<f64> {
+        let fractional = self.fractional_objective;
+        self.rounded_objective
+            .map(|rounded| (rounded - fractional) / fractional.abs().max(f64::EPSILON))
+    }
+
+    /// Demands with a path carrying less than `granularity`, the smallest
+    /// flow real routing could split off
+    pub fn split_below(&self, granularity: f64) -> impl Iterator<Item = &DemandSplit> {
+        self.demands
+            .iter()
+            .filter(move |d| d.paths.len() > 1 && d.paths.iter().any(|&p| p < granularity))
+    }
+}
+
+/// Round the optimal `flows` of each commodity to one path per demand,
+/// largest demands first: each takes the largest of its paths that the
+/// bandwidth rows still have room for. Returns the splits and the columns
+/// of `primitives` on the rounded paths.
+pub(crate) fn round_paths(
+    links: &[ConsolidatedLink],
+    demands: &[ConsolidatedDemand],
+    primitives: &LpPrimitives,
+    mut flows: BTreeMap<u32, Vec<f64>>,
+) -> (Vec<DemandSplit>, HashSet<usize>) {
+    let outgoing = outgoing_links(links);
+    let column: HashMap<(u32, usize), usize> = primitives
+        .col_route
+        .iter()
+        .enumerate()
+        .filter_map(|(col, route)| route.map(|route| (route, col)))
+        .collect();
+    let a_ub = &primitives.a_ub;
+    // Load of `traffic` down `path` on each bandwidth row
+    let row_loads = |kind: u32, path: &[usize], traffic: f64| {
+        let mut loads: BTreeMap<usize, f64> = BTreeMap::new();
+        for col in path.iter().filter_map(|&link| column.get(&(kind, link))) {
+            for idx in a_ub.colptr[*col]..a_ub.colptr[col + 1] {
+                if a_ub.rowval[idx] < primitives.bandwidth_rows {
+                    *loads.entry(a_ub.rowval[idx]).or_default() += a_ub.nzval[idx] * traffic;
+                }
+            }
+        }
+        loads
+    };
+
+    let paths: Vec<Vec<(Vec<usize>, f64)>> = demands
+        .iter()
+        .map(|demand| {
+            let residual = flows
+                .entry(demand.kind)
+                .or_insert_with(|| vec![0.0; links.len()]);
+            let traffic = demand.traffic * demand.receivers as f64;
+            let mut paths = decompose(&outgoing, links, residual, demand, traffic);
+            paths.sort_by(|a, b| b.1.total_cmp(&a.1));
+            paths
+        })
+        .collect();
+
+    let mut order: Vec<usize> = (0..demands.len()).collect();
+    order.sort_by(|&a, &b| {
+        let traffic = |i: usize| demands[i].traffic * demands[i].receivers as f64;
+        traffic(b).total_cmp(&traffic(a))
+    });
+    let mut used = vec![0.0; primitives.bandwidth_rows];
+    let mut kept = HashSet::new();
+    let mut unsplit = vec![false; demands.len()];
+    for i in order {
+        let demand = &demands[i];
+        let traffic = demand.traffic * demand.receivers as f64;
+        let fits = |loads: &BTreeMap<usize, f64>| {
+            loads.iter().all(|(&row, &load)| {
+                let limit = primitives.b_ub[row];
+                used[row] + load <= limit + CAPACITY_TOLERANCE * limit.abs().max(1.0)
+            })
+        };
+        let rounded = (!demand.multicast)
+            .then(|| {
+                paths[i].iter().find_map(|(path, _)| {
+                    let loads = row_loads(demand.kind, path, traffic);
+                    fits(&loads).then_some((path, loads))
+                })
+            })
+            .flatten();
+        let routed: Vec<(&Vec<usize>, BTreeMap<usize, f64>)> = match rounded {
+            Some(path) => {
+                unsplit[i] = true;
+                vec![path]
+            }
+            None => paths[i]
+                .iter()
+                .map(|(path, amount)| (path, row_loads(demand.kind, path, *amount)))
+                .collect(),
+        };
+        for (path, loads) in routed {
+            kept.extend(
+                path.iter()
+                    .filter_map(|&link| column.get(&(demand.kind, link))),
+            );
+            for (row, load) in loads {
+                used[row] += load;
+            }
+        }
+    }
+
+    let splits = demands
+        .iter()
+        .zip(paths)
+        .zip(unsplit)
+        .map(|((demand, paths), unsplit)| DemandSplit {
+            start: demand.start.clone(),
+            end: demand.end.clone(),
+            kind: demand.original,
+            traffic: demand.traffic * demand.receivers as f64,
+            unsplit: unsplit || paths.len() <= 1,
+            paths: paths.into_iter().map(|(_, amount)| amount).collect(),
+        })
+        .collect();
+    (splits, kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        options::ShapleyOptions,
+        shapley::ShapleyInput,
+        types::{Demand, Device, PrivateLink, PublicLink},
+    };
+
+    #[test]
+    fn test_integrality_report() {
+        // The direct SIN-LON link is faster but carries only 5 of the 8 units
+        let input = ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("SIN1".into(), "LON1".into(), 10.0, 5.0, 1.0, None),
+                PrivateLink::new("SIN1".into(), "FRA1".into(), 10.0, 10.0, 1.0, None),
+                PrivateLink::new("FRA1".into(), "LON1".into(), 10.0, 10.0, 1.0, None),
+            ],
+            devices: vec![
+                Device::new("SIN1".into(), 100, "Alpha".into()),
+                Device::new("FRA1".into(), 100, "Alpha".into()),
+                Device::new("LON1".into(), 100, "Alpha".into()),
+            ],
+            demands: vec![Demand::new(
+                "SIN".into(),
+                "LON".into(),
+                1,
+                8.0,
+                1.0,
+                1,
+                false,
+            )],
+            public_links: vec![PublicLink::new("SIN".into(), "LON".into(), 100.0)],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let report = input
+            .integrality_report(&ShapleyOptions::default())
+            .unwrap();
+        let split = &report.demands[0];
+        assert_eq!(split.traffic, 8.0);
+        assert_eq!(split.paths.len(), 2);
+        assert!((split.paths[0] - 5.0).abs() < 1e-6 && (split.paths[1] - 3.0).abs() < 1e-6);
+        // Only the detour through FRA fits all 8 units: 5 of them take 10 longer
+        assert!(split.unsplit);
+        let rounded = report.rounded_objective.unwrap();
+        assert!((rounded - report.fractional_objective - 50.0).abs() < 1e-6);
+        assert!(report.gap().unwrap() > 0.0);
+        assert_eq!(report.split_below(4.0).count(), 1);
+        assert_eq!(report.split_below(1.0).count(), 0);
+    }
+}
@@ -1,11 +1,62 @@
+extern crate alloc;
+
+pub mod anonymize;
+#[cfg(feature = "audit")]
+pub mod audit;
+#[cfg(feature = "unstable-backend")]
+pub mod batch;
+#[cfg(not(feature = "unstable-backend"))]
+pub(crate) mod batch;
+pub mod carbon;
+pub mod commitment;
 pub(crate) mod consolidation;
+pub mod core_math;
+pub mod cost;
+#[cfg(feature = "examples-data")]
+pub mod datasets;
+pub mod distributed;
+pub(crate) mod dot;
 pub mod error;
+#[cfg(feature = "async")]
+pub mod future;
+pub mod game;
+#[cfg(feature = "geo")]
+mod geo;
+pub mod integrality;
 pub(crate) mod lp_builder;
+pub(crate) mod lp_file;
+pub mod manager;
+#[cfg(feature = "manifest")]
+pub mod manifest;
+pub mod metadata;
 pub(crate) mod multicast;
+pub mod onboarding;
+pub mod options;
+pub mod partition;
+pub mod pipeline;
+pub mod preset;
+pub mod profile;
+#[cfg(feature = "research")]
+pub mod research;
+pub mod rewards;
+pub mod rolling;
+pub mod rules;
+pub(crate) mod sampling;
 pub mod shapley;
 pub(crate) mod simplex;
+pub mod sla;
+#[cfg(feature = "borsh")]
+pub mod snapshot;
 pub(crate) mod solver;
 pub(crate) mod sparse;
+pub(crate) mod srlg;
+#[cfg(feature = "surrogate")]
+pub mod surrogate;
+#[cfg(feature = "serde")]
+pub mod tables;
 pub mod types;
+pub mod uptime;
+pub mod usage;
+pub mod utilization;
 pub(crate) mod utils;
 pub(crate) mod validation;
@@ -1,13 +1,15 @@
 use std::collections::{BTreeMap, HashMap, HashSet};
 
 use crate::{
+    cost::{CostModel, CostedLink, LatencyCost},
     error::{Result, ShapleyError},
     multicast::{
         build_j1_matrix, build_j2_matrix, compute_j1_minus_j2, extract_mcast_eligible_columns,
         hstack_matrices,
     },
+    options::CongestionCurve,
     sparse::CscMatrix,
-    types::{ConsolidatedDemand, ConsolidatedLink},
+    types::{ConsolidatedDemand, ConsolidatedLink, LinkProvenance},
 };
 
 type Constraints = (CscMatrix<f64>, Vec<f64>, Vec<String>, Vec<String>);
@@ -17,11 +19,41 @@ type Constraints = (CscMatrix<f64>, Vec<f64>, Vec<String>, Vec<String>);
 pub(crate) struct LpBuilderInput<'a> {
     pub links: &'a [ConsolidatedLink],
     pub demands: &'a [ConsolidatedDemand],
+    /// Crossover latency override keyed by original demand type
+    pub contiguity_bonus_by_type: Option<&'a BTreeMap<u32, f64>>,
+    /// Per-link cost; plain latency when unset
+    pub cost_model: Option<&'a dyn CostModel>,
+    /// Congestion cost of private links, by their devices
+    pub congestion: &'a [CongestionCurve],
 }
 
 impl<'a> LpBuilderInput<'a> {
     pub(crate) fn new(links: &'a [ConsolidatedLink], demands: &'a [ConsolidatedDemand]) -> Self {
-        Self { links, demands }
+        Self {
+            links,
+            demands,
+            contiguity_bonus_by_type: None,
+            cost_model: None,
+            congestion: &[],
+        }
+    }
+
+    pub(crate) fn with_cost_model(mut self, cost_model: &'a dyn CostModel) -> Self {
+        self.cost_model = Some(cost_model);
+        self
+    }
+
+    pub(crate) fn with_congestion(mut self, congestion: &'a [CongestionCurve]) -> Self {
+        self.congestion = congestion;
+        self
+    }
+
+    pub(crate) fn with_contiguity_bonus_by_type(
+        mut self,
+        contiguity_bonus_by_type: &'a BTreeMap<u32, f64>,
+    ) -> Self {
+        self.contiguity_bonus_by_type = Some(contiguity_bonus_by_type);
+        self
     }
 
     /// Build LP problem using the new API
@@ -32,35 +64,19 @@ impl<'a> LpBuilderInput<'a> {
         // Count private links (non-public operators)
         let n_private = links.iter().filter(|l| l.operator1 != "Public").count();
 
-        // Identify multicast eligible/ineligible links
+        // Identify multicast eligible/ineligible private links: those whose
+        // multicast flows into device2 share capacity, and the rest
         let mcast_eligible: Vec<usize> = links
             .iter()
             .enumerate()
-            .filter(|(_i, l)| {
-                // Python checks: ~(str[3:] == "00") & ~(str[3:] == "") & (op1 != "Public")
-                // This means: device2[3:] is not "00" AND device2[3:] is not empty
-                let device2_suffix = if l.device2.len() > 3 {
-                    &l.device2[3..]
-                } else {
-                    ""
-                };
-                device2_suffix != "00" && !device2_suffix.is_empty() && l.operator1 != "Public"
-            })
+            .filter(|(_, l)| l.multicast_capable && l.operator1 != "Public")
             .map(|(i, _)| i)
             .collect();
 
         let mcast_ineligible: Vec<usize> = links
             .iter()
             .enumerate()
-            .filter(|(_, l)| {
-                // Python checks: (str[3:] == "00") | (str[3:] == "") & (op1 != "Public")
-                let device2_suffix = if l.device2.len() > 3 {
-                    &l.device2[3..]
-                } else {
-                    ""
-                };
-                (device2_suffix == "00" || device2_suffix.is_empty()) && l.operator1 != "Public"
-            })
+            .filter(|(_, l)| !l.multicast_capable && l.operator1 != "Public")
             .map(|(i, _)| i)
             .collect();
 
@@ -140,15 +156,19 @@ impl<'a> LpBuilderInput<'a> {
         }
 
         // Build bandwidth constraints
+        let mut shared_groups = SharedGroupIndex::new(&links[..n_private]);
         let (mut a_ub, mut b_ub, mut row_op1, mut row_op2) = build_bandwidth_constraints(
             links,
             n_private,
+            &shared_groups,
             &commodity_multicast_flag,
             &commodities,
             &mcast_eligible,
             &mcast_ineligible,
             multicast_commodities.len(),
         )?;
+        let bandwidth_rows = b_ub.len();
+        let mut ub_row_name = shared_groups.row_names(links);
 
         // Add "within-group" multicast constraints if needed
         let n_multicast_groups = multicast_commodities.len();
@@ -175,11 +195,12 @@ impl<'a> LpBuilderInput<'a> {
                 // Extend row operators with actual operators from multicast eligible links
                 // This matches Python's row_op1_multicast = _rep(link_df["Operator1"].iloc[mcast_eligible], demand_df["Multicast"].sum())
                 let n_multicast_demands = demands.iter().filter(|d| d.multicast).count();
-                for _ in 0..n_multicast_demands {
+                for m in 0..n_multicast_demands {
                     for &idx in &mcast_eligible {
                         if idx < links.len() {
                             row_op1.push(links[idx].operator1.clone());
                             row_op2.push(links[idx].operator2.clone());
+                            ub_row_name.push(link_name("mcast", m, idx, &links[idx]));
                         }
                     }
                 }
@@ -213,6 +234,7 @@ impl<'a> LpBuilderInput<'a> {
         // Filter columns based on extended keep vector
         let a_eq_final = filter_columns(&a_eq_padded, &keep_final)?;
         let a_ub_final = filter_columns(&a_ub, &keep_final)?;
+        shared_groups.index_columns(&a_ub_final);
 
         // Build column operators
         let col_op1 = build_column_operators1(
@@ -232,6 +254,40 @@ impl<'a> LpBuilderInput<'a> {
             n_multicast_groups,
         );
 
+        let col_kind = build_column_kinds(
+            links.len(),
+            demands,
+            &commodities,
+            &multicast_commodities,
+            mcast_eligible.len(),
+            &keep_final,
+        );
+        let col_link = build_column_links(
+            links,
+            &commodities,
+            &commodity_multicast_flag,
+            &mcast_eligible,
+            &keep_final,
+            n_multicast_groups,
+        );
+
+        let col_route = build_column_routes(links.len(), &commodities, &keep_final);
+        let col_name = build_column_names(
+            links,
+            &commodities,
+            &multicast_commodities,
+            &mcast_eligible,
+            &keep_final,
+        );
+        let eq_row_name = commodities
+            .iter()
+            .flat_map(|t| {
+                nodes
+                    .iter()
+                    .map(move |node| lp_name(&format!("flow_{t}_{node}")))
+            })
+            .collect();
+
         // Build RHS vector for flow requirements
         let b_eq = build_flow_requirements(demands, &commodities, &k_of_type, &node_idx, n_nodes)?;
 
@@ -239,6 +295,8 @@ impl<'a> LpBuilderInput<'a> {
         let cost = build_objective_coefficients(
             links,
             demands,
+            self.contiguity_bonus_by_type,
+            self.cost_model.unwrap_or(&LatencyCost),
             &commodities,
             &multicast_commodities,
             &mcast_eligible,
@@ -246,37 +304,267 @@ impl<'a> LpBuilderInput<'a> {
             n_multicast_groups,
         )?;
 
-        Ok(LpPrimitives {
+        let mut primitives = LpPrimitives {
             a_eq: a_eq_final,
             a_ub: a_ub_final,
             b_eq,
             b_ub,
+            bandwidth_rows,
+            shared_groups,
             cost,
             row_op1,
             row_op2,
             col_op1,
             col_op2,
-        })
+            col_link,
+            col_kind,
+            col_route,
+            col_name,
+            eq_row_name,
+            ub_row_name,
+            congestion: Vec::new(),
+        };
+        primitives.add_congestion(links, self.congestion)?;
+        Ok(primitives)
     }
 }
 
 /// Holds all components of the linear program
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct LpBuilderOutput {
     pub a_eq: CscMatrix<f64>,
     pub a_ub: CscMatrix<f64>,
     pub b_eq: Vec<f64>,
     pub b_ub: Vec<f64>,
+    /// Leading rows of `a_ub` that are link bandwidth limits; the rest are
+    /// multicast group constraints. They depend on the links alone.
+    pub bandwidth_rows: usize,
+    /// The bandwidth rows by shared ID, with the links and columns in each
+    pub shared_groups: SharedGroupIndex,
     pub cost: Vec<f64>,
     pub row_op1: Vec<String>,
     pub row_op2: Vec<String>,
     pub col_op1: Vec<String>,
     pub col_op2: Vec<String>,
+    /// Consolidated link whose load each column adds to. `None` for multicast
+    /// commodity columns on multicast-eligible links, whose load is carried by
+    /// the group's auxiliary column instead.
+    pub col_link: Vec<Option<usize>>,
+    /// Original traffic type (`Demand::kind`) of the commodity each column carries
+    pub col_kind: Vec<u32>,
+    /// Commodity (`ConsolidatedDemand::kind`) each column carries and the
+    /// consolidated link it routes it over. `None` for multicast auxiliary
+    /// columns.
+    pub col_route: Vec<Option<(u32, usize)>>,
+    /// Name of each column, row of `a_eq` and row of `a_ub`, usable in LP
+    /// files: flows are `x_{commodity}_{link}_{device1}_{device2}`, multicast
+    /// auxiliary columns `aux_{type}_...`, conservation rows
+    /// `flow_{commodity}_{node}`, bandwidth rows `bw_{shared}_...`,
+    /// multicast group rows `mcast_{demand}_...` and congestion columns and
+    /// rows `cong_{shared}_{breakpoint}_...`
+    pub col_name: Vec<String>,
+    pub eq_row_name: Vec<String>,
+    pub ub_row_name: Vec<String>,
+    /// The trailing rows of `a_ub`, one per congestion curve breakpoint of a
+    /// bandwidth row, in row order
+    pub congestion: Vec<CongestionRow>,
+}
+
+/// Breakpoint of a bandwidth row's congestion curve. Its column carries the
+/// row's load beyond `utilization` of the bandwidth, at the breakpoint's
+/// slope: the row bounds load minus column by `utilization` times bandwidth.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CongestionRow {
+    pub row: usize,
+    /// Bandwidth row (shared group) the breakpoint is on
+    pub group: usize,
+    pub utilization: f64,
+}
+
+impl LpBuilderOutput {
+    /// Take the bandwidth limits from `links`, which must differ from the
+    /// links the LP was built over in bandwidths alone
+    pub(crate) fn patch_bandwidths(&mut self, links: &[ConsolidatedLink]) {
+        for (row, group) in self.shared_groups.groups.iter_mut().enumerate() {
+            group.bandwidth = links[group.links[0]].bandwidth;
+            self.b_ub[row] = group.bandwidth;
+        }
+        for congestion in &self.congestion {
+            self.b_ub[congestion.row] =
+                congestion.utilization * self.shared_groups.groups[congestion.group].bandwidth;
+        }
+    }
+
+    /// The congestion breakpoint of row `row` of `a_ub`, if it is one
+    pub(crate) fn congestion_row(&self, row: usize) -> Option<&CongestionRow> {
+        let first = self.b_ub.len() - self.congestion.len();
+        row.checked_sub(first).map(|i| &self.congestion[i])
+    }
+
+    /// Append a column and an `a_ub` row per breakpoint of the congestion
+    /// curve of every bandwidth row with a link that has one (the first such
+    /// link's curve). The column's cost is the breakpoint's slope, so the LP
+    /// pays the curve's cost of the row's load. It counts toward the lowest
+    /// traffic type loading the row in `col_kind`.
+    fn add_congestion(
+        &mut self,
+        links: &[ConsolidatedLink],
+        curves: &[CongestionCurve],
+    ) -> Result<()> {
+        if curves.is_empty() {
+            return Ok(());
+        }
+        let a_ub = &self.a_ub;
+        let mut triplets: Vec<(usize, usize, f64)> = (0..a_ub.n)
+            .flat_map(|col| {
+                (a_ub.colptr[col]..a_ub.colptr[col + 1])
+                    .map(move |idx| (a_ub.rowval[idx], col, a_ub.nzval[idx]))
+            })
+            .collect();
+        let (mut n_rows, mut n_cols) = (a_ub.m, a_ub.n);
+
+        for (g, group) in self.shared_groups.groups.iter().enumerate() {
+            let Some(curve) = group.links.iter().find_map(|&idx| {
+                let link = &links[idx];
+                matches!(
+                    link.provenance,
+                    LinkProvenance::OriginalPrivate | LinkProvenance::Reverse
+                )
+                .then(|| {
+                    curves
+                        .iter()
+                        .find(|c| c.connects(&link.device1, &link.device2))
+                })
+                .flatten()
+            }) else {
+                continue;
+            };
+            let Some(kind) = group.columns.iter().map(|&col| self.col_kind[col]).min() else {
+                continue;
+            };
+            let load: Vec<(usize, f64)> = group
+                .columns
+                .iter()
+                .flat_map(|&col| {
+                    (a_ub.colptr[col]..a_ub.colptr[col + 1])
+                        .filter(|&idx| a_ub.rowval[idx] == g)
+                        .map(move |idx| (col, a_ub.nzval[idx]))
+                })
+                .collect();
+            let link = &links[group.links[0]];
+            for (i, &(utilization, slope)) in curve.breakpoints.iter().enumerate() {
+                let (row, column) = (n_rows, n_cols);
+                triplets.extend(load.iter().map(|&(col, val)| (row, col, val)));
+                triplets.push((row, column, -1.0));
+                let name = link_name("cong", group.shared as usize, i, link);
+                self.b_ub.push(utilization * group.bandwidth);
+                self.row_op1.push(group.operator1.clone());
+                self.row_op2.push(group.operator2.clone());
+                self.ub_row_name.push(name.clone());
+                self.cost.push(slope);
+                self.col_op1.push(group.operator1.clone());
+                self.col_op2.push(group.operator2.clone());
+                self.col_link.push(None);
+                self.col_kind.push(kind);
+                self.col_route.push(None);
+                self.col_name.push(name);
+                self.congestion.push(CongestionRow {
+                    row,
+                    group: g,
+                    utilization,
+                });
+                n_rows += 1;
+                n_cols += 1;
+            }
+        }
+
+        self.a_ub = build_csc_from_triplets(&triplets, n_rows, n_cols)?;
+        let a_eq = &mut self.a_eq;
+        let nnz = a_eq.colptr[a_eq.n];
+        a_eq.colptr.resize(n_cols + 1, nnz);
+        a_eq.n = n_cols;
+        Ok(())
+    }
 }
 
 // Keep LpPrimitives as an alias for backward compatibility
 pub(crate) type LpPrimitives = LpBuilderOutput;
 
+/// Private links sharing one bandwidth row, i.e. holding the same shared ID
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SharedGroup {
+    pub shared: u32,
+    /// Consolidated links holding the ID, in link order. The first one sets
+    /// the row's limit, owners and name (matching Python's drop_duplicates
+    /// behavior).
+    pub links: Vec<usize>,
+    /// Columns of the built LP with a coefficient in the row
+    pub columns: Vec<usize>,
+    pub bandwidth: f64,
+    pub operator1: String,
+    pub operator2: String,
+}
+
+/// One [`SharedGroup`] per shared ID, in ID order, so group `i` is bandwidth
+/// row `i` of `a_ub`. Built once per LP, so rows can be patched and read back
+/// without going over the links again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct SharedGroupIndex {
+    pub groups: Vec<SharedGroup>,
+}
+
+impl SharedGroupIndex {
+    fn new(private_links: &[ConsolidatedLink]) -> Self {
+        let mut by_shared: BTreeMap<u32, SharedGroup> = BTreeMap::new();
+        for (idx, link) in private_links
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.shared > 0)
+        {
+            by_shared
+                .entry(link.shared)
+                .or_insert_with(|| SharedGroup {
+                    shared: link.shared,
+                    links: Vec::new(),
+                    columns: Vec::new(),
+                    bandwidth: link.bandwidth,
+                    operator1: link.operator1.clone(),
+                    operator2: link.operator2.clone(),
+                })
+                .links
+                .push(idx);
+        }
+        Self {
+            groups: by_shared.into_values().collect(),
+        }
+    }
+
+    /// Record the columns of `a_ub` in each group's row
+    fn index_columns(&mut self, a_ub: &CscMatrix<f64>) {
+        for col in 0..a_ub.n {
+            for idx in a_ub.colptr[col]..a_ub.colptr[col + 1] {
+                if let Some(group) = self.groups.get_mut(a_ub.rowval[idx]) {
+                    group.columns.push(col);
+                }
+            }
+        }
+    }
+
+    /// Name the rows after each group's first link
+    fn row_names(&self, links: &[ConsolidatedLink]) -> Vec<String> {
+        self.groups
+            .iter()
+            .map(|group| {
+                let link = &links[group.links[0]];
+                lp_name(&format!(
+                    "bw_{}_{}_{}",
+                    group.shared, link.device1, link.device2
+                ))
+            })
+            .collect()
+    }
+}
+
 /// Build single commodity flow conservation matrix
 fn build_single_commodity_matrix(
     links: &[ConsolidatedLink],
@@ -341,6 +629,7 @@ fn block_diagonal_csc(matrix: &CscMatrix<f64>, n: usize) -> Result<CscMatrix<f64
 fn build_bandwidth_constraints(
     links: &[ConsolidatedLink],
     n_private: usize,
+    shared_groups: &SharedGroupIndex,
     commodity_multicast_flag: &HashMap<u32, bool>,
     commodities: &[u32],
     mcast_eligible: &[usize],
@@ -370,87 +659,42 @@ fn build_bandwidth_constraints(
     // Build J2 matrix (multicast ineligible links only)
     let j2 = build_j2_matrix(links, mcast_ineligible, max_shared)?;
 
-    // Stack J1 or J2 horizontally for each commodity based on multicast flag
-    let mut i_blocks = Vec::new();
-
-    for t in commodities {
-        let is_multicast = commodity_multicast_flag.get(t).copied().unwrap_or(false);
-        if is_multicast {
-            i_blocks.push(&j2);
-        } else {
-            i_blocks.push(&j1);
-        }
+    // One block of J1 or J2 per commodity, by its multicast flag, then one
+    // block of (J1 - J2)[:, mcast_eligible] per multicast group, stacked by
+    // copying column slices
+    let j1_minus_j2_mcast = if n_multicast_groups > 0 && !mcast_eligible.is_empty() {
+        let j1_minus_j2 = compute_j1_minus_j2(&j1, &j2)?;
+        Some(extract_mcast_eligible_columns(
+            &j1_minus_j2,
+            mcast_eligible,
+        )?)
+    } else {
+        None
+    };
+    let mut blocks: Vec<&CscMatrix<f64>> = commodities
+        .iter()
+        .map(|t| {
+            if commodity_multicast_flag.get(t).copied().unwrap_or(false) {
+                &j2
+            } else {
+                &j1
+            }
+        })
+        .collect();
+    if let Some(extension) = &j1_minus_j2_mcast {
+        blocks.extend(std::iter::repeat_n(extension, n_multicast_groups));
     }
-
-    let mut i = if !i_blocks.is_empty() {
-        hstack_matrices(&i_blocks)?
+    let i = if blocks.is_empty() {
+        CscMatrix::new(0, 0, vec![0], vec![], vec![])
     } else {
-        CscMatrix::new(0, commodities.len() * links.len(), vec![0], vec![], vec![])
+        hstack_matrices(&blocks)?
     };
 
-    let mut b_ub = Vec::new();
-    let mut row_op1 = Vec::new();
-    let mut row_op2 = Vec::new();
-
-    // Build initial bandwidth capacity vector
-    // We need to create a vector with max_shared elements, filling in the bandwidth
-    // for each shared ID that exists
-    let mut bandwidth_by_shared: BTreeMap<usize, f64> = BTreeMap::new();
-    let mut op1_by_shared: BTreeMap<usize, String> = BTreeMap::new();
-    let mut op2_by_shared: BTreeMap<usize, String> = BTreeMap::new();
-
-    // Debug: collect all shared IDs in private links
-    let mut all_shared_ids: HashSet<u32> = HashSet::new();
-
-    for link in links[..n_private].iter() {
-        if link.shared > 0 && link.shared as usize <= max_shared {
-            all_shared_ids.insert(link.shared);
-            let shared_idx = link.shared as usize - 1; // 0-based index
-            // Only keep the first occurrence of each shared ID
-            bandwidth_by_shared
-                .entry(shared_idx)
-                .or_insert(link.bandwidth);
-            op1_by_shared
-                .entry(shared_idx)
-                .or_insert(link.operator1.clone());
-            op2_by_shared
-                .entry(shared_idx)
-                .or_insert(link.operator2.clone());
-        }
-    }
-
-    // Fill b_ub, row_op1, row_op2 only for existing shared IDs (matching Python's drop_duplicates behavior)
-    let mut existing_shared: Vec<usize> = bandwidth_by_shared.keys().copied().collect();
-    existing_shared.sort();
-
-    for shared_id in existing_shared {
-        b_ub.push(bandwidth_by_shared.get(&shared_id).copied().unwrap_or(0.0));
-        row_op1.push(op1_by_shared.get(&shared_id).cloned().unwrap_or_default());
-        row_op2.push(op2_by_shared.get(&shared_id).cloned().unwrap_or_default());
-    }
-
-    // Handle multicast constraints if needed
-    if n_multicast_groups > 0 && !mcast_eligible.is_empty() {
-        // Compute (J1 - J2) matrix
-        let j1_minus_j2 = compute_j1_minus_j2(&j1, &j2)?;
-
-        // Extract columns for multicast eligible links
-        let j1_minus_j2_mcast = extract_mcast_eligible_columns(&j1_minus_j2, mcast_eligible)?;
-
-        // Stack (J1-J2)[:, mcast_eligible] for each unique multicast group
-        let mut extend_blocks = Vec::new();
-        for _ in 0..n_multicast_groups {
-            extend_blocks.push(&j1_minus_j2_mcast);
-        }
-
-        if !extend_blocks.is_empty() {
-            let extension = hstack_matrices(&extend_blocks)?;
-            i = hstack_matrices(&[&i, &extension])?;
-
-            // Note: We don't extend row_op* or b_ub here because hstack adds columns, not rows
-            // The Python code doesn't extend b_ub for the hstack part either
-        }
-    }
+    // One row per shared ID, in ID order
+    let groups = &shared_groups.groups;
+    let b_ub = groups.iter().map(|g| g.bandwidth).collect();
+    let row_op1 = groups.iter().map(|g| g.operator1.clone()).collect();
+    let row_op2 = groups.iter().map(|g| g.operator2.clone()).collect();
 
     Ok((i, b_ub, row_op1, row_op2))
 }
@@ -569,12 +813,14 @@ fn build_within_group_constraints(
         .enumerate()
         .map(|(i, &g)| (g, i))
         .collect();
-    let mut triplets = Vec::new();
-    let mut n_rows = 0;
-
-    let n_mcast_eligible = mcast_eligible.len();
-    let regular_vars_count = n_commodities * n_links;
 
+    // Demand `r` gets row block r, one row per multicast-eligible link. Each
+    // commodity column then holds (1/receivers) in the blocks of its demands
+    // and each auxiliary column -1 in the blocks of its group's demands, so
+    // both are written column by column with rows in order.
+    let mut blocks_of_commodity: Vec<Vec<(usize, f64)>> = vec![Vec::new(); n_commodities];
+    let mut blocks_of_group: Vec<Vec<usize>> = vec![Vec::new(); n_multicast_groups];
+    let mut n_blocks = 0;
     for demand in demands.iter().filter(|d| d.multicast) {
         let k = *k_of_type.get(&demand.kind).ok_or_else(|| {
             ShapleyError::MatrixConstructionError(format!(
@@ -593,25 +839,52 @@ fn build_within_group_constraints(
         if receivers.abs() < 1e-9 {
             continue; // Avoid division by zero
         }
+        blocks_of_commodity[k].push((n_blocks, 1.0 / receivers));
+        blocks_of_group[k_orig_idx].push(n_blocks);
+        n_blocks += 1;
+    }
 
-        // Add one constraint row for each multicast-eligible link for this demand
-        for (mcast_col_idx, &link_idx) in mcast_eligible.iter().enumerate() {
-            let row_idx = n_rows;
+    let n_mcast_eligible = mcast_eligible.len();
+    let regular_vars_count = n_commodities * n_links;
+    let mut eligible_position = vec![None; n_links];
+    for (mcast_col_idx, &link_idx) in mcast_eligible.iter().enumerate() {
+        eligible_position[link_idx] = Some(mcast_col_idx);
+    }
 
+    let nnz = 2 * n_blocks * n_mcast_eligible;
+    let mut col_ptr = Vec::with_capacity(n_total_cols + 1);
+    let mut row_ind = Vec::with_capacity(nnz);
+    let mut values = Vec::with_capacity(nnz);
+    col_ptr.push(0);
+    for col in 0..n_total_cols {
+        if col < regular_vars_count {
             // Part 1: (1/receivers) * x_k
-            let regular_var_col_idx = k * n_links + link_idx;
-            triplets.push((row_idx, regular_var_col_idx, 1.0 / receivers));
-
+            let (k, link_idx) = (col / n_links, col % n_links);
+            if let Some(mcast_col_idx) = eligible_position[link_idx] {
+                for &(block, weight) in &blocks_of_commodity[k] {
+                    row_ind.push(block * n_mcast_eligible + mcast_col_idx);
+                    values.push(weight);
+                }
+            }
+        } else {
             // Part 2: -1 * x_orig
-            let aux_var_col_idx =
-                regular_vars_count + (k_orig_idx * n_mcast_eligible) + mcast_col_idx;
-            triplets.push((row_idx, aux_var_col_idx, -1.0));
-
-            n_rows += 1;
+            let aux = col - regular_vars_count;
+            let (group, mcast_col_idx) = (aux / n_mcast_eligible, aux % n_mcast_eligible);
+            for &block in &blocks_of_group[group] {
+                row_ind.push(block * n_mcast_eligible + mcast_col_idx);
+                values.push(-1.0);
+            }
         }
+        col_ptr.push(row_ind.len());
     }
 
-    build_csc_from_triplets(&triplets, n_rows, n_total_cols)
+    Ok(CscMatrix::new(
+        n_blocks * n_mcast_eligible,
+        n_total_cols,
+        col_ptr,
+        row_ind,
+        values,
+    ))
 }
 
 /// Filter columns of a CSC matrix
@@ -683,6 +956,130 @@ fn build_column_operators1(
     result
 }
 
+/// Map each kept column to the original traffic type it carries
+fn build_column_kinds(
+    n_links: usize,
+    demands: &[ConsolidatedDemand],
+    commodities: &[u32],
+    multicast_commodities: &[u32],
+    n_mcast_eligible: usize,
+    keep: &[usize],
+) -> Vec<u32> {
+    let original: HashMap<u32, u32> = demands.iter().map(|d| (d.kind, d.original)).collect();
+    let mut col_kind = Vec::new();
+
+    // Regular commodity columns
+    for t in commodities {
+        let kind = original.get(t).copied().unwrap_or(*t);
+        col_kind.extend(std::iter::repeat_n(kind, n_links));
+    }
+
+    // Multicast auxiliary variable columns, one block per original type
+    for &t in multicast_commodities {
+        col_kind.extend(std::iter::repeat_n(t, n_mcast_eligible));
+    }
+
+    keep.iter()
+        .filter_map(|&i| col_kind.get(i).copied())
+        .collect()
+}
+
+/// Map each kept column to the commodity it carries and the link it routes
+/// that commodity over; the auxiliary columns after the commodity blocks
+/// carry none
+fn build_column_routes(
+    n_links: usize,
+    commodities: &[u32],
+    keep: &[usize],
+) -> Vec<Option<(u32, usize)>> {
+    keep.iter()
+        .map(|&i| {
+            commodities
+                .get(i / n_links.max(1))
+                .map(|&t| (t, i % n_links.max(1)))
+        })
+        .collect()
+}
+
+/// Name each kept column after the commodity (or multicast group) it
+/// carries and its link
+fn build_column_names(
+    links: &[ConsolidatedLink],
+    commodities: &[u32],
+    multicast_commodities: &[u32],
+    mcast_eligible: &[usize],
+    keep: &[usize],
+) -> Vec<String> {
+    let mut names = Vec::new();
+
+    // Regular commodity columns
+    for &t in commodities {
+        for (idx, link) in links.iter().enumerate() {
+            names.push(link_name("x", t as usize, idx, link));
+        }
+    }
+
+    // Multicast auxiliary variable columns
+    for &t in multicast_commodities {
+        for &idx in mcast_eligible {
+            if idx < links.len() {
+                names.push(link_name("aux", t as usize, idx, &links[idx]));
+            }
+        }
+    }
+
+    keep.iter().filter_map(|&i| names.get(i).cloned()).collect()
+}
+
+fn link_name(prefix: &str, group: usize, idx: usize, link: &ConsolidatedLink) -> String {
+    lp_name(&format!(
+        "{prefix}_{group}_{idx}_{}_{}",
+        link.device1, link.device2
+    ))
+}
+
+/// `raw` with every character other than ASCII letters, digits and `_`
+/// replaced by `_`, which MPS and LP readers all accept
+fn lp_name(raw: &str) -> String {
+    raw.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Map each kept column to the consolidated link it loads
+fn build_column_links(
+    links: &[ConsolidatedLink],
+    commodities: &[u32],
+    commodity_multicast_flag: &HashMap<u32, bool>,
+    mcast_eligible: &[usize],
+    keep: &[usize],
+    n_multicast_groups: usize,
+) -> Vec<Option<usize>> {
+    let mut col_link = Vec::new();
+
+    // Regular commodity columns
+    for t in commodities {
+        let is_multicast = commodity_multicast_flag.get(t).copied().unwrap_or(false);
+        for i in 0..links.len() {
+            let carried_by_aux = is_multicast && mcast_eligible.contains(&i);
+            col_link.push((!carried_by_aux).then_some(i));
+        }
+    }
+
+    // Multicast auxiliary variable columns
+    for _ in 0..n_multicast_groups {
+        for &idx in mcast_eligible {
+            if idx < links.len() {
+                col_link.push(Some(idx));
+            }
+        }
+    }
+
+    keep.iter()
+        .filter_map(|&i| col_link.get(i).copied())
+        .collect()
+}
+
 /// Build column operator tags for operator2
 fn build_column_operators2(
     links: &[ConsolidatedLink],
@@ -759,9 +1156,12 @@ fn build_flow_requirements(
 }
 
 /// Build objective function coefficients
+#[allow(clippy::too_many_arguments)]
 fn build_objective_coefficients(
     links: &[ConsolidatedLink],
     demands: &[ConsolidatedDemand],
+    contiguity_bonus_by_type: Option<&BTreeMap<u32, f64>>,
+    cost_model: &dyn CostModel,
     commodities: &[u32],
     _multicast_commodities: &[u32],
     mcast_eligible: &[usize],
@@ -782,16 +1182,37 @@ fn build_objective_coefficients(
         .map(|(k, (sum, count))| (k, sum / count as f64))
         .collect();
 
+    // Crossover latency overrides, re-keyed from original demand type to commodity
+    let crossover_latency: HashMap<u32, f64> = match contiguity_bonus_by_type {
+        Some(overrides) => demands
+            .iter()
+            .filter_map(|d| overrides.get(&d.original).map(|&bonus| (d.kind, bonus)))
+            .collect(),
+        None => HashMap::new(),
+    };
+
     // Build cost vector
     let mut cost = Vec::new();
 
     // Regular commodity costs
     for &t in commodities {
         let priority = avg_priority.get(&t).copied().unwrap_or(1.0);
+        let crossover_override = crossover_latency.get(&t).copied();
 
         for link in links {
-            let latency = link.latency;
-            cost.push(latency * priority);
+            let latency = match crossover_override {
+                Some(bonus) if link.provenance == LinkProvenance::Crossover => bonus,
+                _ => link.latency,
+            };
+            let link_cost = cost_model.cost(&CostedLink {
+                device1: &link.device1,
+                device2: &link.device2,
+                operator1: &link.operator1,
+                operator2: &link.operator2,
+                latency,
+                provenance: link.provenance,
+            });
+            cost.push(link_cost * priority);
         }
     }
 
@@ -819,6 +1240,8 @@ mod tests {
                 operator2: "Op1".to_string(),
                 shared: 1,
                 link_type: 0,
+                provenance: LinkProvenance::OriginalPrivate,
+                multicast_capable: false,
             },
             ConsolidatedLink {
                 device1: "B".to_string(),
@@ -829,6 +1252,8 @@ mod tests {
                 operator2: "Op1".to_string(),
                 shared: 1,
                 link_type: 0,
+                provenance: LinkProvenance::OriginalPrivate,
+                multicast_capable: false,
             },
         ];
 
@@ -857,6 +1282,8 @@ mod tests {
                 operator2: "Op1".to_string(),
                 shared: 1,
                 link_type: 0,
+                provenance: LinkProvenance::OriginalPrivate,
+                multicast_capable: false,
             },
             ConsolidatedLink {
                 device1: "B".to_string(),
@@ -867,6 +1294,8 @@ mod tests {
                 operator2: "Op1".to_string(),
                 shared: 1,
                 link_type: 0,
+                provenance: LinkProvenance::OriginalPrivate,
+                multicast_capable: false,
             },
         ];
 
@@ -949,6 +1378,41 @@ mod tests {
         assert_eq!(b_vector[2], 3.0); // Dest C: +3
     }
 
+    #[test]
+    fn test_within_group_constraints() {
+        let demand = |kind, receivers| ConsolidatedDemand {
+            start: "A".to_string(),
+            end: "B".to_string(),
+            receivers,
+            traffic: 1.0,
+            priority: 1.0,
+            kind,
+            multicast: true,
+            original: 1,
+        };
+        let demands = [demand(1, 2), demand(2, 4)];
+        let k_of_type = HashMap::from([(1, 0), (2, 1)]);
+
+        // 3 links, 2 commodities, links 0 and 2 multicast eligible
+        let matrix =
+            build_within_group_constraints(&demands, &k_of_type, &[1], &[0, 2], 3, 2, 8).unwrap();
+        let mut dense = vec![[0.0; 8]; matrix.m];
+        for col in 0..matrix.n {
+            for idx in matrix.colptr[col]..matrix.colptr[col + 1] {
+                dense[matrix.rowval[idx]][col] = matrix.nzval[idx];
+            }
+        }
+        assert_eq!(
+            dense,
+            vec![
+                [0.5, 0.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0],
+                [0.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.0, -1.0],
+                [0.0, 0.0, 0.0, 0.25, 0.0, 0.0, -1.0, 0.0],
+                [0.0, 0.0, 0.0, 0.0, 0.0, 0.25, 0.0, -1.0],
+            ]
+        );
+    }
+
     #[test]
     fn test_sparse_matrix_edge_cases() {
         // Test with minimal input
@@ -961,6 +1425,8 @@ mod tests {
             operator2: "Op1".to_string(),
             shared: 1,
             link_type: 0,
+            provenance: LinkProvenance::OriginalPrivate,
+            multicast_capable: false,
         }];
 
         let mut node_idx = HashMap::new();
@@ -988,4 +1454,93 @@ mod tests {
         assert_eq!(matrix.m, 0);
         assert_eq!(matrix.n, 0);
     }
+
+    #[test]
+    fn test_contiguity_bonus_override_by_type() {
+        let crossover = |device1: &str, device2: &str| ConsolidatedLink {
+            device1: device1.to_string(),
+            device2: device2.to_string(),
+            latency: 5.0,
+            bandwidth: 10.0,
+            operator1: "Op1".to_string(),
+            operator2: "Op1".to_string(),
+            shared: 1,
+            link_type: 0,
+            provenance: LinkProvenance::Crossover,
+            multicast_capable: false,
+        };
+        let links = vec![crossover("A00", "A1"), crossover("A1", "B00")];
+        let demand = |kind: u32| ConsolidatedDemand {
+            start: "A00".to_string(),
+            end: "B00".to_string(),
+            receivers: 1,
+            traffic: 1.0,
+            priority: 1.0,
+            kind,
+            multicast: false,
+            original: kind,
+        };
+        let demands = vec![demand(1), demand(2)];
+        let overrides = BTreeMap::from([(2, 40.0)]);
+
+        let primitives = LpBuilderInput::new(&links, &demands)
+            .with_contiguity_bonus_by_type(&overrides)
+            .build()
+            .expect("LP builder should succeed");
+
+        // Type 1 keeps the global bonus, type 2 crossovers cost the override
+        assert_eq!(primitives.cost, vec![5.0, 5.0, 40.0, 40.0]);
+    }
+
+    #[test]
+    fn test_shared_group_index() {
+        let link = |device1: &str, device2: &str, shared, bandwidth| ConsolidatedLink {
+            device1: device1.to_string(),
+            device2: device2.to_string(),
+            latency: 1.0,
+            bandwidth,
+            operator1: "Op1".to_string(),
+            operator2: "Op2".to_string(),
+            shared,
+            link_type: 0,
+            provenance: LinkProvenance::OriginalPrivate,
+            multicast_capable: false,
+        };
+        // Links 0 and 2 share a row, limited by link 0
+        let links = vec![
+            link("A1", "B1", 1, 10.0),
+            link("B1", "C1", 2, 20.0),
+            link("A1", "B1", 1, 30.0),
+        ];
+        let demand = |kind: u32| ConsolidatedDemand {
+            start: "A1".to_string(),
+            end: "C1".to_string(),
+            receivers: 1,
+            traffic: 1.0,
+            priority: 1.0,
+            kind,
+            multicast: false,
+            original: kind,
+        };
+        let mut primitives = LpBuilderInput::new(&links, &[demand(1), demand(2)])
+            .build()
+            .unwrap();
+
+        let groups = &primitives.shared_groups.groups;
+        assert_eq!(groups.len(), primitives.bandwidth_rows);
+        assert_eq!(
+            (groups[0].links.as_slice(), groups[0].bandwidth),
+            (&[0, 2][..], 10.0)
+        );
+        // Three links per commodity
+        assert_eq!(groups[0].columns, vec![0, 2, 3, 5]);
+        assert_eq!(groups[1].columns, vec![1, 4]);
+        assert_eq!(primitives.ub_row_name[0], "bw_1_A1_B1");
+
+        let mut wider = links.clone();
+        wider[0].bandwidth = 15.0;
+        wider[2].bandwidth = 99.0;
+        primitives.patch_bandwidths(&wider);
+        assert_eq!(primitives.b_ub, vec![15.0, 20.0]);
+    }
 }
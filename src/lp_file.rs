@@ -0,0 +1,215 @@
+use std::{fmt::Write, path::Path};
+
+use crate::{
+    error::{Result, ShapleyError},
+    pipeline::{CscMatrix, LpProblem},
+};
+
+/// Name of the objective row
+const OBJECTIVE: &str = "COST";
+
+/// Terms per line of an LP file expression, well within reader line limits
+const TERMS_PER_LINE: usize = 6;
+
+/// Stand-in for unbounded right-hand sides, which the formats cannot spell
+const INFINITY: f64 = 1e30;
+
+impl LpProblem {
+    /// The LP in free MPS format, with the names of
+    /// [`LpColumn::name`](crate::pipeline::LpColumn::name) and
+    /// [`LpRow::name`](crate::pipeline::LpRow::name). Variables keep the
+    /// default bounds of zero to infinity.
+    pub fn to_mps(&self) -> String {
+        let eq_names: Vec<&str> = self.equality_rows.iter().map(String::as_str).collect();
+        let ub_names: Vec<&str> = self
+            .inequality_rows
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect();
+        let mut mps = String::from("NAME network_shapley\nROWS\n");
+        let _ = writeln!(mps, " N  {OBJECTIVE}");
+        for name in &self.equality_rows {
+            let _ = writeln!(mps, " E  {name}");
+        }
+        for row in &self.inequality_rows {
+            let _ = writeln!(mps, " L  {}", row.name);
+        }
+
+        mps.push_str("COLUMNS\n");
+        for (j, column) in self.columns.iter().enumerate() {
+            // Every column is listed, even one absent from the objective
+            let _ = writeln!(mps, "    {}  {OBJECTIVE}  {}", column.name, self.cost[j]);
+            for (matrix, names) in [(&self.a_eq, &eq_names), (&self.a_ub, &ub_names)] {
+                for idx in matrix.colptr[j]..matrix.colptr[j + 1] {
+                    let _ = writeln!(
+                        mps,
+                        "    {}  {}  {}",
+                        column.name, names[matrix.rowval[idx]], matrix.nzval[idx]
+                    );
+                }
+            }
+        }
+
+        mps.push_str("RHS\n");
+        for (name, &rhs) in self.equality_rows.iter().zip(&self.b_eq) {
+            if rhs != 0.0 {
+                let _ = writeln!(mps, "    RHS  {name}  {}", number(rhs));
+            }
+        }
+        for (row, &rhs) in self.inequality_rows.iter().zip(&self.b_ub) {
+            if rhs != 0.0 {
+                let _ = writeln!(mps, "    RHS  {}  {}", row.name, number(rhs));
+            }
+        }
+        mps.push_str("ENDATA\n");
+        mps
+    }
+
+    /// The LP in CPLEX LP format, named as in [`to_mps`](Self::to_mps)
+    pub fn to_lp(&self) -> String {
+        let (eq_rows, ub_rows) = (by_row(&self.a_eq), by_row(&self.a_ub));
+        let mut lp = String::from("\\ Routing LP of network-shapley\nMinimize\n");
+        let objective: Vec<(usize, f64)> = self
+            .cost
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|&(_, c)| c != 0.0)
+            .collect();
+        let _ = write!(lp, " {OBJECTIVE}:");
+        self.write_terms(&mut lp, &objective);
+        lp.push('\n');
+
+        lp.push_str("Subject To\n");
+        for ((name, terms), &rhs) in self.equality_rows.iter().zip(&eq_rows).zip(&self.b_eq) {
+            let _ = write!(lp, " {name}:");
+            self.write_terms(&mut lp, terms);
+            let _ = writeln!(lp, " = {}", number(rhs));
+        }
+        for ((row, terms), &rhs) in self.inequality_rows.iter().zip(&ub_rows).zip(&self.b_ub) {
+            let _ = write!(lp, " {}:", row.name);
+            self.write_terms(&mut lp, terms);
+            let _ = writeln!(lp, " <= {}", number(rhs));
+        }
+        lp.push_str("End\n");
+        lp
+    }
+
+    /// Write [`to_mps`](Self::to_mps) to `path`
+    pub fn write_mps(&self, path: impl AsRef<Path>) -> Result<()> {
+        write_file(path.as_ref(), &self.to_mps())
+    }
+
+    /// Write [`to_lp`](Self::to_lp) to `path`
+    pub fn write_lp(&self, path: impl AsRef<Path>) -> Result<()> {
+        write_file(path.as_ref(), &self.to_lp())
+    }
+
+    /// Append ` + 2 x - y ...`; an empty expression is written as a zero
+    /// term, since LP readers need a variable on every row
+    fn write_terms(&self, out: &mut String, terms: &[(usize, f64)]) {
+        if terms.is_empty() {
+            if let Some(column) = self.columns.first() {
+                let _ = write!(out, " 0 {}", column.name);
+            }
+            return;
+        }
+        for (k, &(j, coefficient)) in terms.iter().enumerate() {
+            if k > 0 && k % TERMS_PER_LINE == 0 {
+                out.push_str("\n   ");
+            }
+            let sign = if coefficient < 0.0 { '-' } else { '+' };
+            let _ = write!(
+                out,
+                " {sign} {} {}",
+                number(coefficient.abs()),
+                self.columns[j].name
+            );
+        }
+    }
+}
+
+/// `(column, coefficient)` entries of each row of `matrix`
+fn by_row(matrix: &CscMatrix<f64>) -> Vec<Vec<(usize, f64)>> {
+    let mut rows = vec![Vec::new(); matrix.m];
+    for j in 0..matrix.n {
+        for idx in matrix.colptr[j]..matrix.colptr[j + 1] {
+            rows[matrix.rowval[idx]].push((j, matrix.nzval[idx]));
+        }
+    }
+    rows
+}
+
+fn number(value: f64) -> String {
+    format!("{}", value.clamp(-INFINITY, INFINITY))
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<()> {
+    std::fs::write(path, contents).map_err(|e| ShapleyError::Io(format!("{}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashSet};
+
+    use crate::{
+        pipeline::build_lp,
+        shapley::testing::{RandomInputConfig, random_input},
+    };
+
+    #[test]
+    fn test_lp_files_name_every_row_and_column() {
+        let lp = build_lp(&random_input(3, &RandomInputConfig::default())).unwrap();
+        let names: HashSet<&str> = lp
+            .columns
+            .iter()
+            .map(|c| c.name.as_str())
+            .chain(lp.equality_rows.iter().map(String::as_str))
+            .chain(lp.inequality_rows.iter().map(|r| r.name.as_str()))
+            .collect();
+        assert_eq!(
+            names.len(),
+            lp.n_cols() + lp.b_eq.len() + lp.b_ub.len(),
+            "names are unique"
+        );
+        assert!(
+            names
+                .iter()
+                .all(|n| n.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        );
+
+        let mps = lp.to_mps();
+        assert!(mps.starts_with("NAME") && mps.ends_with("ENDATA\n"));
+        let lp_text = lp.to_lp();
+        for row in &lp.inequality_rows {
+            assert!(mps.contains(&format!(" L  {}\n", row.name)));
+            assert!(lp_text.contains(&format!(" {}:", row.name)));
+        }
+
+        // A coalition without its first operator loses that operator's links
+        let operators: Vec<&str> = lp
+            .columns
+            .iter()
+            .flat_map(|c| [c.operator1.as_str(), c.operator2.as_str()])
+            .filter(|op| *op != "Public")
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let coalition = lp.coalition(&operators[1..]);
+        assert!(coalition.n_cols() < lp.n_cols());
+        assert_eq!(coalition.a_eq.n, coalition.n_cols());
+        assert_eq!(coalition.a_ub.m, coalition.inequality_rows.len());
+        assert!(
+            coalition
+                .columns
+                .iter()
+                .all(|c| c.operator1 != operators[0])
+        );
+        assert!(
+            coalition
+                .inequality_rows
+                .iter()
+                .all(|r| r.operator1 != operators[0] && r.operator2 != operators[0])
+        );
+    }
+}
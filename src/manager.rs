@@ -0,0 +1,470 @@
+//! Running several computations in one process within shared budgets.
+//!
+//! A [`ComputeManager`] queues submitted inputs and starts them in order of
+//! submission while their estimated memory (see
+//! [`ShapleyInput::estimate_resources`]) and threads fit in what running jobs
+//! leave of the budget. A job that does not fit waits, and so do the jobs
+//! behind it, so a large job is never starved by a stream of small ones.
+//! Each job runs on a thread of its own with a dedicated pool of its thread
+//! share.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Result, ShapleyError},
+    options::{CancellationToken, ComputeResources, ProgressCounter, ShapleyOptions},
+    shapley::{ShapleyInput, ShapleyOutput},
+};
+
+/// Identifies a submitted job
+pub type JobId = u64;
+
+/// Resources shared by every job of a [`ComputeManager`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudget {
+    /// Estimated bytes all running jobs may use together
+    pub max_memory: u64,
+    /// Solver threads all running jobs may use together
+    pub max_threads: usize,
+}
+
+/// Where a job stands
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    /// `solved` of about `total` coalition LPs are done
+    Running {
+        solved: usize,
+        total: usize,
+    },
+    Finished,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_done(self) -> bool {
+        matches!(self, Self::Finished | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// Queue of computations sharing a [`ComputeBudget`]. Clones share the queue.
+#[derive(Debug, Clone)]
+pub struct ComputeManager {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    budget: ComputeBudget,
+    state: Mutex<State>,
+    finished: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    next_id: JobId,
+    queue: VecDeque<JobId>,
+    jobs: BTreeMap<JobId, Job>,
+    memory_in_use: u64,
+    threads_in_use: usize,
+}
+
+#[derive(Debug)]
+struct Job {
+    input: Option<ShapleyInput>,
+    options: ShapleyOptions,
+    memory: u64,
+    threads: usize,
+    total: usize,
+    status: JobStatus,
+    result: Option<Result<ShapleyOutput>>,
+}
+
+impl ComputeManager {
+    pub fn new(budget: ComputeBudget) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                budget,
+                state: Mutex::new(State::default()),
+                finished: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Queue `input` for [`ShapleyInput::compute_with`]. The job runs on
+    /// `options.resources.num_threads` threads, or the whole thread budget
+    /// if unset, at most. Inputs that fail validation or could never fit in
+    /// the budget are rejected here.
+    pub fn submit(&self, input: ShapleyInput, options: &ShapleyOptions) -> Result<JobId> {
+        let budget = self.shared.budget;
+        let threads = match options.resources.num_threads {
+            0 => budget.max_threads,
+            n => n.min(budget.max_threads),
+        }
+        .max(1);
+        let options = ShapleyOptions {
+            resources: ComputeResources::with_threads(threads),
+            // A job's own token, so cancelling it leaves the caller's alone
+            cancellation: Some(match &options.cancellation {
+                Some(caller) => caller.child(),
+                None => CancellationToken::new(),
+            }),
+            progress: Some(ProgressCounter::new()),
+            ..options.clone()
+        };
+        let estimate = input.estimate_resources(&options)?;
+        if estimate.est_memory > budget.max_memory {
+            return Err(ShapleyError::Validation(format!(
+                "Job needs an estimated {} bytes, more than the budget of {}",
+                estimate.est_memory, budget.max_memory
+            )));
+        }
+
+        let mut state = self.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.jobs.insert(
+            id,
+            Job {
+                input: Some(input),
+                options,
+                memory: estimate.est_memory,
+                threads,
+                total: estimate.n_coalitions * estimate.n_scenarios,
+                status: JobStatus::Queued,
+                result: None,
+            },
+        );
+        state.queue.push_back(id);
+        self.start_jobs(&mut state);
+        Ok(id)
+    }
+
+    /// `None` for unknown jobs and jobs whose result was taken by
+    /// [`wait`](Self::wait)
+    pub fn status(&self, id: JobId) -> Option<JobStatus> {
+        let state = self.lock();
+        let job = state.jobs.get(&id)?;
+        Some(match job.status {
+            JobStatus::Running { total, .. } => JobStatus::Running {
+                solved: job
+                    .options
+                    .progress
+                    .as_ref()
+                    .map_or(0, |p| p.solved().min(total)),
+                total,
+            },
+            status => status,
+        })
+    }
+
+    /// Status of every job not yet collected, by id
+    pub fn jobs(&self) -> BTreeMap<JobId, JobStatus> {
+        let ids: Vec<JobId> = self.lock().jobs.keys().copied().collect();
+        ids.into_iter()
+            .filter_map(|id| Some((id, self.status(id)?)))
+            .collect()
+    }
+
+    /// Stop a job: a queued job never starts, a running one stops at its
+    /// next batch of solves. Either way its result is
+    /// [`ShapleyError::Cancelled`].
+    pub fn cancel(&self, id: JobId) {
+        let mut state = self.lock();
+        let Some(job) = state.jobs.get_mut(&id) else {
+            return;
+        };
+        match job.status {
+            JobStatus::Queued => {
+                job.status = JobStatus::Cancelled;
+                job.result = Some(Err(ShapleyError::Cancelled));
+                job.input = None;
+                state.queue.retain(|&queued| queued != id);
+                self.start_jobs(&mut state);
+                self.shared.finished.notify_all();
+            }
+            JobStatus::Running { .. } => {
+                if let Some(cancellation) = &job.options.cancellation {
+                    cancellation.cancel();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Block until the job is done and take its result. A job that panicked
+    /// fails with [`ShapleyError::ThreadPool`].
+    pub fn wait(&self, id: JobId) -> Result<ShapleyOutput> {
+        let mut state = self.lock();
+        loop {
+            match state.jobs.get(&id) {
+                None => {
+                    return Err(ShapleyError::Validation(format!("Unknown job {id}")));
+                }
+                Some(job) if job.status.is_done() => {
+                    let job = state.jobs.remove(&id).expect("job is present");
+                    return job.result.unwrap_or(Err(ShapleyError::Cancelled));
+                }
+                Some(_) => {
+                    state = self
+                        .shared
+                        .finished
+                        .wait(state)
+                        .unwrap_or_else(|e| e.into_inner());
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.shared.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Start queued jobs in order while the first one fits
+    fn start_jobs(&self, state: &mut State) {
+        let budget = self.shared.budget;
+        while let Some(&id) = state.queue.front() {
+            let job = &state.jobs[&id];
+            let idle = state.threads_in_use == 0;
+            let fits = state.memory_in_use + job.memory <= budget.max_memory
+                && state.threads_in_use + job.threads <= budget.max_threads;
+            if !fits && !idle {
+                return;
+            }
+            state.queue.pop_front();
+            state.memory_in_use += job.memory;
+            state.threads_in_use += job.threads;
+
+            let job = state.jobs.get_mut(&id).expect("queued job is present");
+            job.status = JobStatus::Running {
+                solved: 0,
+                total: job.total,
+            };
+            let input = job.input.take().expect("queued job has its input");
+            let options = job.options.clone();
+            let manager = self.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("network-shapley-job-{id}"))
+                .spawn(move || {
+                    // A panic must still release the budget and wake waiters
+                    let result =
+                        panic::catch_unwind(AssertUnwindSafe(|| input.compute_with(&options)))
+                            .unwrap_or_else(|payload| {
+                                let message = payload
+                                    .downcast_ref::<&str>()
+                                    .map(|s| s.to_string())
+                                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                                    .unwrap_or_default();
+                                Err(ShapleyError::ThreadPool(format!(
+                                    "Job {id} panicked: {message}"
+                                )))
+                            });
+                    manager.finish(id, result);
+                });
+            if let Err(e) = spawned {
+                let job = state.jobs.get_mut(&id).expect("queued job is present");
+                job.status = JobStatus::Failed;
+                job.result = Some(Err(ShapleyError::ThreadPool(e.to_string())));
+                state.memory_in_use -= job.memory;
+                state.threads_in_use -= job.threads;
+                self.shared.finished.notify_all();
+            }
+        }
+    }
+
+    fn finish(&self, id: JobId, result: Result<ShapleyOutput>) {
+        let mut state = self.lock();
+        if let Some(job) = state.jobs.get_mut(&id) {
+            job.status = match &result {
+                Ok(_) => JobStatus::Finished,
+                Err(e) if matches!(e.root(), ShapleyError::Cancelled) => JobStatus::Cancelled,
+                Err(_) => JobStatus::Failed,
+            };
+            job.result = Some(result);
+            let (memory, threads) = (job.memory, job.threads);
+            state.memory_in_use -= memory;
+            state.threads_in_use -= threads;
+        }
+        self.start_jobs(&mut state);
+        self.shared.finished.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{
+        rules::{RuleInput, ValidationRule, ValidationRules},
+        shapley::testing::{RandomInputConfig, random_input},
+    };
+
+    /// Passes its first `passes` checks, then panics
+    #[derive(Debug)]
+    struct PanicsAfter {
+        passes: usize,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl ValidationRule for PanicsAfter {
+        fn name(&self) -> &str {
+            "panics_after"
+        }
+
+        fn check(&self, _input: &RuleInput<'_>) -> Result<()> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) >= self.passes {
+                panic!("rule exploded");
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_jobs_run_within_budget() {
+        let inputs: Vec<ShapleyInput> = (0..4)
+            .map(|seed| random_input(seed, &RandomInputConfig::default()))
+            .collect();
+        let options = ShapleyOptions::default();
+        let memory = inputs[0].estimate_resources(&options).unwrap().est_memory;
+
+        // Room for a single job at a time
+        let manager = ComputeManager::new(ComputeBudget {
+            max_memory: u64::MAX,
+            max_threads: 1,
+        });
+        let ids: Vec<JobId> = inputs
+            .iter()
+            .map(|input| manager.submit(input.clone(), &options).unwrap())
+            .collect();
+        let running = manager
+            .jobs()
+            .values()
+            .filter(|s| matches!(s, JobStatus::Running { .. }))
+            .count();
+        assert!(running <= 1);
+
+        for (id, input) in ids.into_iter().zip(&inputs) {
+            assert_eq!(manager.wait(id).unwrap(), input.compute().unwrap());
+            assert_eq!(manager.status(id), None);
+        }
+
+        let tiny = ComputeManager::new(ComputeBudget {
+            max_memory: memory / 2,
+            max_threads: 1,
+        });
+        assert!(tiny.submit(inputs[0].clone(), &options).is_err());
+    }
+
+    #[test]
+    fn test_cancel_jobs() {
+        // Large enough to still be running while the second job is queued
+        let config = RandomInputConfig {
+            operators: 12,
+            private_links: 30,
+            ..Default::default()
+        };
+        let input = random_input(1, &config);
+        let manager = ComputeManager::new(ComputeBudget {
+            max_memory: u64::MAX,
+            max_threads: 1,
+        });
+        let options = ShapleyOptions::default();
+        let first = manager.submit(input.clone(), &options).unwrap();
+        let second = manager.submit(input.clone(), &options).unwrap();
+        assert!(matches!(
+            manager.status(first),
+            Some(JobStatus::Running { total: 4096, .. })
+        ));
+        manager.cancel(second);
+        assert_eq!(manager.status(second), Some(JobStatus::Cancelled));
+        manager.cancel(first);
+
+        assert!(matches!(
+            manager.wait(first).unwrap_err().root(),
+            ShapleyError::Cancelled
+        ));
+        assert!(matches!(
+            manager.wait(second).unwrap_err(),
+            ShapleyError::Cancelled
+        ));
+    }
+
+    #[test]
+    fn test_cancel_leaves_shared_token_alone() {
+        let large = random_input(
+            1,
+            &RandomInputConfig {
+                operators: 12,
+                private_links: 30,
+                ..Default::default()
+            },
+        );
+        let small = random_input(0, &RandomInputConfig::default());
+        let manager = ComputeManager::new(ComputeBudget {
+            max_memory: u64::MAX,
+            max_threads: 1,
+        });
+        let caller = CancellationToken::new();
+        let options = ShapleyOptions {
+            cancellation: Some(caller.clone()),
+            ..Default::default()
+        };
+        let first = manager.submit(large, &options).unwrap();
+        let second = manager.submit(small.clone(), &options).unwrap();
+        manager.cancel(first);
+
+        assert!(manager.wait(first).is_err());
+        assert_eq!(manager.wait(second).unwrap(), small.compute().unwrap());
+        assert!(!caller.is_cancelled());
+
+        caller.cancel();
+        let third = manager.submit(small, &options).unwrap();
+        assert!(matches!(
+            manager.wait(third).unwrap_err().root(),
+            ShapleyError::Cancelled
+        ));
+    }
+
+    #[test]
+    fn test_panicking_job_fails_and_frees_budget() {
+        let input = random_input(0, &RandomInputConfig::default());
+        let manager = ComputeManager::new(ComputeBudget {
+            max_memory: u64::MAX,
+            max_threads: 1,
+        });
+        // Pass the checks of `submit`, panic in the job
+        let calls = Arc::new(AtomicUsize::new(0));
+        let with_rule = |passes| ShapleyOptions {
+            rules: ValidationRules::default().with_rule(PanicsAfter {
+                passes,
+                calls: Arc::clone(&calls),
+            }),
+            ..Default::default()
+        };
+        input.estimate_resources(&with_rule(usize::MAX)).unwrap();
+        let panicking = with_rule(calls.swap(0, Ordering::SeqCst));
+        let failed = manager.submit(input.clone(), &panicking).unwrap();
+        let next = manager
+            .submit(input.clone(), &ShapleyOptions::default())
+            .unwrap();
+
+        match manager.wait(failed).unwrap_err() {
+            ShapleyError::ThreadPool(msg) => assert!(msg.contains("rule exploded"), "{msg}"),
+            other => panic!("Expected a thread pool error, got {other}"),
+        }
+        // The job queued behind it still gets the budget
+        assert_eq!(manager.wait(next).unwrap(), input.compute().unwrap());
+    }
+}
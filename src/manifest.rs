@@ -0,0 +1,149 @@
+//! Scenario manifests: a single TOML or YAML file holding a whole
+//! [`ShapleyInput`], so a scenario can be stored and rerun as one artifact.
+//!
+//! Each of the four tables is either a path to a CSV file, relative to the
+//! manifest, or inline rows with the CSV column names as keys:
+//!
+//! ```toml
+//! private_links = "private_links.csv"
+//! devices = "devices.csv"
+//! public_links = "public_links.csv"
+//! operator_uptime = 0.98
+//! contiguity_bonus = 5.0
+//! demand_multiplier = 1.2
+//!
+//! [[demands]]
+//! start = "SIN"
+//! end = "AMS"
+//! receivers = 40
+//! traffic = 0.1
+//! priority = 0.62
+//! type = 1
+//! multicast = false
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, de::DeserializeOwned};
+
+use crate::{
+    error::{Result, ShapleyError},
+    shapley::ShapleyInput,
+    tables::{TableSchema, read_input_table_file},
+    types::{Demand, Device, PrivateLink, PublicLink},
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    private_links: Table<PrivateLink>,
+    devices: Table<Device>,
+    demands: Table<Demand>,
+    public_links: Table<PublicLink>,
+    operator_uptime: f64,
+    contiguity_bonus: f64,
+    demand_multiplier: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Table<T> {
+    Path(PathBuf),
+    Rows(Vec<T>),
+}
+
+impl<T: DeserializeOwned + TableSchema> Table<T> {
+    fn load(self, dir: &Path) -> Result<Vec<T>> {
+        match self {
+            Self::Path(path) => read_input_table_file(dir.join(path)),
+            Self::Rows(rows) => Ok(rows),
+        }
+    }
+}
+
+impl ShapleyInput {
+    /// Read a scenario manifest, see [`manifest`](crate::manifest). The
+    /// format follows the extension: `.toml`, or `.yaml`/`.yml`.
+    pub fn from_manifest(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let io_error =
+            |e: &dyn std::fmt::Display| ShapleyError::Io(format!("{}: {e}", path.display()));
+        let text = std::fs::read_to_string(path).map_err(|e| io_error(&e))?;
+        let manifest: Manifest = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&text).map_err(|e| io_error(&e))?,
+            Some("yaml" | "yml") => serde_yaml_ng::from_str(&text).map_err(|e| io_error(&e))?,
+            _ => {
+                return Err(ShapleyError::Validation(format!(
+                    "{}: manifests are .toml, .yaml or .yml files",
+                    path.display()
+                )));
+            }
+        };
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+        Ok(ShapleyInput {
+            private_links: manifest.private_links.load(dir)?,
+            devices: manifest.devices.load(dir)?,
+            demands: manifest.demands.load(dir)?,
+            public_links: manifest.public_links.load(dir)?,
+            operator_uptime: manifest.operator_uptime,
+            contiguity_bonus: manifest.contiguity_bonus,
+            demand_multiplier: manifest.demand_multiplier,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_manifest() {
+        let dir = std::env::temp_dir().join("network-shapley-manifest-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+        let table = |name: &str| fixtures.join(name).display().to_string();
+
+        let toml_path = dir.join("scenario.toml");
+        std::fs::write(
+            &toml_path,
+            format!(
+                "private_links = {:?}\ndevices = {:?}\ndemands = {:?}\npublic_links = {:?}\n\
+                 operator_uptime = 0.98\ncontiguity_bonus = 5.0\ndemand_multiplier = 1.2\n",
+                table("private_links.csv"),
+                table("devices.csv"),
+                table("demand1.csv"),
+                table("public_links.csv"),
+            ),
+        )
+        .unwrap();
+        let from_toml = ShapleyInput::from_manifest(&toml_path).unwrap();
+        assert_eq!(
+            from_toml.devices.len(),
+            read_input_table_file::<Device>(fixtures.join("devices.csv"))
+                .unwrap()
+                .len()
+        );
+        assert_eq!(from_toml.operator_uptime, 0.98);
+
+        // Inline rows, and paths relative to the manifest
+        std::fs::copy(fixtures.join("devices.csv"), dir.join("devices.csv")).unwrap();
+        let yaml_path = dir.join("scenario.yaml");
+        std::fs::write(
+            &yaml_path,
+            "private_links: []\ndevices: devices.csv\n\
+             demands:\n  - {start: SIN, end: AMS, receivers: 1, traffic: 2.0, priority: 1.0, type: 3, multicast: false}\n\
+             public_links:\n  - {city1: SIN, city2: AMS, latency: 150.0}\n\
+             operator_uptime: 1.0\ncontiguity_bonus: 5.0\ndemand_multiplier: 1.0\n",
+        )
+        .unwrap();
+        let from_yaml = ShapleyInput::from_manifest(&yaml_path).unwrap();
+        assert_eq!(from_yaml.devices.len(), from_toml.devices.len());
+        assert_eq!(from_yaml.demands[0].kind, 3);
+        assert_eq!(from_yaml.public_links[0].latency, 150.0);
+
+        std::fs::write(dir.join("scenario.txt"), "").unwrap();
+        assert!(ShapleyInput::from_manifest(dir.join("scenario.txt")).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
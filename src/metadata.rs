@@ -0,0 +1,146 @@
+//! How and where a computation ran, recorded in
+//! [`ShapleyReport::metadata`](crate::shapley::ShapleyReport::metadata) so that
+//! reports archived by different validator nodes can be reconciled: two
+//! reports of the same input and options agree bit for bit when their
+//! metadata match and neither took a timing-dependent path.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    options::ShapleyOptions,
+    shapley::{ComputationMethod, SolveSummary},
+};
+
+/// LP solver every coalition is solved with
+const SOLVER: &str = "simplex vendored from microlp 0.4.0";
+
+/// Crate features that can change what a computation does or reports
+const FEATURES: &[(&str, bool)] = &[
+    ("async", cfg!(feature = "async")),
+    ("audit", cfg!(feature = "audit")),
+    ("borsh", cfg!(feature = "borsh")),
+    ("cli", cfg!(feature = "cli")),
+    ("examples-data", cfg!(feature = "examples-data")),
+    ("geo", cfg!(feature = "geo")),
+    ("manifest", cfg!(feature = "manifest")),
+    ("research", cfg!(feature = "research")),
+    ("serde", cfg!(feature = "serde")),
+    ("surrogate", cfg!(feature = "surrogate")),
+];
+
+/// Floating-point arithmetic of the build
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FloatMode {
+    /// Every f64 operation rounded to IEEE 754 binary64, round to nearest,
+    /// without contraction into fused multiply-adds
+    #[default]
+    Binary64,
+    /// 32-bit x86 without SSE2, where the x87 unit may keep intermediates in
+    /// extended precision, so results can differ from `Binary64` builds
+    X87Extended,
+}
+
+impl FloatMode {
+    fn of_build() -> Self {
+        if cfg!(all(target_arch = "x86", not(target_feature = "sse2"))) {
+            Self::X87Extended
+        } else {
+            Self::Binary64
+        }
+    }
+}
+
+/// Build and run settings behind a report
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComputationMetadata {
+    pub crate_version: String,
+    pub solver: String,
+    /// Enabled crate features, sorted
+    pub features: Vec<String>,
+    /// `{arch}-{os}` of the build
+    pub target: String,
+    pub float_mode: FloatMode,
+    /// Threads of the pool the coalitions were solved on. Results do not
+    /// depend on it, see [`ShapleyOptions::deterministic`].
+    pub threads: usize,
+    /// Whether the computation ran in deterministic mode
+    pub deterministic: bool,
+    /// Seed samples were drawn with, for sampled values
+    pub sampling_seed: Option<u64>,
+    /// Seed for SRLG outage sampling, when SRLG availabilities were given
+    pub srlg_seed: Option<u64>,
+    /// A solver time limit was set and some coalition hit a limit or was
+    /// retried, so its value may differ between runs
+    pub timing_dependent: bool,
+}
+
+impl ComputationMetadata {
+    /// Metadata of a computation under `options` that ended with `method`
+    /// and `summary`. Call from inside the computation's pool.
+    pub(crate) fn new(
+        options: &ShapleyOptions,
+        method: &ComputationMethod,
+        summary: &SolveSummary,
+    ) -> Self {
+        let sampling_seed = match method {
+            ComputationMethod::Exact => None,
+            ComputationMethod::Sampled { .. } => options.approximation.map(|policy| policy.seed),
+        };
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            solver: SOLVER.to_string(),
+            features: FEATURES
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            target: format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS),
+            float_mode: FloatMode::of_build(),
+            threads: rayon::current_num_threads(),
+            deterministic: options.deterministic,
+            sampling_seed,
+            srlg_seed: (!options.srlg_availability.is_empty()).then_some(options.srlg_seed),
+            timing_dependent: options.solver.time_limit.is_some()
+                && (summary.limit_reached > 0 || summary.retried > 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        options::{ApproximationPolicy, ComputeResources},
+        shapley::testing::{RandomInputConfig, random_input},
+    };
+
+    #[test]
+    fn test_report_metadata() {
+        let input = random_input(5, &RandomInputConfig::default());
+        let options = ShapleyOptions {
+            resources: ComputeResources::with_threads(2),
+            ..Default::default()
+        };
+        let metadata = input.compute_report(&options).unwrap().metadata;
+        assert_eq!(metadata.crate_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(metadata.threads, 2);
+        assert_eq!(metadata.float_mode, FloatMode::of_build());
+        assert_eq!(
+            metadata.features.contains(&"serde".to_string()),
+            cfg!(feature = "serde")
+        );
+        assert_eq!((metadata.sampling_seed, metadata.srlg_seed), (None, None));
+        assert!(!metadata.timing_dependent);
+
+        let sampled = options.allow_large_games(ApproximationPolicy {
+            exact_up_to: 1,
+            seed: 7,
+            ..Default::default()
+        });
+        let metadata = input.compute_report(&sampled).unwrap().metadata;
+        assert_eq!(metadata.sampling_seed, Some(7));
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
 
 use crate::{
     error::{Result, ShapleyError},
@@ -12,18 +12,8 @@ pub(crate) fn build_j1_matrix(
     n_private: usize,
     max_shared: usize,
 ) -> Result<CscMatrix<f64>> {
-    let n_links = links.len();
-    let mut triplets = Vec::new();
-
     // J1 includes all private links (first n_private links)
-    for (col, link) in links[..n_private].iter().enumerate() {
-        if link.shared > 0 && link.shared as usize <= max_shared {
-            // Row index is shared_id - 1 (0-based)
-            triplets.push((link.shared as usize - 1, col, 1.0));
-        }
-    }
-
-    build_csc_from_triplets(&triplets, max_shared, n_links)
+    Ok(shared_id_matrix(links, max_shared, |col| col < n_private))
 }
 
 /// Build J2 matrix - only multicast ineligible links grouped by shared ID
@@ -32,20 +22,34 @@ pub(crate) fn build_j2_matrix(
     mcast_ineligible: &[usize],
     max_shared: usize,
 ) -> Result<CscMatrix<f64>> {
-    let n_links = links.len();
-    let mut triplets = Vec::new();
-
-    // J2 includes only multicast ineligible links
+    let mut ineligible = vec![false; links.len()];
     for &idx in mcast_ineligible {
         if idx < links.len() {
-            let link = &links[idx];
-            if link.shared > 0 && link.shared as usize <= max_shared {
-                triplets.push((link.shared as usize - 1, idx, 1.0));
-            }
+            ineligible[idx] = true;
         }
     }
+    Ok(shared_id_matrix(links, max_shared, |col| ineligible[col]))
+}
 
-    build_csc_from_triplets(&triplets, max_shared, n_links)
+/// One column per link with a 1 at row `shared - 1` (0-based) for the links
+/// `include` accepts, written column by column. Links without a shared ID,
+/// or one beyond `max_shared`, get an empty column.
+fn shared_id_matrix(
+    links: &[ConsolidatedLink],
+    max_shared: usize,
+    include: impl Fn(usize) -> bool,
+) -> CscMatrix<f64> {
+    let mut col_ptr = Vec::with_capacity(links.len() + 1);
+    let mut row_ind = Vec::with_capacity(links.len());
+    col_ptr.push(0);
+    for (col, link) in links.iter().enumerate() {
+        if include(col) && link.shared > 0 && link.shared as usize <= max_shared {
+            row_ind.push(link.shared as usize - 1);
+        }
+        col_ptr.push(row_ind.len());
+    }
+    let values = vec![1.0; row_ind.len()];
+    CscMatrix::new(max_shared, links.len(), col_ptr, row_ind, values)
 }
 
 /// Compute (J1 - J2) matrix for multicast constraints
@@ -59,31 +63,44 @@ pub(crate) fn compute_j1_minus_j2(
         ));
     }
 
-    // Accumulate entries in a HashMap for O(nnz) performance
-    let mut entries: HashMap<(usize, usize), f64> = HashMap::new();
+    // Merge the sorted row indices of each column pair, dropping entries
+    // that cancel
+    let nnz = j1.nnz() + j2.nnz();
+    let mut col_ptr = Vec::with_capacity(j1.n + 1);
+    let mut row_ind = Vec::with_capacity(nnz);
+    let mut values = Vec::with_capacity(nnz);
+    col_ptr.push(0);
 
-    // Add J1 entries
     for col in 0..j1.n {
-        for idx in j1.colptr[col]..j1.colptr[col + 1] {
-            *entries.entry((j1.rowval[idx], col)).or_default() += j1.nzval[idx];
-        }
-    }
-
-    // Subtract J2 entries
-    for col in 0..j2.n {
-        for idx in j2.colptr[col]..j2.colptr[col + 1] {
-            *entries.entry((j2.rowval[idx], col)).or_default() -= j2.nzval[idx];
+        let (mut a, a_end) = (j1.colptr[col], j1.colptr[col + 1]);
+        let (mut b, b_end) = (j2.colptr[col], j2.colptr[col + 1]);
+        while a < a_end || b < b_end {
+            let row_a = if a < a_end { j1.rowval[a] } else { usize::MAX };
+            let row_b = if b < b_end { j2.rowval[b] } else { usize::MAX };
+            let (row, value) = match row_a.cmp(&row_b) {
+                Ordering::Less => {
+                    a += 1;
+                    (row_a, j1.nzval[a - 1])
+                }
+                Ordering::Greater => {
+                    b += 1;
+                    (row_b, -j2.nzval[b - 1])
+                }
+                Ordering::Equal => {
+                    a += 1;
+                    b += 1;
+                    (row_a, j1.nzval[a - 1] - j2.nzval[b - 1])
+                }
+            };
+            if value.abs() > 1e-10 {
+                row_ind.push(row);
+                values.push(value);
+            }
         }
+        col_ptr.push(row_ind.len());
     }
 
-    // Remove near-zero entries and convert to triplets
-    entries.retain(|_, v| v.abs() > 1e-10);
-    let triplets: Vec<(usize, usize, f64)> = entries
-        .into_iter()
-        .map(|((row, col), val)| (row, col, val))
-        .collect();
-
-    build_csc_from_triplets(&triplets, j1.m, j1.n)
+    Ok(CscMatrix::new(j1.m, j1.n, col_ptr, row_ind, values))
 }
 
 /// Extract columns from a matrix for multicast eligible links
@@ -122,52 +139,6 @@ pub(crate) fn extract_mcast_eligible_columns(
     ))
 }
 
-/// Build CSC matrix from triplets (helper function)
-fn build_csc_from_triplets(
-    triplets: &[(usize, usize, f64)],
-    n_rows: usize,
-    n_cols: usize,
-) -> Result<CscMatrix<f64>> {
-    if triplets.is_empty() {
-        return Ok(CscMatrix::new(
-            n_rows,
-            n_cols,
-            vec![0; n_cols + 1],
-            vec![],
-            vec![],
-        ));
-    }
-
-    // Sort triplets by column, then row
-    let mut sorted_triplets = triplets.to_vec();
-    sorted_triplets.sort_by_key(|&(r, c, _)| (c, r));
-
-    let mut col_ptr = vec![0];
-    let mut row_ind = Vec::new();
-    let mut values = Vec::new();
-
-    let mut current_col = 0;
-
-    for &(row, col, val) in &sorted_triplets {
-        // Fill in empty columns
-        while current_col < col {
-            col_ptr.push(row_ind.len());
-            current_col += 1;
-        }
-
-        row_ind.push(row);
-        values.push(val);
-    }
-
-    // Fill remaining columns
-    while current_col < n_cols {
-        col_ptr.push(row_ind.len());
-        current_col += 1;
-    }
-
-    Ok(CscMatrix::new(n_rows, n_cols, col_ptr, row_ind, values))
-}
-
 /// Horizontally stack matrices
 pub(crate) fn hstack_matrices(matrices: &[&CscMatrix<f64>]) -> Result<CscMatrix<f64>> {
     if matrices.is_empty() {
@@ -187,33 +158,30 @@ pub(crate) fn hstack_matrices(matrices: &[&CscMatrix<f64>]) -> Result<CscMatrix<
         }
     }
 
-    let mut col_ptr = vec![0];
-    let mut row_ind = Vec::new();
-    let mut values = Vec::new();
+    let total_cols: usize = matrices.iter().map(|m| m.n).sum();
+    let total_nnz: usize = matrices.iter().map(|m| m.nnz()).sum();
+    let mut col_ptr = Vec::with_capacity(total_cols + 1);
+    let mut row_ind = Vec::with_capacity(total_nnz);
+    let mut values = Vec::with_capacity(total_nnz);
+    col_ptr.push(0);
 
+    // Each matrix's entries are one contiguous slice; only the column
+    // pointers need shifting
     for &matrix in matrices {
-        for col in 0..matrix.n {
-            let start = matrix.colptr[col];
-            let end = matrix.colptr[col + 1];
-
-            for idx in start..end {
-                row_ind.push(matrix.rowval[idx]);
-                values.push(matrix.nzval[idx]);
-            }
-
-            col_ptr.push(row_ind.len());
-        }
+        let (first, last) = (matrix.colptr[0], matrix.colptr[matrix.n]);
+        let offset = row_ind.len();
+        row_ind.extend_from_slice(&matrix.rowval[first..last]);
+        values.extend_from_slice(&matrix.nzval[first..last]);
+        col_ptr.extend(matrix.colptr[1..].iter().map(|&p| p - first + offset));
     }
 
-    let total_cols = matrices.iter().map(|m| m.n).sum();
-
     Ok(CscMatrix::new(n_rows, total_cols, col_ptr, row_ind, values))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::ConsolidatedLink;
+    use crate::types::{ConsolidatedLink, LinkProvenance};
 
     #[test]
     fn test_build_j2_matrix_empty_ineligible() {
@@ -227,6 +195,8 @@ mod tests {
                 operator2: "Op1".to_string(),
                 shared: 1,
                 link_type: 0,
+                provenance: LinkProvenance::OriginalPrivate,
+                multicast_capable: false,
             },
             ConsolidatedLink {
                 device1: "B".to_string(),
@@ -237,6 +207,8 @@ mod tests {
                 operator2: "Op2".to_string(),
                 shared: 2,
                 link_type: 0,
+                provenance: LinkProvenance::OriginalPrivate,
+                multicast_capable: false,
             },
         ];
 
@@ -264,6 +236,8 @@ mod tests {
                 operator2: "Op1".to_string(),
                 shared: 1,
                 link_type: 0,
+                provenance: LinkProvenance::OriginalPrivate,
+                multicast_capable: false,
             },
             ConsolidatedLink {
                 device1: "B".to_string(),
@@ -274,6 +248,8 @@ mod tests {
                 operator2: "Op2".to_string(),
                 shared: 2,
                 link_type: 0,
+                provenance: LinkProvenance::OriginalPrivate,
+                multicast_capable: false,
             },
         ];
 
@@ -301,6 +277,8 @@ mod tests {
                 operator2: "Op1".to_string(),
                 shared: 1,
                 link_type: 0,
+                provenance: LinkProvenance::OriginalPrivate,
+                multicast_capable: false,
             },
             ConsolidatedLink {
                 device1: "B".to_string(),
@@ -311,6 +289,8 @@ mod tests {
                 operator2: "Op2".to_string(),
                 shared: 2,
                 link_type: 0,
+                provenance: LinkProvenance::OriginalPrivate,
+                multicast_capable: false,
             },
         ];
 
@@ -329,6 +309,18 @@ mod tests {
         assert_eq!(result.n, links.len());
     }
 
+    #[test]
+    fn test_compute_j1_minus_j2_merges_columns() {
+        let j1 = CscMatrix::<f64>::from(&[[1.0, 0.0, 2.0], [3.0, 1.0, 0.0], [0.0, 4.0, 1.0]]);
+        let j2 = CscMatrix::<f64>::from(&[[1.0, 5.0, 0.0], [0.0, 1.0, 0.0], [2.0, 0.0, 1.0]]);
+        let result = compute_j1_minus_j2(&j1, &j2).unwrap();
+
+        // Entries that cancel are dropped; rows stay sorted within columns
+        assert_eq!(result.colptr, vec![0, 2, 4, 5]);
+        assert_eq!(result.rowval, vec![1, 2, 0, 2, 0]);
+        assert_eq!(result.nzval, vec![3.0, -2.0, -5.0, 4.0, 2.0]);
+    }
+
     #[test]
     fn test_compute_j1_minus_j2_error_propagation() {
         let links = vec![ConsolidatedLink {
@@ -340,6 +332,8 @@ mod tests {
             operator2: "Op1".to_string(),
             shared: 3, // Shared ID exceeds max_shared
             link_type: 0,
+            provenance: LinkProvenance::OriginalPrivate,
+            multicast_capable: false,
         }];
 
         let n_private = 1;
@@ -0,0 +1,209 @@
+//! What a prospective operator would earn by joining the network, and what
+//! its arrival would cost each incumbent.
+
+use std::collections::{BTreeMap, HashSet};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Result, ShapleyError},
+    shapley::{Operator, ShapleyInput, ShapleyOutput},
+    types::{Device, PrivateLink},
+};
+
+/// Shapley values with and without a hypothetical new operator, see
+/// [`ShapleyInput::simulate_new_operator`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct OnboardingSimulation {
+    /// The newcomer
+    pub operator: Operator,
+    /// Values of the network as it is
+    pub before: ShapleyOutput,
+    /// Values once the newcomer joins, its own included
+    pub after: ShapleyOutput,
+    /// Each incumbent's value before minus after: positive for incumbents the
+    /// newcomer takes value from, negative for those it complements
+    pub dilution: BTreeMap<Operator, f64>,
+}
+
+impl OnboardingSimulation {
+    pub(crate) fn new(operator: Operator, before: ShapleyOutput, after: ShapleyOutput) -> Self {
+        let dilution = before
+            .iter()
+            .map(|(op, value)| {
+                let joined = after.get(op).map_or(0.0, |v| v.value);
+                (op.clone(), value.value - joined)
+            })
+            .collect();
+        Self {
+            operator,
+            before,
+            after,
+            dilution,
+        }
+    }
+
+    /// The newcomer's expected Shapley value
+    pub fn value(&self) -> f64 {
+        self.after.get(&self.operator).map_or(0.0, |v| v.value)
+    }
+}
+
+/// `input` with the newcomer's devices and links added, and the newcomer.
+/// The devices must all belong to one operator the input does not have yet,
+/// and every link must end at one of them.
+pub(crate) fn augment(
+    input: &ShapleyInput,
+    links: Vec<PrivateLink>,
+    devices: Vec<Device>,
+) -> Result<(ShapleyInput, Operator)> {
+    let Some(operator) = devices.first().map(|d| d.operator.clone()) else {
+        return Err(ShapleyError::Validation(
+            "A new operator needs at least one device".to_string(),
+        ));
+    };
+    if let Some(other) = devices.iter().find(|d| d.operator != operator) {
+        return Err(ShapleyError::Validation(format!(
+            "New devices must belong to one operator, got {operator} and {}",
+            other.operator
+        )));
+    }
+    if operator == "Private" || operator == "Public" {
+        return Err(ShapleyError::Validation(format!(
+            "{operator} is not an operator that can join"
+        )));
+    }
+    if input.devices.iter().any(|d| d.operator == operator) {
+        return Err(ShapleyError::Validation(format!(
+            "Operator {operator} is already in the network"
+        )));
+    }
+
+    let new_devices: HashSet<&str> = devices.iter().map(|d| d.device.as_str()).collect();
+    if let Some(device) = input
+        .devices
+        .iter()
+        .find(|d| new_devices.contains(d.device.as_str()))
+    {
+        return Err(ShapleyError::Validation(format!(
+            "Device {} of {operator} already belongs to {}",
+            device.device, device.operator
+        )));
+    }
+    if let Some(link) = links.iter().find(|l| {
+        !new_devices.contains(l.device1.as_str()) && !new_devices.contains(l.device2.as_str())
+    }) {
+        return Err(ShapleyError::Validation(format!(
+            "Link {}-{} does not end at a device of {operator}",
+            link.device1, link.device2
+        )));
+    }
+
+    let mut augmented = input.clone();
+    augmented.devices.extend(devices);
+    augmented.private_links.extend(links);
+    Ok((augmented, operator))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        options::ShapleyOptions,
+        shapley::ShapleyInput,
+        types::{Demand, Device, PrivateLink, PublicLink},
+    };
+
+    fn input() -> ShapleyInput {
+        ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("SIN1".into(), "FRA1".into(), 40.0, 10.0, 1.0, None),
+                PrivateLink::new("FRA1".into(), "AMS1".into(), 10.0, 10.0, 1.0, None),
+            ],
+            devices: vec![
+                Device::new("SIN1".into(), 1, "Alpha".into()),
+                Device::new("FRA1".into(), 1, "Beta".into()),
+                Device::new("AMS1".into(), 1, "Beta".into()),
+            ],
+            demands: vec![Demand::new(
+                "SIN".into(),
+                "AMS".into(),
+                1,
+                1.0,
+                1.0,
+                1,
+                false,
+            )],
+            public_links: vec![
+                PublicLink::new("SIN".into(), "FRA".into(), 100.0),
+                PublicLink::new("FRA".into(), "AMS".into(), 20.0),
+            ],
+            operator_uptime: 0.9,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_simulate_new_operator() {
+        let input = input();
+        let options = ShapleyOptions::default();
+        let simulation = input
+            .simulate_new_operator(
+                &options,
+                vec![PrivateLink::new(
+                    "FRA1".into(),
+                    "SIN1".into(),
+                    5.0,
+                    10.0,
+                    1.0,
+                    None,
+                )],
+                vec![Device::new("AMS2".into(), 1, "Gamma".into())],
+            )
+            .unwrap_err();
+        assert!(simulation.to_string().contains("FRA1-SIN1"));
+
+        let simulation = input
+            .simulate_new_operator(
+                &options,
+                vec![PrivateLink::new(
+                    "FRA2".into(),
+                    "AMS2".into(),
+                    5.0,
+                    10.0,
+                    1.0,
+                    None,
+                )],
+                vec![
+                    Device::new("FRA2".into(), 1, "Gamma".into()),
+                    Device::new("AMS2".into(), 1, "Gamma".into()),
+                ],
+            )
+            .unwrap();
+
+        // The incumbents' game is the coalitions without the newcomer
+        let before = input.compute_with(&options).unwrap();
+        for (op, value) in &before {
+            assert!((simulation.before[op].value - value.value).abs() < 1e-9);
+        }
+        assert_eq!(simulation.operator, "Gamma");
+        assert!(simulation.value() > 0.0);
+        // Gamma's faster FRA-AMS link competes with Beta's
+        assert!(simulation.dilution["Beta"] > 0.0);
+        let total_after: f64 = simulation.after.values().map(|v| v.value).sum();
+        let total_before: f64 = before.values().map(|v| v.value).sum();
+        assert!(total_after >= total_before - 1e-9);
+
+        assert!(
+            input
+                .simulate_new_operator(
+                    &options,
+                    Vec::new(),
+                    vec![Device::new("AMS2".into(), 1, "Alpha".into())],
+                )
+                .is_err()
+        );
+    }
+}
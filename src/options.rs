@@ -0,0 +1,744 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "unstable-backend")]
+use crate::batch::BatchLpBackend;
+
+use crate::{
+    cost::CostModel,
+    error::{Result, ShapleyError},
+    profile::DemandProfile,
+    rolling::LpCache,
+    rules::ValidationRules,
+    types::{BandwidthUnit, Regions},
+};
+
+/// Optional knobs for a Shapley computation. The defaults reproduce the
+/// behaviour of [`ShapleyInput::compute`](crate::shapley::ShapleyInput::compute).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone)]
+pub struct ShapleyOptions {
+    /// Per traffic type (`Demand::kind`) override of the contiguity bonus, i.e. the
+    /// latency charged when that traffic crosses between the private network and
+    /// the public internet. Types not listed use the city and operator
+    /// overrides below, then `ShapleyInput::contiguity_bonus`.
+    pub contiguity_bonus_by_type: BTreeMap<u32, f64>,
+    /// Per city override of the contiguity bonus, for exchange points whose
+    /// peering with the public internet is better or worse than elsewhere
+    pub contiguity_bonus_by_city: BTreeMap<String, f64>,
+    /// Per operator override of the contiguity bonus, charged where an
+    /// operator's devices cross to the public internet in cities without an
+    /// override of their own
+    pub contiguity_bonus_by_operator: BTreeMap<String, f64>,
+    /// Per traffic type (`Demand::kind`) override of the demand multiplier, e.g.
+    /// a different oversubscription factor for multicast than for unicast
+    /// traffic. Types not listed use `ShapleyInput::demand_multiplier`.
+    pub demand_multiplier_by_type: BTreeMap<u32, f64>,
+    /// Per traffic type (`Demand::kind`) minimum traffic, in the unit of the
+    /// type's demands. Demands of a listed type below the floor are raised to
+    /// it, in every time slot of a type with a profile, before multipliers
+    /// apply, so operators earn value for standing connectivity even while
+    /// the type carries no traffic.
+    pub traffic_floor_by_type: BTreeMap<u32, f64>,
+    /// Value every coalition at the worst traffic of each demand's range
+    /// (`Demand::traffic_low` to `Demand::traffic_high`) instead of at its
+    /// `traffic`, for conservative allocations under volatile demand. Link
+    /// costs are non-negative, so a coalition's routing cost can only grow
+    /// with traffic: the worst case over the ranges is every demand at its
+    /// high end, and the robust LP is the nominal LP there. A custom
+    /// `cost_model` must keep costs non-negative for this to hold. Types
+    /// with a demand profile cannot have ranges, as the profile sets their
+    /// traffic.
+    pub robust: bool,
+    /// Objective cost per link. `None` uses latency, so coalition values are
+    /// latency savings; a [`MonetaryCost`](crate::cost::MonetaryCost) expresses
+    /// them in currency instead, and a [`TransitCost`](crate::cost::TransitCost)
+    /// adds the public transit saved.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub cost_model: Option<Arc<dyn CostModel>>,
+    /// Probability that each shared-risk link group (`PrivateLink::srlg`) is up.
+    /// Groups not listed never fail. A coalition an outage leaves infeasible
+    /// adds nothing for that outage, as under operator uptime; one that no
+    /// outage state can route stays infeasible.
+    pub srlg_availability: BTreeMap<u32, f64>,
+    /// Joint SRLG outage states are enumerated while there are at most this many;
+    /// beyond that, this many are sampled. Each state costs a full set of
+    /// coalition solves.
+    pub srlg_max_scenarios: usize,
+    /// Seed for SRLG outage sampling
+    pub srlg_seed: u64,
+    /// Traffic over time per demand type (`Demand::kind`), replacing the
+    /// `traffic` of that type's demands. Coalition values are averaged over
+    /// the profiles' time slots, each costing a full set of coalition solves
+    /// (per SRLG state); slots with identical traffic are solved once.
+    /// Single-routing reports (utilization, `evaluate_coalition`) use the
+    /// demands as given.
+    pub demand_profiles: BTreeMap<u32, DemandProfile>,
+    /// Cities of each region, with weights, for demands known only region to
+    /// region. A demand whose start or end names a region is expanded into
+    /// demands between its cities, traffic split by the weights, under new
+    /// demand types that inherit the per-type settings above.
+    pub regions: Regions,
+    /// Limits applied to every coalition LP
+    pub solver: SolverOptions,
+    /// Solver of the coalition LPs, handed them in batches. `None` uses the
+    /// vendored simplex, [`SimplexBackend`](crate::batch::SimplexBackend).
+    /// Unstable: the backend interface may change in any release.
+    #[cfg(feature = "unstable-backend")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub backend: Option<Arc<dyn BatchLpBackend>>,
+    /// Handling of coalition LPs that hit a solver limit or fail numerically
+    pub failure_policy: FailurePolicy,
+    /// Skip the LP of any coalition whose value is already pinned to within
+    /// this tolerance by the values of solved subsets and supersets, and use
+    /// the midpoint instead. Every Shapley value then stays within the
+    /// tolerance of the exact one. `None` solves every coalition. Not
+    /// available with `priority_classes`, whose values are not monotone.
+    pub prune_tolerance: Option<f64>,
+    /// Threads the coalition solves run on
+    pub resources: ComputeResources,
+    /// Checked between batches of coalition solves; once cancelled, the
+    /// computation returns [`ShapleyError::Cancelled`]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub cancellation: Option<CancellationToken>,
+    /// Advanced by the number of coalition LPs in each batch solved, for
+    /// following a running computation from another thread
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub progress: Option<ProgressCounter>,
+    /// Built LPs kept between computations, reused when the consolidated
+    /// network is unchanged and patched when only bandwidths changed; see
+    /// [`RollingShapley`](crate::rolling::RollingShapley)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub lp_cache: Option<LpCache>,
+    /// Guarantee bitwise identical output across runs and thread counts.
+    /// Coalitions are always solved independently and their results reduced
+    /// in coalition order, so the only run-to-run variation comes from
+    /// wall-clock limits; this mode rejects `SolverOptions::time_limit`.
+    pub deterministic: bool,
+    /// Handling of private links given more than once
+    pub duplicate_links: DuplicateLinkPolicy,
+    /// Handling of demands given more than once
+    pub duplicate_demands: DuplicateDemandPolicy,
+    /// Private links, by their devices in either order, that every
+    /// coalition may use and no operator is credited for, e.g.
+    /// foundation-owned links between operators' devices. They are treated
+    /// as links between `Private` devices by the coalition game and by
+    /// [`crate::pipeline`] alike. Their devices keep their operators, so a
+    /// coalition still reaches such a link only through its own links or
+    /// other always-present ones.
+    pub unrewarded_links: Vec<(String, String)>,
+    /// Which private link capacity the LP uses
+    pub capacity: CapacityBasis,
+    /// Bandwidth set aside on private links for protected demand types, as
+    /// operators sell it under contract: the reserved capacity carries only
+    /// the protected type, and every other type shares what is left
+    pub reservations: Vec<CapacityReservation>,
+    /// Extra cost of loading private links close to their capacity, so that
+    /// routing at 99% utilization is not free. Links without a curve cost
+    /// the same at any load.
+    pub congestion: Vec<CongestionCurve>,
+    /// Which statistic of private link latency the objective is built from
+    pub latency_metric: LatencyMetric,
+    /// Units assumed for rows that do not name their own
+    pub units: Units,
+    /// Normalization of city codes and device names, applied to every table
+    /// (and to the device and city names in `reservations`,
+    /// `unrewarded_links`, `regions` and `contiguity_bonus_by_city`) before
+    /// validation
+    pub labels: LabelNormalization,
+    /// Checks the inputs must pass, see [`crate::rules`]
+    pub rules: ValidationRules,
+    /// Route demands preemptively by priority class (`Demand::class`): each
+    /// class is routed optimally in the capacity left by the flows of the
+    /// classes before it, which stay fixed. A coalition's value is the sum
+    /// over its classes, so bulk traffic cannot displace priority traffic.
+    /// Utilization reports and single-coalition evaluations still route all
+    /// classes together.
+    pub priority_classes: bool,
+    /// When set, operator counts beyond the policy's exact limit are handled
+    /// by sampling instead of being rejected. `None` computes exactly; see
+    /// [`allow_large_games`](Self::allow_large_games).
+    pub approximation: Option<ApproximationPolicy>,
+    /// How the value of links between two operators' devices is split
+    /// between the owners, see [`CoOwnershipPolicy`]
+    pub co_ownership: CoOwnershipPolicy,
+    /// Name of the [`Preset`](crate::preset::Preset) the options and the
+    /// input's parameters came from, recorded in
+    /// [`ShapleyReport::preset`](crate::shapley::ShapleyReport::preset)
+    pub preset: Option<String>,
+}
+
+impl Default for ShapleyOptions {
+    fn default() -> Self {
+        Self {
+            contiguity_bonus_by_type: BTreeMap::new(),
+            contiguity_bonus_by_city: BTreeMap::new(),
+            contiguity_bonus_by_operator: BTreeMap::new(),
+            demand_multiplier_by_type: BTreeMap::new(),
+            traffic_floor_by_type: BTreeMap::new(),
+            robust: false,
+            cost_model: None,
+            srlg_availability: BTreeMap::new(),
+            srlg_max_scenarios: 256,
+            srlg_seed: 0,
+            demand_profiles: BTreeMap::new(),
+            regions: BTreeMap::new(),
+            solver: SolverOptions::default(),
+            #[cfg(feature = "unstable-backend")]
+            backend: None,
+            failure_policy: FailurePolicy::default(),
+            prune_tolerance: None,
+            resources: ComputeResources::default(),
+            cancellation: None,
+            progress: None,
+            lp_cache: None,
+            deterministic: false,
+            duplicate_links: DuplicateLinkPolicy::default(),
+            duplicate_demands: DuplicateDemandPolicy::default(),
+            unrewarded_links: Vec::new(),
+            capacity: CapacityBasis::default(),
+            reservations: Vec::new(),
+            congestion: Vec::new(),
+            latency_metric: LatencyMetric::default(),
+            units: Units::default(),
+            labels: LabelNormalization::default(),
+            rules: ValidationRules::default(),
+            priority_classes: false,
+            approximation: None,
+            co_ownership: CoOwnershipPolicy::default(),
+            preset: None,
+        }
+    }
+}
+
+impl ShapleyOptions {
+    /// Opt in to games beyond the exact operator limit: inputs with more
+    /// operators than `policy.exact_up_to`, up to 31, are estimated under
+    /// `policy` instead of rejected with [`ShapleyError::TooManyOperators`].
+    /// Reports that need every coalition value keep the exact limit.
+    pub fn allow_large_games(mut self, policy: ApproximationPolicy) -> Self {
+        self.approximation = Some(policy);
+        self
+    }
+}
+
+/// Where coalition LPs are solved in parallel. By default they run on the
+/// caller's current rayon pool, i.e. the global pool unless the call is made
+/// inside [`rayon::ThreadPool::install`]; the crate never configures that pool.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComputeResources {
+    /// Size of the dedicated pool; 0 means one thread per core. Setting it
+    /// implies `use_dedicated_pool`.
+    pub num_threads: usize,
+    /// Build a pool for each computation, torn down when it returns
+    pub use_dedicated_pool: bool,
+}
+
+impl ComputeResources {
+    pub fn with_threads(num_threads: usize) -> Self {
+        Self {
+            num_threads,
+            use_dedicated_pool: true,
+        }
+    }
+
+    /// Run `f` in the pool these settings ask for
+    pub(crate) fn install<T: Send>(&self, f: impl FnOnce() -> Result<T> + Send) -> Result<T> {
+        if !self.use_dedicated_pool && self.num_threads == 0 {
+            return f();
+        }
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(self.num_threads)
+            .build()
+            .map_err(|e| ShapleyError::ThreadPool(e.to_string()))?
+            .install(f)
+    }
+}
+
+/// Shared flag for stopping a running computation from another thread.
+/// Clones share the flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    flag: Arc<AtomicBool>,
+    parent: Option<Box<CancellationToken>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A token cancelled along with this one, whose own cancellation leaves
+    /// this one untouched
+    pub fn child(&self) -> Self {
+        Self {
+            flag: Arc::default(),
+            parent: Some(Box::new(self.clone())),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed) || self.parent.as_ref().is_some_and(|p| p.is_cancelled())
+    }
+}
+
+/// Shared count of coalition LPs solved so far. Clones share the count.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressCounter(Arc<AtomicUsize>);
+
+impl ProgressCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn solved(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn add(&self, solved: usize) {
+        self.0.fetch_add(solved, Ordering::Relaxed);
+    }
+}
+
+/// Default units for link bandwidth and demand traffic, applied to rows whose
+/// `unit` is `None`. Leaving both unset with no unit on any row keeps the
+/// inputs unit-less.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Units {
+    /// Unit of `PrivateLink::bandwidth`
+    pub bandwidth: Option<BandwidthUnit>,
+    /// Unit of `Demand::traffic`
+    pub traffic: Option<BandwidthUnit>,
+}
+
+/// What to do with private links that appear more than once without a bundle
+/// ID, i.e. between the same pair of devices (in either direction)
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateLinkPolicy {
+    /// Treat them as separate parallel links, adding their capacities
+    #[default]
+    Keep,
+    /// Reject the input, naming the duplicated device pairs
+    Error,
+    /// Collapse each group into one link with the lowest latency and summed
+    /// bandwidth, as for a link bundle
+    Merge,
+}
+
+/// What to do with demands that appear more than once, i.e. rows with the
+/// same start, end and type. [`ShapleyInput::usage_report`](crate::shapley::ShapleyInput::usage_report)
+/// lists them whatever the policy.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateDemandPolicy {
+    /// Route every row, adding their traffic
+    #[default]
+    Keep,
+    /// Reject the input, naming the duplicated demands and their rows
+    Error,
+    /// Collapse each group into one demand carrying the summed traffic
+    MergeSum,
+    /// Collapse each group into one demand carrying the largest traffic, for
+    /// rows that repeat one demand rather than add to it
+    MergeMax,
+}
+
+/// How city codes and device names are rewritten before validation, so that
+/// labels from different sources differing only in case or surrounding
+/// whitespace ("nyc " and "NYC") name the same city. Everything is off by
+/// default.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LabelNormalization {
+    /// Strip leading and trailing whitespace
+    pub trim: bool,
+    /// Convert to upper case. A device's city is its first three characters,
+    /// so device names are converted whole.
+    pub uppercase: bool,
+}
+
+impl LabelNormalization {
+    pub fn is_identity(&self) -> bool {
+        !self.trim && !self.uppercase
+    }
+
+    pub fn apply<'a>(&self, label: &'a str) -> Cow<'a, str> {
+        let label = if self.trim { label.trim() } else { label };
+        if self.uppercase && label.chars().any(|c| c.is_lowercase()) {
+            Cow::Owned(label.to_uppercase())
+        } else {
+            Cow::Borrowed(label)
+        }
+    }
+}
+
+/// Private link capacity to value the network at
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CapacityBasis {
+    /// `PrivateLink::bandwidth`, the capacity operators commit to
+    #[default]
+    Declared,
+    /// `PrivateLink::measured_bandwidth` where given, `bandwidth` elsewhere
+    Measured,
+}
+
+/// Dedicated bandwidth for one demand type on a private link, in both
+/// directions. A link with less capacity than reserved is reserved whole.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapacityReservation {
+    pub device1: String,
+    pub device2: String,
+    /// Protected demand type (`Demand::kind`)
+    pub kind: u32,
+    /// Reserved rate, in the unit of the link's `bandwidth`
+    pub bandwidth: f64,
+}
+
+impl CapacityReservation {
+    pub fn new(device1: String, device2: String, kind: u32, bandwidth: f64) -> Self {
+        Self {
+            device1,
+            device2,
+            kind,
+            bandwidth,
+        }
+    }
+}
+
+/// Extra cost of traffic on a private link as it fills up: a convex
+/// piecewise-linear function of the link's utilization. From each
+/// breakpoint's utilization upwards, every further unit of traffic costs the
+/// breakpoint's slope more, so the marginal cost at any utilization is the
+/// sum of the slopes at or below it. Links sharing a bandwidth row (same
+/// `PrivateLink::shared`) congest together, as one row.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CongestionCurve {
+    pub device1: String,
+    pub device2: String,
+    /// `(utilization, slope)` pairs, utilizations ascending in `[0, 1)` and
+    /// slopes non-negative, per unit of traffic in the unit of the objective
+    pub breakpoints: Vec<(f64, f64)>,
+}
+
+impl CongestionCurve {
+    pub fn new(device1: String, device2: String) -> Self {
+        Self {
+            device1,
+            device2,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    pub fn with_breakpoint(mut self, utilization: f64, slope: f64) -> Self {
+        self.breakpoints.push((utilization, slope));
+        self
+    }
+
+    /// Extra cost of carrying `load` over a link of `bandwidth`
+    pub fn cost(&self, load: f64, bandwidth: f64) -> f64 {
+        self.breakpoints
+            .iter()
+            .map(|&(utilization, slope)| slope * (load - utilization * bandwidth).max(0.0))
+            .sum()
+    }
+
+    /// Whether the curve is for a link between `device1` and `device2`, in
+    /// either order
+    pub(crate) fn connects(&self, device1: &str, device2: &str) -> bool {
+        (self.device1 == device1 && self.device2 == device2)
+            || (self.device1 == device2 && self.device2 == device1)
+    }
+}
+
+/// Split of the value two operators create together through co-owned links,
+/// i.e. private links whose devices belong to different operators. Such a
+/// link is only usable when both owners are present, so the Shapley value
+/// gives each owner half of what their joint presence is worth.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum CoOwnershipPolicy {
+    /// Keep the Shapley values: co-owners share their joint value equally
+    #[default]
+    Equal,
+    /// Redistribute the joint value of each listed pair by its declared
+    /// shares after the Shapley values are computed. Totals are unchanged
+    /// and unlisted pairs keep the equal split. Exact computations only.
+    Weighted(Vec<CoOwnerShare>),
+}
+
+/// Declared ownership of the links between two operators' devices
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoOwnerShare {
+    pub operator1: String,
+    pub operator2: String,
+    /// Fraction of the joint value due to `operator1`, in [0, 1]; the rest
+    /// is due to `operator2`
+    pub share1: f64,
+}
+
+impl CoOwnerShare {
+    pub fn new(operator1: String, operator2: String, share1: f64) -> Self {
+        Self {
+            operator1,
+            operator2,
+            share1,
+        }
+    }
+}
+
+/// Statistic of private link latency to route on. Links without the
+/// percentile asked for use their mean `latency`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LatencyMetric {
+    /// `PrivateLink::latency`
+    #[default]
+    Mean,
+    /// `PrivateLink::latency_p50`
+    P50,
+    /// `PrivateLink::latency_p95`
+    P95,
+    /// `PrivateLink::latency_p99`
+    P99,
+    /// `(1 - risk) * latency + risk * latency_p99`, with `risk` in [0, 1],
+    /// for traffic that pays for tail latency more than for the mean
+    RiskWeighted { risk: f64 },
+}
+
+/// When to estimate Shapley values by sampling instead of solving all 2^n
+/// coalition LPs
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ApproximationPolicy {
+    /// Largest operator count computed exactly. Counts above the exact limit
+    /// of 20 are always sampled.
+    pub exact_up_to: usize,
+    pub sampler: SamplerKind,
+    pub samples: SampleBudget,
+    /// Seed for drawing samples
+    pub seed: u64,
+}
+
+impl Default for ApproximationPolicy {
+    fn default() -> Self {
+        Self {
+            exact_up_to: 20,
+            sampler: SamplerKind::default(),
+            samples: SampleBudget::default(),
+            seed: 0,
+        }
+    }
+}
+
+/// Estimator used when sampling
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SamplerKind {
+    /// Mean marginal contribution over uniformly random operator orderings.
+    /// With operator uptime below 1, each ordering also draws which operators
+    /// are up, and down operators contribute nothing.
+    #[default]
+    Permutation,
+    /// Orderings drawn in pairs with their reverse, which cancels much of the
+    /// variance from an operator's position
+    Antithetic,
+    /// Marginal contributions sampled per (operator, coalition size) stratum,
+    /// with samples allocated to strata by Neyman allocation after a pilot
+    Stratified,
+    /// Orderings as for `Permutation`, with an additive
+    /// [`CoalitionSurrogate`](crate::surrogate::CoalitionSurrogate) fit to
+    /// the coalitions they visit as a control variate: the surrogate's
+    /// values are taken exactly and only the residual is sampled
+    #[cfg(feature = "surrogate")]
+    Surrogate,
+}
+
+/// How much sampling to do
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleBudget {
+    /// Number of orderings drawn; the stratified sampler draws as many
+    /// marginal contributions as that many orderings contain
+    Permutations(usize),
+    /// Maximum number of distinct coalition LPs solved (per SRLG scenario)
+    CoalitionSolves(usize),
+}
+
+impl Default for SampleBudget {
+    fn default() -> Self {
+        Self::Permutations(1000)
+    }
+}
+
+/// What to do with a coalition LP that neither solves nor proves infeasible,
+/// i.e. that hits a [`SolverOptions`] limit or fails numerically
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FailurePolicy {
+    /// Abort the computation with an error naming the coalition
+    Error,
+    /// Walk the retry ladder of [`SolverOptions::retry_ladder`] and treat the
+    /// coalition as infeasible if every attempt fails
+    #[default]
+    Retry,
+    /// Give the coalition no value, as if it were infeasible
+    TreatAsInfeasible,
+}
+
+/// Settings for the simplex solve of each coalition LP. The solver has no
+/// user-facing tolerances: comparisons use a fixed epsilon tied to machine
+/// precision. A coalition that hits a limit has no value (see `SolveStatus`).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SolverOptions {
+    /// Maximum number of simplex pivots per LP; `None` is unlimited.
+    pub max_iterations: Option<usize>,
+    /// Wall-clock limit per LP; `None` is unlimited.
+    pub time_limit: Option<Duration>,
+    /// Scale every constraint row to a largest coefficient of 1 before solving,
+    /// which helps the LU factorisation on badly scaled rows.
+    pub equilibrate: bool,
+    /// Rescale LPs whose costs (latencies) or right-hand sides (bandwidths
+    /// and traffic) are far from order one by powers of two, and report the
+    /// scalings in [`SolveSummary::scalings`](crate::shapley::SolveSummary::scalings).
+    /// Values are unchanged up to solver tolerances.
+    pub auto_scale: bool,
+    /// Scale every row and column, costs included, by powers of two computed
+    /// once per LP so that coefficients are close to one, e.g. when 0.1 ms
+    /// metro links meet 300 ms satellite links. Flows are unscaled exactly
+    /// and objective values are unaffected; applied before `equilibrate`.
+    pub geometric_scaling: bool,
+    /// Solve each coalition first without its bandwidth constraints, then add
+    /// back only the rows its flows violate, until none is. Pays off on
+    /// networks with tens of thousands of shared groups, most of which never
+    /// bind; values are unchanged up to solver tolerances. `None` builds
+    /// every row up front.
+    pub lazy_bandwidth: Option<LazyConstraints>,
+}
+
+/// Limits of lazy constraint generation, see [`SolverOptions::lazy_bandwidth`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LazyConstraints {
+    /// Rounds of adding violated rows and re-solving. A coalition still
+    /// violating a row after the last round is solved again with every row.
+    pub max_rounds: usize,
+}
+
+impl Default for LazyConstraints {
+    fn default() -> Self {
+        Self { max_rounds: 20 }
+    }
+}
+
+impl SolverOptions {
+    /// Settings tried in turn after a failed solve: equilibrated and
+    /// scaled LPs with the same limits, then limits raised tenfold, then
+    /// no limits at all.
+    /// Attempts identical to the previous one are skipped.
+    pub fn retry_ladder(&self) -> Vec<SolverOptions> {
+        let equilibrated = SolverOptions {
+            equilibrate: true,
+            auto_scale: true,
+            geometric_scaling: true,
+            ..*self
+        };
+        let raised = SolverOptions {
+            max_iterations: self.max_iterations.map(|n| n.max(1).saturating_mul(10)),
+            time_limit: self.time_limit.map(|t| t.saturating_mul(10)),
+            equilibrate: true,
+            auto_scale: true,
+            geometric_scaling: true,
+            lazy_bandwidth: self.lazy_bandwidth,
+        };
+        let unlimited = SolverOptions {
+            equilibrate: true,
+            auto_scale: true,
+            geometric_scaling: true,
+            ..Default::default()
+        };
+
+        let mut ladder: Vec<SolverOptions> = Vec::new();
+        for attempt in [equilibrated, raised, unlimited] {
+            if ladder.last() != Some(&attempt) && attempt != *self {
+                ladder.push(attempt);
+            }
+        }
+        ladder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_ladder() {
+        let capped = SolverOptions {
+            max_iterations: Some(50),
+            ..Default::default()
+        };
+        let ladder = capped.retry_ladder();
+        assert_eq!(ladder.len(), 3);
+        assert_eq!(ladder[1].max_iterations, Some(500));
+        assert_eq!(ladder[2].max_iterations, None);
+        assert!(
+            ladder
+                .iter()
+                .all(|o| o.equilibrate && o.auto_scale && o.geometric_scaling)
+        );
+
+        // Without limits, only equilibration is left to try
+        assert_eq!(SolverOptions::default().retry_ladder().len(), 1);
+    }
+
+    #[test]
+    fn test_dedicated_pool() {
+        let threads = || Ok(rayon::current_num_threads());
+        assert_eq!(
+            ComputeResources::with_threads(3).install(threads).unwrap(),
+            3
+        );
+        assert_eq!(
+            ComputeResources::default().install(threads).unwrap(),
+            rayon::current_num_threads()
+        );
+        // The caller's pool is left as it was
+        let caller = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        caller.install(|| {
+            ComputeResources::with_threads(5).install(threads).unwrap();
+            assert_eq!(rayon::current_num_threads(), 2);
+        });
+    }
+}
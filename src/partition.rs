@@ -0,0 +1,208 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Result, ShapleyError},
+    shapley::{Operator, ShapleyInput, ShapleyOutput, to_output},
+    utils::city_of,
+};
+
+/// Cities of each region of a partitioned computation, see
+/// [`ShapleyInput::compute_partitioned`]
+pub type RegionPartition = BTreeMap<String, BTreeSet<String>>;
+
+/// Shapley values of each region's game and their sum
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PartitionedOutput {
+    /// Values of each region's game; empty for regions without demands
+    pub regions: BTreeMap<String, ShapleyOutput>,
+    /// Each operator's values summed over regions, with proportions of the
+    /// total
+    pub combined: ShapleyOutput,
+}
+
+impl PartitionedOutput {
+    pub(crate) fn new(regions: BTreeMap<String, ShapleyOutput>) -> Self {
+        let mut totals: BTreeMap<Operator, f64> = BTreeMap::new();
+        for output in regions.values() {
+            for (operator, value) in output {
+                *totals.entry(operator.clone()).or_default() += value.value;
+            }
+        }
+        let (operators, values): (Vec<Operator>, Vec<f64>) = totals.into_iter().unzip();
+        Self {
+            regions,
+            combined: to_output(operators, values),
+        }
+    }
+}
+
+/// One input per region holding the region's private links, devices and
+/// demands and every public link. Private links and demands must lie within
+/// a single region; regions must not share cities.
+pub(crate) fn split_by_region(
+    input: &ShapleyInput,
+    partition: &RegionPartition,
+) -> Result<BTreeMap<String, ShapleyInput>> {
+    let mut region_of: BTreeMap<&str, &str> = BTreeMap::new();
+    for (region, cities) in partition {
+        for city in cities {
+            if let Some(other) = region_of.insert(city, region) {
+                return Err(ShapleyError::Validation(format!(
+                    "City {city} is in both region {other} and region {region}"
+                )));
+            }
+        }
+    }
+    let locate = |node: &str, what: &str| -> Result<&str> {
+        let city = city_of(node);
+        region_of.get(city).copied().ok_or_else(|| {
+            ShapleyError::Validation(format!("City {city} of {what} {node} is in no region"))
+        })
+    };
+
+    let mut inputs: BTreeMap<String, ShapleyInput> = partition
+        .keys()
+        .map(|region| {
+            let empty = ShapleyInput {
+                private_links: Vec::new(),
+                devices: Vec::new(),
+                demands: Vec::new(),
+                public_links: input.public_links.clone(),
+                operator_uptime: input.operator_uptime,
+                contiguity_bonus: input.contiguity_bonus,
+                demand_multiplier: input.demand_multiplier,
+            };
+            (region.clone(), empty)
+        })
+        .collect();
+
+    for link in &input.private_links {
+        let region = locate(&link.device1, "device")?;
+        let other = locate(&link.device2, "device")?;
+        if region != other {
+            return Err(ShapleyError::Validation(format!(
+                "Private link {}-{} crosses from region {region} to region {other}",
+                link.device1, link.device2
+            )));
+        }
+        inputs
+            .get_mut(region)
+            .expect("every region has an input")
+            .private_links
+            .push(link.clone());
+    }
+    for demand in &input.demands {
+        let region = locate(&demand.start, "demand endpoint")?;
+        let other = locate(&demand.end, "demand endpoint")?;
+        if region != other {
+            return Err(ShapleyError::Validation(format!(
+                "Demand {}-{} crosses from region {region} to region {other}",
+                demand.start, demand.end
+            )));
+        }
+        inputs
+            .get_mut(region)
+            .expect("every region has an input")
+            .demands
+            .push(demand.clone());
+    }
+    // Devices outside every region carry no private link and are left out
+    for device in &input.devices {
+        if let Some(region) = region_of.get(city_of(&device.device)) {
+            inputs
+                .get_mut(*region)
+                .expect("every region has an input")
+                .devices
+                .push(device.clone());
+        }
+    }
+    Ok(inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        options::ShapleyOptions,
+        types::{Demand, Device, PrivateLink, PublicLink},
+    };
+
+    fn input() -> ShapleyInput {
+        let link = |a: &str, b: &str| PrivateLink::new(a.into(), b.into(), 10.0, 100.0, 1.0, None);
+        let demand = |a: &str, b: &str| Demand::new(a.into(), b.into(), 1, 5.0, 1.0, 1, false);
+        let public = |a: &str, b: &str, latency| PublicLink::new(a.into(), b.into(), latency);
+        ShapleyInput {
+            private_links: vec![
+                link("NYC1", "CHI1"),
+                link("LON1", "PAR1"),
+                link("PAR2", "LON2"),
+            ],
+            devices: vec![
+                Device::new("NYC1".into(), 1, "Alpha".into()),
+                Device::new("CHI1".into(), 1, "Gamma".into()),
+                Device::new("LON1".into(), 1, "Beta".into()),
+                Device::new("PAR1".into(), 1, "Beta".into()),
+                Device::new("PAR2".into(), 1, "Gamma".into()),
+                Device::new("LON2".into(), 1, "Gamma".into()),
+            ],
+            demands: vec![demand("NYC", "CHI"), demand("LON", "PAR")],
+            public_links: vec![
+                public("NYC", "CHI", 30.0),
+                public("LON", "PAR", 30.0),
+                public("NYC", "LON", 70.0),
+            ],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        }
+    }
+
+    fn partition() -> RegionPartition {
+        BTreeMap::from([
+            ("EU".into(), BTreeSet::from(["LON".into(), "PAR".into()])),
+            ("US".into(), BTreeSet::from(["NYC".into(), "CHI".into()])),
+        ])
+    }
+
+    #[test]
+    fn test_regions_are_computed_separately_and_summed() {
+        let input = input();
+        let options = ShapleyOptions::default();
+        let output = input.compute_partitioned(&options, &partition()).unwrap();
+
+        let split = split_by_region(&input, &partition()).unwrap();
+        for (region, regional) in &split {
+            assert_eq!(output.regions[region], regional.compute().unwrap());
+        }
+        let gamma = output.regions["EU"]["Gamma"].value + output.regions["US"]["Gamma"].value;
+        assert!((output.combined["Gamma"].value - gamma).abs() < 1e-9);
+        assert!(output.combined.contains_key("Alpha") && output.combined.contains_key("Beta"));
+    }
+
+    #[test]
+    fn test_links_and_demands_must_stay_in_a_region() {
+        let mut crossing = input();
+        crossing.private_links.push(PrivateLink::new(
+            "NYC1".into(),
+            "LON1".into(),
+            50.0,
+            100.0,
+            1.0,
+            None,
+        ));
+        let err = split_by_region(&crossing, &partition()).unwrap_err();
+        assert!(err.to_string().contains("crosses"));
+
+        let mut unassigned = partition();
+        unassigned.get_mut("US").unwrap().remove("CHI");
+        assert!(split_by_region(&input(), &unassigned).is_err());
+
+        let mut overlapping = partition();
+        overlapping.get_mut("US").unwrap().insert("LON".into());
+        assert!(split_by_region(&input(), &overlapping).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+//! The consolidation and LP-building stages on their own, for callers that
+//! want the routing LP without the coalition game.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub use crate::sparse::CscMatrix;
+use crate::{
+    error::Result,
+    lp_builder::LpPrimitives,
+    options::ShapleyOptions,
+    shapley::ShapleyInput,
+    types::{ConsolidatedDemand, ConsolidatedLink, LinkProvenance},
+    utils::is_always_present,
+};
+
+/// The routing LP of the whole network, i.e. with every operator present:
+///
+/// ```text
+/// minimise    cost · x
+/// subject to  a_eq x  = b_eq
+///             a_ub x <= b_ub
+///             x >= 0
+/// ```
+///
+/// Equality rows conserve each commodity's flow at each node; inequality rows
+/// are bandwidth limits. Negating the optimal objective gives the value of the
+/// grand coalition.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct LpProblem {
+    pub cost: Vec<f64>,
+    pub a_eq: CscMatrix<f64>,
+    pub b_eq: Vec<f64>,
+    pub a_ub: CscMatrix<f64>,
+    pub b_ub: Vec<f64>,
+    /// One entry per column of `cost`, `a_eq` and `a_ub`
+    pub columns: Vec<LpColumn>,
+    /// Name of each row of `a_eq`
+    pub equality_rows: Vec<String>,
+    /// One entry per row of `a_ub`
+    pub inequality_rows: Vec<LpRow>,
+    /// Consolidated links that columns refer to
+    pub links: Vec<ConsolidatedLink>,
+    /// Consolidated demands the commodities are built from
+    pub demands: Vec<ConsolidatedDemand>,
+}
+
+/// What an LP column stands for
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LpColumn {
+    /// Unique name, for LP files
+    pub name: String,
+    /// Operators at either end of the column's link; `Public` for the public
+    /// internet. The column exists in a coalition's LP only if both are members.
+    pub operator1: String,
+    pub operator2: String,
+    /// Index into [`LpProblem::links`] of the link whose load the column adds
+    /// to. `None` for multicast commodity columns whose load is carried by an
+    /// auxiliary column.
+    pub link: Option<usize>,
+    pub provenance: Option<LinkProvenance>,
+    /// Original traffic type (`Demand::kind`) of the commodity carried
+    pub kind: u32,
+}
+
+/// Operators whose presence an inequality row depends on, as for [`LpColumn`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LpRow {
+    /// Unique name, for LP files
+    pub name: String,
+    pub operator1: String,
+    pub operator2: String,
+}
+
+impl LpProblem {
+    pub(crate) fn new(
+        primitives: LpPrimitives,
+        links: Vec<ConsolidatedLink>,
+        demands: Vec<ConsolidatedDemand>,
+    ) -> Self {
+        let columns = primitives
+            .col_name
+            .into_iter()
+            .zip(primitives.col_op1.into_iter().zip(primitives.col_op2))
+            .zip(primitives.col_link.iter().zip(&primitives.col_kind))
+            .map(
+                |((name, (operator1, operator2)), (&link, &kind))| LpColumn {
+                    name,
+                    operator1,
+                    operator2,
+                    link,
+                    provenance: link.map(|idx| links[idx].provenance),
+                    kind,
+                },
+            )
+            .collect();
+        let inequality_rows = primitives
+            .ub_row_name
+            .into_iter()
+            .zip(primitives.row_op1.into_iter().zip(primitives.row_op2))
+            .map(|(name, (operator1, operator2))| LpRow {
+                name,
+                operator1,
+                operator2,
+            })
+            .collect();
+
+        Self {
+            cost: primitives.cost,
+            a_eq: primitives.a_eq,
+            b_eq: primitives.b_eq,
+            a_ub: primitives.a_ub,
+            b_ub: primitives.b_ub,
+            columns,
+            equality_rows: primitives.eq_row_name,
+            inequality_rows,
+            links,
+            demands,
+        }
+    }
+
+    pub fn n_cols(&self) -> usize {
+        self.cost.len()
+    }
+
+    /// The LP of the coalition of `members`: columns and inequality rows
+    /// that need an absent operator are dropped, as in the coalition solves.
+    /// Every conservation row stays.
+    pub fn coalition<S: AsRef<str>>(&self, members: &[S]) -> LpProblem {
+        let present = |op: &str| is_always_present(op) || members.iter().any(|m| m.as_ref() == op);
+        let keep_cols: Vec<usize> = (0..self.n_cols())
+            .filter(|&j| present(&self.columns[j].operator1) && present(&self.columns[j].operator2))
+            .collect();
+        let keep_rows: Vec<usize> = (0..self.inequality_rows.len())
+            .filter(|&i| {
+                let row = &self.inequality_rows[i];
+                present(&row.operator1) && present(&row.operator2)
+            })
+            .collect();
+
+        let mut ub_row = vec![usize::MAX; self.a_ub.m];
+        for (new, &old) in keep_rows.iter().enumerate() {
+            ub_row[old] = new;
+        }
+        LpProblem {
+            cost: keep_cols.iter().map(|&j| self.cost[j]).collect(),
+            a_eq: select(
+                &self.a_eq,
+                &keep_cols,
+                &(0..self.a_eq.m).collect::<Vec<_>>(),
+            ),
+            b_eq: self.b_eq.clone(),
+            a_ub: select(&self.a_ub, &keep_cols, &ub_row),
+            b_ub: keep_rows.iter().map(|&i| self.b_ub[i]).collect(),
+            columns: keep_cols.iter().map(|&j| self.columns[j].clone()).collect(),
+            equality_rows: self.equality_rows.clone(),
+            inequality_rows: keep_rows
+                .iter()
+                .map(|&i| self.inequality_rows[i].clone())
+                .collect(),
+            links: self.links.clone(),
+            demands: self.demands.clone(),
+        }
+    }
+}
+
+/// Columns `cols` of `matrix`, with row `i` moved to `rows[i]` or dropped if
+/// that is `usize::MAX`
+fn select(matrix: &CscMatrix<f64>, cols: &[usize], rows: &[usize]) -> CscMatrix<f64> {
+    let mut colptr = Vec::with_capacity(cols.len() + 1);
+    let (mut rowval, mut nzval) = (Vec::new(), Vec::new());
+    colptr.push(0);
+    for &j in cols {
+        for idx in matrix.colptr[j]..matrix.colptr[j + 1] {
+            let row = rows[matrix.rowval[idx]];
+            if row != usize::MAX {
+                rowval.push(row);
+                nzval.push(matrix.nzval[idx]);
+            }
+        }
+        colptr.push(rowval.len());
+    }
+    let m = rows.iter().filter(|&&r| r != usize::MAX).count();
+    CscMatrix::new(m, cols.len(), colptr, rowval, nzval)
+}
+
+/// Validate and consolidate `input` and build its LP with default options
+pub fn build_lp(input: &ShapleyInput) -> Result<LpProblem> {
+    build_lp_with(input, &ShapleyOptions::default())
+}
+
+/// [`build_lp`] honouring the options that shape the LP: contiguity bonus
+/// overrides, cost model, units and duplicate links. Only the LP is built, so
+/// the operator count is bounded by the bitmask width alone.
+pub fn build_lp_with(input: &ShapleyInput, options: &ShapleyOptions) -> Result<LpProblem> {
+    input.lp_problem(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Demand, Device, PrivateLink, PublicLink};
+
+    #[test]
+    fn test_build_lp_metadata() {
+        let input = ShapleyInput {
+            private_links: vec![PrivateLink::new(
+                "NYC1".into(),
+                "LON1".into(),
+                10.0,
+                100.0,
+                1.0,
+                None,
+            )],
+            devices: vec![
+                Device::new("NYC1".into(), 100, "Alpha".into()),
+                Device::new("LON1".into(), 100, "Beta".into()),
+            ],
+            demands: vec![Demand::new(
+                "NYC".into(),
+                "LON".into(),
+                1,
+                5.0,
+                1.0,
+                3,
+                false,
+            )],
+            public_links: vec![PublicLink::new("NYC".into(), "LON".into(), 100.0)],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let lp = build_lp(&input).unwrap();
+
+        assert_eq!(lp.columns.len(), lp.n_cols());
+        assert_eq!(lp.a_eq.n, lp.n_cols());
+        assert_eq!(lp.a_ub.m, lp.inequality_rows.len());
+        assert_eq!(lp.a_ub.m, lp.b_ub.len());
+        assert!(lp.columns.iter().all(|col| col.kind == 3));
+
+        let private = lp
+            .columns
+            .iter()
+            .find(|col| col.provenance == Some(LinkProvenance::OriginalPrivate))
+            .unwrap();
+        assert_eq!(
+            (private.operator1.as_str(), private.operator2.as_str()),
+            ("Alpha", "Beta")
+        );
+        let link = &lp.links[private.link.unwrap()];
+        assert_eq!(
+            (link.device1.as_str(), link.device2.as_str()),
+            ("NYC1", "LON1")
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! Named parameterizations, so that epoch runs by different teams use the
+//! same settings.
+//!
+//! A [`Preset`] fixes the parameters of a [`ShapleyInput`] (uptime,
+//! contiguity bonus, demand multiplier) and the solver settings of the
+//! [`ShapleyOptions`] it produces. Those options carry the preset's name,
+//! which [`ShapleyInput::compute_report`] records in the report.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    options::{ShapleyOptions, SolverOptions},
+    shapley::ShapleyInput,
+};
+
+/// A named set of parameters
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preset {
+    pub name: String,
+    pub operator_uptime: f64,
+    /// Latency charged for crossing between the private network and the
+    /// public internet
+    pub contiguity_bonus: f64,
+    pub demand_multiplier: f64,
+    pub solver: SolverOptions,
+}
+
+impl Preset {
+    /// Production epochs: 98% operator uptime, a 5 ms crossover charge and
+    /// 20% headroom on demand, with equilibrated LPs
+    pub fn mainnet() -> Self {
+        Self {
+            name: "mainnet".to_string(),
+            operator_uptime: 0.98,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.2,
+            solver: SolverOptions {
+                equilibrate: true,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Test networks: operators always up and demand taken as given, which
+    /// skips the expected-value stage
+    pub fn testnet() -> Self {
+        Self {
+            name: "testnet".to_string(),
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+            solver: SolverOptions::default(),
+        }
+    }
+
+    /// Default options with this preset's solver settings and name
+    pub fn options(&self) -> ShapleyOptions {
+        ShapleyOptions {
+            solver: self.solver,
+            preset: Some(self.name.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+impl ShapleyInput {
+    /// Replace the input's parameters with those of `preset`; pair with
+    /// [`Preset::options`]
+    pub fn with_preset(mut self, preset: &Preset) -> Self {
+        self.operator_uptime = preset.operator_uptime;
+        self.contiguity_bonus = preset.contiguity_bonus;
+        self.demand_multiplier = preset.demand_multiplier;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapley::testing::{RandomInputConfig, random_input};
+
+    #[test]
+    fn test_presets() {
+        let preset = Preset::mainnet();
+        let input = random_input(3, &RandomInputConfig::default()).with_preset(&preset);
+        assert_eq!(input.operator_uptime, 0.98);
+        assert_eq!(input.demand_multiplier, 1.2);
+
+        let report = input.compute_report(&preset.options()).unwrap();
+        assert_eq!(report.preset.as_deref(), Some("mainnet"));
+        assert_eq!(
+            report.values,
+            input.compute_with(&preset.options()).unwrap()
+        );
+        let plain = input.compute_report(&ShapleyOptions::default()).unwrap();
+        assert_eq!(plain.preset, None);
+    }
+}
@@ -0,0 +1,169 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Result, ShapleyError},
+    types::{Demand, Demands},
+};
+
+/// Traffic of one demand type over a day (or any other period): a peak rate
+/// and the fraction of it carried in each of a number of equally long time
+/// slots. Set per type in `ShapleyOptions::demand_profiles`; coalition values
+/// are then averaged over the slots instead of being taken at one rate.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DemandProfile {
+    /// Busiest rate, in the unit of `Demand::traffic`
+    pub peak: f64,
+    /// Fraction of `peak` in each slot, within [0, 1]
+    pub shape: Vec<f64>,
+}
+
+impl DemandProfile {
+    pub fn new(peak: f64, shape: Vec<f64>) -> Self {
+        Self { peak, shape }
+    }
+
+    /// Traffic in time slot `slot`
+    pub fn traffic(&self, slot: usize) -> f64 {
+        self.peak * self.shape[slot]
+    }
+}
+
+/// The demands of one or more time slots with the same traffic, and the
+/// fraction of the period they cover
+#[derive(Debug, Clone)]
+pub(crate) struct DemandSlot<'a> {
+    pub weight: f64,
+    pub demands: Cow<'a, Demands>,
+}
+
+/// Expand `profiles` into weighted demand tables, one per distinct time slot.
+/// Without profiles the demands are used as given, in a single slot. Every
+/// profile must cover the same number of slots and name a type in `demands`.
+/// A profiled type's traffic is raised to its floor in `floors` (type →
+/// minimum traffic) in every slot.
+pub(crate) fn demand_slots<'a>(
+    demands: &'a Demands,
+    profiles: &BTreeMap<u32, DemandProfile>,
+    floors: &BTreeMap<u32, f64>,
+) -> Result<Vec<DemandSlot<'a>>> {
+    let Some(n_slots) = profiles.values().map(|p| p.shape.len()).next() else {
+        return Ok(vec![DemandSlot {
+            weight: 1.0,
+            demands: Cow::Borrowed(demands),
+        }]);
+    };
+
+    for (&kind, profile) in profiles {
+        if !demands.iter().any(|d| d.kind == kind) {
+            return Err(ShapleyError::Validation(format!(
+                "Demand profile for type {kind} matches no demand"
+            )));
+        }
+        if profile.shape.is_empty() || profile.shape.len() != n_slots {
+            return Err(ShapleyError::Validation(format!(
+                "Demand profile for type {kind} has {} slots; every profile needs the same, nonzero number",
+                profile.shape.len()
+            )));
+        }
+        if !(profile.peak.is_finite() && profile.peak >= 0.0)
+            || profile.shape.iter().any(|s| !(0.0..=1.0).contains(s))
+        {
+            return Err(ShapleyError::Validation(format!(
+                "Demand profile for type {kind} needs a non-negative peak and a shape within [0, 1]"
+            )));
+        }
+    }
+
+    // Slots with the same traffic for every type need only be solved once
+    let mut slots: Vec<(Vec<f64>, usize)> = Vec::new();
+    for slot in 0..n_slots {
+        let traffic: Vec<f64> = profiles
+            .iter()
+            .map(|(kind, p)| {
+                let traffic = p.traffic(slot);
+                floors
+                    .get(kind)
+                    .map_or(traffic, |&floor| traffic.max(floor))
+            })
+            .collect();
+        match slots.iter_mut().find(|(t, _)| *t == traffic) {
+            Some((_, count)) => *count += 1,
+            None => slots.push((traffic, 1)),
+        }
+    }
+
+    Ok(slots
+        .into_iter()
+        .map(|(traffic, count)| {
+            let by_kind: BTreeMap<u32, f64> = profiles.keys().copied().zip(traffic).collect();
+            let demands = demands
+                .iter()
+                .map(|demand| Demand {
+                    traffic: by_kind.get(&demand.kind).copied().unwrap_or(demand.traffic),
+                    ..demand.clone()
+                })
+                .collect();
+            DemandSlot {
+                weight: count as f64 / n_slots as f64,
+                demands: Cow::Owned(demands),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demand_slots() {
+        let demands = vec![
+            Demand::new("NYC".into(), "LON".into(), 1, 5.0, 1.0, 1, false),
+            Demand::new("NYC".into(), "PAR".into(), 1, 5.0, 1.0, 1, false),
+            Demand::new("LON".into(), "PAR".into(), 1, 2.0, 1.0, 2, false),
+        ];
+        let unprofiled = demand_slots(&demands, &BTreeMap::new(), &BTreeMap::new()).unwrap();
+        assert_eq!(unprofiled.len(), 1);
+        assert_eq!(unprofiled[0].weight, 1.0);
+        assert!(matches!(unprofiled[0].demands, Cow::Borrowed(_)));
+
+        let profiles = BTreeMap::from([(1, DemandProfile::new(10.0, vec![0.2, 1.0, 0.2, 0.5]))]);
+        let slots = demand_slots(&demands, &profiles, &BTreeMap::new()).unwrap();
+        // The two night slots are merged
+        assert_eq!(slots.len(), 3);
+        assert_eq!(slots[0].weight, 0.5);
+        let traffic: Vec<Vec<f64>> = slots
+            .iter()
+            .map(|s| s.demands.iter().map(|d| d.traffic).collect())
+            .collect();
+        assert_eq!(
+            traffic,
+            [[2.0, 2.0, 2.0], [10.0, 10.0, 2.0], [5.0, 5.0, 2.0]]
+        );
+
+        // Floors apply to each slot, and can merge slots
+        let floors = BTreeMap::from([(1, 5.0), (2, 3.0)]);
+        let floored = demand_slots(&demands, &profiles, &floors).unwrap();
+        assert_eq!(floored.len(), 2);
+        assert_eq!(floored[0].weight, 0.75);
+        assert_eq!(floored[0].demands[0].traffic, 5.0);
+        assert_eq!(floored[1].demands[0].traffic, 10.0);
+
+        let mismatched = BTreeMap::from([
+            (1, DemandProfile::new(10.0, vec![0.2, 1.0])),
+            (2, DemandProfile::new(4.0, vec![1.0])),
+        ]);
+        assert!(demand_slots(&demands, &mismatched, &BTreeMap::new()).is_err());
+        let unknown = BTreeMap::from([(7, DemandProfile::new(10.0, vec![1.0]))]);
+        assert!(demand_slots(&demands, &unknown, &BTreeMap::new()).is_err());
+        let overfull = BTreeMap::from([(1, DemandProfile::new(10.0, vec![1.5]))]);
+        assert!(demand_slots(&demands, &overfull, &BTreeMap::new()).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+//! Intermediate results of the exact computation, for research into other
+//! ways of aggregating coalition values
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::shapley::Operator;
+
+/// Every coalition's value as computed by
+/// [`ShapleyInput::coalition_data`](crate::shapley::ShapleyInput::coalition_data).
+///
+/// Coalition `i` is the set of operators `operators[k]` for which bit k of
+/// `bitmap[i]` is set; the vectors below are indexed the same way.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoalitionData {
+    /// Operators, sorted
+    pub operators: Vec<Operator>,
+    /// Membership bitmask of each coalition
+    pub bitmap: Vec<u32>,
+    /// Raw LP values, one set per demand time slot and SRLG outage state (a
+    /// single scenario with probability 1 without profiles or availabilities)
+    pub scenarios: Vec<ScenarioValues>,
+    /// Expected value of each coalition under operator uptime, weighted over
+    /// the scenarios. Shapley values are computed from these.
+    pub evalue: Vec<f64>,
+}
+
+/// Coalition LP values with every link of the failed SRLGs removed, at the
+/// traffic of one demand time slot
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScenarioValues {
+    pub probability: f64,
+    /// Negated optimal objective of each coalition; `None` when infeasible
+    pub svalue: Vec<Option<f64>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core_math,
+        options::ShapleyOptions,
+        shapley::ShapleyInput,
+        types::{Demand, Device, PrivateLink, PublicLink},
+    };
+
+    #[test]
+    fn test_coalition_data_matches_compute() {
+        let input = ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 100.0, 1.0, None),
+                PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 100.0, 1.0, None),
+            ],
+            devices: vec![
+                Device::new("NYC1".into(), 100, "Alpha".into()),
+                Device::new("LON1".into(), 100, "Alpha".into()),
+                Device::new("PAR1".into(), 100, "Beta".into()),
+            ],
+            demands: vec![Demand::new(
+                "NYC".into(),
+                "PAR".into(),
+                1,
+                5.0,
+                1.0,
+                1,
+                false,
+            )],
+            public_links: vec![PublicLink::new("NYC".into(), "PAR".into(), 100.0)],
+            operator_uptime: 0.9,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let options = ShapleyOptions::default();
+        let data = input.coalition_data(&options).unwrap();
+        assert_eq!(data.operators, vec!["Alpha", "Beta"]);
+        assert_eq!(data.bitmap, vec![0, 1, 2, 3]);
+        assert_eq!(data.scenarios.len(), 1);
+
+        let svalue = &data.scenarios[0].svalue;
+        assert_eq!(
+            data.evalue,
+            core_math::uptime_adjusted_values(svalue, 2, input.operator_uptime)
+        );
+        let values = input.compute_with(&options).unwrap();
+        for (k, shapley) in core_math::shapley_values(&data.evalue, 2)
+            .iter()
+            .enumerate()
+        {
+            assert_eq!(*shapley, values[&data.operators[k]].value);
+        }
+    }
+}
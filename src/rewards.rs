@@ -0,0 +1,481 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Result, ShapleyError},
+    shapley::{Operator, ShapleyOutput, ShapleyValue},
+};
+
+/// Operator name [`DustPolicy::Group`] collects small operators under
+pub const OTHER_OPERATOR: &str = "Other";
+
+/// Optional per-operator bounds on the payout, in the same unit as the budget.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct PayoutCaps {
+    pub min: BTreeMap<Operator, u64>,
+    pub max: BTreeMap<Operator, u64>,
+}
+
+/// Split `budget` between operators according to their Shapley proportions.
+///
+/// Payouts always sum to exactly `budget`: each operator first receives the
+/// floor of its share and the leftover units go one at a time to the largest
+/// fractional remainders (ties broken by operator name). Caps are applied by
+/// water-filling, so the excess cut from a capped operator is shared among the
+/// others in proportion to their Shapley values.
+pub fn distribute(
+    output: &ShapleyOutput,
+    budget: u64,
+    caps: &PayoutCaps,
+) -> Result<Vec<(Operator, u64)>> {
+    let operators: Vec<&Operator> = output.keys().collect();
+    for op in caps.min.keys().chain(caps.max.keys()) {
+        if !output.contains_key(op) {
+            return Err(ShapleyError::Validation(format!(
+                "Payout cap given for unknown operator {op}"
+            )));
+        }
+    }
+
+    let min: Vec<f64> = operators
+        .iter()
+        .map(|op| caps.min.get(*op).copied().unwrap_or(0) as f64)
+        .collect();
+    let max: Vec<f64> = operators
+        .iter()
+        .map(|op| caps.max.get(*op).map_or(f64::INFINITY, |&m| m as f64))
+        .collect();
+    if let Some(i) = (0..operators.len()).find(|&i| min[i] > max[i]) {
+        return Err(ShapleyError::Validation(format!(
+            "Minimum payout exceeds maximum for operator {}",
+            operators[i]
+        )));
+    }
+
+    let budget_f = budget as f64;
+    if min.iter().sum::<f64>() > budget_f {
+        return Err(ShapleyError::Validation(
+            "Minimum payouts exceed the budget".to_string(),
+        ));
+    }
+    if max.iter().sum::<f64>() < budget_f {
+        return Err(ShapleyError::Validation(
+            "Maximum payouts cannot absorb the whole budget".to_string(),
+        ));
+    }
+
+    let weights: Vec<f64> = output.values().map(|sv| sv.proportion.max(0.0)).collect();
+    let shares = water_fill(&weights, &min, &max, budget_f)?;
+
+    // Integer allocation: floors first, then hand out the remainder by largest fraction
+    let mut payouts: Vec<u64> = shares
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| (x.floor() as u64).clamp(min[i] as u64, max[i].min(u64::MAX as f64) as u64))
+        .collect();
+    let mut order: Vec<usize> = (0..operators.len()).collect();
+    order.sort_by(|&a, &b| {
+        let frac = |i: usize| shares[i] - shares[i].floor();
+        frac(b)
+            .total_cmp(&frac(a))
+            .then(operators[a].cmp(operators[b]))
+    });
+
+    let allocated: u128 = payouts.iter().map(|&p| p as u128).sum();
+    let budget_u = budget as u128;
+    if allocated < budget_u {
+        let mut left = budget_u - allocated;
+        while left > 0 {
+            let before = left;
+            for &i in &order {
+                if left > 0 && (payouts[i] as f64) < max[i] {
+                    payouts[i] += 1;
+                    left -= 1;
+                }
+            }
+            if left == before {
+                break;
+            }
+        }
+    } else {
+        let mut excess = allocated - budget_u;
+        while excess > 0 {
+            let before = excess;
+            for &i in order.iter().rev() {
+                if excess > 0 && (payouts[i] as f64) > min[i] {
+                    payouts[i] -= 1;
+                    excess -= 1;
+                }
+            }
+            if excess == before {
+                break;
+            }
+        }
+    }
+
+    Ok(operators.into_iter().cloned().zip(payouts).collect())
+}
+
+/// What happens to operators below a [`DustFilter`] threshold
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DustPolicy {
+    /// Zero them and share their value among the rest in proportion
+    #[default]
+    Redistribute,
+    /// Replace them with one [`OTHER_OPERATOR`] entry holding their sum
+    Group,
+}
+
+/// Minimum participation for a payout of its own
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DustFilter {
+    /// Smallest Shapley proportion kept, e.g. 0.01 for 1%
+    pub min_proportion: f64,
+    pub policy: DustPolicy,
+}
+
+/// Apply `filter` to Shapley values before distributing a budget, so payouts
+/// are not dribbled out in dust amounts to many tiny contributors. Values are
+/// rescaled along with proportions, keeping the total value unchanged.
+pub fn filter_dust(output: &ShapleyOutput, filter: &DustFilter) -> Result<ShapleyOutput> {
+    if !(0.0..=1.0).contains(&filter.min_proportion) {
+        return Err(ShapleyError::Validation(format!(
+            "Dust threshold {} is outside [0, 1]",
+            filter.min_proportion
+        )));
+    }
+    let total_value: f64 = output.values().map(|sv| sv.value).sum();
+    let is_dust = |sv: &ShapleyValue| sv.proportion < filter.min_proportion;
+
+    match filter.policy {
+        DustPolicy::Redistribute => {
+            let kept: f64 = output
+                .values()
+                .filter(|sv| !is_dust(sv))
+                .map(|sv| sv.proportion)
+                .sum();
+            if kept <= 0.0 {
+                return Err(ShapleyError::Validation(
+                    "Every operator falls below the dust threshold".to_string(),
+                ));
+            }
+            Ok(output
+                .iter()
+                .map(|(op, sv)| {
+                    let proportion = if is_dust(sv) {
+                        0.0
+                    } else {
+                        sv.proportion / kept
+                    };
+                    let value = proportion * total_value;
+                    (op.clone(), ShapleyValue { value, proportion })
+                })
+                .collect())
+        }
+        DustPolicy::Group => {
+            if output.contains_key(OTHER_OPERATOR) {
+                return Err(ShapleyError::Validation(format!(
+                    "Operator {OTHER_OPERATOR} clashes with the dust bucket"
+                )));
+            }
+            let (dust, mut kept): (ShapleyOutput, ShapleyOutput) = output
+                .iter()
+                .map(|(op, sv)| (op.clone(), sv.clone()))
+                .partition(|(_, sv)| is_dust(sv));
+            if !dust.is_empty() {
+                let other = dust.values().fold(
+                    ShapleyValue {
+                        value: 0.0,
+                        proportion: 0.0,
+                    },
+                    |acc, sv| ShapleyValue {
+                        value: acc.value + sv.value,
+                        proportion: acc.proportion + sv.proportion,
+                    },
+                );
+                kept.insert(OTHER_OPERATOR.to_string(), other);
+            }
+            Ok(kept)
+        }
+    }
+}
+
+/// Upper bounds on operators' shares of the total value, as proportions in
+/// [0, 1], for [`cap_shares`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct ShareCaps {
+    /// Cap of every operator not in `max`; `None` leaves them uncapped
+    pub default_max: Option<f64>,
+    pub max: BTreeMap<Operator, f64>,
+}
+
+/// Shapley values before and after [`cap_shares`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CappedAllocation {
+    pub raw: ShapleyOutput,
+    pub capped: ShapleyOutput,
+}
+
+/// Cap each operator's share of the total value, for payout contracts with a
+/// maximum percentage per operator. Shares are water-filled: the excess cut
+/// from capped operators goes to the rest in proportion to their Shapley
+/// values, repeatedly, until no share exceeds its cap. Values are rescaled
+/// along with proportions, keeping the total value unchanged.
+pub fn cap_shares(output: &ShapleyOutput, caps: &ShareCaps) -> Result<CappedAllocation> {
+    for (op, &cap) in &caps.max {
+        if !output.contains_key(op) {
+            return Err(ShapleyError::Validation(format!(
+                "Share cap given for unknown operator {op}"
+            )));
+        }
+        if !(0.0..=1.0).contains(&cap) {
+            return Err(ShapleyError::Validation(format!(
+                "Share cap {cap} of operator {op} is outside [0, 1]"
+            )));
+        }
+    }
+    if let Some(cap) = caps.default_max
+        && !(0.0..=1.0).contains(&cap)
+    {
+        return Err(ShapleyError::Validation(format!(
+            "Default share cap {cap} is outside [0, 1]"
+        )));
+    }
+
+    let max: Vec<f64> = output
+        .keys()
+        .map(|op| {
+            caps.max
+                .get(op)
+                .copied()
+                .or(caps.default_max)
+                .unwrap_or(f64::INFINITY)
+        })
+        .collect();
+    if max.iter().sum::<f64>() < 1.0 {
+        return Err(ShapleyError::Validation(
+            "Share caps sum to less than the whole".to_string(),
+        ));
+    }
+
+    let weights: Vec<f64> = output.values().map(|sv| sv.proportion.max(0.0)).collect();
+    let min = vec![0.0; weights.len()];
+    let shares = water_fill(&weights, &min, &max, 1.0)?;
+    let total_value: f64 = output.values().map(|sv| sv.value).sum();
+    let capped = output
+        .keys()
+        .zip(shares)
+        .map(|(op, proportion)| {
+            let value = proportion * total_value;
+            (op.clone(), ShapleyValue { value, proportion })
+        })
+        .collect();
+    Ok(CappedAllocation {
+        raw: output.clone(),
+        capped,
+    })
+}
+
+/// Real-valued allocation x_i = clamp(λ·w_i, min_i, max_i) with Σ x_i = budget.
+/// Operators are pinned to whichever bound is violated most in aggregate, and
+/// the rest re-scaled, until no bound is violated.
+fn water_fill(weights: &[f64], min: &[f64], max: &[f64], budget: f64) -> Result<Vec<f64>> {
+    let n = weights.len();
+    let mut pinned: Vec<Option<f64>> = vec![None; n];
+
+    loop {
+        let fixed: f64 = pinned.iter().flatten().sum();
+        let remaining = (budget - fixed).max(0.0);
+        let free_weight: f64 = (0..n)
+            .filter(|&i| pinned[i].is_none())
+            .map(|i| weights[i])
+            .sum();
+
+        let shares: Vec<f64> = (0..n)
+            .map(|i| match pinned[i] {
+                Some(x) => x,
+                None if free_weight > 0.0 => remaining * weights[i] / free_weight,
+                None => 0.0,
+            })
+            .collect();
+
+        let over: f64 = (0..n)
+            .filter(|&i| pinned[i].is_none() && shares[i] > max[i])
+            .map(|i| shares[i] - max[i])
+            .sum();
+        let under: f64 = (0..n)
+            .filter(|&i| pinned[i].is_none() && shares[i] < min[i])
+            .map(|i| min[i] - shares[i])
+            .sum();
+
+        if over == 0.0 && under == 0.0 {
+            if free_weight == 0.0 && remaining > 0.0 {
+                return Err(ShapleyError::Validation(
+                    "No operator with a positive Shapley proportion can absorb the budget"
+                        .to_string(),
+                ));
+            }
+            return Ok(shares);
+        }
+
+        for i in 0..n {
+            if pinned[i].is_some() {
+                continue;
+            }
+            if over >= under && shares[i] > max[i] {
+                pinned[i] = Some(max[i]);
+            } else if over < under && shares[i] < min[i] {
+                pinned[i] = Some(min[i]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapley::ShapleyValue;
+
+    fn output(proportions: &[(&str, f64)]) -> ShapleyOutput {
+        proportions
+            .iter()
+            .map(|&(op, proportion)| {
+                (
+                    op.to_string(),
+                    ShapleyValue {
+                        value: proportion,
+                        proportion,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn total(payouts: &[(Operator, u64)]) -> u64 {
+        payouts.iter().map(|(_, p)| p).sum()
+    }
+
+    #[test]
+    fn test_largest_remainder_is_exact() {
+        let out = output(&[("A", 1.0 / 3.0), ("B", 1.0 / 3.0), ("C", 1.0 / 3.0)]);
+        let payouts = distribute(&out, 100, &PayoutCaps::default()).unwrap();
+
+        assert_eq!(total(&payouts), 100);
+        // The single leftover unit goes to the first operator by name
+        assert_eq!(
+            payouts,
+            vec![("A".into(), 34), ("B".into(), 33), ("C".into(), 33)]
+        );
+    }
+
+    #[test]
+    fn test_max_cap_redistributes_excess() {
+        let out = output(&[("A", 0.8), ("B", 0.15), ("C", 0.05)]);
+        let caps = PayoutCaps {
+            max: BTreeMap::from([("A".to_string(), 500)]),
+            ..Default::default()
+        };
+        let payouts = distribute(&out, 1000, &caps).unwrap();
+
+        assert_eq!(total(&payouts), 1000);
+        assert_eq!(payouts[0].1, 500);
+        // Remaining 500 split 3:1 between B and C
+        assert_eq!(payouts[1].1, 375);
+        assert_eq!(payouts[2].1, 125);
+    }
+
+    #[test]
+    fn test_min_cap_is_honoured() {
+        let out = output(&[("A", 0.99), ("B", 0.01)]);
+        let caps = PayoutCaps {
+            min: BTreeMap::from([("B".to_string(), 50)]),
+            ..Default::default()
+        };
+        let payouts = distribute(&out, 1000, &caps).unwrap();
+
+        assert_eq!(payouts, vec![("A".into(), 950), ("B".into(), 50)]);
+    }
+
+    #[test]
+    fn test_infeasible_caps_are_rejected() {
+        let out = output(&[("A", 0.5), ("B", 0.5)]);
+        let caps = PayoutCaps {
+            max: BTreeMap::from([("A".to_string(), 10), ("B".to_string(), 10)]),
+            ..Default::default()
+        };
+        assert!(distribute(&out, 100, &caps).is_err());
+    }
+
+    #[test]
+    fn test_cap_shares_water_fills() {
+        let out = output(&[("A", 0.6), ("B", 0.3), ("C", 0.1)]);
+        let caps = ShareCaps {
+            default_max: Some(0.4),
+            ..Default::default()
+        };
+        let allocation = cap_shares(&out, &caps).unwrap();
+        assert_eq!(allocation.raw, out);
+        // A's excess lifts B over the cap too, so C takes the rest
+        let capped = &allocation.capped;
+        assert!((capped["A"].proportion - 0.4).abs() < 1e-12);
+        assert!((capped["B"].proportion - 0.4).abs() < 1e-12);
+        assert!((capped["C"].proportion - 0.2).abs() < 1e-12);
+        let value: f64 = capped.values().map(|sv| sv.value).sum();
+        assert!((value - 1.0).abs() < 1e-12);
+
+        // Per-operator caps override the default
+        let caps = ShareCaps {
+            default_max: Some(0.4),
+            max: BTreeMap::from([("A".to_string(), 0.5)]),
+        };
+        let capped = cap_shares(&out, &caps).unwrap().capped;
+        assert!((capped["A"].proportion - 0.5).abs() < 1e-12);
+        assert!((capped["B"].proportion - 0.375).abs() < 1e-12);
+
+        let too_tight = ShareCaps {
+            default_max: Some(0.3),
+            ..Default::default()
+        };
+        assert!(cap_shares(&out, &too_tight).is_err());
+    }
+
+    #[test]
+    fn test_filter_dust() {
+        let out = output(&[("A", 0.6), ("B", 0.3), ("C", 0.06), ("D", 0.04)]);
+        let redistribute = DustFilter {
+            min_proportion: 0.05,
+            policy: DustPolicy::Redistribute,
+        };
+        let filtered = filter_dust(&out, &redistribute).unwrap();
+        assert_eq!(filtered["D"].proportion, 0.0);
+        assert!((filtered["A"].proportion - 0.625).abs() < 1e-12);
+        let value: f64 = filtered.values().map(|sv| sv.value).sum();
+        assert!((value - 1.0).abs() < 1e-12);
+        let payouts = distribute(&filtered, 1000, &PayoutCaps::default()).unwrap();
+        assert_eq!(payouts[3], ("D".into(), 0));
+
+        let group = DustFilter {
+            min_proportion: 0.1,
+            policy: DustPolicy::Group,
+        };
+        let grouped = filter_dust(&out, &group).unwrap();
+        assert_eq!(
+            grouped.keys().collect::<Vec<_>>(),
+            ["A", "B", OTHER_OPERATOR]
+        );
+        assert!((grouped[OTHER_OPERATOR].proportion - 0.1).abs() < 1e-12);
+
+        let everything = DustFilter {
+            min_proportion: 0.9,
+            ..redistribute
+        };
+        assert!(filter_dust(&out, &everything).is_err());
+    }
+}
@@ -0,0 +1,445 @@
+//! Recomputing the values epoch after epoch as the network changes a little
+//! at a time.
+//!
+//! A [`RollingShapley`] holds the current input and an [`LpCache`] of the
+//! LPs built so far. [`NetworkDelta`]s edit the input; the next
+//! [`compute`](RollingShapley::compute) reuses every LP whose consolidated
+//! links and demands did not change, and patches the bandwidth limits of
+//! those whose links changed only in bandwidth, instead of building them
+//! again.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cost::CostModel,
+    error::{Result, ShapleyError},
+    lp_builder::LpPrimitives,
+    options::{CongestionCurve, ShapleyOptions},
+    shapley::{ShapleyInput, ShapleyReport},
+    types::{ConsolidatedDemand, ConsolidatedLink, Demand, Device, PrivateLink},
+};
+
+/// LPs kept by an [`LpCache`]: enough for every scenario and priority class
+/// of a typical epoch
+const CACHE_CAPACITY: usize = 64;
+
+/// How the LPs requested from an [`LpCache`] were obtained
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LpCacheStats {
+    /// Built from scratch
+    pub built: usize,
+    /// Copied from a cached LP over the same links with other bandwidths,
+    /// with the bandwidth limits replaced
+    pub patched: usize,
+    /// Cached LP over the same links and demands, used as is
+    pub reused: usize,
+}
+
+/// Built LPs shared between computations, see
+/// [`ShapleyOptions::lp_cache`]. Clones share the cache.
+#[derive(Debug, Clone, Default)]
+pub struct LpCache(Arc<Mutex<CacheState>>);
+
+#[derive(Debug, Default)]
+struct CacheState {
+    /// Least recently used first
+    entries: VecDeque<CacheEntry>,
+    stats: LpCacheStats,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    links: Vec<ConsolidatedLink>,
+    demands: Vec<ConsolidatedDemand>,
+    contiguity_bonus_by_type: BTreeMap<u32, f64>,
+    /// Held so its address stays its own; models are compared by identity
+    cost_model: Option<Arc<dyn CostModel>>,
+    congestion: Vec<CongestionCurve>,
+    primitives: LpPrimitives,
+}
+
+impl CacheEntry {
+    fn builds_same_lp(
+        &self,
+        demands: &[ConsolidatedDemand],
+        contiguity_bonus_by_type: &BTreeMap<u32, f64>,
+        cost_model: Option<&Arc<dyn CostModel>>,
+        congestion: &[CongestionCurve],
+    ) -> bool {
+        let same_model = match (&self.cost_model, cost_model) {
+            (None, None) => true,
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        };
+        self.demands == demands
+            && self.contiguity_bonus_by_type == *contiguity_bonus_by_type
+            && same_model
+            && self.congestion == congestion
+    }
+}
+
+impl LpCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stats(&self) -> LpCacheStats {
+        self.lock().stats
+    }
+
+    /// Forget every cached LP, keeping the stats
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        // The state is consistent between statements, so a panic elsewhere
+        // while holding the lock leaves nothing half-updated
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The LP over `links` and `demands`, from the cache or from `build`
+    pub(crate) fn primitives(
+        &self,
+        links: &[ConsolidatedLink],
+        demands: &[ConsolidatedDemand],
+        contiguity_bonus_by_type: &BTreeMap<u32, f64>,
+        cost_model: Option<&Arc<dyn CostModel>>,
+        congestion: &[CongestionCurve],
+        build: impl FnOnce() -> Result<LpPrimitives>,
+    ) -> Result<LpPrimitives> {
+        {
+            let mut state = self.lock();
+            let candidates = |entry: &CacheEntry| {
+                entry.links.len() == links.len()
+                    && entry.builds_same_lp(
+                        demands,
+                        contiguity_bonus_by_type,
+                        cost_model,
+                        congestion,
+                    )
+            };
+            if let Some(i) = state
+                .entries
+                .iter()
+                .position(|e| candidates(e) && e.links == links)
+            {
+                let entry = state.entries.remove(i).expect("position is in range");
+                let primitives = entry.primitives.clone();
+                state.entries.push_back(entry);
+                state.stats.reused += 1;
+                return Ok(primitives);
+            }
+            if let Some(i) = state
+                .entries
+                .iter()
+                .position(|e| candidates(e) && same_but_bandwidth(&e.links, links))
+            {
+                let mut entry = state.entries.remove(i).expect("position is in range");
+                entry.primitives.patch_bandwidths(links);
+                entry.links = links.to_vec();
+                let primitives = entry.primitives.clone();
+                state.entries.push_back(entry);
+                state.stats.patched += 1;
+                return Ok(primitives);
+            }
+        }
+
+        // Build without the lock, so scenarios solved in parallel do not wait
+        // on each other's builds
+        let primitives = build()?;
+        let mut state = self.lock();
+        state.stats.built += 1;
+        if state.entries.len() == CACHE_CAPACITY {
+            state.entries.pop_front();
+        }
+        state.entries.push_back(CacheEntry {
+            links: links.to_vec(),
+            demands: demands.to_vec(),
+            contiguity_bonus_by_type: contiguity_bonus_by_type.clone(),
+            cost_model: cost_model.cloned(),
+            congestion: congestion.to_vec(),
+            primitives: primitives.clone(),
+        });
+        Ok(primitives)
+    }
+}
+
+/// Whether the links are the same but for their bandwidths. Shared IDs are
+/// compared, so the bandwidth rows are the same rows.
+fn same_but_bandwidth(cached: &[ConsolidatedLink], links: &[ConsolidatedLink]) -> bool {
+    cached.iter().zip(links).all(|(cached, link)| {
+        ConsolidatedLink {
+            bandwidth: link.bandwidth,
+            ..cached.clone()
+        } == *link
+    })
+}
+
+/// One structural change to the network between epochs. Links are matched
+/// by their devices in either order.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub enum NetworkDelta {
+    AddLink(PrivateLink),
+    /// Remove every private link between the devices
+    RemoveLink {
+        device1: String,
+        device2: String,
+    },
+    /// Set the bandwidth of every private link between the devices
+    SetBandwidth {
+        device1: String,
+        device2: String,
+        bandwidth: f64,
+    },
+    AddDevice(Device),
+    /// Remove the device and the private links ending at it
+    RemoveDevice(String),
+    AddDemand(Demand),
+    /// Remove every demand from `start` to `end` of traffic type `kind`
+    RemoveDemand {
+        start: String,
+        end: String,
+        kind: u32,
+    },
+}
+
+impl NetworkDelta {
+    fn apply(self, input: &mut ShapleyInput) -> Result<()> {
+        let between = |link: &PrivateLink, a: &str, b: &str| {
+            (link.device1 == a && link.device2 == b) || (link.device1 == b && link.device2 == a)
+        };
+        match self {
+            Self::AddLink(link) => input.private_links.push(link),
+            Self::RemoveLink { device1, device2 } => {
+                let before = input.private_links.len();
+                input
+                    .private_links
+                    .retain(|l| !between(l, &device1, &device2));
+                if input.private_links.len() == before {
+                    return Err(no_link(&device1, &device2));
+                }
+            }
+            Self::SetBandwidth {
+                device1,
+                device2,
+                bandwidth,
+            } => {
+                let mut found = false;
+                for link in input
+                    .private_links
+                    .iter_mut()
+                    .filter(|l| between(l, &device1, &device2))
+                {
+                    link.bandwidth = bandwidth;
+                    found = true;
+                }
+                if !found {
+                    return Err(no_link(&device1, &device2));
+                }
+            }
+            Self::AddDevice(device) => {
+                if input.devices.iter().any(|d| d.device == device.device) {
+                    return Err(ShapleyError::Validation(format!(
+                        "Device {} already exists",
+                        device.device
+                    )));
+                }
+                input.devices.push(device);
+            }
+            Self::RemoveDevice(name) => {
+                let before = input.devices.len();
+                input.devices.retain(|d| d.device != name);
+                if input.devices.len() == before {
+                    return Err(ShapleyError::Validation(format!(
+                        "No device {name} to remove"
+                    )));
+                }
+                input
+                    .private_links
+                    .retain(|l| l.device1 != name && l.device2 != name);
+            }
+            Self::AddDemand(demand) => input.demands.push(demand),
+            Self::RemoveDemand { start, end, kind } => {
+                let before = input.demands.len();
+                input
+                    .demands
+                    .retain(|d| !(d.start == start && d.end == end && d.kind == kind));
+                if input.demands.len() == before {
+                    return Err(ShapleyError::Validation(format!(
+                        "No demand {start}-{end} of type {kind} to remove"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn no_link(device1: &str, device2: &str) -> ShapleyError {
+    ShapleyError::Validation(format!("No private link {device1}-{device2}"))
+}
+
+/// Values of a network computed epoch after epoch, keeping the built LPs
+/// between epochs, see [`rolling`](crate::rolling)
+#[derive(Debug, Clone)]
+pub struct RollingShapley {
+    input: ShapleyInput,
+    options: ShapleyOptions,
+    epoch: u64,
+}
+
+impl RollingShapley {
+    /// Start at epoch 0 with `input`. The computations share the options'
+    /// [`lp_cache`](ShapleyOptions::lp_cache), or a new one.
+    pub fn new(input: ShapleyInput, options: &ShapleyOptions) -> Self {
+        let mut options = options.clone();
+        options.lp_cache.get_or_insert_with(LpCache::new);
+        Self {
+            input,
+            options,
+            epoch: 0,
+        }
+    }
+
+    pub fn input(&self) -> &ShapleyInput {
+        &self.input
+    }
+
+    /// Number of [`apply`](Self::apply) calls so far
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Apply `deltas` in order and move to the next epoch. Either every delta
+    /// applies or, on the first that names a missing link, device or demand,
+    /// the input is left as it was.
+    pub fn apply(&mut self, deltas: impl IntoIterator<Item = NetworkDelta>) -> Result<()> {
+        let mut input = self.input.clone();
+        for delta in deltas {
+            delta.apply(&mut input)?;
+        }
+        self.input = input;
+        self.epoch += 1;
+        Ok(())
+    }
+
+    /// The report of [`ShapleyInput::compute_report`] for the current epoch
+    pub fn compute(&self) -> Result<ShapleyReport> {
+        self.input.compute_report(&self.options)
+    }
+
+    pub fn cache_stats(&self) -> LpCacheStats {
+        self.options
+            .lp_cache
+            .as_ref()
+            .map(LpCache::stats)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapley::testing::{RandomInputConfig, random_input};
+
+    fn rolling() -> RollingShapley {
+        let input = random_input(3, &RandomInputConfig::default());
+        RollingShapley::new(input, &ShapleyOptions::default())
+    }
+
+    #[test]
+    fn test_bandwidth_changes_patch_the_cached_lps() {
+        let mut rolling = rolling();
+        rolling.compute().unwrap();
+        let built = rolling.cache_stats().built;
+        assert!(built > 0);
+
+        // Unchanged network: every LP comes from the cache
+        rolling.compute().unwrap();
+        assert_eq!(rolling.cache_stats().built, built);
+        assert_eq!(rolling.cache_stats().reused, built);
+
+        let link = &rolling.input().private_links[0];
+        let delta = NetworkDelta::SetBandwidth {
+            device1: link.device2.clone(),
+            device2: link.device1.clone(),
+            bandwidth: link.bandwidth / 4.0,
+        };
+        rolling.apply([delta]).unwrap();
+        assert_eq!(rolling.epoch(), 1);
+        let patched = rolling.compute().unwrap();
+        let stats = rolling.cache_stats();
+        assert_eq!(stats.built, built);
+        assert!(stats.patched > 0);
+
+        let fresh = rolling.input().compute().unwrap();
+        for (op, value) in &fresh {
+            assert!((patched.values[op].value - value.value).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_cost_models_are_cached_by_identity() {
+        let input = random_input(3, &RandomInputConfig::default());
+        let cache = LpCache::new();
+        let with_model = || ShapleyOptions {
+            cost_model: Some(Arc::new(crate::cost::MonetaryCost::new(2.0))),
+            lp_cache: Some(cache.clone()),
+            ..Default::default()
+        };
+        let options = with_model();
+        input.compute_with(&options).unwrap();
+        let built = cache.stats().built;
+        input.compute_with(&options).unwrap();
+        assert_eq!(cache.stats().built, built);
+
+        // An equal model behind another Arc, which may land at the address
+        // of a dropped one, is not taken for the cached model
+        drop(options);
+        input.compute_with(&with_model()).unwrap();
+        assert_eq!(cache.stats().built, 2 * built);
+    }
+
+    #[test]
+    fn test_structural_deltas() {
+        let mut rolling = rolling();
+        rolling.compute().unwrap();
+        let built = rolling.cache_stats().built;
+
+        let mut link = rolling.input().private_links[0].clone();
+        std::mem::swap(&mut link.device1, &mut link.device2);
+        link.latency += 1.0;
+        rolling.apply([NetworkDelta::AddLink(link)]).unwrap();
+        rolling.compute().unwrap();
+        assert!(rolling.cache_stats().built > built);
+
+        // A failing delta leaves the input and the epoch as they were
+        let before = rolling.input().clone();
+        let deltas = [
+            NetworkDelta::RemoveDemand {
+                start: before.demands[0].start.clone(),
+                end: before.demands[0].end.clone(),
+                kind: before.demands[0].kind,
+            },
+            NetworkDelta::RemoveLink {
+                device1: "XXX1".into(),
+                device2: "YYY1".into(),
+            },
+        ];
+        assert!(matches!(
+            rolling.apply(deltas),
+            Err(ShapleyError::Validation(_))
+        ));
+        assert_eq!(rolling.input().demands.len(), before.demands.len());
+        assert_eq!(rolling.epoch(), 1);
+    }
+}
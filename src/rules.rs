@@ -0,0 +1,216 @@
+//! Input validation as a set of named rules that integrators can extend or
+//! trim without forking the crate.
+//!
+//! The default set reproduces the checks of the reference implementation.
+//! Each built-in rule has a stable name (see [`ValidationRules::builtin`]).
+//! Policy rules can be disabled under it, e.g. `public_operator` to allow
+//! devices of an operator named `Public` in a sandbox; the others guard
+//! assumptions of the computation and stay on. Extra rules implement
+//! [`ValidationRule`] and run after the built-in ones. The operator count is
+//! checked before any rule and cannot be disabled.
+
+use std::{collections::BTreeSet, fmt::Debug, sync::Arc};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{ErrorContext, InputTable, Result, ShapleyError},
+    types::{Demands, Devices, PrivateLinks, PublicLinks},
+    validation::BUILTIN_RULES,
+};
+
+/// The input tables as seen by a [`ValidationRule`], before any
+/// consolidation
+#[derive(Debug, Clone, Copy)]
+pub struct RuleInput<'a> {
+    pub private_links: &'a PrivateLinks,
+    pub devices: &'a Devices,
+    pub demands: &'a Demands,
+    pub public_links: &'a PublicLinks,
+}
+
+/// One check of the inputs. An error rejects the computation.
+pub trait ValidationRule: Debug + Send + Sync {
+    /// Name the rule is reported and disabled under
+    fn name(&self) -> &str;
+    fn check(&self, input: &RuleInput<'_>) -> Result<()>;
+}
+
+/// A rule of the default set
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BuiltinRule {
+    pub(crate) name: &'static str,
+    pub(crate) check: fn(&RuleInput<'_>) -> Result<()>,
+    /// A policy the computation does not rely on
+    pub(crate) disableable: bool,
+}
+
+impl ValidationRule for BuiltinRule {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn check(&self, input: &RuleInput<'_>) -> Result<()> {
+        (self.check)(input)
+    }
+}
+
+/// The rules inputs are validated with: the built-in ones not disabled,
+/// then the added ones in the order they were added
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, Default)]
+pub struct ValidationRules {
+    /// Names of built-in rules to skip
+    pub disabled: BTreeSet<String>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub extra: Vec<Arc<dyn ValidationRule>>,
+}
+
+impl ValidationRules {
+    /// Names of the built-in rules, in the order they run
+    pub fn builtin() -> impl Iterator<Item = &'static str> {
+        BUILTIN_RULES.iter().map(|rule| rule.name)
+    }
+
+    /// Names of the built-in rules [`disable`](Self::disable) accepts
+    pub fn disableable() -> impl Iterator<Item = &'static str> {
+        BUILTIN_RULES
+            .iter()
+            .filter(|rule| rule.disableable)
+            .map(|rule| rule.name)
+    }
+
+    /// Skip a built-in policy rule. Checking fails for names not in
+    /// [`disableable`](Self::disableable).
+    pub fn disable(mut self, name: &str) -> Self {
+        self.disabled.insert(name.to_string());
+        self
+    }
+
+    pub fn with_rule(mut self, rule: impl ValidationRule + 'static) -> Self {
+        self.extra.push(Arc::new(rule));
+        self
+    }
+
+    pub(crate) fn check(&self, input: &RuleInput<'_>) -> Result<()> {
+        if let Some(unknown) = self
+            .disabled
+            .iter()
+            .find(|name| !Self::builtin().any(|builtin| builtin == name.as_str()))
+        {
+            return Err(ShapleyError::Validation(format!(
+                "Cannot disable unknown validation rule {unknown}"
+            )));
+        }
+        if let Some(required) = self
+            .disabled
+            .iter()
+            .find(|name| !Self::disableable().any(|policy| policy == name.as_str()))
+        {
+            return Err(ShapleyError::Validation(format!(
+                "Cannot disable validation rule {required}: the computation relies on it"
+            )));
+        }
+        for rule in BUILTIN_RULES {
+            if !self.disabled.contains(rule.name) {
+                rule.check(input)?;
+            }
+        }
+        for rule in &self.extra {
+            rule.check(input)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rejects private links slower than `max_latency`, in the unit of
+/// `PrivateLink::latency`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaxLinkLatency {
+    pub max_latency: f64,
+}
+
+impl ValidationRule for MaxLinkLatency {
+    fn name(&self) -> &str {
+        "max_link_latency"
+    }
+
+    fn check(&self, input: &RuleInput<'_>) -> Result<()> {
+        for (row, link) in input.private_links.iter().enumerate() {
+            if link.latency > self.max_latency {
+                return Err(ShapleyError::Validation(format!(
+                    "Private link {}-{} has latency {} above the limit of {}",
+                    link.device1, link.device2, link.latency, self.max_latency
+                ))
+                .with_context(ErrorContext::cell(
+                    InputTable::PrivateLinks,
+                    row,
+                    "latency",
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        options::ShapleyOptions,
+        shapley::testing::{RandomInputConfig, random_input},
+    };
+
+    #[test]
+    fn test_rules_can_be_added_and_disabled() {
+        let input = random_input(5, &RandomInputConfig::default());
+        assert!(input.compute().is_ok());
+
+        let strict = ShapleyOptions {
+            rules: ValidationRules::default().with_rule(MaxLinkLatency { max_latency: 1.0 }),
+            ..Default::default()
+        };
+        assert!(matches!(
+            input.compute_with(&strict),
+            Err(ShapleyError::Validation(_))
+        ));
+        let err = input.validate(&strict).unwrap_err();
+        assert_eq!(err.context().unwrap().column, Some("latency"));
+
+        // A sandbox may use the protected operator name
+        let mut sandbox = input.clone();
+        sandbox.devices[0].operator = "Public".to_string();
+        assert!(sandbox.compute().is_err());
+        let lenient = ShapleyOptions {
+            rules: ValidationRules::default().disable("public_operator"),
+            ..Default::default()
+        };
+        assert!(sandbox.compute_with(&lenient).is_ok());
+
+        let typo = ShapleyOptions {
+            rules: ValidationRules::default().disable("public_operators"),
+            ..Default::default()
+        };
+        assert!(input.compute_with(&typo).is_err());
+
+        // Invariants stay on: without them demands would go unrouted or
+        // devices be merged
+        for required in ["reachable_demands", "unique_devices"] {
+            let options = ShapleyOptions {
+                rules: ValidationRules::default().disable(required),
+                ..Default::default()
+            };
+            match input.compute_with(&options) {
+                Err(ShapleyError::Validation(msg)) => assert!(msg.contains(required), "{msg}"),
+                other => panic!("Expected a validation error, got {other:?}"),
+            }
+        }
+        assert_eq!(
+            ValidationRules::disableable().collect::<Vec<_>>(),
+            ["public_operator", "demand_receivers"]
+        );
+        assert_eq!(ValidationRules::builtin().count(), BUILTIN_RULES.len());
+    }
+}
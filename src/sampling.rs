@@ -0,0 +1,581 @@
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "surrogate")]
+use crate::surrogate::CoalitionSurrogate;
+use crate::{
+    error::{Result, ShapleyError},
+    options::{SampleBudget, SamplerKind},
+    utils::SplitMix64,
+};
+
+/// Sampled Shapley values with the standard error of each estimate
+#[derive(Debug, Clone)]
+pub(crate) struct Estimate {
+    pub values: Vec<f64>,
+    /// NaN where fewer than two independent samples back the estimate
+    pub standard_errors: Vec<f64>,
+    /// Orderings drawn, or marginal contributions for the stratified sampler
+    pub samples: usize,
+    /// Distinct coalitions whose value was needed
+    pub coalitions: usize,
+}
+
+/// One sampled operator ordering, with the operators that are up in it
+#[derive(Debug, Clone)]
+struct PermutationSample {
+    order: Vec<usize>,
+    up: u32,
+}
+
+impl PermutationSample {
+    /// Coalitions visited while walking the ordering, starting from the empty one.
+    /// Down operators are skipped, so they add no coalition.
+    fn prefixes(&self) -> impl Iterator<Item = u32> + '_ {
+        let mut coalition = 0u32;
+        std::iter::once(0).chain(self.order.iter().filter(|&&k| self.up & (1 << k) != 0).map(
+            move |&k| {
+                coalition |= 1 << k;
+                coalition
+            },
+        ))
+    }
+
+    /// Marginal contribution of every operator along the ordering
+    fn marginals(&self, n_operators: usize, oracle: &Oracle<'_>) -> Vec<f64> {
+        let mut contributions = vec![0.0; n_operators];
+        let mut coalition = 0u32;
+        let mut previous = oracle.value(0);
+        for &k in &self.order {
+            if self.up & (1 << k) == 0 {
+                continue;
+            }
+            coalition |= 1 << k;
+            let current = oracle.value(coalition);
+            contributions[k] = current - previous;
+            previous = current;
+        }
+        contributions
+    }
+}
+
+/// Memoised coalition values; `solve` is called once per batch of unseen coalitions
+struct Oracle<'a> {
+    solve: &'a mut dyn FnMut(&[u32]) -> Result<Vec<f64>>,
+    values: HashMap<u32, f64>,
+}
+
+impl Oracle<'_> {
+    fn prefetch(&mut self, coalitions: impl IntoIterator<Item = u32>) -> Result<()> {
+        let mut missing: Vec<u32> = coalitions
+            .into_iter()
+            .filter(|c| !self.values.contains_key(c))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        missing.sort_unstable();
+        let values = (self.solve)(&missing)?;
+        self.values.extend(missing.into_iter().zip(values));
+        Ok(())
+    }
+
+    fn value(&self, coalition: u32) -> f64 {
+        self.values[&coalition]
+    }
+}
+
+/// Estimate Shapley values by sampling. `solve` returns the value of each
+/// requested coalition (bit k ↔ operator k).
+///
+/// With operator uptime below 1, every sample also draws which operators are
+/// up; a down operator contributes nothing, which keeps the estimate unbiased
+/// for the expected-value game of the exact computation.
+pub(crate) fn estimate(
+    n_operators: usize,
+    operator_uptime: f64,
+    sampler: SamplerKind,
+    budget: SampleBudget,
+    seed: u64,
+    solve: &mut dyn FnMut(&[u32]) -> Result<Vec<f64>>,
+) -> Result<Estimate> {
+    let limit = match budget {
+        SampleBudget::Permutations(n) | SampleBudget::CoalitionSolves(n) => n,
+    };
+    if limit == 0 {
+        return Err(ShapleyError::Validation(
+            "Sample budget must be positive".to_string(),
+        ));
+    }
+
+    let mut rng = SplitMix64::new(seed);
+    let mut oracle = Oracle {
+        solve,
+        values: HashMap::new(),
+    };
+    match sampler {
+        SamplerKind::Permutation | SamplerKind::Antithetic => permutation_estimate(
+            n_operators,
+            operator_uptime,
+            sampler == SamplerKind::Antithetic,
+            budget,
+            &mut rng,
+            &mut oracle,
+        ),
+        SamplerKind::Stratified => {
+            stratified_estimate(n_operators, operator_uptime, budget, &mut rng, &mut oracle)
+        }
+        #[cfg(feature = "surrogate")]
+        SamplerKind::Surrogate => {
+            surrogate_estimate(n_operators, operator_uptime, budget, &mut rng, &mut oracle)
+        }
+    }
+}
+
+fn permutation_estimate(
+    n_operators: usize,
+    operator_uptime: f64,
+    antithetic: bool,
+    budget: SampleBudget,
+    rng: &mut SplitMix64,
+    oracle: &mut Oracle<'_>,
+) -> Result<Estimate> {
+    let samples = draw_permutations(n_operators, operator_uptime, antithetic, budget, rng)?;
+    oracle.prefetch(samples.iter().flat_map(PermutationSample::prefixes))?;
+
+    let contributions: Vec<Vec<f64>> = samples
+        .iter()
+        .map(|s| s.marginals(n_operators, oracle))
+        .collect();
+    // An ordering and its reverse are one (negatively correlated) unit
+    let units: Vec<Vec<f64>> = if antithetic {
+        contributions
+            .chunks(2)
+            .map(|pair| {
+                (0..n_operators)
+                    .map(|k| pair.iter().map(|c| c[k]).sum::<f64>() / pair.len() as f64)
+                    .collect()
+            })
+            .collect()
+    } else {
+        contributions
+    };
+
+    let (values, standard_errors) = (0..n_operators)
+        .map(|k| {
+            let (mean, variance) = mean_variance(units.iter().map(|u| u[k]));
+            (mean, (variance / units.len() as f64).sqrt())
+        })
+        .unzip();
+
+    Ok(Estimate {
+        values,
+        standard_errors,
+        samples: samples.len(),
+        coalitions: oracle.values.len(),
+    })
+}
+
+/// Permutation sampling of the residual of an additive surrogate fit to the
+/// coalitions the orderings visit. The surrogate's Shapley values are exact,
+/// and along every ordering the residual marginals sum to what the surrogate
+/// misses of v(N) - v(∅), so the estimate stays efficient.
+#[cfg(feature = "surrogate")]
+fn surrogate_estimate(
+    n_operators: usize,
+    operator_uptime: f64,
+    budget: SampleBudget,
+    rng: &mut SplitMix64,
+    oracle: &mut Oracle<'_>,
+) -> Result<Estimate> {
+    let samples = draw_permutations(n_operators, operator_uptime, false, budget, rng)?;
+    oracle.prefetch(samples.iter().flat_map(PermutationSample::prefixes))?;
+
+    let mut visited: Vec<(u32, f64)> = oracle.values.iter().map(|(&c, &v)| (c, v)).collect();
+    visited.sort_unstable_by_key(|&(c, _)| c);
+    let surrogate = CoalitionSurrogate::fit(n_operators, &visited)?;
+    let fitted = surrogate.shapley_values(operator_uptime);
+
+    let contributions: Vec<Vec<f64>> = samples
+        .iter()
+        .map(|sample| {
+            let mut residuals = sample.marginals(n_operators, oracle);
+            for (k, residual) in residuals.iter_mut().enumerate() {
+                if sample.up & (1 << k) != 0 {
+                    *residual -= surrogate.weights[k];
+                }
+            }
+            residuals
+        })
+        .collect();
+    let (values, standard_errors) = (0..n_operators)
+        .map(|k| {
+            let (mean, variance) = mean_variance(contributions.iter().map(|c| c[k]));
+            (
+                fitted[k] + mean,
+                (variance / contributions.len() as f64).sqrt(),
+            )
+        })
+        .unzip();
+
+    Ok(Estimate {
+        values,
+        standard_errors,
+        samples: samples.len(),
+        coalitions: oracle.values.len(),
+    })
+}
+
+/// Draw operator orderings until the budget is spent; antithetic draws come in
+/// (ordering, reversed ordering) pairs sharing their up operators. Under a
+/// `CoalitionSolves` budget, drawing stops before a draw would push the number
+/// of distinct coalitions over the budget, or once every coalition has been
+/// visited; the budget must cover the coalitions of a single draw.
+fn draw_permutations(
+    n_operators: usize,
+    operator_uptime: f64,
+    antithetic: bool,
+    budget: SampleBudget,
+    rng: &mut SplitMix64,
+) -> Result<Vec<PermutationSample>> {
+    let mut draw = || {
+        let mut order: Vec<usize> = (0..n_operators).collect();
+        // Fisher-Yates shuffle
+        for i in (1..n_operators).rev() {
+            let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+            order.swap(i, j);
+        }
+        let up = draw_up(0..n_operators, operator_uptime, rng);
+        let mut draws = vec![PermutationSample {
+            order: order.clone(),
+            up,
+        }];
+        if antithetic {
+            order.reverse();
+            draws.push(PermutationSample { order, up });
+        }
+        draws
+    };
+
+    let mut samples = Vec::new();
+    match budget {
+        SampleBudget::Permutations(n) => {
+            while samples.len() < n {
+                samples.extend(draw());
+            }
+            // An odd antithetic budget ends on an unpaired ordering
+            samples.truncate(n);
+        }
+        SampleBudget::CoalitionSolves(max_solves) => {
+            // An ordering visits n + 1 coalitions; its reverse shares the
+            // empty and grand coalitions
+            let per_draw = match antithetic {
+                true => (2 * n_operators).max(n_operators + 1),
+                false => n_operators + 1,
+            };
+            if max_solves < per_draw {
+                return Err(ShapleyError::Validation(format!(
+                    "Sample budget of {max_solves} coalition solves is below the {per_draw} of a single draw for {n_operators} operators"
+                )));
+            }
+            let n_coalitions = 1u64 << n_operators;
+            let mut seen: HashSet<u32> = HashSet::new();
+            while (seen.len() as u64) < n_coalitions {
+                let draws = draw();
+                let new: HashSet<u32> = draws
+                    .iter()
+                    .flat_map(PermutationSample::prefixes)
+                    .filter(|c| !seen.contains(c))
+                    .collect();
+                if seen.len() + new.len() > max_solves {
+                    break;
+                }
+                seen.extend(new);
+                samples.extend(draws);
+            }
+        }
+    }
+    Ok(samples)
+}
+
+/// Stratified sampling over (operator, coalition size) strata with Neyman
+/// allocation: half the budget is spread evenly as a pilot (two samples per
+/// stratum when the budget allows, one at least), the rest in proportion to
+/// each stratum's pilot standard deviation. The budget must cover one sample
+/// per stratum. Each sample is one marginal contribution, i.e. two
+/// coalition LPs; a `Permutations(p)` budget buys `p × n` of them, the same
+/// number of marginals as `p` orderings.
+fn stratified_estimate(
+    n_operators: usize,
+    operator_uptime: f64,
+    budget: SampleBudget,
+    rng: &mut SplitMix64,
+    oracle: &mut Oracle<'_>,
+) -> Result<Estimate> {
+    let n = n_operators;
+    let n_strata = n * n;
+    let total = match budget {
+        SampleBudget::Permutations(p) => p.saturating_mul(n),
+        SampleBudget::CoalitionSolves(k) => k / 2,
+    };
+
+    if total < n_strata {
+        return Err(ShapleyError::Validation(format!(
+            "Sample budget of {total} marginal contributions is below the {n_strata} strata of {n} operators"
+        )));
+    }
+
+    // strata[i * n + s]: marginal contributions of operator i to coalitions of size s
+    let mut strata: Vec<Vec<f64>> = vec![Vec::new(); n_strata];
+    let pilot = (total / (2 * n_strata)).max(2).min(total / n_strata);
+    stratified_round(
+        n,
+        operator_uptime,
+        &vec![pilot; n_strata],
+        rng,
+        oracle,
+        &mut strata,
+    )?;
+
+    let remaining = total.saturating_sub(pilot * n_strata);
+    if remaining > 0 {
+        let sigma: Vec<f64> = strata
+            .iter()
+            .map(|xs| mean_variance(xs.iter().copied()).1.sqrt())
+            .collect();
+        let sigma_sum: f64 = sigma.iter().sum();
+        let shares: Vec<f64> = sigma
+            .iter()
+            .map(|&s| {
+                if sigma_sum > 0.0 {
+                    remaining as f64 * s / sigma_sum
+                } else {
+                    remaining as f64 / n_strata as f64
+                }
+            })
+            .collect();
+
+        // Floors first, leftovers to the largest fractional shares
+        let mut counts: Vec<usize> = shares.iter().map(|&x| x as usize).collect();
+        let mut order: Vec<usize> = (0..n_strata).collect();
+        order.sort_by(|&a, &b| {
+            (shares[b] - shares[b].floor()).total_cmp(&(shares[a] - shares[a].floor()))
+        });
+        let leftover = remaining - counts.iter().sum::<usize>();
+        for &stratum in order.iter().take(leftover) {
+            counts[stratum] += 1;
+        }
+        stratified_round(n, operator_uptime, &counts, rng, oracle, &mut strata)?;
+    }
+
+    // φ_i is the plain average of the size-stratum means
+    let (values, standard_errors) = (0..n)
+        .map(|i| {
+            let (mut value, mut variance) = (0.0, 0.0);
+            for xs in &strata[i * n..(i + 1) * n] {
+                let (mean, var) = mean_variance(xs.iter().copied());
+                value += mean;
+                variance += var / xs.len() as f64;
+            }
+            (value / n as f64, variance.sqrt() / n as f64)
+        })
+        .unzip();
+
+    Ok(Estimate {
+        values,
+        standard_errors,
+        samples: strata.iter().map(Vec::len).sum(),
+        coalitions: oracle.values.len(),
+    })
+}
+
+/// Draw `counts[stratum]` marginal contributions for every stratum
+fn stratified_round(
+    n: usize,
+    operator_uptime: f64,
+    counts: &[usize],
+    rng: &mut SplitMix64,
+    oracle: &mut Oracle<'_>,
+    strata: &mut [Vec<f64>],
+) -> Result<()> {
+    // (stratum, operator bit, coalition of up operators, whether the operator is up)
+    let mut draws: Vec<(usize, u32, u32, bool)> = Vec::new();
+    for (stratum, &count) in counts.iter().enumerate() {
+        let (i, size) = (stratum / n, stratum % n);
+        let mut others: Vec<usize> = (0..n).filter(|&k| k != i).collect();
+        for _ in 0..count {
+            // Partial Fisher-Yates: the first `size` entries are a uniform subset
+            for j in 0..size {
+                let pick = j + (rng.next_u64() % (others.len() - j) as u64) as usize;
+                others.swap(j, pick);
+            }
+            let coalition = draw_up(others[..size].iter().copied(), operator_uptime, rng);
+            let up = draw_up(std::iter::once(i), operator_uptime, rng) != 0;
+            draws.push((stratum, 1 << i, coalition, up));
+        }
+    }
+
+    oracle.prefetch(
+        draws
+            .iter()
+            .filter(|d| d.3)
+            .flat_map(|&(_, bit, coalition, _)| [coalition, coalition | bit]),
+    )?;
+    for (stratum, bit, coalition, up) in draws {
+        strata[stratum].push(if up {
+            oracle.value(coalition | bit) - oracle.value(coalition)
+        } else {
+            0.0
+        });
+    }
+    Ok(())
+}
+
+/// Bitmask of the given operators that are up, each independently with
+/// probability `operator_uptime`
+fn draw_up(
+    operators: impl Iterator<Item = usize>,
+    operator_uptime: f64,
+    rng: &mut SplitMix64,
+) -> u32 {
+    operators
+        .filter(|_| operator_uptime >= 1.0 || rng.next_f64() < operator_uptime)
+        .fold(0u32, |mask, k| mask | 1 << k)
+}
+
+/// Sample mean and unbiased sample variance (NaN below two samples)
+fn mean_variance(xs: impl Iterator<Item = f64> + Clone) -> (f64, f64) {
+    let n = xs.clone().count() as f64;
+    let mean = xs.clone().sum::<f64>() / n;
+    let variance = if n < 2.0 {
+        f64::NAN
+    } else {
+        xs.map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0)
+    };
+    (mean, variance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// v(S) = |S|², whose marginals depend only on coalition size
+    fn squared_size(coalitions: &[u32]) -> Result<Vec<f64>> {
+        Ok(coalitions
+            .iter()
+            .map(|c| (c.count_ones() as f64).powi(2))
+            .collect())
+    }
+
+    fn run(sampler: SamplerKind, budget: SampleBudget) -> Estimate {
+        estimate(4, 1.0, sampler, budget, 0, &mut squared_size).unwrap()
+    }
+
+    #[test]
+    fn test_permutations_are_reproducible() {
+        let draw = |seed| {
+            let mut rng = SplitMix64::new(seed);
+            draw_permutations(5, 1.0, false, SampleBudget::Permutations(20), &mut rng).unwrap()
+        };
+        let (a, b) = (draw(3), draw(3));
+        for (x, y) in a.iter().zip(&b) {
+            assert_eq!(x.order, y.order);
+            let mut sorted = x.order.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, vec![0, 1, 2, 3, 4]);
+            assert_eq!(x.up, 0b11111);
+        }
+    }
+
+    #[test]
+    fn test_antithetic_pairs_are_reversed() {
+        let mut rng = SplitMix64::new(1);
+        let samples =
+            draw_permutations(5, 0.5, true, SampleBudget::Permutations(7), &mut rng).unwrap();
+        assert_eq!(samples.len(), 7);
+        for pair in samples.chunks_exact(2) {
+            let reversed: Vec<usize> = pair[0].order.iter().rev().copied().collect();
+            assert_eq!(pair[1].order, reversed);
+            assert_eq!(pair[0].up, pair[1].up);
+        }
+    }
+
+    #[test]
+    fn test_coalition_budget_is_respected() {
+        let mut rng = SplitMix64::new(1);
+        let samples =
+            draw_permutations(8, 1.0, false, SampleBudget::CoalitionSolves(40), &mut rng).unwrap();
+        let coalitions: HashSet<u32> = samples.iter().flat_map(|s| s.prefixes()).collect();
+        assert!(coalitions.len() <= 40);
+
+        // A budget must cover the first draw, which is never exceeded
+        for (antithetic, per_draw) in [(false, 9), (true, 16)] {
+            let mut rng = SplitMix64::new(1);
+            let budget = SampleBudget::CoalitionSolves;
+            assert!(draw_permutations(8, 1.0, antithetic, budget(per_draw - 1), &mut rng).is_err());
+            let samples =
+                draw_permutations(8, 1.0, antithetic, budget(per_draw), &mut rng).unwrap();
+            let coalitions: HashSet<u32> = samples.iter().flat_map(|s| s.prefixes()).collect();
+            assert!(!samples.is_empty() && coalitions.len() <= per_draw);
+        }
+
+        // A budget beyond 2^n stops once every coalition is visited
+        let estimate = run(SamplerKind::Permutation, SampleBudget::CoalitionSolves(100));
+        assert_eq!(estimate.coalitions, 16);
+    }
+
+    #[test]
+    fn test_samplers_are_efficient() {
+        // Every ordering hands out exactly v(N) - v(∅); by symmetry each
+        // operator's value is 4
+        for sampler in [
+            SamplerKind::Permutation,
+            SamplerKind::Antithetic,
+            #[cfg(feature = "surrogate")]
+            SamplerKind::Surrogate,
+        ] {
+            let estimate = run(sampler, SampleBudget::Permutations(7));
+            assert!((estimate.values.iter().sum::<f64>() - 16.0).abs() < 1e-12);
+        }
+
+        // Within a size stratum every marginal is 2s + 1, so stratification
+        // is exact and has no error
+        let estimate = run(SamplerKind::Stratified, SampleBudget::Permutations(10));
+        for (value, se) in estimate.values.iter().zip(&estimate.standard_errors) {
+            assert!((value - 4.0).abs() < 1e-12);
+            assert_eq!(*se, 0.0);
+        }
+        assert_eq!(estimate.samples, 40);
+    }
+
+    #[test]
+    fn test_stratified_stays_within_budget() {
+        // 16 strata, so a pilot of two per stratum alone would take 32
+        for marginals in [16, 20, 31, 33, 100] {
+            let estimate = run(
+                SamplerKind::Stratified,
+                SampleBudget::CoalitionSolves(2 * marginals),
+            );
+            assert!(estimate.samples <= marginals, "{marginals}");
+        }
+        for permutations in [4, 5, 9] {
+            let estimate = run(
+                SamplerKind::Stratified,
+                SampleBudget::Permutations(permutations),
+            );
+            assert!(estimate.samples <= 4 * permutations);
+        }
+        assert!(matches!(
+            estimate(
+                4,
+                1.0,
+                SamplerKind::Stratified,
+                SampleBudget::CoalitionSolves(30),
+                0,
+                &mut squared_size,
+            ),
+            Err(ShapleyError::Validation(_))
+        ));
+    }
+}
@@ -1,9 +1,13 @@
 use std::{
-    cell::RefCell,
-    collections::{BTreeMap, HashMap},
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fmt::{Display, Formatter},
+    sync::Arc,
+    time::Duration,
 };
 
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
 use rayon::prelude::*;
 #[cfg(feature = "serde")]
 use {
@@ -11,30 +15,87 @@ use {
     tabled::Tabled,
 };
 
+#[cfg(feature = "audit")]
+use crate::audit::PrecisionAudit;
+#[cfg(feature = "research")]
+use crate::research::{CoalitionData, ScenarioValues};
 use crate::{
-    consolidation::{consolidate_demand, consolidate_links},
-    error::{Result, ShapleyError},
-    lp_builder::LpBuilderInput,
-    solver::{CoalitionBuffers, PrecomputedRows, SolveStatus, solve_coalition},
-    types::{Demands, Devices, PrivateLinks, PublicLinks},
-    utils::factorial,
-    validation::check_inputs,
+    anonymize::{Pseudonyms, anonymize},
+    batch::{BatchLpBackend, CoalitionLp, SimplexBackend, batch_len},
+    carbon::CarbonReport,
+    commitment::CommitmentReport,
+    consolidation::{
+        apply_traffic_floor, apply_traffic_range, consolidate_demand, consolidate_links,
+        expand_regions, mark_unrewarded, override_contiguity_bonus, reserve_capacity,
+    },
+    core_math,
+    cost::{CarbonCost, CarbonIntensity},
+    distributed::{PartialResult, PartialScenario, WorkPartition},
+    error::{ErrorContext, Result, ShapleyError, SolverStatus},
+    game::{
+        Coalitions, ContributionReport, GameProperties, MarginalReport, SynergyMatrix,
+        game_properties, members, monotone_bounds,
+    },
+    integrality::{IntegralityReport, round_paths},
+    lp_builder::{LpBuilderInput, LpPrimitives},
+    metadata::ComputationMetadata,
+    onboarding::{OnboardingSimulation, augment},
+    options::{
+        ApproximationPolicy, CancellationToken, CapacityBasis, CoOwnershipPolicy, FailurePolicy,
+        ProgressCounter, SamplerKind, ShapleyOptions, SolverOptions,
+    },
+    partition::{PartitionedOutput, RegionPartition, split_by_region},
+    pipeline::LpProblem,
+    profile::{DemandProfile, demand_slots},
+    sampling,
+    sla::SlaReport,
+    solver::{CoalitionBuffers, CoalitionResult, PrecomputedRows, SolveStatus},
+    srlg::outage_scenarios,
+    types::{
+        ConsolidatedDemand, ConsolidatedLink, ConsolidationReport, Demands, Device, Devices,
+        PrivateLink, PrivateLinks, PublicLinks,
+    },
+    uptime::{UptimeReport, UptimeSweep},
+    usage::InputUsageReport,
+    utilization::UtilizationReport,
+    utils::is_always_present,
+    validation::{
+        CANONICAL_UNIT, MAX_SAMPLED_OPERATORS, apply_capacity_basis, apply_latency_metric,
+        check_congestion, check_inputs, check_parameters, check_unrewarded_links, check_uptime,
+        normalize_labels, normalize_reservations, normalize_units, resolve_duplicate_demands,
+        resolve_duplicate_links,
+    },
 };
 
+pub mod testing;
+
 /// Sentinel bit for operators that are always included in every coalition
 /// (Public, Private, empty). Set in bit 31 so it never collides with
 /// operator index bits 0..19.
 const ALWAYS_BIT: u32 = 1 << 31;
 
+/// Relative slack below which a bandwidth constraint counts as binding
+const BINDING_TOLERANCE: f64 = 1e-7;
+
+/// Most operators computed exactly; beyond this only sampling is possible
+const MAX_OPERATORS: usize = 20;
+
+/// Solves kept in [`SolveStats::slowest`]
+const SLOWEST_SOLVES: usize = 10;
+
 // For clarity
 pub type Operator = String;
 
 // Since shapley value is per operator, we just use a hashmap
 pub type ShapleyOutput = BTreeMap<Operator, ShapleyValue>;
 
+/// Shapley value of each operator split by traffic type (`Demand::kind`).
+/// Each operator's entries sum to its Shapley value.
+pub type DemandDecomposition = BTreeMap<Operator, BTreeMap<u32, f64>>;
+
 /// Input parameters for Shapley computation
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ShapleyInput {
     pub private_links: PrivateLinks,
     pub devices: Devices,
@@ -47,23 +108,843 @@ pub struct ShapleyInput {
 
 impl ShapleyInput {
     pub fn compute(&self) -> Result<ShapleyOutput> {
-        let shapley = Shapley::new(
-            self.private_links.clone(),
-            self.devices.clone(),
-            self.demands.clone(),
-            self.public_links.clone(),
+        self.compute_with(&ShapleyOptions::default())
+    }
+
+    /// Check `operator_uptime`, `contiguity_bonus` and `demand_multiplier`,
+    /// and their overrides in `options`, are in range without computing
+    /// anything. Every computation runs the same check first.
+    pub fn check_parameters(&self, options: &ShapleyOptions) -> Result<()> {
+        check_parameters(self, options)
+    }
+
+    /// Run the checks every computation starts with, without computing
+    /// anything. Unlike the computations, which return the bare error, a
+    /// failed rule comes wrapped in [`ShapleyError::WithContext`] pointing at
+    /// the offending row.
+    pub fn validate(&self, options: &ShapleyOptions) -> Result<()> {
+        check_parameters(self, options)?;
+        check_inputs(
+            &self.private_links,
+            &self.devices,
+            &self.demands,
+            &self.public_links,
+            options.approximation.is_some(),
+            &options.rules,
+        )
+    }
+
+    /// Same as [`compute`](Self::compute), with optional knobs applied.
+    pub fn compute_with(&self, options: &ShapleyOptions) -> Result<ShapleyOutput> {
+        Ok(self.compute_report(options)?.values)
+    }
+
+    /// Shapley values together with diagnostics on how the coalition LPs went
+    pub fn compute_report(&self, options: &ShapleyOptions) -> Result<ShapleyReport> {
+        self.in_pool(options, |shapley| shapley.compute_report())
+    }
+
+    /// Solve every coalition LP under `options` and check whether the induced
+    /// game is superadditive and convex, reporting the offending coalition
+    /// pairs.
+    pub fn game_properties(&self, options: &ShapleyOptions) -> Result<GameProperties> {
+        self.in_pool(options, |shapley| {
+            let Some(problem) = shapley.coalition_problem()? else {
+                return Ok(GameProperties::default());
+            };
+            let (svalue, _) = problem.solve_all(&options.solver, options.failure_policy)?;
+            Ok(game_properties(&problem.operators, &svalue))
+        })
+    }
+
+    /// Pairwise interaction indices between operators, computed from the same
+    /// coalition values as [`compute_with`](Self::compute_with)
+    pub fn synergy_matrix(&self, options: &ShapleyOptions) -> Result<SynergyMatrix> {
+        self.in_pool(options, |shapley| shapley.synergy_matrix())
+    }
+
+    /// The `k` coalitions where each operator's marginal contribution was
+    /// largest, from the same coalition values as
+    /// [`compute_with`](Self::compute_with): why an operator's number is what
+    /// it is
+    pub fn marginal_report(&self, options: &ShapleyOptions, k: usize) -> Result<MarginalReport> {
+        self.in_pool(options, |shapley| shapley.marginal_report(k))
+    }
+
+    /// Mean and variance of each operator's marginal contributions, overall
+    /// and by coalition size, from the same coalition values as
+    /// [`compute_with`](Self::compute_with): whether an operator's value
+    /// holds across coalitions or comes from a few
+    pub fn contribution_report(&self, options: &ShapleyOptions) -> Result<ContributionReport> {
+        self.in_pool(options, |shapley| shapley.contribution_report())
+    }
+
+    /// Which traffic types each operator's value comes from.
+    ///
+    /// Every coalition's optimal objective is split by the traffic type of the
+    /// flow that incurs it, giving one value function per type that sum to the
+    /// coalition value; Shapley values are linear, so the per-type values sum
+    /// to each operator's total. Demands of one type share a commodity in the
+    /// LP, so give demands distinct types to split them individually. Where
+    /// the LP has several optimal routings, the split follows the one found.
+    pub fn demand_decomposition(&self, options: &ShapleyOptions) -> Result<DemandDecomposition> {
+        self.in_pool(options, |shapley| shapley.demand_decomposition())
+    }
+
+    /// Coalition and Shapley values of each priority class (`Demand::class`)
+    /// when classes are routed preemptively, whatever
+    /// `options.priority_classes` says. Valuing each class on its own keeps
+    /// priority customers' value from being diluted by bulk traffic;
+    /// `combined` is what [`compute_with`](Self::compute_with) returns with
+    /// `priority_classes` set.
+    pub fn priority_class_values(&self, options: &ShapleyOptions) -> Result<PriorityClassValues> {
+        self.in_pool(options, |shapley| shapley.priority_class_values())
+    }
+
+    /// Shapley values at an operator uptime of 1 and at `operator_uptime`,
+    /// from the same coalition solves, so operators see the value downtime
+    /// costs them
+    pub fn uptime_report(&self, options: &ShapleyOptions) -> Result<UptimeReport> {
+        self.in_pool(options, |shapley| shapley.uptime_report())
+    }
+
+    /// Shapley values at each of `uptimes` in place of `operator_uptime`.
+    /// Coalition LP values do not depend on uptime, so the LPs are solved
+    /// once and only the expected values and the Shapley stage are repeated.
+    pub fn compute_for_uptimes(
+        &self,
+        options: &ShapleyOptions,
+        uptimes: &[f64],
+    ) -> Result<UptimeSweep> {
+        self.in_pool(options, |shapley| shapley.compute_for_uptimes(uptimes))
+    }
+
+    /// Shapley values once an operator with `devices` and `links` joins,
+    /// against the values without it. Coalitions without the newcomer are
+    /// the network as it is, so one set of solves on the enlarged network
+    /// gives both.
+    pub fn simulate_new_operator(
+        &self,
+        options: &ShapleyOptions,
+        links: Vec<PrivateLink>,
+        devices: Vec<Device>,
+    ) -> Result<OnboardingSimulation> {
+        let (input, operator) = augment(self, links, devices)?;
+        input.in_pool(options, |shapley| shapley.simulate_new_operator(operator))
+    }
+
+    /// Shapley values on the capacity operators declared and on the capacity
+    /// measured on their links (`PrivateLink::measured_bandwidth`), whatever
+    /// `options.capacity` says, with each operator's capacity shortfall and
+    /// the value it loses to it.
+    pub fn commitment_report(&self, options: &ShapleyOptions) -> Result<CommitmentReport> {
+        let declared_options = ShapleyOptions {
+            capacity: CapacityBasis::Declared,
+            ..options.clone()
+        };
+        let measured_options = ShapleyOptions {
+            capacity: CapacityBasis::Measured,
+            ..options.clone()
+        };
+        let shapley = self.to_shapley(&declared_options)?;
+        let declared = self.compute_with(&declared_options)?;
+        let measured = self.compute_with(&measured_options)?;
+        Ok(CommitmentReport::new(
+            &shapley.private_links,
+            &shapley.devices,
+            declared,
+            measured,
+        ))
+    }
+
+    /// Shapley values under `options.cost_model` and with `weight` times each
+    /// link's carbon intensity added to its cost, so operators see what
+    /// greener routing is worth to them
+    pub fn carbon_report(
+        &self,
+        options: &ShapleyOptions,
+        intensity: &CarbonIntensity,
+        weight: f64,
+    ) -> Result<CarbonReport> {
+        let mut carbon = CarbonCost::new(intensity.clone(), weight);
+        if let Some(base) = &options.cost_model {
+            carbon = carbon.with_base(base.clone());
+        }
+        let carbon_options = ShapleyOptions {
+            cost_model: Some(Arc::new(carbon)),
+            ..options.clone()
+        };
+        let base = self.compute_with(options)?;
+        let carbon_adjusted = self.compute_with(&carbon_options)?;
+        Ok(CarbonReport::new(base, carbon_adjusted))
+    }
+
+    /// Owen values for operators negotiating in consortia: value is first split
+    /// between consortia, then within each. Operators not listed in any
+    /// consortium negotiate alone.
+    pub fn owen_values(
+        &self,
+        options: &ShapleyOptions,
+        consortia: &[Vec<Operator>],
+    ) -> Result<ShapleyOutput> {
+        self.in_pool(options, |shapley| shapley.owen_values(consortia))
+    }
+
+    /// Shapley values of separate regional games that share the public
+    /// internet, solved concurrently. Each region gets the private links,
+    /// devices and demands of its cities and every public link; a private
+    /// link or demand between regions is an error, as are cities of
+    /// endpoints outside every region. Operators present in several regions
+    /// are credited the sum of their regional values.
+    pub fn compute_partitioned(
+        &self,
+        options: &ShapleyOptions,
+        partition: &RegionPartition,
+    ) -> Result<PartitionedOutput> {
+        let inputs = split_by_region(self, partition)?;
+        let regions = options.resources.install(|| {
+            inputs
+                .into_par_iter()
+                .map(|(region, input)| {
+                    if input.demands.is_empty() {
+                        return Ok((region, ShapleyOutput::new()));
+                    }
+                    let values = input.to_shapley(options)?.compute_report()?.values;
+                    Ok((region, values))
+                })
+                .collect::<Result<BTreeMap<_, _>>>()
+        })?;
+        Ok(PartitionedOutput::new(regions))
+    }
+
+    /// The consolidated links and demands the LP is built from under
+    /// `options`, including the synthesized reverse, ramp and crossover links
+    pub fn consolidation_report(&self, options: &ShapleyOptions) -> Result<ConsolidationReport> {
+        self.to_shapley(options)?.consolidation_report()
+    }
+
+    /// Devices, public links and cities no demand can use, which are
+    /// usually typos; see [`crate::usage`]
+    pub fn usage_report(&self) -> InputUsageReport {
+        InputUsageReport::new(self)
+    }
+
+    /// Latency, kind of routing and public traffic share of every demand in
+    /// the grand coalition's optimal routing, as customers' service levels
+    /// are reported alongside the value split
+    pub fn sla_report(&self, options: &ShapleyOptions) -> Result<SlaReport> {
+        self.to_shapley(options)?.sla_report()
+    }
+
+    /// Cost of routing the grand coalition's demands without the LP's
+    /// fractional splits, see [`crate::integrality`]
+    pub fn integrality_report(&self, options: &ShapleyOptions) -> Result<IntegralityReport> {
+        self.to_shapley(options)?.integrality_report()
+    }
+
+    /// Flow on every consolidated link in the grand coalition's optimal
+    /// routing, with `values` (as computed with the same `options`) attributed
+    /// to links in proportion to that flow
+    pub fn utilization_report(
+        &self,
+        options: &ShapleyOptions,
+        values: &ShapleyOutput,
+    ) -> Result<UtilizationReport> {
+        self.to_shapley(options)?.utilization_report(values)
+    }
+
+    /// Solve the LP of a single coalition, e.g. to inspect a what-if subset of
+    /// operators without the full 2^n computation. Operator uptime and SRLG
+    /// outages are ignored: every member and link is up.
+    pub fn evaluate_coalition(
+        &self,
+        options: &ShapleyOptions,
+        coalition: &[Operator],
+    ) -> Result<CoalitionSolution> {
+        self.to_shapley(options)?.evaluate_coalition(coalition)
+    }
+
+    /// Run `f` on the prepared computation in the pool chosen by
+    /// `options.resources`
+    fn in_pool<T: Send>(
+        &self,
+        options: &ShapleyOptions,
+        f: impl FnOnce(Shapley) -> Result<T> + Send,
+    ) -> Result<T> {
+        let shapley = self.to_shapley(options)?;
+        options.resources.install(move || f(shapley))
+    }
+
+    /// The raw coalition values behind [`compute_with`](Self::compute_with),
+    /// for experimenting with other aggregations
+    #[cfg(feature = "research")]
+    pub fn coalition_data(&self, options: &ShapleyOptions) -> Result<CoalitionData> {
+        self.in_pool(options, |shapley| shapley.coalition_data())
+    }
+
+    /// The Shapley values of [`compute_with`](Self::compute_with), before
+    /// co-ownership shares, against the same aggregation of the LP values in
+    /// exact arithmetic
+    #[cfg(feature = "audit")]
+    pub fn precision_audit(&self, options: &ShapleyOptions) -> Result<PrecisionAudit> {
+        self.in_pool(options, |shapley| shapley.precision_audit())
+    }
+
+    /// Operators and the coalition values the Shapley stage aggregates:
+    /// uptime-adjusted and weighted over scenarios
+    #[cfg(feature = "borsh")]
+    pub(crate) fn game_values(
+        &self,
+        options: &ShapleyOptions,
+    ) -> Result<(Vec<Operator>, Vec<f64>)> {
+        self.in_pool(options, |shapley| {
+            let Some(operators) = shapley.operators(false)? else {
+                return Ok((Vec::new(), Vec::new()));
+            };
+            let game = shapley.coalition_values(operators, &mut |_, _| {})?;
+            Ok((game.operators, game.values))
+        })
+    }
+
+    /// A copy with every operator, device and city name replaced by a
+    /// pseudonym drawn from `seed`, and the pseudonyms used; see
+    /// [`crate::anonymize`]
+    pub fn anonymize(&self, seed: u64) -> (ShapleyInput, Pseudonyms) {
+        anonymize(self, seed)
+    }
+
+    /// Split the coalition LPs of [`compute_with`](Self::compute_with) into
+    /// `shards` for [`compute_shard`](Self::compute_shard)
+    pub fn work_partition(&self, options: &ShapleyOptions, shards: usize) -> Result<WorkPartition> {
+        let operators = self.to_shapley(options)?.operators(false)?;
+        WorkPartition::new(operators.unwrap_or_default(), shards)
+    }
+
+    /// Solve the coalition LPs of one shard of `partition`; see
+    /// [`crate::distributed`]. Every coalition is solved exactly:
+    /// `options.prune_tolerance` needs the values of other shards and is not
+    /// applied.
+    pub fn compute_shard(
+        &self,
+        options: &ShapleyOptions,
+        partition: &WorkPartition,
+        shard: usize,
+    ) -> Result<PartialResult> {
+        self.in_pool(options, |shapley| shapley.partial_result(partition, shard))
+    }
+
+    /// Size of the work [`compute_with`](Self::compute_with) would do, from
+    /// the inputs alone, so that oversized jobs can be rejected or queued
+    /// before anything is solved. Inputs are validated as for a computation,
+    /// but up to `MAX_SAMPLED_OPERATORS` operators are accepted so the
+    /// estimate covers inputs too large to run exactly.
+    pub fn estimate_resources(&self, options: &ShapleyOptions) -> Result<ResourceEstimate> {
+        self.in_pool(options, |shapley| shapley.estimate_resources())
+    }
+
+    /// Backs [`crate::pipeline::build_lp_with`]
+    pub(crate) fn lp_problem(&self, options: &ShapleyOptions) -> Result<LpProblem> {
+        self.to_shapley(options)?.lp_problem()
+    }
+
+    fn to_shapley(&self, options: &ShapleyOptions) -> Result<Shapley> {
+        if options.deterministic && options.solver.time_limit.is_some() {
+            return Err(ShapleyError::Validation(
+                "A solver time limit makes results depend on timing; use max_iterations in deterministic mode"
+                    .to_string(),
+            ));
+        }
+        check_parameters(self, options)?;
+        let (input, options) = normalize_labels(self, options);
+        let (input, options) = (input.as_ref(), options.as_ref());
+        let private_links = apply_latency_metric(&input.private_links, options.latency_metric)?;
+        let private_links = apply_capacity_basis(&private_links, options.capacity)?;
+        let ranged = apply_traffic_range(&input.demands, options.robust, &options.demand_profiles)?;
+        let floored = apply_traffic_floor(&ranged, &options.traffic_floor_by_type)?;
+        let (private_links, demands) = normalize_units(&private_links, &floored, &options.units)?;
+        let private_links = resolve_duplicate_links(&private_links, options.duplicate_links)?;
+        let mut options = options.clone();
+        normalize_reservations(
+            &mut options.reservations,
+            &input.private_links,
+            &options.units,
+        )?;
+        check_unrewarded_links(&options.unrewarded_links, &input.private_links)?;
+        check_congestion(&options.congestion, &input.private_links)?;
+        if let Cow::Owned(_) = demands {
+            // Profile peaks and traffic floors are in the unit of their type's
+            // demands
+            let unit_of = |kind: u32| {
+                input
+                    .demands
+                    .iter()
+                    .find(|d| d.kind == kind)
+                    .and_then(|d| d.unit.or(options.units.traffic))
+            };
+            for (kind, profile) in &mut options.demand_profiles {
+                if let Some(unit) = unit_of(*kind) {
+                    profile.peak = unit.convert(profile.peak, CANONICAL_UNIT);
+                }
+            }
+            for (kind, floor) in &mut options.traffic_floor_by_type {
+                if let Some(unit) = unit_of(*kind) {
+                    *floor = unit.convert(*floor, CANONICAL_UNIT);
+                }
+            }
+        }
+        let demands = resolve_duplicate_demands(&demands, options.duplicate_demands)?;
+        let (demands, origins) = expand_regions(&demands, &options.regions, &input.public_links)?;
+        for (&kind, &(original, share)) in &origins {
+            if let Some(&bonus) = options.contiguity_bonus_by_type.get(&original) {
+                options.contiguity_bonus_by_type.insert(kind, bonus);
+            }
+            if let Some(&multiplier) = options.demand_multiplier_by_type.get(&original) {
+                options.demand_multiplier_by_type.insert(kind, multiplier);
+            }
+            if let Some(&floor) = options.traffic_floor_by_type.get(&original) {
+                options.traffic_floor_by_type.insert(kind, floor * share);
+            }
+            if let Some(profile) = options.demand_profiles.get(&original).cloned() {
+                options.demand_profiles.insert(
+                    kind,
+                    DemandProfile {
+                        peak: profile.peak * share,
+                        ..profile
+                    },
+                );
+            }
+        }
+        // Types whose every demand was expanded keep no profile of their own
+        for (original, _) in origins.values() {
+            if !demands.iter().any(|d| d.kind == *original) {
+                options.demand_profiles.remove(original);
+            }
+        }
+        let mut shapley = Shapley::new(
+            private_links.into_owned(),
+            input.devices.clone(),
+            demands.into_owned(),
+            input.public_links.clone(),
+            input.operator_uptime,
+            input.contiguity_bonus,
+            input.demand_multiplier,
+        );
+        shapley.options = options;
+        Ok(shapley)
+    }
+}
+
+/// Shapley computation from published per-coalition values, skipping the LP stage.
+///
+/// `values[i]` is the value of the coalition whose members are the set bits of
+/// `i`, where bit k ↔ `operators[k]`; `None` marks an infeasible coalition.
+/// This is the layout the oracle produces when operators are sorted by name.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct ShapleyFromValues {
+    pub operators: Vec<Operator>,
+    pub values: Vec<Option<f64>>,
+    pub operator_uptime: f64,
+}
+
+impl ShapleyFromValues {
+    pub fn new(operators: Vec<Operator>, values: Vec<Option<f64>>, operator_uptime: f64) -> Self {
+        Self {
+            operators,
+            values,
+            operator_uptime,
+        }
+    }
+
+    pub fn compute(&self) -> Result<ShapleyOutput> {
+        self.validate()?;
+        let shapley_values =
+            core_math::aggregate(&self.values, self.operators.len(), self.operator_uptime);
+        Ok(to_output(self.operators.iter().cloned(), shapley_values))
+    }
+
+    /// Pairwise interaction indices between operators
+    pub fn synergy_matrix(&self) -> Result<SynergyMatrix> {
+        self.validate()?;
+        let values = core_math::uptime_adjusted_values(
+            &self.values,
+            self.operators.len(),
+            self.operator_uptime,
+        );
+        Ok(SynergyMatrix::new(self.operators.clone(), &values))
+    }
+
+    /// The `k` coalitions where each operator's marginal contribution was largest
+    pub fn marginal_report(&self, k: usize) -> Result<MarginalReport> {
+        self.validate()?;
+        let values = core_math::uptime_adjusted_values(
+            &self.values,
+            self.operators.len(),
+            self.operator_uptime,
+        );
+        Ok(MarginalReport::new(&self.operators, &values, k))
+    }
+
+    /// The Shapley values of [`compute`](Self::compute) against the same
+    /// aggregation in exact arithmetic
+    #[cfg(feature = "audit")]
+    pub fn precision_audit(&self) -> Result<PrecisionAudit> {
+        self.validate()?;
+        let values = core_math::uptime_adjusted_values(
+            &self.values,
+            self.operators.len(),
+            self.operator_uptime,
+        );
+        PrecisionAudit::new(
+            &self.operators,
+            &[(1.0, self.values.clone())],
+            &values,
+            self.operator_uptime,
+        )
+    }
+
+    /// Mean and variance of each operator's marginal contributions
+    pub fn contribution_report(&self) -> Result<ContributionReport> {
+        self.validate()?;
+        let values = core_math::uptime_adjusted_values(
+            &self.values,
+            self.operators.len(),
             self.operator_uptime,
-            self.contiguity_bonus,
-            self.demand_multiplier,
         );
+        Ok(ContributionReport::new(&self.operators, &values))
+    }
 
-        let output = shapley.compute()?;
-        Ok(output)
+    /// Owen values for operators grouped into consortia, as in
+    /// [`ShapleyInput::owen_values`]
+    pub fn owen_values(&self, consortia: &[Vec<Operator>]) -> Result<ShapleyOutput> {
+        self.validate()?;
+        let n_operators = self.operators.len();
+        let blocks = block_masks(&self.operators, consortia)?;
+        let values =
+            core_math::uptime_adjusted_values(&self.values, n_operators, self.operator_uptime);
+        let owen = core_math::owen_values(&values, n_operators, &blocks);
+        Ok(to_output(self.operators.iter().cloned(), owen))
     }
+
+    fn validate(&self) -> Result<()> {
+        let n_operators = self.operators.len();
+        if n_operators > MAX_OPERATORS {
+            return Err(ShapleyError::TooManyOperators {
+                count: n_operators,
+                limit: MAX_OPERATORS,
+            });
+        }
+        if self.values.len() != 1 << n_operators {
+            return Err(ShapleyError::DataInconsistency(format!(
+                "Expected {} coalition values for {n_operators} operators, got {}",
+                1usize << n_operators,
+                self.values.len()
+            )));
+        }
+        check_uptime(self.operator_uptime)?;
+        let unique: HashSet<&Operator> = self.operators.iter().collect();
+        if unique.len() != n_operators {
+            return Err(ShapleyError::Validation(
+                "Operator names must be unique".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Optimal routing of one coalition, see [`ShapleyInput::evaluate_coalition`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct CoalitionSolution {
+    /// Members of the coalition, sorted
+    pub operators: Vec<Operator>,
+    /// Optimal objective; the coalition value is its negation. `None` when
+    /// the coalition cannot carry the demand.
+    pub objective: Option<f64>,
+    /// Every consolidated link, including those outside the coalition
+    pub links: Vec<ConsolidatedLink>,
+    /// Traffic on each of `links`; all zero when infeasible
+    pub flows: Vec<f64>,
+    /// Bandwidth constraints met with equality at the optimum
+    pub binding: Vec<BindingConstraint>,
+}
+
+/// A bandwidth constraint with no slack left
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BindingConstraint {
+    /// Indices into [`CoalitionSolution::links`] of the links sharing the limit
+    pub links: Vec<usize>,
+    /// Capacity of the constraint
+    pub limit: f64,
+}
+
+/// Values under preemptive routing by priority class, see
+/// [`ShapleyInput::priority_class_values`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PriorityClassValues {
+    /// Operators, sorted; bit `k` of a coalition index is `operators[k]`
+    pub operators: Vec<Operator>,
+    /// Each class's share of every coalition value, uptime-adjusted and
+    /// weighted over SRLG scenarios, indexed by coalition bitmask
+    pub coalition_values: BTreeMap<u32, Vec<f64>>,
+    /// Shapley values of each class's game
+    pub shapley: BTreeMap<u32, ShapleyOutput>,
+    /// Shapley values of the whole game; each value is the sum over classes
+    pub combined: ShapleyOutput,
+}
+
+/// Result of a Shapley computation with solver diagnostics
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShapleyReport {
+    pub values: ShapleyOutput,
+    pub solve_summary: SolveSummary,
+    pub method: ComputationMethod,
+    /// Standard error of each sampled Shapley value; empty for exact values
+    pub standard_errors: BTreeMap<Operator, f64>,
+    /// Preset the computation ran under, from [`ShapleyOptions::preset`]
+    pub preset: Option<String>,
+    /// Versions, build and run settings, for reconciling reports computed
+    /// on different machines
+    pub metadata: ComputationMetadata,
+}
+
+/// How the Shapley values in a report were obtained
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ComputationMethod {
+    /// Every coalition LP was solved
+    #[default]
+    Exact,
+    /// Estimated under an [`ApproximationPolicy`]
+    Sampled {
+        sampler: SamplerKind,
+        /// Orderings drawn, or marginal contributions for the stratified
+        /// sampler, summed over SRLG scenarios
+        samples: usize,
+        /// Distinct coalitions solved, summed over SRLG scenarios
+        coalitions: usize,
+    },
+}
+
+/// Number of coalition LPs by final status, summed over SRLG outage scenarios
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolveSummary {
+    pub solved: usize,
+    pub infeasible: usize,
+    /// Stopped by an iteration or time limit and left without a value
+    pub limit_reached: usize,
+    /// Solver errors (e.g. a singular basis) left without a value
+    pub failed: usize,
+    /// Coalitions re-solved under `FailurePolicy::Retry`; each is also
+    /// counted under its final status.
+    pub retried: usize,
+    /// Total number of retry attempts across those coalitions
+    pub retry_attempts: usize,
+    /// Coalitions not solved because their value was pinned by bounds, see
+    /// `ShapleyOptions::prune_tolerance`
+    pub pruned: usize,
+    /// Distinct scalings applied to badly scaled LPs under
+    /// [`SolverOptions::auto_scale`]
+    pub scalings: BTreeSet<LpScaling>,
+    /// Coalitions whose LP kept the same columns and rows as another
+    /// coalition's and took its result instead of being solved; each is also
+    /// counted under that result's status
+    pub deduplicated: usize,
+    /// Pivots and solve times behind these counts
+    pub stats: SolveStats,
+}
+
+impl SolveSummary {
+    fn merge(&mut self, other: &SolveSummary) {
+        self.solved += other.solved;
+        self.infeasible += other.infeasible;
+        self.limit_reached += other.limit_reached;
+        self.failed += other.failed;
+        self.retried += other.retried;
+        self.retry_attempts += other.retry_attempts;
+        self.pruned += other.pruned;
+        self.deduplicated += other.deduplicated;
+        self.scalings.extend(&other.scalings);
+        self.stats.merge(&other.stats);
+    }
+}
+
+/// Powers of two an LP's costs and right-hand sides are multiplied by before
+/// solving, so that both are of order one. Results are divided by the same
+/// powers, which is exact. Uniform scaling cannot narrow a spread of
+/// magnitudes, so the spreads are reported as well.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LpScaling {
+    pub cost_exponent: i32,
+    /// Also the exponent the flows are scaled by
+    pub rhs_exponent: i32,
+    /// Base-2 logarithm of the ratio of the largest to the smallest nonzero
+    /// cost, rounded up
+    pub cost_spread: i32,
+    /// The same for the nonzero right-hand sides
+    pub rhs_spread: i32,
+}
+
+impl LpScaling {
+    /// Magnitudes within this many powers of two of one are left alone
+    const TOLERATED_EXPONENT: i32 = 10;
+
+    /// The scaling of an LP with these costs and right-hand sides
+    pub(crate) fn of(cost: &[f64], rhs: &[f64]) -> Self {
+        let (cost_exponent, cost_spread) = Self::exponent_and_spread(cost);
+        let (rhs_exponent, rhs_spread) = Self::exponent_and_spread(rhs);
+        Self {
+            cost_exponent,
+            rhs_exponent,
+            cost_spread,
+            rhs_spread,
+        }
+    }
+
+    /// Whether costs or right-hand sides are rescaled
+    pub fn is_applied(&self) -> bool {
+        self.cost_exponent != 0 || self.rhs_exponent != 0
+    }
+
+    pub(crate) fn cost_factor(&self) -> f64 {
+        2f64.powi(self.cost_exponent)
+    }
+
+    pub(crate) fn rhs_factor(&self) -> f64 {
+        2f64.powi(self.rhs_exponent)
+    }
+
+    /// The power of two centring the nonzero magnitudes of `values` on one,
+    /// or 0 if they already are close enough, and their spread
+    fn exponent_and_spread(values: &[f64]) -> (i32, i32) {
+        let (min, max) = values
+            .iter()
+            .map(|v| v.abs())
+            .filter(|v| *v > 0.0 && v.is_finite())
+            .fold((f64::INFINITY, 0.0f64), |(lo, hi), v| {
+                (lo.min(v), hi.max(v))
+            });
+        if max == 0.0 {
+            return (0, 0);
+        }
+        let centre = (min.log2() + max.log2()) / 2.0;
+        let exponent = -centre.round() as i32;
+        let spread = (max.log2() - min.log2()).ceil() as i32;
+        if exponent.abs() <= Self::TOLERATED_EXPONENT {
+            (0, spread)
+        } else {
+            (exponent, spread)
+        }
+    }
+}
+
+/// Solver work of every coalition LP that returned a status, including
+/// infeasible and limited ones. A retried coalition counts its last attempt.
+///
+/// Times are wall-clock measurements and vary from run to run, so equality
+/// only compares iteration counts.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct SolveStats {
+    /// Number of solves by simplex iterations, keyed by the smallest power
+    /// of two at or above the count (0 for none)
+    pub iteration_histogram: BTreeMap<usize, usize>,
+    pub total_iterations: usize,
+    /// Summed over solves, so above the elapsed time when solving in parallel
+    pub total_solve_time: Duration,
+    /// Longest solves, slowest first
+    pub slowest: Vec<SlowSolve>,
+}
+
+/// One coalition LP in [`SolveStats::slowest`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowSolve {
+    pub coalition: Vec<Operator>,
+    pub iterations: usize,
+    pub solve_time: Duration,
+}
+
+impl SolveStats {
+    /// Count one solve; `coalition` names its members if it ranks among the
+    /// slowest
+    fn record(&mut self, result: &CoalitionResult, coalition: impl FnOnce() -> Vec<Operator>) {
+        let bucket = match result.iterations {
+            0 => 0,
+            n => n.next_power_of_two(),
+        };
+        *self.iteration_histogram.entry(bucket).or_default() += 1;
+        self.total_iterations += result.iterations;
+        self.total_solve_time += result.solve_time;
+        if self.slowest.len() < SLOWEST_SOLVES
+            || self
+                .slowest
+                .last()
+                .is_some_and(|slow| result.solve_time > slow.solve_time)
+        {
+            self.slowest.push(SlowSolve {
+                coalition: coalition(),
+                iterations: result.iterations,
+                solve_time: result.solve_time,
+            });
+            self.rank_slowest();
+        }
+    }
+
+    fn merge(&mut self, other: &SolveStats) {
+        for (&bucket, &count) in &other.iteration_histogram {
+            *self.iteration_histogram.entry(bucket).or_default() += count;
+        }
+        self.total_iterations += other.total_iterations;
+        self.total_solve_time += other.total_solve_time;
+        self.slowest.extend(other.slowest.iter().cloned());
+        self.rank_slowest();
+    }
+
+    fn rank_slowest(&mut self) {
+        self.slowest.sort_by(|a, b| b.solve_time.cmp(&a.solve_time));
+        self.slowest.truncate(SLOWEST_SOLVES);
+    }
+}
+
+impl PartialEq for SolveStats {
+    fn eq(&self, other: &Self) -> bool {
+        self.iteration_histogram == other.iteration_histogram
+            && self.total_iterations == other.total_iterations
+    }
+}
+
+impl Eq for SolveStats {}
+
+/// Work of an exact computation, see
+/// [`ShapleyInput::estimate_resources`]. Memory and time come from a cost
+/// model of the solver and are meant as orders of magnitude; sampled
+/// approximations solve fewer coalitions than counted here.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceEstimate {
+    /// Coalition LPs per scenario, 2^operators
+    pub n_coalitions: usize,
+    /// Demand time slots times SRLG outage states
+    pub n_scenarios: usize,
+    /// Columns of the grand coalition's LP; smaller coalitions keep a subset
+    pub lp_vars: usize,
+    /// Equality and inequality rows of the grand coalition's LP
+    pub lp_constraints: usize,
+    /// Peak memory in bytes: coalition values plus one LP per worker thread
+    pub est_memory: u64,
+    /// Wall-clock time on the pool the computation runs in
+    pub est_time: Duration,
 }
 
 /// Individual Shapley value for an operator
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize, Tabled))]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct ShapleyValue {
     pub value: f64,
@@ -85,6 +966,7 @@ struct Shapley {
     pub operator_uptime: f64,
     pub contiguity_bonus: f64,
     pub demand_multiplier: f64,
+    pub options: ShapleyOptions,
 }
 
 impl Shapley {
@@ -105,69 +987,1023 @@ impl Shapley {
             operator_uptime,
             contiguity_bonus,
             demand_multiplier,
+            options: ShapleyOptions::default(),
         }
     }
 
+    #[cfg(test)]
     fn compute(&self) -> Result<ShapleyOutput> {
-        // Validate inputs
-        check_inputs(
-            &self.private_links,
-            &self.devices,
-            &self.demands,
-            &self.public_links,
-            self.operator_uptime,
-        )?;
-
-        // Enumerate all operators (excluding "Private" and "Public")
-        let mut operators: Vec<String> = self
-            .devices
-            .iter()
-            .map(|d| d.operator.clone())
-            .filter(|op| op != "Private" && op != "Public")
-            .collect::<std::collections::HashSet<_>>()
-            .into_iter()
-            .collect();
-        operators.sort();
-
-        let n_operators = operators.len();
-        if n_operators == 0 {
-            return Ok(ShapleyOutput::new());
-        }
+        Ok(self.compute_report()?.values)
+    }
 
-        // Add hard limit to prevent computationally infeasible problems
-        const MAX_OPERATORS: usize = 20;
-        if n_operators > MAX_OPERATORS {
-            return Err(ShapleyError::TooManyOperators {
-                count: n_operators,
-                limit: MAX_OPERATORS,
+    fn compute_report(&self) -> Result<ShapleyReport> {
+        let approximation = self.options.approximation;
+        let Some(operators) = self.operators(approximation.is_some())? else {
+            return Ok(ShapleyReport {
+                preset: self.options.preset.clone(),
+                metadata: ComputationMetadata::new(
+                    &self.options,
+                    &ComputationMethod::Exact,
+                    &SolveSummary::default(),
+                ),
+                ..Default::default()
             });
-        }
+        };
 
-        // Consolidate demands and links
-        let full_demand = consolidate_demand(&self.demands, self.demand_multiplier)?;
-        let full_map = consolidate_links(
-            &self.private_links,
-            &self.devices,
-            &full_demand,
-            &self.public_links,
-            self.contiguity_bonus,
-        )?;
+        if let Some(policy) = approximation
+            && operators.len() > policy.exact_up_to.min(MAX_OPERATORS)
+        {
+            if self.options.co_ownership != CoOwnershipPolicy::Equal {
+                return Err(ShapleyError::Validation(
+                    "Weighted co-ownership needs exact Shapley values, not sampled ones"
+                        .to_string(),
+                ));
+            }
+            return self.sampled_report(operators, &policy);
+        }
 
-        // Build LP primitives
-        let primitives = LpBuilderInput::new(&full_map, &full_demand).build()?;
+        let game = self.coalition_values(operators, &mut |_, _| {})?;
+        let mut shapley_values = core_math::shapley_values(&game.values, game.operators.len());
+        self.redistribute_co_ownership(&game.operators, &game.values, &mut shapley_values)?;
 
-        // Pre-compute row-oriented constraint data (once, before the coalition loop)
-        let precomputed = PrecomputedRows::new(&primitives);
+        let method = ComputationMethod::Exact;
+        Ok(ShapleyReport {
+            values: to_output(game.operators, shapley_values),
+            metadata: ComputationMetadata::new(&self.options, &method, &game.solve_summary),
+            solve_summary: game.solve_summary,
+            method,
+            standard_errors: BTreeMap::new(),
+            preset: self.options.preset.clone(),
+        })
+    }
 
-        // Pre-compute operator bitmasks (once, before the parallel loop)
-        let op_index: HashMap<&str, u8> = operators
+    /// Move the joint value of each pair of co-owners declared in
+    /// `options.co_ownership` to match their shares. The Shapley values
+    /// credit each owner half of it, see [`core_math::joint_credit`].
+    fn redistribute_co_ownership(
+        &self,
+        operators: &[Operator],
+        coalition_values: &[f64],
+        shapley_values: &mut [f64],
+    ) -> Result<()> {
+        let CoOwnershipPolicy::Weighted(shares) = &self.options.co_ownership else {
+            return Ok(());
+        };
+        let operator_of: HashMap<&str, &str> = self
+            .devices
             .iter()
-            .enumerate()
-            .map(|(i, op)| (op.as_str(), i as u8))
+            .map(|d| (d.device.as_str(), d.operator.as_str()))
             .collect();
-
-        let operator_mask = |op: &str| -> u32 {
-            if op == "Public" || op == "Private" || op.is_empty() {
+        let unrewarded: HashSet<(&str, &str)> = self
+            .options
+            .unrewarded_links
+            .iter()
+            .map(|(a, b)| (a.as_str().min(b), a.as_str().max(b)))
+            .collect();
+        let co_owned: HashSet<(&str, &str)> = self
+            .private_links
+            .iter()
+            .filter(|link| {
+                let (a, b) = (link.device1.as_str(), link.device2.as_str());
+                !unrewarded.contains(&(a.min(b), a.max(b)))
+            })
+            .filter_map(|link| {
+                let op1 = *operator_of.get(link.device1.as_str())?;
+                let op2 = *operator_of.get(link.device2.as_str())?;
+                (op1 != op2).then(|| (op1.min(op2), op1.max(op2)))
+            })
+            .collect();
+
+        let mut declared = HashSet::new();
+        for share in shares {
+            let (op1, op2) = (share.operator1.as_str(), share.operator2.as_str());
+            if !(0.0..=1.0).contains(&share.share1) {
+                return Err(ShapleyError::Validation(format!(
+                    "Share of {op1} in its links with {op2} must be between 0 and 1, got {}",
+                    share.share1
+                )));
+            }
+            let pair = (op1.min(op2), op1.max(op2));
+            if !co_owned.contains(&pair) {
+                return Err(ShapleyError::Validation(format!(
+                    "Operators {op1} and {op2} co-own no private link"
+                )));
+            }
+            if !declared.insert(pair) {
+                return Err(ShapleyError::Validation(format!(
+                    "Co-ownership of {op1} and {op2} is declared more than once"
+                )));
+            }
+            let index = |op: &str| {
+                operators.iter().position(|o| o == op).ok_or_else(|| {
+                    ShapleyError::Validation(format!("{op} is not an operator of the game"))
+                })
+            };
+            let (a, b) = (index(op1)?, index(op2)?);
+            let joint = core_math::joint_credit(coalition_values, operators.len(), a, b);
+            let transfer = (2.0 * share.share1 - 1.0) * joint;
+            shapley_values[a] += transfer;
+            shapley_values[b] -= transfer;
+        }
+        Ok(())
+    }
+
+    /// Estimate Shapley values by sampling, solving only the coalitions the
+    /// samples visit. SRLG scenarios are sampled independently and combined
+    /// by probability, standard errors included.
+    fn sampled_report(
+        &self,
+        operators: Vec<Operator>,
+        policy: &ApproximationPolicy,
+    ) -> Result<ShapleyReport> {
+        let n_operators = operators.len();
+        let scenarios = self.scenarios()?;
+
+        let mut shapley_values = vec![0.0; n_operators];
+        let mut variances = vec![0.0; n_operators];
+        let (mut samples, mut coalitions) = (0, 0);
+        let mut solve_summary = SolveSummary::default();
+        for (idx, scenario) in scenarios.iter().enumerate() {
+            // Infeasible coalitions count nothing under uptime < 1 or in an
+            // uncertain scenario, -inf otherwise. Scenarios are sampled on
+            // their own, so unlike the exact path a coalition no uncertain
+            // scenario can route also counts nothing.
+            let infeasible = if self.operator_uptime < 1.0 || scenario.probability < 1.0 {
+                0.0
+            } else {
+                f64::NEG_INFINITY
+            };
+            let problem = self.build_problem(
+                operators.clone(),
+                &scenario.private_links,
+                &scenario.demands,
+            )?;
+            let mut solve = |coalitions: &[u32]| -> Result<Vec<f64>> {
+                let (values, summary) = problem.solve_coalitions(
+                    coalitions,
+                    &self.options.solver,
+                    self.options.failure_policy,
+                )?;
+                solve_summary.merge(&summary);
+                Ok(values
+                    .into_iter()
+                    .map(|v| v.unwrap_or(infeasible))
+                    .collect())
+            };
+            let estimate = sampling::estimate(
+                n_operators,
+                self.operator_uptime,
+                policy.sampler,
+                policy.samples,
+                policy.seed.wrapping_add(idx as u64),
+                &mut solve,
+            )?;
+
+            let p = scenario.probability;
+            for k in 0..n_operators {
+                shapley_values[k] += p * estimate.values[k];
+                variances[k] += p * p * estimate.standard_errors[k].powi(2);
+            }
+            samples += estimate.samples;
+            coalitions += estimate.coalitions;
+        }
+
+        let method = ComputationMethod::Sampled {
+            sampler: policy.sampler,
+            samples,
+            coalitions,
+        };
+        Ok(ShapleyReport {
+            standard_errors: operators
+                .iter()
+                .cloned()
+                .zip(variances.iter().map(|v| v.sqrt()))
+                .collect(),
+            values: to_output(operators, shapley_values),
+            metadata: ComputationMetadata::new(&self.options, &method, &solve_summary),
+            solve_summary,
+            method,
+            preset: self.options.preset.clone(),
+        })
+    }
+
+    fn demand_decomposition(&self) -> Result<DemandDecomposition> {
+        let Some(operators) = self.operators(false)? else {
+            return Ok(DemandDecomposition::new());
+        };
+        let n_operators = operators.len();
+        let scenarios = self.scenarios()?;
+
+        // Per-type coalition values, uptime-adjusted and weighted over scenarios
+        let coalitions: Vec<u32> = Coalitions::new(n_operators)
+            .iter()
+            .map(|(_, c)| c.bits())
+            .collect();
+        let mut values_by_kind: BTreeMap<u32, core_math::ScenarioSum> = BTreeMap::new();
+        for scenario in &scenarios {
+            let mut problem = self.build_problem(
+                operators.clone(),
+                &scenario.private_links,
+                &scenario.demands,
+            )?;
+            let kinds = problem.group_by_kind();
+            let (results, _) = problem.solve_results(
+                &coalitions,
+                &self.options.solver,
+                self.options.failure_policy,
+            )?;
+
+            for (group, kind) in kinds.into_iter().enumerate() {
+                let svalue: Vec<Option<f64>> = results
+                    .iter()
+                    .map(|r| r.as_ref().map(|r| -r.group_objectives[group]))
+                    .collect();
+                let values =
+                    core_math::uptime_adjusted_values(&svalue, n_operators, self.operator_uptime);
+                values_by_kind
+                    .entry(kind)
+                    .or_insert_with(|| core_math::ScenarioSum::new(coalitions.len()))
+                    .add(scenario.probability, &values);
+            }
+        }
+
+        let mut decomposition = DemandDecomposition::new();
+        for (kind, values) in values_by_kind {
+            let shapley_values = core_math::shapley_values(&values.finish(), n_operators);
+            for (op, value) in operators.iter().zip(shapley_values) {
+                decomposition
+                    .entry(op.clone())
+                    .or_default()
+                    .insert(kind, value);
+            }
+        }
+        Ok(decomposition)
+    }
+
+    fn uptime_report(&self) -> Result<UptimeReport> {
+        let Some(operators) = self.operators(false)? else {
+            return Ok(UptimeReport::default());
+        };
+        let n_operators = operators.len();
+        let mut gross = core_math::ScenarioSum::new(1 << n_operators);
+        let game = self.coalition_values(operators, &mut |probability, svalue| {
+            let values = core_math::uptime_adjusted_values(svalue, n_operators, 1.0);
+            gross.add(probability, &values);
+        })?;
+
+        let gross = core_math::shapley_values(&gross.finish(), n_operators);
+        let adjusted = core_math::shapley_values(&game.values, n_operators);
+        Ok(UptimeReport::new(
+            to_output(game.operators.iter().cloned(), gross),
+            to_output(game.operators, adjusted),
+        ))
+    }
+
+    fn compute_for_uptimes(&self, uptimes: &[f64]) -> Result<UptimeSweep> {
+        let mut uptimes = uptimes.to_vec();
+        for &uptime in &uptimes {
+            check_uptime(uptime)?;
+        }
+        uptimes.sort_by(f64::total_cmp);
+        uptimes.dedup();
+        let Some(operators) = self.operators(false)? else {
+            return Ok(UptimeSweep {
+                values: uptimes.into_iter().map(|u| (u, BTreeMap::new())).collect(),
+            });
+        };
+
+        let n_operators = operators.len();
+        let mut expected = vec![core_math::ScenarioSum::new(1 << n_operators); uptimes.len()];
+        let game = self.coalition_values(operators, &mut |probability, svalue| {
+            for (expected, &uptime) in expected.iter_mut().zip(&uptimes) {
+                let values = core_math::uptime_adjusted_values(svalue, n_operators, uptime);
+                expected.add(probability, &values);
+            }
+        })?;
+
+        let values = uptimes
+            .into_iter()
+            .zip(expected)
+            .map(|(uptime, expected)| {
+                let expected = expected.finish();
+                let mut values = core_math::shapley_values(&expected, n_operators);
+                self.redistribute_co_ownership(&game.operators, &expected, &mut values)?;
+                Ok((uptime, to_output(game.operators.iter().cloned(), values)))
+            })
+            .collect::<Result<_>>()?;
+        Ok(UptimeSweep { values })
+    }
+
+    fn simulate_new_operator(&self, newcomer: Operator) -> Result<OnboardingSimulation> {
+        let operators = self.operators(false)?.unwrap_or_default();
+        let Ok(bit) = operators.binary_search(&newcomer) else {
+            return Err(ShapleyError::Validation(format!(
+                "Operator {newcomer} has no device to join with"
+            )));
+        };
+        let n_operators = operators.len();
+        let incumbents: Vec<Operator> = operators
+            .iter()
+            .filter(|op| **op != newcomer)
+            .cloned()
+            .collect();
+        let game = self.coalition_values(operators, &mut |_, _| {})?;
+
+        // Coalitions without the newcomer, in order, are the incumbents' game
+        let without: Vec<f64> = (0..game.values.len())
+            .filter(|c| (c >> bit) & 1 == 0)
+            .map(|c| game.values[c])
+            .collect();
+        let mut before = core_math::shapley_values(&without, n_operators - 1);
+        self.redistribute_co_ownership(&incumbents, &without, &mut before)?;
+        let mut after = core_math::shapley_values(&game.values, n_operators);
+        self.redistribute_co_ownership(&game.operators, &game.values, &mut after)?;
+
+        Ok(OnboardingSimulation::new(
+            newcomer,
+            to_output(incumbents, before),
+            to_output(game.operators, after),
+        ))
+    }
+
+    fn priority_class_values(&self) -> Result<PriorityClassValues> {
+        let Some(operators) = self.operators(false)? else {
+            return Ok(PriorityClassValues::default());
+        };
+        let n_operators = operators.len();
+        let scenarios = self.scenarios()?;
+
+        let mut classes: Vec<u32> = self.demands.iter().map(|d| d.priority_class()).collect();
+        classes.sort_unstable();
+        classes.dedup();
+        let coalitions: Vec<u32> = Coalitions::new(n_operators)
+            .iter()
+            .map(|(_, c)| c.bits())
+            .collect();
+        let mut coalition_values: BTreeMap<u32, core_math::ScenarioSum> = classes
+            .iter()
+            .map(|&class| (class, core_math::ScenarioSum::new(coalitions.len())))
+            .collect();
+        for scenario in &scenarios {
+            let problem = self.build_problem_by_class(
+                operators.clone(),
+                &scenario.private_links,
+                &scenario.demands,
+                true,
+            )?;
+            let (results, _) = problem.solve_results(
+                &coalitions,
+                &self.options.solver,
+                self.options.failure_policy,
+            )?;
+
+            for (stage, acc) in coalition_values.values_mut().enumerate() {
+                // A single class reports no split
+                let svalue: Vec<Option<f64>> = results
+                    .iter()
+                    .map(|r| {
+                        r.as_ref().map(|r| {
+                            -r.class_objectives
+                                .get(stage)
+                                .copied()
+                                .unwrap_or(r.objective_value)
+                        })
+                    })
+                    .collect();
+                let values =
+                    core_math::uptime_adjusted_values(&svalue, n_operators, self.operator_uptime);
+                acc.add(scenario.probability, &values);
+            }
+        }
+        let coalition_values: BTreeMap<u32, Vec<f64>> = coalition_values
+            .into_iter()
+            .map(|(class, values)| (class, values.finish()))
+            .collect();
+
+        let mut total = vec![0.0; coalitions.len()];
+        let mut shapley = BTreeMap::new();
+        for (&class, values) in &coalition_values {
+            for (t, v) in total.iter_mut().zip(values) {
+                *t += v;
+            }
+            let class_values = core_math::shapley_values(values, n_operators);
+            shapley.insert(class, to_output(operators.iter().cloned(), class_values));
+        }
+        let combined = to_output(
+            operators.iter().cloned(),
+            core_math::shapley_values(&total, n_operators),
+        );
+        Ok(PriorityClassValues {
+            operators,
+            coalition_values,
+            shapley,
+            combined,
+        })
+    }
+
+    fn owen_values(&self, consortia: &[Vec<Operator>]) -> Result<ShapleyOutput> {
+        let Some(operators) = self.operators(false)? else {
+            return Ok(ShapleyOutput::new());
+        };
+        let blocks = block_masks(&operators, consortia)?;
+        let game = self.coalition_values(operators, &mut |_, _| {})?;
+        let owen = core_math::owen_values(&game.values, game.operators.len(), &blocks);
+        Ok(to_output(game.operators, owen))
+    }
+
+    #[cfg(feature = "audit")]
+    fn precision_audit(&self) -> Result<PrecisionAudit> {
+        let Some(operators) = self.operators(false)? else {
+            return Ok(PrecisionAudit::default());
+        };
+        let mut scenarios = Vec::new();
+        let game = self.coalition_values(operators, &mut |probability, svalue| {
+            scenarios.push((probability, svalue.to_vec()));
+        })?;
+        PrecisionAudit::new(
+            &game.operators,
+            &scenarios,
+            &game.values,
+            self.operator_uptime,
+        )
+    }
+
+    #[cfg(feature = "research")]
+    fn coalition_data(&self) -> Result<CoalitionData> {
+        let Some(operators) = self.operators(false)? else {
+            return Ok(CoalitionData::default());
+        };
+        let mut scenarios = Vec::new();
+        let game = self.coalition_values(operators, &mut |probability, svalue| {
+            scenarios.push(ScenarioValues {
+                probability,
+                svalue: svalue.to_vec(),
+            });
+        })?;
+        Ok(CoalitionData {
+            bitmap: Coalitions::new(game.operators.len())
+                .iter()
+                .map(|(_, c)| c.bits())
+                .collect(),
+            operators: game.operators,
+            scenarios,
+            evalue: game.values,
+        })
+    }
+
+    fn synergy_matrix(&self) -> Result<SynergyMatrix> {
+        let Some(operators) = self.operators(false)? else {
+            return Ok(SynergyMatrix::default());
+        };
+        let game = self.coalition_values(operators, &mut |_, _| {})?;
+        Ok(SynergyMatrix::new(game.operators, &game.values))
+    }
+
+    fn marginal_report(&self, k: usize) -> Result<MarginalReport> {
+        let Some(operators) = self.operators(false)? else {
+            return Ok(MarginalReport::default());
+        };
+        let game = self.coalition_values(operators, &mut |_, _| {})?;
+        Ok(MarginalReport::new(&game.operators, &game.values, k))
+    }
+
+    fn contribution_report(&self) -> Result<ContributionReport> {
+        let Some(operators) = self.operators(false)? else {
+            return Ok(ContributionReport::default());
+        };
+        let game = self.coalition_values(operators, &mut |_, _| {})?;
+        Ok(ContributionReport::new(&game.operators, &game.values))
+    }
+
+    fn partial_result(&self, partition: &WorkPartition, shard: usize) -> Result<PartialResult> {
+        let range = partition.range(shard)?;
+        let operators = self.operators(false)?.unwrap_or_default();
+        if operators != partition.operators {
+            return Err(ShapleyError::DataInconsistency(format!(
+                "Work partition is for operators {:?}, the input has {operators:?}",
+                partition.operators
+            )));
+        }
+
+        let coalitions: Vec<u32> = (range.start as u32..range.end as u32).collect();
+        let mut scenarios = Vec::new();
+        let mut solve_summary = SolveSummary::default();
+        for scenario in self.scenarios()? {
+            let problem = self.build_problem(
+                operators.clone(),
+                &scenario.private_links,
+                &scenario.demands,
+            )?;
+            let (svalue, summary) = problem.solve_coalitions(
+                &coalitions,
+                &self.options.solver,
+                self.options.failure_policy,
+            )?;
+            solve_summary.merge(&summary);
+            scenarios.push(PartialScenario {
+                probability: scenario.probability,
+                svalue,
+            });
+        }
+
+        Ok(PartialResult {
+            partition: partition.clone(),
+            shard,
+            operator_uptime: self.operator_uptime,
+            scenarios,
+            solve_summary,
+        })
+    }
+
+    /// The coalition values fed to the Shapley stage: uptime-adjusted and
+    /// weighted over demand time slots and SRLG outage states. `on_scenario`
+    /// sees each scenario's
+    /// probability and raw coalition LP values.
+    fn coalition_values(
+        &self,
+        operators: Vec<Operator>,
+        on_scenario: &mut dyn FnMut(f64, &[Option<f64>]),
+    ) -> Result<CoalitionValues> {
+        let n_operators = operators.len();
+
+        // Time slots and correlated link outages: one full set of coalition
+        // solves per scenario
+        let scenarios = self.scenarios()?;
+
+        if let Some(tolerance) = self.options.prune_tolerance
+            && (tolerance.is_nan() || tolerance < 0.0)
+        {
+            return Err(ShapleyError::Validation(format!(
+                "Prune tolerance must be non-negative, got {tolerance}"
+            )));
+        }
+        if self.options.prune_tolerance.is_some() && self.options.priority_classes {
+            // A class routed first can take capacity a later class needed, so
+            // a larger coalition may be worth less and bounds prune nothing
+            return Err(ShapleyError::Validation(
+                "Prune tolerance needs monotone coalition values, which priority classes do not give"
+                    .to_string(),
+            ));
+        }
+
+        let mut expected_values = core_math::ScenarioSum::new(1 << n_operators);
+        let mut solve_summary = SolveSummary::default();
+        for scenario in &scenarios {
+            // Solve LP for each coalition
+            let problem = self.build_problem(
+                operators.clone(),
+                &scenario.private_links,
+                &scenario.demands,
+            )?;
+            let (coalition_values, summary) = match self.options.prune_tolerance {
+                Some(tolerance) => problem.solve_all_pruned(
+                    &self.options.solver,
+                    self.options.failure_policy,
+                    tolerance,
+                )?,
+                None => problem.solve_all(&self.options.solver, self.options.failure_policy)?,
+            };
+            solve_summary.merge(&summary);
+            on_scenario(scenario.probability, &coalition_values);
+
+            // Expected values under operator uptime, weighted by scenario probability
+            let values = core_math::uptime_adjusted_values(
+                &coalition_values,
+                n_operators,
+                self.operator_uptime,
+            );
+            expected_values.add(scenario.probability, &values);
+        }
+
+        Ok(CoalitionValues {
+            operators,
+            values: expected_values.finish(),
+            solve_summary,
+        })
+    }
+
+    /// Every pair of demand time slot and SRLG outage state, each needing a
+    /// full set of coalition solves
+    fn scenarios(&self) -> Result<Vec<Scenario<'_>>> {
+        let slots = demand_slots(
+            &self.demands,
+            &self.options.demand_profiles,
+            &self.options.traffic_floor_by_type,
+        )?;
+        let outages = outage_scenarios(
+            &self.private_links,
+            &self.options.srlg_availability,
+            self.options.srlg_max_scenarios,
+            self.options.srlg_seed,
+        )?;
+
+        let mut scenarios = Vec::with_capacity(slots.len() * outages.len());
+        for slot in &slots {
+            for outage in &outages {
+                scenarios.push(Scenario {
+                    probability: slot.weight * outage.probability,
+                    private_links: outage.surviving(&self.private_links),
+                    demands: slot.demands.clone(),
+                });
+            }
+        }
+        Ok(scenarios)
+    }
+
+    /// Validate the inputs and build the LP structure shared by every coalition.
+    /// Returns `None` when there are no operators to evaluate.
+    fn coalition_problem(&self) -> Result<Option<CoalitionProblem>> {
+        let Some(operators) = self.operators(false)? else {
+            return Ok(None);
+        };
+        self.build_problem(operators, &self.private_links, &self.demands)
+            .map(Some)
+    }
+
+    /// Validate the inputs and list the operators, sorted. Returns `None` when
+    /// there are no operators to evaluate. `sampling` lifts the operator limit
+    /// of exact computation.
+    fn operators(&self, sampling: bool) -> Result<Option<Vec<Operator>>> {
+        // Validate inputs
+        check_inputs(
+            &self.private_links,
+            &self.devices,
+            &self.demands,
+            &self.public_links,
+            sampling,
+            &self.options.rules,
+        )
+        .map_err(ShapleyError::into_root)?;
+
+        // Enumerate all operators (excluding "Private" and "Public")
+        let mut operators: Vec<String> = self
+            .devices
+            .iter()
+            .map(|d| d.operator.clone())
+            .filter(|op| op != "Private" && op != "Public")
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .collect();
+        operators.sort();
+
+        let n_operators = operators.len();
+        if n_operators == 0 {
+            return Ok(None);
+        }
+
+        // Add hard limit to prevent computationally infeasible problems
+        let limit = if sampling {
+            MAX_SAMPLED_OPERATORS
+        } else {
+            MAX_OPERATORS
+        };
+        if n_operators > limit {
+            return Err(ShapleyError::TooManyOperators {
+                count: n_operators,
+                limit,
+            });
+        }
+
+        Ok(Some(operators))
+    }
+
+    fn consolidation_report(&self) -> Result<ConsolidationReport> {
+        self.consolidate(self.options.approximation.is_some())
+    }
+
+    /// Validate and consolidate the inputs; `sampling` as for [`check_inputs`]
+    fn consolidate(&self, sampling: bool) -> Result<ConsolidationReport> {
+        check_inputs(
+            &self.private_links,
+            &self.devices,
+            &self.demands,
+            &self.public_links,
+            sampling,
+            &self.options.rules,
+        )
+        .map_err(ShapleyError::into_root)?;
+        self.consolidate_tables(&self.private_links, &self.demands)
+    }
+
+    /// Consolidated links and demands of the given private links and
+    /// demands, with the link options applied. Inputs are not validated.
+    fn consolidate_tables(
+        &self,
+        private_links: &PrivateLinks,
+        demands: &Demands,
+    ) -> Result<ConsolidationReport> {
+        let demands = consolidate_demand(
+            demands,
+            self.demand_multiplier,
+            &self.options.demand_multiplier_by_type,
+        )?;
+        let mut links = consolidate_links(
+            private_links,
+            &self.devices,
+            &demands,
+            &self.public_links,
+            self.contiguity_bonus,
+        )?;
+        override_contiguity_bonus(
+            &mut links,
+            &self.options.contiguity_bonus_by_city,
+            &self.options.contiguity_bonus_by_operator,
+        )?;
+        mark_unrewarded(&mut links, &self.options.unrewarded_links);
+        reserve_capacity(&mut links, &demands, &self.options.reservations);
+        Ok(ConsolidationReport { links, demands })
+    }
+
+    fn sla_report(&self) -> Result<SlaReport> {
+        // Only the grand coalition is solved, so large networks are fine
+        let Some(operators) = self.operators(self.options.approximation.is_some())? else {
+            return Ok(SlaReport::default());
+        };
+        let problem =
+            self.build_problem_by_class(operators, &self.private_links, &self.demands, false)?;
+        let ConsolidationReport { links, demands } = self.consolidation_report()?;
+        let primal = problem.grand_coalition_primal(&self.options.solver)?;
+        let flows = problem.commodity_flows(&primal, links.len());
+        Ok(SlaReport::new(&links, &demands, flows))
+    }
+
+    fn integrality_report(&self) -> Result<IntegralityReport> {
+        // Only the grand coalition is solved, so large networks are fine
+        let Some(operators) = self.operators(self.options.approximation.is_some())? else {
+            return Ok(IntegralityReport::default());
+        };
+        let problem =
+            self.build_problem_by_class(operators, &self.private_links, &self.demands, false)?;
+        let ConsolidationReport { links, demands } = self.consolidation_report()?;
+        let grand = (1usize << problem.operators.len()) - 1;
+        let Some((fractional_objective, primal)) =
+            problem.solve_primal(grand, &self.options.solver)?
+        else {
+            return Err(ShapleyError::LpSolver(
+                "Grand coalition could not be solved: Infeasible".to_string(),
+            ));
+        };
+        let flows = problem.commodity_flows(&primal, links.len());
+        let (splits, kept) = round_paths(&links, &demands, &problem.primitives, flows);
+        // Columns carrying no commodity, e.g. multicast auxiliaries, stay
+        let rounded_objective = problem.solve_restricted(
+            grand,
+            |col| problem.primitives.col_route[col].is_none() || kept.contains(&col),
+            &self.options.solver,
+        )?;
+        Ok(IntegralityReport {
+            demands: splits,
+            fractional_objective,
+            rounded_objective,
+        })
+    }
+
+    fn utilization_report(&self, values: &ShapleyOutput) -> Result<UtilizationReport> {
+        // Only the grand coalition is solved, so large networks are fine
+        let Some(operators) = self.operators(self.options.approximation.is_some())? else {
+            return Ok(UtilizationReport::default());
+        };
+        let problem =
+            self.build_problem_by_class(operators, &self.private_links, &self.demands, false)?;
+        let links = self.consolidation_report()?.links;
+        let flows = problem.grand_coalition_flows(links.len(), &self.options.solver)?;
+        Ok(UtilizationReport::new(links, &flows, values))
+    }
+
+    fn evaluate_coalition(&self, coalition: &[Operator]) -> Result<CoalitionSolution> {
+        // Only one coalition is solved, so large networks are fine
+        let operators = self.operators(true)?.unwrap_or_default();
+        let mut mask = 0usize;
+        for op in coalition {
+            let Some(k) = operators.iter().position(|o| o == op) else {
+                return Err(ShapleyError::Validation(format!(
+                    "Coalition member {op} is not an operator"
+                )));
+            };
+            mask |= 1 << k;
+        }
+        let members = operators
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| mask & (1 << k) != 0)
+            .map(|(_, op)| op.clone())
+            .collect();
+
+        let links = self.consolidate(true)?.links;
+        let problem =
+            self.build_problem_by_class(operators, &self.private_links, &self.demands, false)?;
+        let Some((objective, primal)) = problem.solve_primal(mask, &self.options.solver)? else {
+            return Ok(CoalitionSolution {
+                operators: members,
+                objective: None,
+                flows: vec![0.0; links.len()],
+                links,
+                binding: Vec::new(),
+            });
+        };
+
+        Ok(CoalitionSolution {
+            operators: members,
+            objective: Some(objective),
+            flows: problem.link_flows(&primal, links.len()),
+            links,
+            binding: problem.binding_constraints(mask, &primal),
+        })
+    }
+
+    /// Build the LP structure shared by every coalition over the given private
+    /// links and demands, one stage per priority class if
+    /// `options.priority_classes` is set
+    fn build_problem(
+        &self,
+        operators: Vec<Operator>,
+        private_links: &PrivateLinks,
+        demands: &Demands,
+    ) -> Result<CoalitionProblem> {
+        self.build_problem_by_class(
+            operators,
+            private_links,
+            demands,
+            self.options.priority_classes,
+        )
+    }
+
+    /// [`build_problem`](Self::build_problem) with priority classes routed in
+    /// turn if `by_class`, or all together otherwise
+    fn build_problem_by_class(
+        &self,
+        operators: Vec<Operator>,
+        private_links: &PrivateLinks,
+        demands: &Demands,
+        by_class: bool,
+    ) -> Result<CoalitionProblem> {
+        let ConsolidationReport { links, demands } =
+            self.consolidate_tables(private_links, demands)?;
+
+        // Every class is built over the same links, so bandwidth rows line up
+        let stages = if by_class {
+            self.split_by_class(demands)
+        } else {
+            vec![demands]
+        };
+        let mut problems = stages
+            .into_iter()
+            .map(|demands| {
+                let primitives = self.build_primitives(&links, &demands)?;
+                Ok(CoalitionProblem::new(operators.clone(), primitives))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut problem = problems.remove(0);
+        problem.residual = problems;
+        problem.cancellation = self.options.cancellation.clone();
+        problem.progress = self.options.progress.clone();
+        #[cfg(feature = "unstable-backend")]
+        {
+            problem.backend = self.options.backend.clone();
+        }
+        Ok(problem)
+    }
+
+    /// Consolidated demands grouped by the priority class of their original
+    /// type, highest priority (lowest class) first. Never empty: without
+    /// demands there is one empty class.
+    fn split_by_class(&self, demands: Vec<ConsolidatedDemand>) -> Vec<Vec<ConsolidatedDemand>> {
+        let class_of: HashMap<u32, u32> = self
+            .demands
+            .iter()
+            .map(|d| (d.kind, d.priority_class()))
+            .collect();
+        let mut by_class: BTreeMap<u32, Vec<ConsolidatedDemand>> = BTreeMap::new();
+        for demand in demands {
+            let class = class_of.get(&demand.original).copied().unwrap_or(1);
+            by_class.entry(class).or_default().push(demand);
+        }
+        if by_class.is_empty() {
+            return vec![Vec::new()];
+        }
+        by_class.into_values().collect()
+    }
+
+    fn lp_problem(&self) -> Result<LpProblem> {
+        let ConsolidationReport { links, demands } = self.consolidate(true)?;
+        let primitives = self.build_primitives(&links, &demands)?;
+        Ok(LpProblem::new(primitives, links, demands))
+    }
+
+    fn estimate_resources(&self) -> Result<ResourceEstimate> {
+        // Solver cost model: sparse factors take a few times the matrix, and
+        // a solve pivots about once per row over every nonzero
+        const BYTES_PER_NONZERO: u64 = 64;
+        const BYTES_PER_DIMENSION: u64 = 128;
+        const BYTES_PER_COALITION: u64 = 40;
+        const NANOS_PER_PIVOT_NONZERO: u64 = 2;
+
+        let Some(operators) = self.operators(true)? else {
+            return Ok(ResourceEstimate::default());
+        };
+        let problem = self.lp_problem()?;
+        let n_coalitions = 1usize << operators.len();
+        let n_scenarios = self.scenarios()?.len();
+        let lp_vars = problem.n_cols();
+        let lp_constraints = problem.b_eq.len() + problem.b_ub.len();
+        let nnz = (problem.a_eq.nnz() + problem.a_ub.nnz()) as u64;
+
+        let threads = rayon::current_num_threads() as u64;
+        let lp_memory =
+            nnz * BYTES_PER_NONZERO + (lp_vars + lp_constraints) as u64 * BYTES_PER_DIMENSION;
+        let solves = (n_coalitions * n_scenarios) as u64;
+        let solve_nanos = lp_constraints as u64 * nnz * NANOS_PER_PIVOT_NONZERO;
+        Ok(ResourceEstimate {
+            n_coalitions,
+            n_scenarios,
+            lp_vars,
+            lp_constraints,
+            est_memory: n_coalitions as u64 * BYTES_PER_COALITION + threads * lp_memory,
+            est_time: Duration::from_nanos(solves.div_ceil(threads).saturating_mul(solve_nanos)),
+        })
+    }
+
+    fn build_primitives(
+        &self,
+        links: &[ConsolidatedLink],
+        demands: &[ConsolidatedDemand],
+    ) -> Result<LpPrimitives> {
+        let build = || {
+            let mut builder = LpBuilderInput::new(links, demands)
+                .with_contiguity_bonus_by_type(&self.options.contiguity_bonus_by_type)
+                .with_congestion(&self.options.congestion);
+            if let Some(cost_model) = &self.options.cost_model {
+                builder = builder.with_cost_model(cost_model.as_ref());
+            }
+            builder.build()
+        };
+        match &self.options.lp_cache {
+            Some(cache) => cache.primitives(
+                links,
+                demands,
+                &self.options.contiguity_bonus_by_type,
+                self.options.cost_model.as_ref(),
+                &self.options.congestion,
+                build,
+            ),
+            None => build(),
+        }
+    }
+}
+
+/// Network state the coalition values are averaged over
+struct Scenario<'a> {
+    probability: f64,
+    private_links: Cow<'a, PrivateLinks>,
+    demands: Cow<'a, Demands>,
+}
+
+/// Coalition values of the game over `operators`, indexed by coalition bitmask
+struct CoalitionValues {
+    operators: Vec<Operator>,
+    values: Vec<f64>,
+    solve_summary: SolveSummary,
+}
+
+/// LP primitives plus the operator bitmasks needed to carve out the
+/// sub-problem of any coalition. Built once, shared by every coalition solve.
+struct CoalitionProblem {
+    operators: Vec<Operator>,
+    primitives: LpPrimitives,
+    precomputed: PrecomputedRows,
+    col_op1_mask: Vec<u32>,
+    col_op2_mask: Vec<u32>,
+    row_op1_mask: Vec<u32>,
+    row_op2_mask: Vec<u32>,
+    /// Distinct `(op1, op2)` mask pairs of the columns and rows; a coalition
+    /// keeps a column or row iff it keeps its pair, see [`Self::signature`]
+    mask_pairs: Vec<(u32, u32)>,
+    /// Column groups reported by every solve, see [`CoalitionLp::col_group`]
+    col_group: Option<(Vec<usize>, usize)>,
+    /// Checked before each batch of solves
+    cancellation: Option<CancellationToken>,
+    /// Advanced after each batch of solves
+    progress: Option<ProgressCounter>,
+    /// Solves the batches; `None` uses [`SimplexBackend`]
+    backend: Option<Arc<dyn BatchLpBackend>>,
+    /// Problems of lower priority classes, see [`CoalitionLp::residual`]
+    residual: Vec<CoalitionProblem>,
+}
+
+impl CoalitionProblem {
+    fn new(operators: Vec<Operator>, primitives: LpPrimitives) -> Self {
+        // Pre-compute row-oriented constraint data (once, before the coalition loop)
+        let precomputed = PrecomputedRows::new(&primitives);
+
+        // Pre-compute operator bitmasks (once, before the parallel loop)
+        let op_index: HashMap<&str, u8> = operators
+            .iter()
+            .enumerate()
+            .map(|(i, op)| (op.as_str(), i as u8))
+            .collect();
+
+        let operator_mask = |op: &str| -> u32 {
+            if is_always_present(op) {
                 ALWAYS_BIT
             } else if let Some(&idx) = op_index.get(op) {
                 1u32 << idx
@@ -196,243 +2032,2169 @@ impl Shapley {
             .iter()
             .map(|s| operator_mask(s))
             .collect();
+        let mut mask_pairs: Vec<(u32, u32)> = col_op1_mask
+            .iter()
+            .zip(&col_op2_mask)
+            .chain(row_op1_mask.iter().zip(&row_op2_mask))
+            .map(|(&a, &b)| (a, b))
+            .collect();
+        mask_pairs.sort_unstable();
+        mask_pairs.dedup();
+
+        Self {
+            operators,
+            primitives,
+            precomputed,
+            col_op1_mask,
+            col_op2_mask,
+            row_op1_mask,
+            row_op2_mask,
+            mask_pairs,
+            col_group: None,
+            cancellation: None,
+            progress: None,
+            backend: None,
+            residual: Vec::new(),
+        }
+    }
+
+    /// Have every solve split its objective by original traffic type;
+    /// returns the types in group order
+    fn group_by_kind(&mut self) -> Vec<u32> {
+        let mut kinds: Vec<u32> = std::iter::once(&*self)
+            .chain(&self.residual)
+            .flat_map(|problem| problem.primitives.col_kind.iter().copied())
+            .collect();
+        kinds.sort_unstable();
+        kinds.dedup();
+        self.set_kind_groups(&kinds);
+        for problem in &mut self.residual {
+            problem.set_kind_groups(&kinds);
+        }
+        kinds
+    }
+
+    fn set_kind_groups(&mut self, kinds: &[u32]) {
+        let groups = self
+            .primitives
+            .col_kind
+            .iter()
+            .map(|k| kinds.binary_search(k).unwrap_or(0))
+            .collect();
+        self.col_group = Some((groups, kinds.len()));
+    }
+
+    /// Solve the LP of every coalition. Index `i` holds the value of the
+    /// coalition whose members are the set bits of `i`; `None` marks an
+    /// infeasible coalition, or a failed one when the policy allows it.
+    fn solve_all(
+        &self,
+        solver_options: &SolverOptions,
+        policy: FailurePolicy,
+    ) -> Result<(Vec<Option<f64>>, SolveSummary)> {
+        let coalitions: Vec<u32> = Coalitions::new(self.operators.len())
+            .iter()
+            .map(|(_, c)| c.bits())
+            .collect();
+        self.solve_coalitions(&coalitions, solver_options, policy)
+    }
+
+    /// [`solve_all`](Self::solve_all), skipping coalitions whose value is
+    /// pinned to within `tolerance` by [`monotone_bounds`]. Coalitions are
+    /// solved by size from both ends inwards (empty and grand coalition, then
+    /// singletons and all-but-one, ...), so that every layer is bounded from
+    /// below and above by solved ones. A coalition whose bounds are out of
+    /// order is solved: the values are not monotone there.
+    fn solve_all_pruned(
+        &self,
+        solver_options: &SolverOptions,
+        policy: FailurePolicy,
+        tolerance: f64,
+    ) -> Result<(Vec<Option<f64>>, SolveSummary)> {
+        let n = self.operators.len();
+        let mut layers = Vec::with_capacity(n + 1);
+        let (mut low, mut high) = (0, n);
+        while low < high {
+            layers.extend([low, high]);
+            low += 1;
+            high -= 1;
+        }
+        if low == high {
+            layers.push(low);
+        }
+
+        // Infeasible coalitions are known as -inf
+        let mut known: Vec<Option<f64>> = vec![None; 1 << n];
+        let mut summary = SolveSummary::default();
+        for size in layers {
+            let (lower, upper) = monotone_bounds(&known, n);
+            let mut to_solve = Vec::new();
+            for (idx, coalition) in Coalitions::new(n).of_size(size) {
+                // Bounds out of order mean the values are not monotone after
+                // all, so they bound nothing
+                if lower[idx] > upper[idx] {
+                    to_solve.push(coalition.bits());
+                } else if upper[idx] == f64::NEG_INFINITY {
+                    known[idx] = Some(f64::NEG_INFINITY);
+                    summary.pruned += 1;
+                } else if upper[idx] - lower[idx] <= tolerance {
+                    known[idx] = Some((lower[idx] + upper[idx]) / 2.0);
+                    summary.pruned += 1;
+                } else {
+                    to_solve.push(coalition.bits());
+                }
+            }
+
+            let (values, layer_summary) =
+                self.solve_coalitions(&to_solve, solver_options, policy)?;
+            summary.merge(&layer_summary);
+            for (coalition, value) in to_solve.into_iter().zip(values) {
+                known[coalition as usize] = Some(value.unwrap_or(f64::NEG_INFINITY));
+            }
+        }
+
+        let values = known
+            .into_iter()
+            .map(|v| v.filter(|v| v.is_finite()))
+            .collect();
+        Ok((values, summary))
+    }
+
+    /// Solve the LPs of the given coalitions, one value per coalition as in
+    /// [`solve_all`](Self::solve_all)
+    fn solve_coalitions(
+        &self,
+        coalitions: &[u32],
+        solver_options: &SolverOptions,
+        policy: FailurePolicy,
+    ) -> Result<(Vec<Option<f64>>, SolveSummary)> {
+        let (results, summary) = self.solve_results(coalitions, solver_options, policy)?;
+        let values = results
+            .into_iter()
+            .map(|r| r.map(|r| -r.objective_value)) // Negative because we minimize
+            .collect();
+        Ok((values, summary))
+    }
+
+    /// Like [`solve_coalitions`](Self::solve_coalitions), keeping the full
+    /// result of every solved coalition. Coalitions with the same
+    /// [`signature`](Self::signature) induce the same LP, which is solved once.
+    fn solve_results(
+        &self,
+        coalitions: &[u32],
+        solver_options: &SolverOptions,
+        policy: FailurePolicy,
+    ) -> Result<(Vec<Option<CoalitionResult>>, SolveSummary)> {
+        let residual = self.residual_lps();
+        let lp = CoalitionLp {
+            residual: &residual,
+            ..self.lp()
+        };
+
+        // Only the first coalition of each signature is solved
+        let mut first_of: HashMap<Vec<u64>, usize> = HashMap::new();
+        let mut unique: Vec<u32> = Vec::new();
+        let slots: Vec<usize> = coalitions
+            .iter()
+            .map(|&coalition| {
+                *first_of
+                    .entry(self.signature(coalition | ALWAYS_BIT))
+                    .or_insert_with(|| {
+                        unique.push(coalition);
+                        unique.len() - 1
+                    })
+            })
+            .collect();
+        if let Some(progress) = &self.progress {
+            progress.add(coalitions.len() - unique.len());
+        }
+
+        // Coalitions go to the backend in batches; batches are solved in parallel
+        let masks: Vec<u32> = unique.iter().map(|&c| c | ALWAYS_BIT).collect();
+        let cancelled = || self.cancellation.as_ref().is_some_and(|c| c.is_cancelled());
+        let backend = self.backend.as_deref().unwrap_or(&SimplexBackend);
+        // Each worker reuses its buffers across its batches; they live only
+        // as long as this call
+        let outcomes: Vec<Result<CoalitionResult>> = masks
+            .par_chunks(batch_len(masks.len(), rayon::current_num_threads()))
+            .map_init(
+                || CoalitionBuffers::new(lp.n_cols()),
+                |buffers, batch| {
+                    if cancelled() {
+                        return batch.iter().map(|_| Err(ShapleyError::Cancelled)).collect();
+                    }
+                    let results = backend.solve_batch(lp, batch, buffers, solver_options);
+                    if let Some(progress) = &self.progress {
+                        progress.add(batch.len());
+                    }
+                    results
+                },
+            )
+            .flat_map_iter(Vec::into_iter)
+            .collect();
+        if cancelled() {
+            return Err(ShapleyError::Cancelled);
+        }
+
+        let mut summary = SolveSummary {
+            deduplicated: coalitions.len() - unique.len(),
+            ..Default::default()
+        };
+        if solver_options.auto_scale {
+            summary.scalings = std::iter::once(self)
+                .chain(&self.residual)
+                .map(|problem| problem.precomputed.scaling)
+                .filter(LpScaling::is_applied)
+                .collect();
+        }
+        let mut retry_buffers = None;
+        let mut finals = Vec::with_capacity(outcomes.len());
+        for (&coalition, mut outcome) in unique.iter().zip(outcomes) {
+            let coalition_idx = coalition as usize;
+            if !is_conclusive(&outcome) {
+                match policy {
+                    FailurePolicy::Error => {
+                        let (reason, status) = match outcome {
+                            Err(e) => (e.to_string(), SolverStatus::Failed),
+                            Ok(_) => (
+                                "solver limit reached".to_string(),
+                                SolverStatus::LimitReached,
+                            ),
+                        };
+                        return Err(ShapleyError::LpSolver(format!(
+                            "Coalition {:?} could not be solved: {reason}",
+                            members(coalition_idx, &self.operators)
+                        ))
+                        .with_context(ErrorContext::coalition(coalition_idx, status)));
+                    }
+                    FailurePolicy::Retry => {
+                        let buf =
+                            retry_buffers.get_or_insert_with(|| CoalitionBuffers::new(lp.n_cols()));
+                        summary.retried += 1;
+                        for attempt in solver_options.retry_ladder() {
+                            summary.retry_attempts += 1;
+                            outcome = self.solve_one(coalition_idx, buf, &attempt);
+                            if is_conclusive(&outcome) {
+                                break;
+                            }
+                        }
+                    }
+                    FailurePolicy::TreatAsInfeasible => {}
+                }
+            }
+
+            if let Ok(result) = &outcome {
+                summary
+                    .stats
+                    .record(result, || members(coalition_idx, &self.operators));
+            }
+            finals.push(outcome);
+        }
+
+        let values = slots
+            .into_iter()
+            .map(|slot| match &finals[slot] {
+                Ok(result) => match result.status {
+                    SolveStatus::Solved => {
+                        summary.solved += 1;
+                        Some(result.clone())
+                    }
+                    SolveStatus::Infeasible => {
+                        summary.infeasible += 1;
+                        None
+                    }
+                    SolveStatus::LimitReached => {
+                        summary.limit_reached += 1;
+                        None
+                    }
+                },
+                Err(_) => {
+                    summary.failed += 1;
+                    None
+                }
+            })
+            .collect();
+
+        Ok((values, summary))
+    }
+
+    /// Which of the distinct column and row mask pairs of this problem and
+    /// its residual problems the coalition `mask` keeps, one bit per pair.
+    /// Coalitions with equal signatures keep the same columns and rows in
+    /// every class and so have the same LP.
+    fn signature(&self, mask: u32) -> Vec<u64> {
+        let pairs = std::iter::once(self)
+            .chain(&self.residual)
+            .flat_map(|problem| &problem.mask_pairs);
+        let mut bits = Vec::new();
+        for (k, &(op1, op2)) in pairs.enumerate() {
+            if k % 64 == 0 {
+                bits.push(0);
+            }
+            if op1 & mask != 0 && op2 & mask != 0 {
+                *bits.last_mut().expect("pushed above") |= 1 << (k % 64);
+            }
+        }
+        bits
+    }
+
+    fn solve_one(
+        &self,
+        coalition_idx: usize,
+        buffers: &mut CoalitionBuffers,
+        solver_options: &SolverOptions,
+    ) -> Result<CoalitionResult> {
+        let residual = self.residual_lps();
+        CoalitionLp {
+            residual: &residual,
+            ..self.lp()
+        }
+        .solve((coalition_idx as u32) | ALWAYS_BIT, buffers, solver_options)
+    }
+
+    /// Traffic on each consolidated link in the optimal routing of the grand coalition
+    fn grand_coalition_flows(
+        &self,
+        n_links: usize,
+        solver_options: &SolverOptions,
+    ) -> Result<Vec<f64>> {
+        let primal = self.grand_coalition_primal(solver_options)?;
+        Ok(self.link_flows(&primal, n_links))
+    }
+
+    fn grand_coalition_primal(&self, solver_options: &SolverOptions) -> Result<Vec<f64>> {
+        let grand = (1usize << self.operators.len()) - 1;
+        let Some((_, primal)) = self.solve_primal(grand, solver_options)? else {
+            return Err(ShapleyError::LpSolver(
+                "Grand coalition could not be solved: Infeasible".to_string(),
+            ));
+        };
+        Ok(primal)
+    }
+
+    /// Optimal objective of one coalition with only the columns `keep`
+    /// accepts, or `None` if that is infeasible
+    fn solve_restricted(
+        &self,
+        coalition_idx: usize,
+        keep: impl Fn(usize) -> bool,
+        solver_options: &SolverOptions,
+    ) -> Result<Option<f64>> {
+        let col_op1_mask: Vec<u32> = self
+            .col_op1_mask
+            .iter()
+            .enumerate()
+            .map(|(col, &mask)| if keep(col) { mask } else { 0 })
+            .collect();
+        let lp = CoalitionLp {
+            col_op1_mask: &col_op1_mask,
+            ..self.lp()
+        };
+        let mut buffers = CoalitionBuffers::new(lp.n_cols());
+        let result = lp
+            .solve(
+                (coalition_idx as u32) | ALWAYS_BIT,
+                &mut buffers,
+                solver_options,
+            )
+            .map_err(|e| {
+                e.with_context(ErrorContext::coalition(coalition_idx, SolverStatus::Failed))
+            })?;
+        match result.status {
+            SolveStatus::Solved => Ok(Some(result.objective_value)),
+            SolveStatus::Infeasible => Ok(None),
+            status => Err(ShapleyError::LpSolver(format!(
+                "Coalition {coalition_idx} could not be solved: {status:?}"
+            ))
+            .with_context(ErrorContext::coalition(
+                coalition_idx,
+                SolverStatus::LimitReached,
+            ))),
+        }
+    }
+
+    /// Optimal objective and per-column solution of one coalition, or `None`
+    /// if it is infeasible
+    fn solve_primal(
+        &self,
+        coalition_idx: usize,
+        solver_options: &SolverOptions,
+    ) -> Result<Option<(f64, Vec<f64>)>> {
+        let mut buffers = CoalitionBuffers::new(self.lp().n_cols());
+        buffers.primal = Some(Vec::new());
+        let result = self
+            .solve_one(coalition_idx, &mut buffers, solver_options)
+            .map_err(|e| {
+                e.with_context(ErrorContext::coalition(coalition_idx, SolverStatus::Failed))
+            })?;
+        match result.status {
+            SolveStatus::Solved => Ok(Some((
+                result.objective_value,
+                buffers.primal.unwrap_or_default(),
+            ))),
+            SolveStatus::Infeasible => Ok(None),
+            status => Err(ShapleyError::LpSolver(format!(
+                "Coalition {coalition_idx} could not be solved: {status:?}"
+            ))
+            .with_context(ErrorContext::coalition(
+                coalition_idx,
+                SolverStatus::LimitReached,
+            ))),
+        }
+    }
+
+    /// Sum a per-column solution into traffic per consolidated link
+    fn link_flows(&self, primal: &[f64], n_links: usize) -> Vec<f64> {
+        let mut flows = vec![0.0; n_links];
+        for (col, link) in self.primitives.col_link.iter().enumerate() {
+            if let Some(link) = link {
+                flows[*link] += primal[col];
+            }
+        }
+        flows
+    }
+
+    /// Split a per-column solution into each commodity's flow per
+    /// consolidated link
+    fn commodity_flows(&self, primal: &[f64], n_links: usize) -> BTreeMap<u32, Vec<f64>> {
+        let mut flows: BTreeMap<u32, Vec<f64>> = BTreeMap::new();
+        for (col, route) in self.primitives.col_route.iter().enumerate() {
+            if let Some((kind, link)) = route {
+                flows.entry(*kind).or_insert_with(|| vec![0.0; n_links])[*link] += primal[col];
+            }
+        }
+        flows
+    }
+
+    /// Inequality rows of the coalition's LP that `primal` meets with equality
+    fn binding_constraints(&self, coalition_idx: usize, primal: &[f64]) -> Vec<BindingConstraint> {
+        let mask = (coalition_idx as u32) | ALWAYS_BIT;
+        let a_ub = &self.primitives.a_ub;
+        let col_link = &self.primitives.col_link;
+        let groups = &self.primitives.shared_groups.groups;
+        // Bandwidth rows take their links from the shared-group index
+        let mut row_links: Vec<Vec<usize>> = groups
+            .iter()
+            .map(|g| g.columns.iter().filter_map(|&col| col_link[col]).collect())
+            .collect();
+        row_links.resize(a_ub.m, Vec::new());
+        let mut activity = vec![0.0; a_ub.m];
+        for (col, &x) in primal.iter().enumerate() {
+            for idx in a_ub.colptr[col]..a_ub.colptr[col + 1] {
+                let row = a_ub.rowval[idx];
+                activity[row] += a_ub.nzval[idx] * x;
+                if row >= groups.len()
+                    && let Some(link) = col_link[col]
+                {
+                    row_links[row].push(link);
+                }
+            }
+        }
+
+        let mut binding = Vec::new();
+        for (row, mut links) in row_links.into_iter().enumerate() {
+            let limit = self.primitives.b_ub[row];
+            let in_coalition =
+                self.row_op1_mask[row] & mask != 0 && self.row_op2_mask[row] & mask != 0;
+            // Congestion breakpoints bound the load their column carries, not the link
+            let congestion = self.primitives.congestion_row(row).is_some();
+            if in_coalition
+                && !congestion
+                && limit - activity[row] <= BINDING_TOLERANCE * limit.abs().max(1.0)
+            {
+                links.sort_unstable();
+                links.dedup();
+                binding.push(BindingConstraint { links, limit });
+            }
+        }
+        binding
+    }
+
+    fn lp(&self) -> CoalitionLp<'_> {
+        CoalitionLp {
+            primitives: &self.primitives,
+            precomputed: &self.precomputed,
+            col_op1_mask: &self.col_op1_mask,
+            col_op2_mask: &self.col_op2_mask,
+            row_op1_mask: &self.row_op1_mask,
+            row_op2_mask: &self.row_op2_mask,
+            col_group: self.col_group.as_ref().map(|(g, n)| (g.as_slice(), *n)),
+            residual: &[],
+        }
+    }
+
+    fn residual_lps(&self) -> Vec<CoalitionLp<'_>> {
+        self.residual.iter().map(|problem| problem.lp()).collect()
+    }
+}
+
+/// One bitmask per consortium over `operators`; empty consortia are dropped and
+/// operators in none form a consortium of their own
+fn block_masks(operators: &[Operator], consortia: &[Vec<Operator>]) -> Result<Vec<u32>> {
+    let mut assigned = 0u32;
+    let mut blocks = Vec::new();
+    for consortium in consortia.iter().filter(|c| !c.is_empty()) {
+        let mut block = 0u32;
+        for op in consortium {
+            let Some(k) = operators.iter().position(|o| o == op) else {
+                return Err(ShapleyError::Validation(format!(
+                    "Consortium member {op} is not an operator"
+                )));
+            };
+            if assigned & (1 << k) != 0 {
+                return Err(ShapleyError::Validation(format!(
+                    "Operator {op} appears in more than one consortium"
+                )));
+            }
+            assigned |= 1 << k;
+            block |= 1 << k;
+        }
+        blocks.push(block);
+    }
+    blocks.extend(
+        (0..operators.len())
+            .filter(|k| assigned & (1 << k) == 0)
+            .map(|k| 1u32 << k),
+    );
+    Ok(blocks)
+}
+
+/// Whether a coalition solve settled the coalition's value (or its infeasibility)
+fn is_conclusive(outcome: &Result<CoalitionResult>) -> bool {
+    matches!(
+        outcome,
+        Ok(CoalitionResult {
+            status: SolveStatus::Solved | SolveStatus::Infeasible,
+            ..
+        })
+    )
+}
+
+/// Pair operators with their Shapley values and proportions
+pub(crate) fn to_output(
+    operators: impl IntoIterator<Item = Operator>,
+    shapley_values: Vec<f64>,
+) -> ShapleyOutput {
+    let proportions = core_math::proportions(&shapley_values);
+    operators
+        .into_iter()
+        .zip(shapley_values.into_iter().zip(proportions))
+        .map(|(operator, (value, proportion))| (operator, ShapleyValue { value, proportion }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::ErrorCode,
+        options::{
+            CapacityReservation, CoOwnerShare, ComputeResources, CongestionCurve,
+            LabelNormalization, SampleBudget,
+        },
+        profile::DemandProfile,
+        types::{BandwidthUnit, Demand, Device, PrivateLink, PublicLink},
+    };
+
+    #[test]
+    fn test_shapley_computation() {
+        // Create simple test data following the example format
+        let private_links = vec![
+            PrivateLink::new(
+                "NYC1".to_string(),
+                "LON1".to_string(),
+                10.0,
+                100.0,
+                1.0,
+                Some(1),
+            ),
+            PrivateLink::new(
+                "LON1".to_string(),
+                "PAR1".to_string(),
+                10.0,
+                100.0,
+                1.0,
+                Some(2),
+            ),
+        ];
+
+        let devices = vec![
+            Device::new("NYC1".to_string(), 1, "Operator1".to_string()),
+            Device::new("LON1".to_string(), 1, "Operator1".to_string()),
+            Device::new("PAR1".to_string(), 1, "Operator2".to_string()),
+        ];
+
+        let demands = vec![Demand::new(
+            "NYC".to_string(),
+            "PAR".to_string(),
+            1,
+            50.0,
+            1.0,
+            1,
+            false,
+        )];
+
+        let public_links = vec![PublicLink::new("NYC".to_string(), "PAR".to_string(), 100.0)];
+
+        let shapley = Shapley::new(private_links, devices, demands, public_links, 1.0, 5.0, 1.0);
+
+        let result = shapley.compute();
+        assert!(result.is_ok(), "Error in test: {result:?}");
+
+        let values = result.expect("Shapley computation should succeed in tests");
+        assert_eq!(values.len(), 2); // Two operators
+    }
+
+    #[test]
+    fn test_utilization_report() {
+        let input = ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 100.0, 1.0, None),
+                PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 100.0, 1.0, None),
+            ],
+            devices: vec![
+                Device::new("NYC1".into(), 100, "Operator1".into()),
+                Device::new("LON1".into(), 100, "Operator1".into()),
+                Device::new("PAR1".into(), 100, "Operator2".into()),
+            ],
+            demands: vec![Demand::new(
+                "NYC".into(),
+                "PAR".into(),
+                1,
+                50.0,
+                1.0,
+                1,
+                false,
+            )],
+            public_links: vec![PublicLink::new("NYC".into(), "PAR".into(), 100.0)],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let options = ShapleyOptions::default();
+        let values = input.compute_with(&options).unwrap();
+        let report = input.utilization_report(&options, &values).unwrap();
+
+        // All 50 units ride the private path NYC1 -> LON1 -> PAR1
+        let flow = |d1: &str, d2: &str| {
+            report
+                .links
+                .iter()
+                .find(|l| l.link.device1 == d1 && l.link.device2 == d2)
+                .map(|l| l.flow)
+                .unwrap()
+        };
+        assert!((flow("NYC1", "LON1") - 50.0).abs() < 1e-6);
+        assert!((flow("LON1", "PAR1") - 50.0).abs() < 1e-6);
+        assert!(flow("NYC00", "PAR00").abs() < 1e-6);
+
+        let earned: f64 = report.links.iter().map(|l| l.earned_value).sum();
+        let total: f64 = values.values().map(|v| v.value).sum();
+        assert!((earned - total).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_demand_decomposition_sums_to_total() {
+        let private_links = vec![
+            PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 100.0, 1.0, None),
+            PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 100.0, 1.0, None),
+        ];
+        let devices = vec![
+            Device::new("NYC1".into(), 100, "Operator1".into()),
+            Device::new("LON1".into(), 100, "Operator1".into()),
+            Device::new("PAR1".into(), 100, "Operator2".into()),
+        ];
+        let demands = vec![
+            Demand::new("NYC".into(), "PAR".into(), 1, 50.0, 1.0, 1, false),
+            Demand::new("NYC".into(), "LON".into(), 1, 20.0, 2.0, 2, false),
+        ];
+        let public_links = vec![
+            PublicLink::new("NYC".into(), "PAR".into(), 100.0),
+            PublicLink::new("NYC".into(), "LON".into(), 80.0),
+        ];
+        let shapley = Shapley::new(private_links, devices, demands, public_links, 0.9, 5.0, 1.0);
+
+        let total = shapley.compute().unwrap();
+        let decomposition = shapley.demand_decomposition().unwrap();
+        for (op, value) in &total {
+            let by_kind = &decomposition[op];
+            assert_eq!(by_kind.keys().copied().collect::<Vec<_>>(), vec![1, 2]);
+            assert!((by_kind.values().sum::<f64>() - value.value).abs() < 1e-6);
+        }
+        // Only Operator1 reaches LON
+        assert!(decomposition["Operator2"][&2].abs() < 1e-6);
+        assert!(decomposition["Operator1"][&2] > 0.0);
+    }
+
+    #[test]
+    fn test_from_values_matches_full_computation() {
+        let private_links = vec![
+            PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 100.0, 1.0, None),
+            PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 100.0, 1.0, None),
+        ];
+        let devices = vec![
+            Device::new("NYC1".into(), 1, "Operator1".into()),
+            Device::new("LON1".into(), 1, "Operator1".into()),
+            Device::new("PAR1".into(), 1, "Operator2".into()),
+        ];
+        let demands = vec![Demand::new(
+            "NYC".into(),
+            "PAR".into(),
+            1,
+            50.0,
+            1.0,
+            1,
+            false,
+        )];
+        let public_links = vec![PublicLink::new("NYC".into(), "PAR".into(), 100.0)];
+        let shapley = Shapley::new(private_links, devices, demands, public_links, 0.9, 5.0, 1.0);
+
+        let expected = shapley.compute().unwrap();
+        let problem = shapley.coalition_problem().unwrap().unwrap();
+        let (values, _) = problem
+            .solve_all(&SolverOptions::default(), FailurePolicy::default())
+            .unwrap();
+        let from_values = ShapleyFromValues::new(problem.operators, values, 0.9);
+
+        assert_eq!(from_values.compute().unwrap(), expected);
+
+        // Two operators: the interaction is the complement of additivity,
+        // v(AB) - v(A) - v(B) + v(∅), and matches the full computation
+        let synergy = from_values.synergy_matrix().unwrap();
+        assert_eq!(synergy, shapley.synergy_matrix().unwrap());
+
+        // One consortium of everyone, or none at all, is the Shapley value
+        let everyone = vec![vec!["Operator1".to_string(), "Operator2".to_string()]];
+        let owen = from_values.owen_values(&everyone).unwrap();
+        assert_eq!(owen, shapley.owen_values(&everyone).unwrap());
+        for (op, value) in &from_values.owen_values(&[]).unwrap() {
+            assert!((value.value - expected[op].value).abs() < 1e-9);
+            assert!((owen[op].value - expected[op].value).abs() < 1e-9);
+        }
+        assert!(
+            from_values
+                .owen_values(&[vec!["Nobody".to_string()]])
+                .is_err()
+        );
+        assert_eq!(
+            synergy.get("Operator1", "Operator2"),
+            Some(synergy.interaction[1][0])
+        );
+    }
+
+    #[test]
+    fn test_srlg_outage_scales_values() {
+        let private_links = vec![
+            PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 100.0, 1.0, None).with_srlg(1),
+            PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 100.0, 1.0, None).with_srlg(1),
+        ];
+        let devices = vec![
+            Device::new("NYC1".into(), 1, "Operator1".into()),
+            Device::new("LON1".into(), 1, "Operator1".into()),
+            Device::new("PAR1".into(), 1, "Operator2".into()),
+        ];
+        let demands = vec![Demand::new(
+            "NYC".into(),
+            "PAR".into(),
+            1,
+            50.0,
+            1.0,
+            1,
+            false,
+        )];
+        let public_links = vec![PublicLink::new("NYC".into(), "PAR".into(), 100.0)];
+        let mut shapley =
+            Shapley::new(private_links, devices, demands, public_links, 1.0, 5.0, 1.0);
+
+        let always_up = shapley.compute().unwrap();
+        shapley.options.srlg_availability = BTreeMap::from([(1, 0.5)]);
+        let half_up = shapley.compute().unwrap();
+
+        // With the whole private network down every coalition is worth the same,
+        // so each operator keeps exactly half of its value
+        for (op, value) in &always_up {
+            assert!((half_up[op].value - 0.5 * value.value).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_srlg_outages_keep_infeasible_coalitions() {
+        // PAR is off the public network, so without the private links the
+        // demand cannot be routed at all
+        let private_links = vec![
+            PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 100.0, 1.0, None).with_srlg(1),
+            PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 100.0, 1.0, None).with_srlg(1),
+        ];
+        let devices = vec![
+            Device::new("NYC1".into(), 100, "Operator1".into()),
+            Device::new("LON1".into(), 100, "Operator1".into()),
+            Device::new("PAR1".into(), 100, "Operator2".into()),
+        ];
+        let demands = vec![Demand::new(
+            "NYC".into(),
+            "PAR".into(),
+            1,
+            50.0,
+            1.0,
+            1,
+            false,
+        )];
+        let public_links = vec![
+            PublicLink::new("NYC".into(), "LON".into(), 100.0),
+            PublicLink::new("PAR".into(), "TOK".into(), 100.0),
+        ];
+        let mut shapley =
+            Shapley::new(private_links, devices, demands, public_links, 1.0, 5.0, 1.0);
+
+        // Only the grand coalition routes the demand, over the private links,
+        // and the outage leaves it unable to
+        let (always_up, _) = shapley
+            .coalition_problem()
+            .unwrap()
+            .unwrap()
+            .solve_all(&SolverOptions::default(), FailurePolicy::default())
+            .unwrap();
+        assert_eq!(always_up[..3], [None, None, None]);
+        assert!(always_up[3].is_some());
+
+        // The other coalitions are infeasible in every scenario, so at full
+        // uptime they stay -inf whatever the group's availability, as
+        // without any outage
+        assert!(
+            shapley
+                .compute()
+                .unwrap()
+                .values()
+                .all(|v| !v.value.is_finite())
+        );
+        for availability in [1.0, 0.8, 0.4] {
+            shapley.options.srlg_availability = BTreeMap::from([(1, availability)]);
+            let values = shapley.compute().unwrap();
+            assert!(
+                values.values().all(|v| !v.value.is_finite()),
+                "{availability}"
+            );
+        }
+
+        // Under operator uptime an infeasible state adds nothing
+        shapley.operator_uptime = 0.9;
+        for availability in [1.0, 0.8] {
+            shapley.options.srlg_availability = BTreeMap::from([(1, availability)]);
+            let values = shapley.compute().unwrap();
+            assert!(
+                values.values().all(|v| v.value.is_finite()),
+                "{availability}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_priority_classes_route_preemptively() {
+        let private_links = vec![
+            PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 10.0, 1.0, None),
+            PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 10.0, 1.0, None),
+        ];
+        let devices = vec![
+            Device::new("NYC1".into(), 1, "Alpha".into()),
+            Device::new("LON1".into(), 1, "Beta".into()),
+            Device::new("PAR1".into(), 1, "Gamma".into()),
+        ];
+        let priority = Demand::new("NYC".into(), "PAR".into(), 1, 6.0, 1.0, 1, false);
+        let bulk = Demand::new("LON".into(), "PAR".into(), 1, 8.0, 1.0, 2, false).with_class(2);
+        let input = |demands| ShapleyInput {
+            private_links: private_links.clone(),
+            devices: devices.clone(),
+            demands,
+            public_links: vec![
+                PublicLink::new("NYC".into(), "LON".into(), 100.0),
+                PublicLink::new("LON".into(), "PAR".into(), 100.0),
+            ],
+            operator_uptime: 0.98,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let options = ShapleyOptions {
+            priority_classes: true,
+            ..Default::default()
+        };
+
+        let both = input(vec![priority.clone(), bulk.clone()]);
+        let classes = both.priority_class_values(&options).unwrap();
+        assert_eq!(classes.shapley.keys().copied().collect::<Vec<_>>(), [1, 2]);
+        let combined = both.compute_with(&options).unwrap();
+        for (op, value) in &combined {
+            assert!((classes.combined[op].value - value.value).abs() < 1e-9);
+            let by_class: f64 = classes.shapley.values().map(|s| s[op].value).sum();
+            assert!((by_class - value.value).abs() < 1e-9);
+        }
+
+        // Bulk traffic only gets the capacity priority traffic leaves, so the
+        // priority class is valued as if it were alone
+        let alone = input(vec![priority.clone()]).compute().unwrap();
+        for (op, value) in &alone {
+            assert!((classes.shapley[&1][op].value - value.value).abs() < 1e-9);
+        }
+
+        // A traffic type has one class
+        let split = input(vec![priority.clone(), priority.with_class(2)]);
+        assert!(matches!(
+            split.compute_with(&options).unwrap_err().root(),
+            ShapleyError::DataInconsistency(_)
+        ));
+    }
+
+    #[test]
+    fn test_demand_profile_averages_time_slots() {
+        let input = |traffic: f64| ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 6.0, 1.0, None),
+                PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 6.0, 1.0, None),
+            ],
+            devices: vec![
+                Device::new("NYC1".into(), 1, "Alpha".into()),
+                Device::new("LON1".into(), 1, "Beta".into()),
+                Device::new("PAR1".into(), 1, "Gamma".into()),
+            ],
+            demands: vec![
+                Demand::new("NYC".into(), "PAR".into(), 1, traffic, 1.0, 1, false),
+                Demand::new("LON".into(), "PAR".into(), 1, 2.0, 1.0, 2, false),
+            ],
+            public_links: vec![
+                PublicLink::new("NYC".into(), "LON".into(), 100.0),
+                PublicLink::new("LON".into(), "PAR".into(), 100.0),
+            ],
+            operator_uptime: 0.98,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let options = ShapleyOptions {
+            demand_profiles: BTreeMap::from([(1, DemandProfile::new(10.0, vec![0.5, 1.0]))]),
+            ..Default::default()
+        };
+
+        // Shapley values are linear in coalition values, so the profile gives
+        // the average of the two slots computed on their own
+        let profiled = input(1.0).compute_with(&options).unwrap();
+        let night = input(5.0).compute().unwrap();
+        let day = input(10.0).compute().unwrap();
+        for (op, value) in &profiled {
+            let expected = (night[op].value + day[op].value) / 2.0;
+            assert!((value.value - expected).abs() < 1e-9);
+        }
+
+        // The peak is in the unit of the demands it applies to
+        let mut in_mbps = input(1.0);
+        for link in &mut in_mbps.private_links {
+            link.unit = Some(BandwidthUnit::Gbps);
+        }
+        for demand in &mut in_mbps.demands {
+            demand.traffic *= 1000.0;
+            demand.unit = Some(BandwidthUnit::Mbps);
+        }
+        let options = ShapleyOptions {
+            demand_profiles: BTreeMap::from([(1, DemandProfile::new(10_000.0, vec![0.5, 1.0]))]),
+            ..Default::default()
+        };
+        let converted = in_mbps.compute_with(&options).unwrap();
+        for (op, value) in &profiled {
+            assert!((converted[op].value - value.value).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_region_demand_expands_to_cities() {
+        let input = |demands| ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 10.0, 1.0, None),
+                PrivateLink::new("NYC1".into(), "PAR1".into(), 10.0, 10.0, 1.0, None),
+            ],
+            devices: vec![
+                Device::new("NYC1".into(), 100, "Alpha".into()),
+                Device::new("LON1".into(), 100, "Beta".into()),
+                Device::new("PAR1".into(), 100, "Gamma".into()),
+            ],
+            demands,
+            public_links: vec![
+                PublicLink::new("NYC".into(), "LON".into(), 100.0),
+                PublicLink::new("NYC".into(), "PAR".into(), 100.0),
+            ],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let options = ShapleyOptions {
+            regions: BTreeMap::from([(
+                "EU".to_string(),
+                BTreeMap::from([("LON".to_string(), 3.0), ("PAR".to_string(), 1.0)]),
+            )]),
+            ..Default::default()
+        };
+        let by_region = input(vec![Demand::new(
+            "NYC".into(),
+            "EU".into(),
+            1,
+            8.0,
+            1.0,
+            1,
+            false,
+        )])
+        .compute_with(&options)
+        .unwrap();
+        let by_city = input(vec![
+            Demand::new("NYC".into(), "LON".into(), 1, 6.0, 1.0, 1, false),
+            Demand::new("NYC".into(), "PAR".into(), 1, 2.0, 1.0, 2, false),
+        ])
+        .compute()
+        .unwrap();
+        for (op, value) in &by_city {
+            assert!((by_region[op].value - value.value).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_multicast_group_table() {
+        let input = ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 10.0, 1.0, None),
+                PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 10.0, 1.0, None),
+            ],
+            devices: vec![
+                Device::new("NYC1".into(), 100, "Alpha".into()),
+                Device::new("LON1".into(), 100, "Alpha".into()),
+                Device::new("PAR1".into(), 100, "Beta".into()),
+            ],
+            demands: vec![
+                Demand::new("NYC".into(), "LON".into(), 3, 1.0, 1.0, 1, true),
+                Demand::new("NYC".into(), "PAR".into(), 2, 1.0, 1.0, 1, true),
+                Demand::new("LON".into(), "PAR".into(), 1, 4.0, 1.0, 2, false),
+            ],
+            public_links: vec![
+                PublicLink::new("NYC".into(), "LON".into(), 100.0),
+                PublicLink::new("LON".into(), "PAR".into(), 100.0),
+            ],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 2.0,
+        };
+        let groups = input
+            .consolidation_report(&ShapleyOptions::default())
+            .unwrap()
+            .multicast_groups();
+        assert_eq!(
+            groups,
+            vec![crate::types::MulticastGroup {
+                group: 1,
+                sources: vec!["NYC".into()],
+                rows: 2,
+                receivers: 5,
+                traffic: 10.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_capacity_reservation() {
+        let input = ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 10.0, 1.0, None),
+                PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 10.0, 1.0, None),
+            ],
+            devices: vec![
+                Device::new("NYC1".into(), 100, "Alpha".into()),
+                Device::new("LON1".into(), 100, "Alpha".into()),
+                Device::new("PAR1".into(), 100, "Beta".into()),
+            ],
+            demands: vec![
+                Demand::new("NYC".into(), "LON".into(), 1, 4.0, 1.0, 1, false),
+                Demand::new("NYC".into(), "PAR".into(), 1, 10.0, 1.0, 2, false),
+            ],
+            public_links: vec![
+                PublicLink::new("NYC".into(), "LON".into(), 100.0),
+                PublicLink::new("LON".into(), "PAR".into(), 100.0),
+            ],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let options = ShapleyOptions {
+            reservations: vec![CapacityReservation::new(
+                "LON1".into(),
+                "NYC1".into(),
+                1,
+                8.0,
+            )],
+            ..Default::default()
+        };
+
+        let links = input.consolidation_report(&options).unwrap().links;
+        let between = |link: &&ConsolidatedLink| {
+            [link.device1.as_str(), link.device2.as_str()] == ["NYC1", "LON1"]
+        };
+        let nyc_lon: Vec<(f64, u32)> = links
+            .iter()
+            .filter(between)
+            .map(|l| (l.bandwidth, l.link_type))
+            .collect();
+        assert_eq!(nyc_lon.len(), 2);
+        assert!((nyc_lon[0].0 - 2.0).abs() < 1e-9 && nyc_lon[0].1 == 0);
+        assert_eq!(nyc_lon[1], (8.0, 1));
+        // Private links still precede the public ones
+        let first_public = links.iter().position(|l| l.operator1 == "Public").unwrap();
+        assert!(
+            links[first_public..]
+                .iter()
+                .all(|l| l.operator1 == "Public")
+        );
 
-        let n_coalitions = 1 << n_operators;
-        let n_cols = col_op1_mask.len();
-
-        thread_local! {
-            static BUFFERS: RefCell<Option<CoalitionBuffers>> = const { RefCell::new(None) };
-        }
-
-        // Solve LP for each coalition
-        let coalition_values: Vec<Option<f64>> = (0..n_coalitions)
-            .into_par_iter()
-            .map(|coalition_idx| {
-                BUFFERS.with(|cell| {
-                    let mut borrow = cell.borrow_mut();
-                    let buf = borrow.get_or_insert_with(|| CoalitionBuffers::new(n_cols));
-
-                    let coalition_mask = (coalition_idx as u32) | ALWAYS_BIT;
-
-                    match solve_coalition(
-                        &primitives,
-                        &precomputed,
-                        buf,
-                        coalition_mask,
-                        &col_op1_mask,
-                        &col_op2_mask,
-                        &row_op1_mask,
-                        &row_op2_mask,
-                    ) {
-                        Ok(result) => {
-                            if matches!(result.status, SolveStatus::Solved) {
-                                Some(-result.objective_value) // Negative because we minimize
-                            } else {
-                                None // Infeasible coalition
-                            }
-                        }
-                        Err(_) => None,
-                    }
-                })
+        // Capacity left idle by the protected type is lost to the rest
+        let reserved = input.compute_with(&options).unwrap();
+        let shared = input.compute().unwrap();
+        assert!(reserved["Alpha"].value < shared["Alpha"].value);
+
+        let unknown = ShapleyOptions {
+            reservations: vec![CapacityReservation::new(
+                "NYC1".into(),
+                "PAR1".into(),
+                1,
+                1.0,
+            )],
+            ..Default::default()
+        };
+        assert!(matches!(
+            input.compute_with(&unknown),
+            Err(ShapleyError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_concurrent_computations_do_not_interfere() {
+        // Inputs of different sizes solved at once on the same global pool
+        let inputs: Vec<ShapleyInput> = [(3, 4), (4, 8)]
+            .into_iter()
+            .map(|(operators, private_links)| {
+                let config = testing::RandomInputConfig {
+                    operators,
+                    private_links,
+                    ..Default::default()
+                };
+                testing::random_input(operators as u64, &config)
             })
             .collect();
+        let expected: Vec<ShapleyOutput> = inputs.iter().map(|i| i.compute().unwrap()).collect();
 
-        // Compute expected values with operator uptime
-        let expected_values = if self.operator_uptime < 1.0 {
-            compute_expected_values(&coalition_values, n_operators, self.operator_uptime)?
-        } else {
-            coalition_values
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = inputs
                 .iter()
-                .map(|&v| v.unwrap_or(f64::NEG_INFINITY))
-                .collect()
+                .map(|input| scope.spawn(|| input.compute().unwrap()))
+                .collect();
+            for (handle, expected) in handles.into_iter().zip(&expected) {
+                assert_eq!(&handle.join().unwrap(), expected);
+            }
+        });
+    }
+
+    #[test]
+    fn test_sla_report() {
+        let input = testing::random_input(2, &testing::RandomInputConfig::default());
+        let report = input.sla_report(&ShapleyOptions::default()).unwrap();
+        assert_eq!(report.demands.len(), input.demands.len());
+        for (sla, demand) in report.demands.iter().zip(&input.demands) {
+            assert_eq!(sla.traffic, demand.traffic);
+            assert!(sla.latency > 0.0 && sla.latency <= sla.max_latency);
+            assert!((0.0..=1.0).contains(&sla.public_share));
+        }
+        assert!((0.0..=1.0).contains(&report.public_share));
+    }
+
+    #[test]
+    fn test_uptime_report() {
+        let config = testing::RandomInputConfig {
+            operator_uptime: 0.9,
+            ..Default::default()
         };
+        let input = testing::random_input(7, &config);
+        let report = input.uptime_report(&ShapleyOptions::default()).unwrap();
+        assert_eq!(report.adjusted, input.compute().unwrap());
 
-        // Compute Shapley values
-        let shapley_values = compute_shapley_values(&expected_values, n_operators);
+        let always_up = ShapleyInput {
+            operator_uptime: 1.0,
+            ..input.clone()
+        };
+        for (op, value) in always_up.compute().unwrap() {
+            assert!((report.gross[&op].value - value.value).abs() < 1e-9);
+            let delta = report.adjusted[&op].value - report.gross[&op].value;
+            assert_eq!(report.delta[&op], delta);
+        }
+    }
 
-        // Convert to output format
-        let total_value: f64 = shapley_values.iter().map(|v| v.max(0.0)).sum();
+    #[test]
+    fn test_carbon_report() {
+        let input = testing::random_input(3, &testing::RandomInputConfig::default());
+        let options = ShapleyOptions::default();
+        let intensity = CarbonIntensity::new(10.0);
 
-        let output = operators
-            .into_iter()
-            .zip(shapley_values)
-            .map(|(operator, value)| {
-                let proportion = if total_value > 0.0 {
-                    (value.max(0.0) / total_value * 100.0) / 100.0
-                } else {
-                    0.0
-                };
+        let unweighted = input.carbon_report(&options, &intensity, 0.0).unwrap();
+        assert_eq!(unweighted.base, input.compute().unwrap());
+        assert_eq!(unweighted.carbon_adjusted, unweighted.base);
 
-                (operator, ShapleyValue { value, proportion })
-            })
-            .collect();
+        // Private links emit nothing here, so operators save the public carbon
+        let report = input.carbon_report(&options, &intensity, 1.0).unwrap();
+        assert!(report.difference.values().sum::<f64>() > 0.0);
+        for (op, difference) in &report.difference {
+            let expected = report.carbon_adjusted[op].value - report.base[op].value;
+            assert!((difference - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_commitment_report() {
+        let input = ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 10.0, 1.0, None)
+                    .with_measured_bandwidth(2.0),
+                PrivateLink::new("LON1".into(), "LON2".into(), 1.0, 10.0, 1.0, None),
+                PrivateLink::new("LON2".into(), "PAR1".into(), 10.0, 10.0, 1.0, None),
+            ],
+            devices: vec![
+                Device::new("NYC1".into(), 100, "Alpha".into()),
+                Device::new("LON1".into(), 100, "Alpha".into()),
+                Device::new("LON2".into(), 100, "Beta".into()),
+                Device::new("PAR1".into(), 100, "Beta".into()),
+            ],
+            demands: vec![Demand::new(
+                "NYC".into(),
+                "PAR".into(),
+                1,
+                8.0,
+                1.0,
+                1,
+                false,
+            )],
+            public_links: vec![
+                PublicLink::new("NYC".into(), "LON".into(), 100.0),
+                PublicLink::new("LON".into(), "PAR".into(), 100.0),
+            ],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let report = input.commitment_report(&ShapleyOptions::default()).unwrap();
+        assert_eq!(report.declared, input.compute().unwrap());
+        let measured = ShapleyOptions {
+            capacity: CapacityBasis::Measured,
+            ..Default::default()
+        };
+        assert_eq!(report.measured, input.compute_with(&measured).unwrap());
+
+        assert_eq!(report.shortfall["Alpha"], 8.0);
+        assert_eq!(report.shortfall["Beta"], 0.0);
+        assert!(report.penalty["Alpha"] > 0.0);
+        assert_eq!(report.penalty["Beta"], 0.0);
 
-        Ok(output)
+        let mut negative = input.clone();
+        negative.private_links[0].measured_bandwidth = Some(-1.0);
+        assert!(matches!(
+            negative.commitment_report(&ShapleyOptions::default()),
+            Err(ShapleyError::Validation(_))
+        ));
     }
-}
 
-/// Compute expected values considering operator uptime.
-///
-/// For each coalition S, computes:
-///   evalue[S] = Σ_{T⊆S} uptime^|T| × (1-uptime)^(|S\T|) × svalue[T]
-///
-/// Uses Gosper's subset iteration (`t = (t-1) & s`) for O(3^n) total work
-/// instead of O(4^n) dense matrix operations.
-fn compute_expected_values(
-    svalue: &[Option<f64>],
-    n_operators: usize,
-    operator_uptime: f64,
-) -> Result<Vec<f64>> {
-    let n_coal = 1 << n_operators;
-    let downtime = 1.0 - operator_uptime;
+    #[test]
+    fn test_evaluate_coalition() {
+        let input = ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 100.0, 1.0, None),
+                PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 100.0, 1.0, None),
+            ],
+            devices: vec![
+                Device::new("NYC1".into(), 1000, "Operator1".into()),
+                Device::new("LON1".into(), 1000, "Operator1".into()),
+                Device::new("PAR1".into(), 1000, "Operator2".into()),
+            ],
+            demands: vec![Demand::new(
+                "NYC".into(),
+                "PAR".into(),
+                1,
+                150.0,
+                1.0,
+                1,
+                false,
+            )],
+            public_links: vec![PublicLink::new("NYC".into(), "PAR".into(), 100.0)],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let options = ShapleyOptions::default();
+        let flow = |solution: &CoalitionSolution, d1: &str, d2: &str| {
+            let idx = solution
+                .links
+                .iter()
+                .position(|l| l.device1 == d1 && l.device2 == d2)
+                .unwrap();
+            (idx, solution.flows[idx])
+        };
 
-    let svalue_vec: Vec<f64> = svalue
-        .iter()
-        .map(|&v| v.unwrap_or(f64::NEG_INFINITY))
-        .collect();
+        // Both operators: the private path fills up and the rest goes public
+        let both = input
+            .evaluate_coalition(&options, &["Operator2".into(), "Operator1".into()])
+            .unwrap();
+        assert_eq!(both.operators, vec!["Operator1", "Operator2"]);
+        let (nyc_lon, nyc_lon_flow) = flow(&both, "NYC1", "LON1");
+        assert!((nyc_lon_flow - 100.0).abs() < 1e-6);
+        assert!((flow(&both, "NYC00", "PAR00").1 - 50.0).abs() < 1e-6);
+        assert!(both.binding.iter().any(|b| b.links.contains(&nyc_lon)));
 
-    let mut evalue = vec![0.0; n_coal];
+        // Operator1 alone cannot reach PAR privately
+        let alone = input
+            .evaluate_coalition(&options, &["Operator1".into()])
+            .unwrap();
+        assert!(alone.objective.unwrap() > both.objective.unwrap());
+        assert!(flow(&alone, "NYC1", "LON1").1.abs() < 1e-6);
 
-    for (s, ev) in evalue.iter_mut().enumerate() {
-        let s_size = (s as u32).count_ones() as i32;
-        let mut sum = 0.0;
+        assert!(
+            input
+                .evaluate_coalition(&options, &["Operator3".into()])
+                .is_err()
+        );
+    }
 
-        // Iterate over all subsets t of s (including empty set)
-        let mut t = s;
-        loop {
-            let val = svalue_vec[t];
-            if val.is_finite() {
-                let t_size = (t as u32).count_ones() as i32;
-                let prob = operator_uptime.powi(t_size) * downtime.powi(s_size - t_size);
-                sum += prob * val;
-            }
-            if t == 0 {
-                break;
-            }
-            t = (t - 1) & s;
+    /// Operators Op0..Op6 each own one city of a chain of private links
+    fn chain_input() -> ShapleyInput {
+        // Seven operators give 128 coalitions, i.e. more than one batch
+        let cities = ["NYC", "LON", "PAR", "FRA", "AMS", "BER", "MAD"];
+        let devices: Vec<Device> = cities
+            .iter()
+            .enumerate()
+            .map(|(i, city)| Device::new(format!("{city}1"), 10, format!("Op{i}")))
+            .collect();
+        let private_links = cities
+            .windows(2)
+            .enumerate()
+            .map(|(i, pair)| {
+                let latency = 10.0 + i as f64 / 3.0;
+                PrivateLink::new(
+                    format!("{}1", pair[0]),
+                    format!("{}1", pair[1]),
+                    latency,
+                    5.0,
+                    1.0,
+                    None,
+                )
+            })
+            .collect();
+        let public_links = cities
+            .windows(2)
+            .map(|pair| PublicLink::new(pair[0].into(), pair[1].into(), 30.0))
+            .collect();
+        let demands = vec![
+            Demand::new("NYC".into(), "MAD".into(), 1, 7.0, 1.0, 1, false),
+            Demand::new("LON".into(), "BER".into(), 1, 3.0, 0.7, 2, false),
+        ];
+        ShapleyInput {
+            private_links,
+            devices,
+            demands,
+            public_links,
+            operator_uptime: 0.97,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
         }
+    }
+
+    #[test]
+    fn test_deterministic_across_thread_counts() {
+        let input = chain_input();
+        let options = ShapleyOptions {
+            deterministic: true,
+            ..Default::default()
+        };
 
-        *ev = sum;
+        let run = |threads: usize| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| input.compute_with(&options).unwrap())
+        };
+        let single = run(1);
+        let multi = run(4);
+        for (op, value) in &single {
+            assert_eq!(value.value.to_bits(), multi[op].value.to_bits());
+            assert_eq!(value.proportion.to_bits(), multi[op].proportion.to_bits());
+        }
+
+        let timed = ShapleyOptions {
+            solver: SolverOptions {
+                time_limit: Some(std::time::Duration::from_secs(1)),
+                ..Default::default()
+            },
+            ..options
+        };
+        assert!(matches!(
+            input.compute_with(&timed),
+            Err(ShapleyError::Validation(_))
+        ));
     }
 
-    // Preserve empty coalition value
-    if let Some(v) = svalue[0]
-        && v.is_finite()
-    {
-        evalue[0] = v;
+    #[test]
+    fn test_pruned_solves_stay_within_tolerance() {
+        let input = chain_input();
+        let exact = input.compute_report(&ShapleyOptions::default()).unwrap();
+        assert_eq!(exact.solve_summary.pruned, 0);
+
+        for tolerance in [0.0, 0.5] {
+            let options = ShapleyOptions {
+                prune_tolerance: Some(tolerance),
+                ..Default::default()
+            };
+            let pruned = input.compute_report(&options).unwrap();
+            let summary = pruned.solve_summary;
+            assert_eq!(summary.solved + summary.infeasible + summary.pruned, 128);
+            assert!(summary.pruned > 0);
+            for (op, value) in &exact.values {
+                assert!((pruned.values[op].value - value.value).abs() <= tolerance + 1e-9);
+            }
+        }
     }
 
-    Ok(evalue)
-}
+    #[test]
+    fn test_pruning_keeps_null_player_under_priority_classes() {
+        // Alpha's NYC-LON link lets the priority class take the LON-PAR link
+        // the bulk class was using, so adding Alpha to Beta lowers the value.
+        // Gamma's link is far from every demand.
+        let input = ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 100.0, 1.0, None),
+                PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 10.0, 1.0, None),
+                PrivateLink::new("TOK1".into(), "SYD1".into(), 10.0, 100.0, 1.0, None),
+            ],
+            devices: vec![
+                Device::new("NYC1".into(), 100, "Alpha".into()),
+                Device::new("LON1".into(), 100, "Beta".into()),
+                Device::new("PAR1".into(), 100, "Beta".into()),
+                Device::new("TOK1".into(), 100, "Gamma".into()),
+                Device::new("SYD1".into(), 100, "Gamma".into()),
+            ],
+            demands: vec![
+                Demand::new("NYC".into(), "PAR".into(), 1, 6.0, 1.0, 1, false),
+                Demand::new("LON".into(), "PAR".into(), 1, 8.0, 1.0, 2, false).with_class(2),
+            ],
+            public_links: vec![
+                PublicLink::new("NYC".into(), "PAR".into(), 25.0),
+                PublicLink::new("NYC".into(), "LON".into(), 100.0),
+                PublicLink::new("LON".into(), "PAR".into(), 100.0),
+                PublicLink::new("TOK".into(), "SYD".into(), 100.0),
+            ],
+            operator_uptime: 1.0,
+            contiguity_bonus: 0.0,
+            demand_multiplier: 1.0,
+        };
+        let options = ShapleyOptions {
+            priority_classes: true,
+            ..Default::default()
+        };
+        let shapley = input.to_shapley(&options).unwrap();
+        let problem = shapley.coalition_problem().unwrap().unwrap();
+        let (exact, _) = problem
+            .solve_all(&options.solver, options.failure_policy)
+            .unwrap();
+        let (alpha, beta) = (0b001, 0b010);
+        assert!(exact[alpha | beta].unwrap() < exact[beta].unwrap());
 
-/// Compute Shapley values from coalition values
-fn compute_shapley_values(coalition_values: &[f64], n_operators: usize) -> Vec<f64> {
-    let mut shapley_values = vec![0.0; n_operators];
-    let fact_n = factorial(n_operators);
+        // The inverted bracket of {Alpha, Beta} is solved, not averaged
+        let (pruned, _) = problem
+            .solve_all_pruned(&options.solver, options.failure_policy, 0.0)
+            .unwrap();
+        assert_eq!(pruned, exact);
+        let values = ShapleyFromValues::new(problem.operators.clone(), pruned, 1.0)
+            .compute()
+            .unwrap();
+        assert!(values["Gamma"].value.abs() < 1e-9);
 
-    for (k, sv) in shapley_values.iter_mut().enumerate() {
-        let mut value = 0.0;
+        let pruning = ShapleyOptions {
+            prune_tolerance: Some(0.0),
+            ..options
+        };
+        assert!(matches!(
+            input.compute_with(&pruning).unwrap_err().root(),
+            ShapleyError::Validation(_)
+        ));
+    }
+
+    #[test]
+    fn test_sampling_approximates_exact_values() {
+        let private_links = vec![
+            PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 100.0, 1.0, None),
+            PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 100.0, 1.0, None),
+            PrivateLink::new("NYC2".into(), "PAR2".into(), 30.0, 100.0, 1.0, None),
+        ];
+        let devices = vec![
+            Device::new("NYC1".into(), 100, "Operator1".into()),
+            Device::new("LON1".into(), 100, "Operator2".into()),
+            Device::new("PAR1".into(), 100, "Operator2".into()),
+            Device::new("NYC2".into(), 100, "Operator3".into()),
+            Device::new("PAR2".into(), 100, "Operator3".into()),
+        ];
+        let demands = vec![Demand::new(
+            "NYC".into(),
+            "PAR".into(),
+            1,
+            50.0,
+            1.0,
+            1,
+            false,
+        )];
+        let public_links = vec![PublicLink::new("NYC".into(), "PAR".into(), 100.0)];
+        let mut shapley =
+            Shapley::new(private_links, devices, demands, public_links, 1.0, 5.0, 1.0);
 
-        // Find coalitions with this operator
-        for (coalition_idx, &with_value) in coalition_values.iter().enumerate() {
-            if (coalition_idx >> k) & 1 == 1 {
-                // Coalition without operator (remove bit k)
-                let without_idx = coalition_idx ^ (1 << k);
-                let without_value = coalition_values[without_idx];
+        let exact = shapley.compute_report().unwrap();
+        assert_eq!(exact.method, ComputationMethod::Exact);
 
-                // Coalition size
-                let coalition_size = (coalition_idx as u32).count_ones() as usize;
+        // Within the exact limit the policy changes nothing
+        shapley.options.approximation = Some(ApproximationPolicy::default());
+        assert_eq!(shapley.compute_report().unwrap(), exact);
 
-                // Weight calculation
-                let weight = factorial(coalition_size - 1)
-                    * factorial(n_operators - coalition_size)
-                    / fact_n;
+        let total: f64 = exact.values.values().map(|v| v.value.abs()).sum();
+        for sampler in [
+            SamplerKind::Permutation,
+            SamplerKind::Antithetic,
+            SamplerKind::Stratified,
+            #[cfg(feature = "surrogate")]
+            SamplerKind::Surrogate,
+        ] {
+            shapley.options.approximation = Some(ApproximationPolicy {
+                exact_up_to: 2,
+                sampler,
+                samples: SampleBudget::Permutations(4000),
+                ..Default::default()
+            });
+            let sampled = shapley.compute_report().unwrap();
+            assert!(matches!(
+                sampled.method,
+                ComputationMethod::Sampled { sampler: s, .. } if s == sampler
+            ));
 
-                value += weight * (with_value - without_value);
+            for (op, value) in &exact.values {
+                assert!((sampled.values[op].value - value.value).abs() < 0.05 * total);
+                let se = sampled.standard_errors[op];
+                assert!(se.is_finite() && se < 0.05 * total);
             }
         }
+    }
+
+    #[test]
+    fn test_large_games_need_explicit_approximation() {
+        let config = testing::RandomInputConfig {
+            operators: 25,
+            cities: 6,
+            private_links: 30,
+            ..Default::default()
+        };
+        let input = testing::random_input(4, &config);
+        let err = input.compute().unwrap_err();
+        assert!(matches!(
+            err.root(),
+            ShapleyError::TooManyOperators {
+                count: 25,
+                limit: 20
+            }
+        ));
 
-        *sv = value;
+        let options = ShapleyOptions::default().allow_large_games(ApproximationPolicy {
+            samples: SampleBudget::Permutations(20),
+            ..Default::default()
+        });
+        let report = input.compute_report(&options).unwrap();
+        assert_eq!(report.values.len(), 25);
+        assert!(matches!(report.method, ComputationMethod::Sampled { .. }));
     }
 
-    shapley_values
-}
+    #[test]
+    fn test_co_ownership_policy() {
+        // Alpha and Beta co-own the NYC-CHI link; Gamma owns LON-PAR alone
+        let input = ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("NYC1".into(), "CHI1".into(), 10.0, 100.0, 1.0, None),
+                PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 100.0, 1.0, None),
+            ],
+            devices: vec![
+                Device::new("NYC1".into(), 1, "Alpha".into()),
+                Device::new("CHI1".into(), 1, "Beta".into()),
+                Device::new("LON1".into(), 1, "Gamma".into()),
+                Device::new("PAR1".into(), 1, "Gamma".into()),
+            ],
+            demands: vec![
+                Demand::new("NYC".into(), "CHI".into(), 1, 5.0, 1.0, 1, false),
+                Demand::new("LON".into(), "PAR".into(), 1, 5.0, 1.0, 2, false),
+            ],
+            public_links: vec![
+                PublicLink::new("NYC".into(), "CHI".into(), 30.0),
+                PublicLink::new("LON".into(), "PAR".into(), 30.0),
+            ],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let equal = input.compute().unwrap();
+        assert!(equal["Alpha"].value > 0.0);
+        assert!((equal["Alpha"].value - equal["Beta"].value).abs() < 1e-9);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::types::{Demand, Device, PrivateLink, PublicLink};
+        let weighted = |share1| ShapleyOptions {
+            co_ownership: CoOwnershipPolicy::Weighted(vec![CoOwnerShare::new(
+                "Beta".into(),
+                "Alpha".into(),
+                share1,
+            )]),
+            ..Default::default()
+        };
+        let output = input.compute_with(&weighted(0.75)).unwrap();
+        let joint = equal["Alpha"].value + equal["Beta"].value;
+        assert!((output["Beta"].value - 0.75 * joint).abs() < 1e-9);
+        assert!((output["Alpha"].value - 0.25 * joint).abs() < 1e-9);
+        assert!((output["Gamma"].value - equal["Gamma"].value).abs() < 1e-9);
+
+        assert!(input.compute_with(&weighted(1.5)).is_err());
+        let unrelated = ShapleyOptions {
+            co_ownership: CoOwnershipPolicy::Weighted(vec![CoOwnerShare::new(
+                "Alpha".into(),
+                "Gamma".into(),
+                0.5,
+            )]),
+            ..Default::default()
+        };
+        assert!(input.compute_with(&unrelated).is_err());
+    }
 
     #[test]
-    fn test_shapley_computation() {
-        // Create simple test data following the example format
-        let private_links = vec![
-            PrivateLink::new(
-                "NYC1".to_string(),
-                "LON1".to_string(),
-                10.0,
-                100.0,
+    fn test_unrewarded_links() {
+        // Beta's FRA1 hosts the SIN-FRA-LON route, but the links are the
+        // foundation's
+        let input = ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("SIN1".into(), "FRA1".into(), 10.0, 100.0, 1.0, None),
+                PrivateLink::new("FRA1".into(), "LON1".into(), 10.0, 100.0, 1.0, None),
+            ],
+            devices: vec![
+                Device::new("SIN1".into(), 1, "Alpha".into()),
+                Device::new("FRA1".into(), 1, "Beta".into()),
+                Device::new("LON1".into(), 1, "Alpha".into()),
+            ],
+            demands: vec![Demand::new(
+                "SIN".into(),
+                "LON".into(),
+                1,
+                5.0,
                 1.0,
-                Some(1),
-            ),
-            PrivateLink::new(
-                "LON1".to_string(),
-                "PAR1".to_string(),
+                1,
+                false,
+            )],
+            public_links: vec![PublicLink::new("SIN".into(), "LON".into(), 100.0)],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let co_owned = input.compute().unwrap();
+        assert!(co_owned["Beta"].value > 0.0);
+        assert!((co_owned["Alpha"].value - co_owned["Beta"].value).abs() < 1e-9);
+
+        let options = ShapleyOptions {
+            unrewarded_links: vec![
+                ("FRA1".into(), "SIN1".into()),
+                ("FRA1".into(), "LON1".into()),
+            ],
+            ..Default::default()
+        };
+        let unrewarded = input.compute_with(&options).unwrap();
+        assert!(unrewarded["Beta"].value.abs() < 1e-9);
+        let total = co_owned["Alpha"].value + co_owned["Beta"].value;
+        assert!((unrewarded["Alpha"].value - total).abs() < 1e-9);
+
+        // The standalone LP keeps the links in every coalition
+        let lp = crate::pipeline::build_lp_with(&input, &options).unwrap();
+        let alone = lp.coalition(&["Alpha"]);
+        assert_eq!(
+            alone
+                .columns
+                .iter()
+                .filter(|c| c.operator1 == "Private")
+                .count(),
+            lp.columns
+                .iter()
+                .filter(|c| c.operator1 == "Private")
+                .count()
+        );
+        assert!(alone.columns.iter().any(|c| c.operator1 == "Private"));
+
+        let typo = ShapleyOptions {
+            unrewarded_links: vec![("FRA1".into(), "LON2".into())],
+            ..Default::default()
+        };
+        assert!(input.compute_with(&typo).is_err());
+    }
+
+    #[test]
+    fn test_congestion_cost() {
+        let input = ShapleyInput {
+            private_links: vec![PrivateLink::new(
+                "SIN1".into(),
+                "LON1".into(),
+                10.0,
                 10.0,
-                100.0,
                 1.0,
-                Some(2),
-            ),
-        ];
+                None,
+            )],
+            devices: vec![
+                Device::new("SIN1".into(), 100, "Alpha".into()),
+                Device::new("LON1".into(), 100, "Alpha".into()),
+            ],
+            demands: vec![Demand::new(
+                "SIN".into(),
+                "LON".into(),
+                1,
+                8.0,
+                1.0,
+                1,
+                false,
+            )],
+            public_links: vec![PublicLink::new("SIN".into(), "LON".into(), 100.0)],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let free = input.compute().unwrap();
+
+        // At 80% utilization, the 3 units beyond half the link pay 30 more each
+        let curve = CongestionCurve::new("LON1".into(), "SIN1".into()).with_breakpoint(0.5, 30.0);
+        assert_eq!(curve.cost(8.0, 10.0), 90.0);
+        let options = ShapleyOptions {
+            congestion: vec![curve.clone()],
+            ..Default::default()
+        };
+        let congested = input.compute_with(&options).unwrap();
+        assert!((free["Alpha"].value - congested["Alpha"].value - 90.0).abs() < 1e-6);
+
+        // Past 70% the private link costs more than the public path, which
+        // takes the excess
+        let steep = ShapleyOptions {
+            congestion: vec![curve.with_breakpoint(0.7, 1000.0)],
+            ..Default::default()
+        };
+        let diverted = input.compute_with(&steep).unwrap()["Alpha"].value;
+        assert!(diverted > 0.0 && diverted < congested["Alpha"].value);
+
+        for curve in [
+            CongestionCurve::new("SIN1".into(), "LON2".into()).with_breakpoint(0.5, 1.0),
+            CongestionCurve::new("SIN1".into(), "LON1".into()).with_breakpoint(1.0, 1.0),
+            CongestionCurve::new("SIN1".into(), "LON1".into())
+                .with_breakpoint(0.5, 1.0)
+                .with_breakpoint(0.4, 1.0),
+            CongestionCurve::new("SIN1".into(), "LON1".into()).with_breakpoint(0.5, -1.0),
+        ] {
+            let options = ShapleyOptions {
+                congestion: vec![curve],
+                ..Default::default()
+            };
+            assert!(input.compute_with(&options).is_err());
+        }
+    }
 
+    #[test]
+    fn test_failure_policy() {
+        let private_links = vec![
+            PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 100.0, 1.0, None),
+            PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 100.0, 1.0, None),
+        ];
         let devices = vec![
-            Device::new("NYC1".to_string(), 1, "Operator1".to_string()),
-            Device::new("LON1".to_string(), 1, "Operator1".to_string()),
-            Device::new("PAR1".to_string(), 1, "Operator2".to_string()),
+            Device::new("NYC1".into(), 1, "Operator1".into()),
+            Device::new("LON1".into(), 1, "Operator1".into()),
+            Device::new("PAR1".into(), 1, "Operator2".into()),
         ];
+        let demands = vec![Demand::new(
+            "NYC".into(),
+            "PAR".into(),
+            1,
+            50.0,
+            1.0,
+            1,
+            false,
+        )];
+        let public_links = vec![PublicLink::new("NYC".into(), "PAR".into(), 100.0)];
+        let mut shapley =
+            Shapley::new(private_links, devices, demands, public_links, 1.0, 5.0, 1.0);
+
+        let baseline = shapley.compute_report().unwrap();
+        assert_eq!(baseline.solve_summary.solved, 4);
+        assert_eq!(baseline.solve_summary.limit_reached, 0);
+        let stats = &baseline.solve_summary.stats;
+        assert_eq!(stats.iteration_histogram.values().sum::<usize>(), 4);
+        assert!(stats.total_iterations > 0);
+        assert_eq!(stats.slowest.len(), 4);
+        assert!(
+            stats
+                .slowest
+                .windows(2)
+                .all(|w| w[0].solve_time >= w[1].solve_time)
+        );
+        assert_eq!(
+            stats.total_solve_time,
+            stats.slowest.iter().map(|s| s.solve_time).sum()
+        );
+
+        // No pivots allowed: every coalition stops at the limit
+        shapley.options.solver.max_iterations = Some(0);
+        shapley.options.failure_policy = FailurePolicy::TreatAsInfeasible;
+        let limited = shapley.compute_report().unwrap();
+        assert_eq!(limited.solve_summary.limit_reached, 4);
+        assert_eq!(limited.solve_summary.stats.total_iterations, 0);
+
+        shapley.options.failure_policy = FailurePolicy::Error;
+        let err = shapley.compute_report().unwrap_err();
+        assert_eq!(err.code(), ErrorCode::LpSolver);
+        assert!(matches!(err.root(), ShapleyError::LpSolver(_)));
+        let context = err.context().unwrap();
+        assert!(context.coalition.is_some());
+        assert_eq!(context.solver_status, Some(SolverStatus::LimitReached));
 
+        shapley.options.failure_policy = FailurePolicy::Retry;
+        let retried = shapley.compute_report().unwrap();
+        assert_eq!(retried.solve_summary.retried, 4);
+        // The ladder climbs from 0 pivots until an attempt succeeds
+        assert!(retried.solve_summary.retry_attempts >= 4);
+        assert_eq!(retried.solve_summary.solved, 4);
+        assert_eq!(retried.values, baseline.values);
+    }
+
+    #[test]
+    fn test_game_properties_follow_the_options() {
+        let input = testing::random_input(2, &testing::RandomInputConfig::default());
+        input.game_properties(&ShapleyOptions::default()).unwrap();
+
+        let mut options = ShapleyOptions::default();
+        options.solver.max_iterations = Some(0);
+        options.failure_policy = FailurePolicy::Error;
+        assert!(matches!(
+            input.game_properties(&options).unwrap_err().root(),
+            ShapleyError::LpSolver(_)
+        ));
+    }
+
+    #[test]
+    fn test_identical_coalition_lps_are_solved_once() {
+        let private_links = vec![
+            PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 100.0, 1.0, None),
+            PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 100.0, 1.0, None),
+        ];
+        let mut devices = vec![
+            Device::new("NYC1".into(), 1, "Operator1".into()),
+            Device::new("LON1".into(), 1, "Operator1".into()),
+            Device::new("PAR1".into(), 1, "Operator2".into()),
+        ];
         let demands = vec![Demand::new(
-            "NYC".to_string(),
-            "PAR".to_string(),
+            "NYC".into(),
+            "PAR".into(),
             1,
             50.0,
             1.0,
             1,
             false,
         )];
+        let public_links = vec![PublicLink::new("NYC".into(), "PAR".into(), 100.0)];
+        let shapley = |devices| {
+            Shapley::new(
+                private_links.clone(),
+                devices,
+                demands.clone(),
+                public_links.clone(),
+                1.0,
+                5.0,
+                1.0,
+            )
+        };
+        let baseline = shapley(devices.clone()).compute_report().unwrap();
+        assert_eq!(baseline.solve_summary.deduplicated, 0);
 
-        let public_links = vec![PublicLink::new("NYC".to_string(), "PAR".to_string(), 100.0)];
+        // An operator without links leaves every coalition's LP as it was
+        devices.push(Device::new("CHI1".into(), 1, "Operator3".into()));
+        let idle = shapley(devices).compute_report().unwrap();
+        let summary = &idle.solve_summary;
+        assert_eq!(summary.solved, 8);
+        assert_eq!(summary.deduplicated, 4);
+        assert_eq!(summary.stats.iteration_histogram.values().sum::<usize>(), 4);
+        for (op, value) in &baseline.values {
+            assert_eq!(idle.values[op].value, value.value);
+        }
+        assert_eq!(idle.values["Operator3"].value, 0.0);
+    }
 
-        let shapley = Shapley::new(private_links, devices, demands, public_links, 1.0, 5.0, 1.0);
+    #[test]
+    fn test_contribution_means_are_shapley_values() {
+        let input = testing::random_input(11, &testing::RandomInputConfig::default());
+        let options = ShapleyOptions::default();
+        let values = input.compute_with(&options).unwrap();
+        let report = input.contribution_report(&options).unwrap();
+        assert_eq!(report.operators.len(), values.len());
+        for (op, stats) in &report.operators {
+            assert!((stats.mean - values[op].value).abs() < 1e-6);
+            assert_eq!(stats.by_size.len(), values.len());
+            assert!(stats.variance >= 0.0);
+        }
+    }
 
-        let result = shapley.compute();
-        assert!(result.is_ok(), "Error in test: {result:?}");
+    #[test]
+    fn test_auto_scale_reports_scalings() {
+        // Latencies in picoseconds rather than milliseconds
+        let mut input = testing::random_input(6, &testing::RandomInputConfig::default());
+        for link in &mut input.private_links {
+            link.latency *= 1e9;
+        }
+        for link in &mut input.public_links {
+            link.latency *= 1e9;
+        }
+        input.contiguity_bonus *= 1e9;
+        let plain = input.compute_report(&ShapleyOptions::default()).unwrap();
+        assert!(plain.solve_summary.scalings.is_empty());
 
-        let values = result.expect("Shapley computation should succeed in tests");
-        assert_eq!(values.len(), 2); // Two operators
+        let mut options = ShapleyOptions::default();
+        options.solver.auto_scale = true;
+        let scaled = input.compute_report(&options).unwrap();
+        let scalings = &scaled.solve_summary.scalings;
+        assert_eq!(scalings.len(), 1);
+        assert!(scalings.iter().all(|s| s.cost_exponent < 0));
+        for (op, value) in &plain.values {
+            let scaled = scaled.values[op].value;
+            assert!((scaled - value.value).abs() <= 1e-6 * value.value.abs().max(1e-9));
+        }
     }
 
     #[test]
-    fn test_compute_expected_values_simple() {
-        // Test with 2 operators, uptime = 0.9
-        let n_ops = 2;
-        let uptime = 0.9; // 0.9
+    fn test_traffic_floor_values_idle_demands() {
+        let mut input = testing::random_input(5, &testing::RandomInputConfig::default());
+        for demand in &mut input.demands {
+            demand.traffic = 0.0;
+        }
+        let idle = input.compute().unwrap();
+        assert!(idle.values().all(|v| v.value == 0.0));
 
-        // svalue for coalitions: {}, {B}, {A}, {A,B}
-        let svalue = vec![Some(100.0), Some(120.0), Some(150.0), Some(200.0)];
+        let mut options = ShapleyOptions {
+            traffic_floor_by_type: input.demands.iter().map(|d| (d.kind, 1.0)).collect(),
+            ..Default::default()
+        };
+        let presence = input.compute_with(&options).unwrap();
+        assert!(presence.values().any(|v| v.value != 0.0));
 
-        let evalue = compute_expected_values(&svalue, n_ops, uptime)
-            .expect("Expected value computation should succeed in tests");
+        options.traffic_floor_by_type.insert(1, -1.0);
+        assert!(input.compute_with(&options).is_err());
+    }
 
-        // These expected values are derived from running the reference Python code
-        // with the same inputs.
-        let expected_evalue = vec![100.0, 118.0, 145.0, 187.3];
+    #[test]
+    fn test_traffic_floor_applies_to_profiled_types() {
+        let mut input = testing::random_input(5, &testing::RandomInputConfig::default());
+        for demand in &mut input.demands {
+            demand.traffic = 0.0;
+        }
+        let kinds: BTreeSet<u32> = input.demands.iter().map(|d| d.kind).collect();
+        let floors: BTreeMap<u32, f64> = kinds.iter().map(|&k| (k, 1.0)).collect();
+        let floored = input
+            .compute_with(&ShapleyOptions {
+                traffic_floor_by_type: floors.clone(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(floored.values().any(|v| v.value != 0.0));
 
-        for (i, (val, exp)) in evalue.iter().zip(expected_evalue).enumerate() {
-            assert!(
-                (val - exp).abs() < 1e-9,
-                "Mismatch at index {i}: got {val}, expected {exp}",
-            );
+        // An idle profile keeps the floor's traffic in every slot
+        let profiled = input
+            .compute_with(&ShapleyOptions {
+                traffic_floor_by_type: floors,
+                demand_profiles: kinds
+                    .iter()
+                    .map(|&k| (k, DemandProfile::new(0.0, vec![0.0, 1.0])))
+                    .collect(),
+                ..Default::default()
+            })
+            .unwrap();
+        for (op, value) in &floored {
+            assert!((profiled[op].value - value.value).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_robust_values_worst_case_traffic() {
+        let input = testing::random_input(5, &testing::RandomInputConfig::default());
+        let mut ranged = input.clone();
+        for demand in &mut ranged.demands {
+            *demand = demand
+                .clone()
+                .with_traffic_range(0.5 * demand.traffic, 2.0 * demand.traffic);
+        }
+        // Ranges alone change nothing
+        let nominal = input.compute().unwrap();
+        let ignored = ranged.compute().unwrap();
+        for (op, value) in &nominal {
+            assert!((ignored[op].value - value.value).abs() < 1e-9);
+        }
+
+        let options = ShapleyOptions {
+            robust: true,
+            ..Default::default()
+        };
+        let robust = ranged.compute_with(&options).unwrap();
+        let mut worst = input.clone();
+        for demand in &mut worst.demands {
+            demand.traffic *= 2.0;
+        }
+        let expected = worst.compute().unwrap();
+        for (op, value) in &expected {
+            assert!((robust[op].value - value.value).abs() < 1e-6);
+        }
+
+        // A profile would overwrite the worst-case traffic
+        let kind = ranged.demands[0].kind;
+        let profiled = ShapleyOptions {
+            demand_profiles: BTreeMap::from([(kind, DemandProfile::new(1.0, vec![0.5, 1.0]))]),
+            ..options.clone()
+        };
+        assert!(matches!(
+            ranged.compute_with(&profiled).unwrap_err().root(),
+            ShapleyError::Validation(_)
+        ));
+
+        ranged.demands[0].traffic_high = Some(0.5 * ranged.demands[0].traffic);
+        assert!(ranged.compute_with(&options).is_err());
+    }
+
+    #[test]
+    fn test_compute_for_uptimes() {
+        let mut input = testing::random_input(11, &testing::RandomInputConfig::default());
+        let options = ShapleyOptions::default();
+        let sweep = input
+            .compute_for_uptimes(&options, &[0.995, 0.95, 0.98, 0.95])
+            .unwrap();
+        assert_eq!(sweep.uptimes().collect::<Vec<_>>(), vec![0.95, 0.98, 0.995]);
+        for uptime in [0.95, 0.98, 0.995] {
+            input.operator_uptime = uptime;
+            let values = input.compute_with(&options).unwrap();
+            for (op, value) in &values {
+                assert!((sweep.get(uptime).unwrap()[op].value - value.value).abs() < 1e-9);
+            }
         }
+        assert!(sweep.get(0.9).is_none());
+        assert!(input.compute_for_uptimes(&options, &[1.5]).is_err());
+        // Rejected here as by compute_with and ShapleyFromValues
+        assert!(input.compute_for_uptimes(&options, &[0.0]).is_err());
+    }
+
+    #[test]
+    fn test_contiguity_bonus_by_city_and_operator() {
+        let mut input = testing::random_input(7, &testing::RandomInputConfig::default());
+        let close = |a: &ShapleyOutput, b: &ShapleyOutput| {
+            a.iter().all(|(op, v)| (v.value - b[op].value).abs() < 1e-9)
+        };
+        let mut options = ShapleyOptions {
+            contiguity_bonus_by_operator: input
+                .devices
+                .iter()
+                .map(|d| (d.operator.clone(), 9.0))
+                .collect(),
+            ..Default::default()
+        };
+        let by_operator = input.compute_with(&options).unwrap();
+
+        // Cities take precedence over operators
+        options
+            .contiguity_bonus_by_operator
+            .values_mut()
+            .for_each(|b| *b = 2.0);
+        options.contiguity_bonus_by_city = input
+            .devices
+            .iter()
+            .map(|d| (crate::utils::city_of(&d.device).to_string(), 9.0))
+            .collect();
+        let by_city = input.compute_with(&options).unwrap();
+
+        input.contiguity_bonus = 9.0;
+        let uniform = input.compute().unwrap();
+        assert!(close(&by_operator, &uniform) && close(&by_city, &uniform));
+
+        options
+            .contiguity_bonus_by_city
+            .insert("XXX".into(), f64::NAN);
+        assert!(matches!(
+            input.compute_with(&options),
+            Err(ShapleyError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_label_normalization() {
+        let input = testing::random_input(3, &testing::RandomInputConfig::default());
+        let expected = input.compute().unwrap();
+
+        let mut messy = input.clone();
+        messy.demands[0].start = format!(" {} ", messy.demands[0].start.to_lowercase());
+        messy.private_links[0].device1 = messy.private_links[0].device1.to_lowercase();
+        messy.public_links[0].city2 = messy.public_links[0].city2.to_lowercase();
+        assert!(messy.compute().is_err());
+
+        let options = ShapleyOptions {
+            labels: LabelNormalization {
+                trim: true,
+                uppercase: true,
+            },
+            ..Default::default()
+        };
+        assert_eq!(messy.compute_with(&options).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_estimate_resources() {
+        let input = testing::random_input(9, &testing::RandomInputConfig::default());
+        let options = ShapleyOptions::default();
+        let estimate = input.estimate_resources(&options).unwrap();
+        let lp = crate::pipeline::build_lp_with(&input, &options).unwrap();
+        assert_eq!(estimate.n_coalitions, 8);
+        assert_eq!(estimate.n_scenarios, 1);
+        assert_eq!(estimate.lp_vars, lp.n_cols());
+        assert_eq!(estimate.lp_constraints, lp.b_eq.len() + lp.b_ub.len());
+        assert!(estimate.est_memory > 0 && estimate.est_time > Duration::ZERO);
+
+        // Each worker thread holds its own LP
+        let on_threads = |threads| {
+            let options = ShapleyOptions {
+                resources: ComputeResources::with_threads(threads),
+                ..Default::default()
+            };
+            input.estimate_resources(&options).unwrap()
+        };
+        let (one, four) = (on_threads(1), on_threads(4));
+        assert!(four.est_memory > one.est_memory && four.est_time <= one.est_time);
+    }
+
+    #[test]
+    fn test_from_values_allows_sixteen_operators_with_uptime() {
+        let operators: Vec<Operator> = (0..16).map(|i| format!("Op{i:02}")).collect();
+        let values = (0..1u32 << 16)
+            .map(|s| Some(s.count_ones() as f64))
+            .collect();
+        let output = ShapleyFromValues::new(operators, values, 0.9)
+            .compute()
+            .unwrap();
+        // Symmetric additive game: every operator adds its uptime
+        for value in output.values() {
+            assert!((value.value - 0.9).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_from_values_rejects_wrong_length() {
+        let result =
+            ShapleyFromValues::new(vec!["A".into(), "B".into()], vec![Some(0.0); 3], 1.0).compute();
+        assert!(matches!(result, Err(ShapleyError::DataInconsistency(_))));
+
+        let never_up =
+            ShapleyFromValues::new(vec!["A".into()], vec![Some(0.0), Some(1.0)], 0.0).compute();
+        assert!(matches!(never_up, Err(ShapleyError::Validation(_))));
     }
 }
@@ -0,0 +1,166 @@
+//! Seeded random inputs for smoke and load testing.
+//!
+//! [`random_input`] is stable: the same seed and configuration produce the
+//! same input in every release with the same major version, so a seed that
+//! exposes a problem can be replayed later.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    shapley::ShapleyInput,
+    types::{Demand, Device, PrivateLink, PublicLink},
+    utils::{SplitMix64, letter_code},
+};
+
+/// Size of the networks [`random_input`] draws
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RandomInputConfig {
+    /// Operators owning the devices. More than the exact limit (see
+    /// [`ShapleyOptions::approximation`](crate::options::ShapleyOptions::approximation))
+    /// only computes with sampling.
+    pub operators: usize,
+    /// Cities, at least 2; one device is placed per city before any city
+    /// gets a second
+    pub cities: usize,
+    /// Private links, at least 1
+    pub private_links: usize,
+    /// Demands, each of its own type
+    pub demands: usize,
+    pub operator_uptime: f64,
+}
+
+impl Default for RandomInputConfig {
+    fn default() -> Self {
+        Self {
+            operators: 3,
+            cities: 5,
+            private_links: 6,
+            demands: 3,
+            operator_uptime: 1.0,
+        }
+    }
+}
+
+/// A random input that passes validation: the cities are joined by a chain
+/// of public links, every operator owns at least one device, and private
+/// links join random distinct devices. Sizes below the minimums in
+/// [`RandomInputConfig`] are raised to them.
+pub fn random_input(seed: u64, config: &RandomInputConfig) -> ShapleyInput {
+    let mut rng = SplitMix64::new(seed);
+
+    let n_cities = config.cities.max(2);
+    let n_operators = config.operators.max(1);
+    let cities: Vec<String> = (0..n_cities).map(letter_code).collect();
+
+    // Each city gets a device before any gets a second, and each operator
+    // owns one before any owns a second
+    let n_devices = n_cities.max(n_operators);
+    let devices: Vec<Device> = (0..n_devices)
+        .map(|i| {
+            let operator = if i < n_operators {
+                i
+            } else {
+                below(&mut rng, n_operators)
+            };
+            Device::new(
+                format!("{}{}", cities[i % n_cities], i / n_cities + 1),
+                10 + below(&mut rng, 91) as u32,
+                format!("Op{operator:02}"),
+            )
+        })
+        .collect();
+
+    let private_links = (0..config.private_links.max(1))
+        .map(|_| {
+            let a = below(&mut rng, n_devices);
+            let b = (a + 1 + below(&mut rng, n_devices - 1)) % n_devices;
+            PrivateLink::new(
+                devices[a].device.clone(),
+                devices[b].device.clone(),
+                range(&mut rng, 5.0, 80.0),
+                range(&mut rng, 1.0, 20.0),
+                1.0,
+                None,
+            )
+        })
+        .collect();
+
+    let public_links = cities
+        .windows(2)
+        .map(|pair| {
+            PublicLink::new(
+                pair[0].clone(),
+                pair[1].clone(),
+                range(&mut rng, 20.0, 120.0),
+            )
+        })
+        .collect();
+
+    let demands = (0..config.demands)
+        .map(|kind| {
+            let start = below(&mut rng, n_cities);
+            let end = (start + 1 + below(&mut rng, n_cities - 1)) % n_cities;
+            Demand::new(
+                cities[start].clone(),
+                cities[end].clone(),
+                1,
+                range(&mut rng, 0.5, 10.0),
+                range(&mut rng, 0.5, 2.0),
+                kind as u32 + 1,
+                false,
+            )
+        })
+        .collect();
+
+    ShapleyInput {
+        private_links,
+        devices,
+        demands,
+        public_links,
+        operator_uptime: config.operator_uptime,
+        contiguity_bonus: range(&mut rng, 0.0, 10.0),
+        demand_multiplier: 1.0,
+    }
+}
+
+fn below(rng: &mut SplitMix64, n: usize) -> usize {
+    (rng.next_u64() % n as u64) as usize
+}
+
+/// Uniform in [lo, hi), rounded to one decimal
+fn range(rng: &mut SplitMix64, lo: f64, hi: f64) -> f64 {
+    ((lo + rng.next_f64() * (hi - lo)) * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_input_is_valid_and_stable() {
+        let config = RandomInputConfig::default();
+        for seed in 0..10 {
+            let input = random_input(seed, &config);
+            assert_eq!(input.private_links.len(), config.private_links);
+            assert_eq!(input.demands.len(), config.demands);
+            let values = input.compute().unwrap();
+            assert_eq!(values.len(), config.operators);
+
+            let again = random_input(seed, &config).compute().unwrap();
+            assert_eq!(values, again);
+        }
+
+        // Minimums are enforced
+        let tiny = RandomInputConfig {
+            operators: 0,
+            cities: 0,
+            private_links: 0,
+            demands: 1,
+            operator_uptime: 0.98,
+        };
+        assert!(random_input(7, &tiny).compute().is_ok());
+    }
+}
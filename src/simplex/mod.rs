@@ -16,6 +16,26 @@
 //!                   └── simplex::helpers   ← small sparse-vector utilities
 //! ```
 //!
+//! ## Why a simplex for every coalition
+//!
+//! Coalition LPs are small (one column per link and commodity) and there are
+//! 2^n of them, so per-solve setup dominates. A revised simplex has almost no
+//! setup beyond one LU factorisation, which is why it is used for every
+//! coalition regardless of size; there is no interior-point path to fall back
+//! from, and no Clarabel path left to benchmark against.
+//!
+//! Each coalition is solved cold, from its own compacted matrix. Warm starts
+//! happen only within a coalition, when lazy bandwidth rows are added through
+//! `Solver::add_constraint` and the dual simplex resumes from the
+//! current basis. Reusing a basis across coalitions is deliberately not done:
+//! a coalition's solution, down to the last bit, would then depend on which
+//! coalition was solved before it on the same worker, and batches are split
+//! by thread count, so `ShapleyOptions::deterministic` could no longer promise
+//! identical output across thread counts. Coalitions that differ by one
+//! operator also differ in both columns and bandwidth rows, so resuming would
+//! need a column-insertion entry point for the primal simplex on top of
+//! `add_constraint` for the dual simplex.
+//!
 //! The `#[allow(dead_code)]` annotations exist because this is a vendored library
 //! with internal infrastructure that isn't all reachable from our public API, but
 //! is required for the solver's correctness.
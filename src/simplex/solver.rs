@@ -65,6 +65,11 @@ pub(crate) struct Solver {
     /// Number of original (non-slack) decision variables.
     pub(crate) num_vars: usize,
     pub(crate) deadline: Deadline,
+    /// Optional cap on the number of pivots across both simplex phases;
+    /// reaching it returns `StopReason::Limit`.
+    pub(crate) max_iterations: Option<usize>,
+    /// Pivots performed so far.
+    pub(crate) iterations: usize,
 
     // ── Problem data (immutable after construction) ──────────────────────
     /// Objective function coefficients for all variables (original + slack).
@@ -394,6 +399,8 @@ impl Solver {
             orig_constraints_csc,
             orig_rhs,
             deadline,
+            max_iterations: None,
+            iterations: 0,
             orig_var_domains: var_domains.to_vec(),
             enable_primal_steepest_edge,
             enable_dual_steepest_edge,
@@ -695,6 +702,8 @@ impl Solver {
             orig_constraints_csc,
             orig_rhs,
             deadline,
+            max_iterations: None,
+            iterations: 0,
             orig_var_domains: var_domains.to_vec(),
             enable_primal_steepest_edge,
             enable_dual_steepest_edge,
@@ -768,6 +777,11 @@ impl Solver {
         Ok(StopReason::Finished)
     }
 
+    fn iteration_limit_reached(&self) -> bool {
+        self.max_iterations
+            .is_some_and(|max| self.iterations >= max)
+    }
+
     /// Primal simplex loop: repeatedly pick the best entering variable
     /// (choose_pivot) and perform the basis exchange (pivot) until no
     /// improving variable remains, meaning we've reached the optimum.
@@ -779,8 +793,13 @@ impl Solver {
                 }
             }
 
+            if self.iteration_limit_reached() {
+                return Ok(StopReason::Limit);
+            }
+
             if let Some(pivot_info) = self.choose_pivot()? {
                 self.pivot(&pivot_info)?;
+                self.iterations += 1;
             } else {
                 break;
             }
@@ -803,11 +822,16 @@ impl Solver {
                 }
             }
 
+            if self.iteration_limit_reached() {
+                return Ok(StopReason::Limit);
+            }
+
             if let Some((row, leaving_new_val)) = self.choose_pivot_row_dual() {
                 self.calc_row_coeffs(row);
                 let pivot_info = self.choose_entering_col_dual(row, leaving_new_val)?;
                 self.calc_col_coeffs(pivot_info.col);
                 self.pivot(&pivot_info)?;
+                self.iterations += 1;
             } else {
                 break;
             }
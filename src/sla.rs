@@ -0,0 +1,265 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::types::{ConsolidatedDemand, ConsolidatedLink, LinkProvenance};
+
+/// Flow below which a link is treated as unused when tracing paths
+const FLOW_EPSILON: f64 = 1e-9;
+
+/// Kind of network the paths of a demand cross
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Routing {
+    /// Private links only
+    Private,
+    /// Public internet only
+    #[default]
+    Public,
+    /// Both, on one path or across its paths
+    Hybrid,
+}
+
+/// How one demand is served in the grand coalition's optimal routing
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DemandSla {
+    pub start: String,
+    pub end: String,
+    /// Traffic type as given (`Demand::kind`)
+    pub kind: u32,
+    pub multicast: bool,
+    /// Traffic delivered to `end`: traffic times receivers
+    pub traffic: f64,
+    /// Traffic-weighted latency of the paths carrying the demand. Crossover
+    /// penalties are not latency and are left out.
+    pub latency: f64,
+    /// Latency of its slowest path
+    pub max_latency: f64,
+    pub routing: Routing,
+    /// Share of the demand's traffic on paths that use the public internet
+    pub public_share: f64,
+}
+
+/// Per-demand service levels of the grand coalition, see
+/// [`ShapleyInput::sla_report`](crate::shapley::ShapleyInput::sla_report).
+///
+/// Demands are listed after consolidation: rows of one type with the same
+/// destination and priority are merged. Paths are traced through each
+/// commodity's optimal flow, so a demand split over several routes reports
+/// their traffic-weighted latency.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SlaReport {
+    pub demands: Vec<DemandSla>,
+    /// Share of all delivered traffic on paths that use the public internet
+    pub public_share: f64,
+}
+
+impl SlaReport {
+    /// `flows` holds each commodity's flow on every link of `links`
+    pub(crate) fn new(
+        links: &[ConsolidatedLink],
+        demands: &[ConsolidatedDemand],
+        mut flows: BTreeMap<u32, Vec<f64>>,
+    ) -> Self {
+        let outgoing = outgoing_links(links);
+        let mut rows = Vec::with_capacity(demands.len());
+        let (mut total, mut public) = (0.0, 0.0);
+        for demand in demands {
+            let traffic = demand.traffic * demand.receivers as f64;
+            let residual = flows
+                .entry(demand.kind)
+                .or_insert_with(|| vec![0.0; links.len()]);
+
+            let (mut routed, mut latency_sum, mut max_latency, mut on_public) =
+                (0.0, 0.0, 0.0f64, 0.0);
+            let (mut uses_private, mut uses_public) = (false, false);
+            for (path, amount) in decompose(&outgoing, links, residual, demand, traffic) {
+                let (mut latency, mut private, mut public) = (0.0, false, false);
+                for &idx in &path {
+                    let link = &links[idx];
+                    match link.provenance {
+                        LinkProvenance::OriginalPrivate | LinkProvenance::Reverse => {
+                            latency += link.latency;
+                            private = true;
+                        }
+                        LinkProvenance::PublicBackbone => {
+                            latency += link.latency;
+                            public = true;
+                        }
+                        _ => {}
+                    }
+                }
+                routed += amount;
+                latency_sum += amount * latency;
+                max_latency = max_latency.max(latency);
+                uses_private |= private;
+                uses_public |= public;
+                if public {
+                    on_public += amount;
+                }
+            }
+
+            total += routed;
+            public += on_public;
+            rows.push(DemandSla {
+                start: demand.start.clone(),
+                end: demand.end.clone(),
+                kind: demand.original,
+                multicast: demand.multicast,
+                traffic,
+                latency: if routed > 0.0 {
+                    latency_sum / routed
+                } else {
+                    0.0
+                },
+                max_latency,
+                routing: match (uses_private, uses_public) {
+                    (true, true) => Routing::Hybrid,
+                    (true, false) => Routing::Private,
+                    _ => Routing::Public,
+                },
+                public_share: if routed > 0.0 {
+                    on_public / routed
+                } else {
+                    0.0
+                },
+            });
+        }
+
+        Self {
+            demands: rows,
+            public_share: if total > 0.0 { public / total } else { 0.0 },
+        }
+    }
+}
+
+/// Links by the device they leave
+pub(crate) fn outgoing_links(links: &[ConsolidatedLink]) -> HashMap<&str, Vec<usize>> {
+    let mut outgoing: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (idx, link) in links.iter().enumerate() {
+        outgoing.entry(link.device1.as_str()).or_default().push(idx);
+    }
+    outgoing
+}
+
+/// Split up to `traffic` of `demand` into paths through its commodity's flow
+/// `residual`, taking each path's share out of `residual`
+pub(crate) fn decompose(
+    outgoing: &HashMap<&str, Vec<usize>>,
+    links: &[ConsolidatedLink],
+    residual: &mut [f64],
+    demand: &ConsolidatedDemand,
+    traffic: f64,
+) -> Vec<(Vec<usize>, f64)> {
+    let mut paths = Vec::new();
+    let mut remaining = traffic;
+    while remaining > FLOW_EPSILON {
+        let Some(path) = trace(outgoing, links, residual, &demand.start, &demand.end) else {
+            break;
+        };
+        let amount = path
+            .iter()
+            .map(|&idx| residual[idx])
+            .fold(remaining, f64::min);
+        for &idx in &path {
+            residual[idx] -= amount;
+        }
+        remaining -= amount;
+        paths.push((path, amount));
+    }
+    paths
+}
+
+/// Fewest-hop path from `start` to `end` over links with flow left
+fn trace(
+    outgoing: &HashMap<&str, Vec<usize>>,
+    links: &[ConsolidatedLink],
+    residual: &[f64],
+    start: &str,
+    end: &str,
+) -> Option<Vec<usize>> {
+    let mut via: HashMap<&str, usize> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        if node == end {
+            let mut path = Vec::new();
+            let mut node = end;
+            while node != start {
+                let idx = via[node];
+                path.push(idx);
+                node = links[idx].device1.as_str();
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &idx in outgoing.get(node).into_iter().flatten() {
+            let next = links[idx].device2.as_str();
+            if residual[idx] > FLOW_EPSILON && next != start && !via.contains_key(next) {
+                via.insert(next, idx);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(
+        device1: &str,
+        device2: &str,
+        latency: f64,
+        provenance: LinkProvenance,
+    ) -> ConsolidatedLink {
+        ConsolidatedLink {
+            device1: device1.to_string(),
+            device2: device2.to_string(),
+            latency,
+            bandwidth: 0.0,
+            operator1: "Op".to_string(),
+            operator2: "Op".to_string(),
+            shared: 0,
+            link_type: 0,
+            provenance,
+            multicast_capable: false,
+        }
+    }
+
+    #[test]
+    fn test_split_demand_reports_weighted_latency() {
+        let links = vec![
+            link("AAA", "AAA1", 0.0, LinkProvenance::OnRamp),
+            link("AAA1", "BBB1", 10.0, LinkProvenance::OriginalPrivate),
+            link("BBB1", "BBB", 0.0, LinkProvenance::OffRamp),
+            link("AAA", "AAA00", 0.0, LinkProvenance::OnRamp),
+            link("AAA00", "BBB00", 30.0, LinkProvenance::PublicBackbone),
+            link("BBB00", "BBB", 0.0, LinkProvenance::OffRamp),
+        ];
+        let demands = vec![ConsolidatedDemand {
+            start: "AAA".to_string(),
+            end: "BBB".to_string(),
+            receivers: 2,
+            traffic: 2.0,
+            priority: 1.0,
+            kind: 1,
+            multicast: false,
+            original: 1,
+        }];
+        // Three units over the private link, one over the internet
+        let flows = BTreeMap::from([(1, vec![3.0, 3.0, 3.0, 1.0, 1.0, 1.0])]);
+
+        let report = SlaReport::new(&links, &demands, flows);
+        let sla = &report.demands[0];
+        assert_eq!(sla.traffic, 4.0);
+        assert_eq!(sla.latency, 15.0);
+        assert_eq!(sla.max_latency, 30.0);
+        assert_eq!(sla.routing, Routing::Hybrid);
+        assert_eq!(sla.public_share, 0.25);
+        assert_eq!(report.public_share, 0.25);
+    }
+}
@@ -0,0 +1,449 @@
+//! Published epochs as self-checking binary snapshots.
+//!
+//! A [`Snapshot`] bundles the input of an epoch and the options it was
+//! computed with with the coalition values its LPs produced and the resulting
+//! Shapley values, borsh-encoded behind a
+//! format version, together with a SHA-256 hash of the contents. Checking an
+//! old epoch does not need the LPs again: [`Snapshot::verify`] recomputes the
+//! hash and the Shapley stage from the stored coalition values.
+//!
+//! The input tables are encoded with every field. The input types' own borsh
+//! encoding keeps to their original fields, so snapshots go through records
+//! of their own.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    core_math,
+    error::{Result, ShapleyError},
+    options::ShapleyOptions,
+    shapley::{Operator, ShapleyInput, ShapleyOutput, to_output},
+    types::{
+        BandwidthUnit, Demand, Demands, Device, Devices, PrivateLink, PrivateLinks, PublicLinks,
+    },
+};
+
+/// Format version written by this crate
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// What a snapshot records, in encoding order
+#[derive(Debug, Clone)]
+pub struct SnapshotContents {
+    pub version: u32,
+    /// Caller-defined epoch number
+    pub epoch: u64,
+    pub private_links: PrivateLinks,
+    pub devices: Devices,
+    pub demands: Demands,
+    pub public_links: PublicLinks,
+    pub operator_uptime: f64,
+    pub contiguity_bonus: f64,
+    pub demand_multiplier: f64,
+    /// The options the coalition values were computed with, as JSON. Options
+    /// without a serialized form (`cost_model`, the cancellation token,
+    /// progress counter and LP cache) are left out, as are the
+    /// `resources`, which do not change the values.
+    pub options: String,
+    /// Operators in bit order of `coalition_values`
+    pub operators: Vec<Operator>,
+    /// Uptime-adjusted value of each coalition, indexed by bitmask
+    pub coalition_values: Vec<f64>,
+    pub results: ShapleyOutput,
+}
+
+impl BorshSerialize for SnapshotContents {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        ContentsRecord::from(self).serialize(writer)
+    }
+}
+
+impl BorshDeserialize for SnapshotContents {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        ContentsRecord::deserialize_reader(reader).map(Self::from)
+    }
+}
+
+/// Snapshot contents with their integrity hash
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct Snapshot {
+    pub contents: SnapshotContents,
+    /// SHA-256 of the borsh encoding of `contents`
+    pub hash: [u8; 32],
+}
+
+impl Snapshot {
+    pub fn new(contents: SnapshotContents) -> Result<Self> {
+        let hash = hash(&contents)?;
+        Ok(Self { contents, hash })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        borsh::to_vec(self).map_err(|e| ShapleyError::Io(format!("Snapshot encoding: {e}")))
+    }
+
+    /// Decode a snapshot, rejecting versions this crate does not know. The
+    /// hash is not checked here; see [`verify`](Self::verify).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let version = bytes
+            .first_chunk()
+            .map(|b| u32::from_le_bytes(*b))
+            .ok_or_else(|| ShapleyError::Io("Snapshot is truncated".to_string()))?;
+        if version == 0 || version > SNAPSHOT_VERSION {
+            return Err(ShapleyError::Validation(format!(
+                "Snapshot version {version} is not supported, expected at most {SNAPSHOT_VERSION}"
+            )));
+        }
+        borsh::from_slice(bytes).map_err(|e| ShapleyError::Io(format!("Snapshot decoding: {e}")))
+    }
+
+    /// Check the hash, then recompute the Shapley values from the stored
+    /// coalition values and compare them with the stored results
+    pub fn verify(&self) -> Result<()> {
+        if hash(&self.contents)? != self.hash {
+            return Err(ShapleyError::DataInconsistency(format!(
+                "Snapshot of epoch {} does not match its hash",
+                self.contents.epoch
+            )));
+        }
+
+        let contents = &self.contents;
+        let n_operators = contents.operators.len();
+        let expected = match n_operators {
+            0 => 0,
+            n => 1usize.checked_shl(n as u32).unwrap_or(0),
+        };
+        if contents.coalition_values.len() != expected {
+            return Err(ShapleyError::DataInconsistency(format!(
+                "Snapshot holds {} coalition values for {n_operators} operators",
+                contents.coalition_values.len()
+            )));
+        }
+        let recomputed = to_output(
+            contents.operators.iter().cloned(),
+            core_math::shapley_values(&contents.coalition_values, n_operators),
+        );
+        if recomputed != contents.results {
+            return Err(ShapleyError::DataInconsistency(format!(
+                "Snapshot of epoch {} records results its coalition values do not give",
+                contents.epoch
+            )));
+        }
+        Ok(())
+    }
+
+    /// The input the snapshot was taken of
+    pub fn input(&self) -> ShapleyInput {
+        let contents = &self.contents;
+        ShapleyInput {
+            private_links: contents.private_links.clone(),
+            devices: contents.devices.clone(),
+            demands: contents.demands.clone(),
+            public_links: contents.public_links.clone(),
+            operator_uptime: contents.operator_uptime,
+            contiguity_bonus: contents.contiguity_bonus,
+            demand_multiplier: contents.demand_multiplier,
+        }
+    }
+
+    /// The options the snapshot was taken with, less those it does not
+    /// record (see [`SnapshotContents::options`])
+    pub fn options(&self) -> Result<ShapleyOptions> {
+        serde_json::from_str(&self.contents.options)
+            .map_err(|e| ShapleyError::Io(format!("Snapshot options: {e}")))
+    }
+}
+
+impl ShapleyInput {
+    /// Compute the Shapley values under `options` and record them with this
+    /// input, the options and the coalition values as a [`Snapshot`] of
+    /// `epoch`
+    pub fn snapshot(&self, epoch: u64, options: &ShapleyOptions) -> Result<Snapshot> {
+        let recorded = serde_json::to_string(&ShapleyOptions {
+            resources: Default::default(),
+            ..options.clone()
+        })
+        .map_err(|e| ShapleyError::Io(format!("Snapshot options: {e}")))?;
+        let (operators, coalition_values) = self.game_values(options)?;
+        let results = to_output(
+            operators.iter().cloned(),
+            core_math::shapley_values(&coalition_values, operators.len()),
+        );
+        Snapshot::new(SnapshotContents {
+            version: SNAPSHOT_VERSION,
+            epoch,
+            private_links: self.private_links.clone(),
+            devices: self.devices.clone(),
+            demands: self.demands.clone(),
+            public_links: self.public_links.clone(),
+            operator_uptime: self.operator_uptime,
+            contiguity_bonus: self.contiguity_bonus,
+            demand_multiplier: self.demand_multiplier,
+            options: recorded,
+            operators,
+            coalition_values,
+            results,
+        })
+    }
+}
+
+fn hash(contents: &SnapshotContents) -> Result<[u8; 32]> {
+    let bytes =
+        borsh::to_vec(contents).map_err(|e| ShapleyError::Io(format!("Snapshot encoding: {e}")))?;
+    Ok(Sha256::digest(bytes).into())
+}
+
+/// [`SnapshotContents`] as encoded, with every field of the input tables
+#[derive(BorshDeserialize, BorshSerialize)]
+struct ContentsRecord {
+    version: u32,
+    epoch: u64,
+    private_links: Vec<PrivateLinkRecord>,
+    devices: Vec<DeviceRecord>,
+    demands: Vec<DemandRecord>,
+    public_links: PublicLinks,
+    operator_uptime: f64,
+    contiguity_bonus: f64,
+    demand_multiplier: f64,
+    options: String,
+    operators: Vec<Operator>,
+    coalition_values: Vec<f64>,
+    results: ShapleyOutput,
+}
+
+impl From<&SnapshotContents> for ContentsRecord {
+    fn from(c: &SnapshotContents) -> Self {
+        Self {
+            version: c.version,
+            epoch: c.epoch,
+            private_links: c
+                .private_links
+                .iter()
+                .map(PrivateLinkRecord::from)
+                .collect(),
+            devices: c.devices.iter().map(DeviceRecord::from).collect(),
+            demands: c.demands.iter().map(DemandRecord::from).collect(),
+            public_links: c.public_links.clone(),
+            operator_uptime: c.operator_uptime,
+            contiguity_bonus: c.contiguity_bonus,
+            demand_multiplier: c.demand_multiplier,
+            options: c.options.clone(),
+            operators: c.operators.clone(),
+            coalition_values: c.coalition_values.clone(),
+            results: c.results.clone(),
+        }
+    }
+}
+
+impl From<ContentsRecord> for SnapshotContents {
+    fn from(c: ContentsRecord) -> Self {
+        Self {
+            version: c.version,
+            epoch: c.epoch,
+            private_links: c.private_links.into_iter().map(PrivateLink::from).collect(),
+            devices: c.devices.into_iter().map(Device::from).collect(),
+            demands: c.demands.into_iter().map(Demand::from).collect(),
+            public_links: c.public_links,
+            operator_uptime: c.operator_uptime,
+            contiguity_bonus: c.contiguity_bonus,
+            demand_multiplier: c.demand_multiplier,
+            options: c.options,
+            operators: c.operators,
+            coalition_values: c.coalition_values,
+            results: c.results,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+struct DeviceRecord {
+    device: String,
+    edge: u32,
+    operator: String,
+    multicast_capable: Option<bool>,
+}
+
+impl From<&Device> for DeviceRecord {
+    fn from(d: &Device) -> Self {
+        Self {
+            device: d.device.clone(),
+            edge: d.edge,
+            operator: d.operator.clone(),
+            multicast_capable: d.multicast_capable,
+        }
+    }
+}
+
+impl From<DeviceRecord> for Device {
+    fn from(d: DeviceRecord) -> Self {
+        Self {
+            device: d.device,
+            edge: d.edge,
+            operator: d.operator,
+            multicast_capable: d.multicast_capable,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+struct PrivateLinkRecord {
+    device1: String,
+    device2: String,
+    latency: f64,
+    bandwidth: f64,
+    uptime: f64,
+    shared: Option<u32>,
+    bundle: Option<u32>,
+    srlg: Option<u32>,
+    unit: Option<BandwidthUnit>,
+    measured_bandwidth: Option<f64>,
+    latency_p50: Option<f64>,
+    latency_p95: Option<f64>,
+    latency_p99: Option<f64>,
+    multicast_capable: Option<bool>,
+}
+
+impl From<&PrivateLink> for PrivateLinkRecord {
+    fn from(l: &PrivateLink) -> Self {
+        Self {
+            device1: l.device1.clone(),
+            device2: l.device2.clone(),
+            latency: l.latency,
+            bandwidth: l.bandwidth,
+            uptime: l.uptime,
+            shared: l.shared,
+            bundle: l.bundle,
+            srlg: l.srlg,
+            unit: l.unit,
+            measured_bandwidth: l.measured_bandwidth,
+            latency_p50: l.latency_p50,
+            latency_p95: l.latency_p95,
+            latency_p99: l.latency_p99,
+            multicast_capable: l.multicast_capable,
+        }
+    }
+}
+
+impl From<PrivateLinkRecord> for PrivateLink {
+    fn from(l: PrivateLinkRecord) -> Self {
+        Self {
+            device1: l.device1,
+            device2: l.device2,
+            latency: l.latency,
+            bandwidth: l.bandwidth,
+            uptime: l.uptime,
+            shared: l.shared,
+            bundle: l.bundle,
+            srlg: l.srlg,
+            unit: l.unit,
+            measured_bandwidth: l.measured_bandwidth,
+            latency_p50: l.latency_p50,
+            latency_p95: l.latency_p95,
+            latency_p99: l.latency_p99,
+            multicast_capable: l.multicast_capable,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+struct DemandRecord {
+    start: String,
+    end: String,
+    receivers: u32,
+    traffic: f64,
+    priority: f64,
+    kind: u32,
+    multicast: bool,
+    unit: Option<BandwidthUnit>,
+    class: Option<u32>,
+    traffic_low: Option<f64>,
+    traffic_high: Option<f64>,
+}
+
+impl From<&Demand> for DemandRecord {
+    fn from(d: &Demand) -> Self {
+        Self {
+            start: d.start.clone(),
+            end: d.end.clone(),
+            receivers: d.receivers,
+            traffic: d.traffic,
+            priority: d.priority,
+            kind: d.kind,
+            multicast: d.multicast,
+            unit: d.unit,
+            class: d.class,
+            traffic_low: d.traffic_low,
+            traffic_high: d.traffic_high,
+        }
+    }
+}
+
+impl From<DemandRecord> for Demand {
+    fn from(d: DemandRecord) -> Self {
+        Self {
+            start: d.start,
+            end: d.end,
+            receivers: d.receivers,
+            traffic: d.traffic,
+            priority: d.priority,
+            kind: d.kind,
+            multicast: d.multicast,
+            unit: d.unit,
+            class: d.class,
+            traffic_low: d.traffic_low,
+            traffic_high: d.traffic_high,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapley::testing::{RandomInputConfig, random_input};
+
+    #[test]
+    fn test_snapshot_round_trip_and_verify() {
+        let mut input = random_input(2, &RandomInputConfig::default());
+        input.private_links[0].latency_p95 = Some(99.0);
+        let options = ShapleyOptions {
+            demand_multiplier_by_type: [(1, 2.0)].into(),
+            ..Default::default()
+        };
+        let snapshot = input.snapshot(42, &options).unwrap();
+        assert_eq!(
+            snapshot.contents.results,
+            input.compute_with(&options).unwrap()
+        );
+        // The options are part of what the hash covers
+        let default = input.snapshot(42, &ShapleyOptions::default()).unwrap();
+        assert_ne!(default.hash, snapshot.hash);
+
+        let decoded = Snapshot::from_bytes(&snapshot.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.hash, snapshot.hash);
+        decoded.verify().unwrap();
+        // Fields the input types' own encoding leaves out are kept
+        assert_eq!(decoded.contents.private_links[0].latency_p95, Some(99.0));
+        let recorded = decoded.options().unwrap();
+        assert_eq!(recorded.demand_multiplier_by_type, [(1, 2.0)].into());
+        assert_eq!(
+            decoded.input().compute_with(&recorded).unwrap(),
+            snapshot.contents.results
+        );
+
+        // Tampering with a coalition value breaks the hash, and rehashing
+        // does not hide it
+        let mut tampered = decoded.clone();
+        let last = tampered.contents.coalition_values.len() - 1;
+        tampered.contents.coalition_values[last] += 1.0;
+        assert!(tampered.verify().is_err());
+        let rehashed = Snapshot::new(tampered.contents).unwrap();
+        assert!(matches!(
+            rehashed.verify(),
+            Err(ShapleyError::DataInconsistency(_))
+        ));
+
+        let mut future = snapshot.to_bytes().unwrap();
+        future[..4].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_le_bytes());
+        assert!(Snapshot::from_bytes(&future).is_err());
+    }
+}
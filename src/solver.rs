@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use microlp::{ComparisonOp, StopReason, VarDomain};
 #[cfg(test)]
 use microlp::{OptimizationDirection, Variable};
@@ -6,9 +8,16 @@ use sprs::TriMatI;
 use crate::{
     error::{Result, ShapleyError},
     lp_builder::LpPrimitives,
+    options::SolverOptions,
+    shapley::LpScaling,
+    simplex::solver::CsVec,
     sparse::CscMatrix,
 };
 
+/// Relative overrun of a pending bandwidth row, beyond solver tolerances,
+/// that adds it to a lazily constrained LP
+const LAZY_TOLERANCE: f64 = 1e-9;
+
 /// Pre-computed row-oriented representation of the LP constraint matrices.
 /// Built once from the full primitives, then reused for every coalition.
 pub(crate) struct PrecomputedRows {
@@ -16,28 +25,116 @@ pub(crate) struct PrecomputedRows {
     eq_rows: Vec<Vec<(usize, f64)>>,
     /// Inequality constraint rows: each entry is (original_col_index, coefficient)
     ub_rows: Vec<Vec<(usize, f64)>>,
+    /// Applied when solving with `SolverOptions::auto_scale`
+    pub scaling: LpScaling,
+    /// Applied when solving with `SolverOptions::geometric_scaling`
+    geometric: GeometricScaling,
+}
+
+/// Power-of-two factors for every row and column of an LP that bring its
+/// coefficients, costs included, close to one; see [`GeometricScaling::new`]
+struct GeometricScaling {
+    eq_rows: Vec<f64>,
+    ub_rows: Vec<f64>,
+    cols: Vec<f64>,
+}
+
+impl GeometricScaling {
+    /// Passes over rows then columns; each pass divides a row or column by
+    /// the geometric mean of its smallest and largest scaled magnitude,
+    /// rounded to a power of two so that scaling and unscaling are exact
+    const PASSES: usize = 4;
+
+    fn new(
+        primitives: &LpPrimitives,
+        eq_rows: &[Vec<(usize, f64)>],
+        ub_rows: &[Vec<(usize, f64)>],
+    ) -> Self {
+        let mut scaling = Self {
+            eq_rows: vec![1.0; eq_rows.len()],
+            ub_rows: vec![1.0; ub_rows.len()],
+            cols: vec![1.0; primitives.cost.len()],
+        };
+        for _ in 0..Self::PASSES {
+            for (rows, factors) in [
+                (eq_rows, &mut scaling.eq_rows),
+                (ub_rows, &mut scaling.ub_rows),
+            ] {
+                for (entries, factor) in rows.iter().zip(factors.iter_mut()) {
+                    *factor = centring_power_of_two(
+                        entries.iter().map(|&(col, val)| val * scaling.cols[col]),
+                    );
+                }
+            }
+            for (col, factor) in scaling.cols.iter_mut().enumerate() {
+                let column = |matrix: &CscMatrix<f64>, rows: &[f64]| {
+                    (matrix.colptr[col]..matrix.colptr[col + 1])
+                        .map(|idx| matrix.nzval[idx] * rows[matrix.rowval[idx]])
+                        .collect::<Vec<_>>()
+                };
+                *factor = centring_power_of_two(
+                    column(&primitives.a_eq, &scaling.eq_rows)
+                        .into_iter()
+                        .chain(column(&primitives.a_ub, &scaling.ub_rows))
+                        .chain([primitives.cost[col]]),
+                );
+            }
+        }
+        scaling
+    }
+}
+
+/// The power of two that centres the nonzero magnitudes of `values` on one
+fn centring_power_of_two(values: impl Iterator<Item = f64>) -> f64 {
+    let (min, max) = values
+        .map(f64::abs)
+        .filter(|v| *v > 0.0 && v.is_finite())
+        .fold((f64::INFINITY, 0.0f64), |(lo, hi), v| {
+            (lo.min(v), hi.max(v))
+        });
+    if max == 0.0 {
+        return 1.0;
+    }
+    2f64.powi(-((min.log2() + max.log2()) / 2.0).round() as i32)
 }
 
 impl PrecomputedRows {
     /// Build from the full (unfiltered) LP primitives. Call once before the coalition loop.
     pub(crate) fn new(primitives: &LpPrimitives) -> Self {
+        let eq_rows = rows_from_csc(&primitives.a_eq);
+        let ub_rows = rows_from_csc(&primitives.a_ub);
         Self {
-            eq_rows: rows_from_csc(&primitives.a_eq),
-            ub_rows: rows_from_csc(&primitives.a_ub),
+            scaling: LpScaling::of(
+                &primitives.cost,
+                &[primitives.b_eq.as_slice(), &primitives.b_ub].concat(),
+            ),
+            geometric: GeometricScaling::new(primitives, &eq_rows, &ub_rows),
+            eq_rows,
+            ub_rows,
         }
     }
 }
 
-/// Reusable per-thread buffers for coalition LP construction.
-pub(crate) struct CoalitionBuffers {
-    pub col_remap: Vec<usize>,
-    pub cost: Vec<f64>,
-    pub keep_rows: Vec<usize>,
-    pub var_mins: Vec<f64>,
-    pub var_maxs: Vec<f64>,
-    pub var_domains: Vec<VarDomain>,
-    pub ops: Vec<ComparisonOp>,
-    pub rhs: Vec<f64>,
+/// Reusable per-thread buffers for coalition LP construction; one per worker,
+/// sized by [`CoalitionLp::n_cols`](crate::batch::CoalitionLp::n_cols).
+pub struct CoalitionBuffers {
+    pub(crate) col_remap: Vec<usize>,
+    pub(crate) cost: Vec<f64>,
+    pub(crate) keep_rows: Vec<usize>,
+    /// Bandwidth rows of the coalition left out of its LP until its flows
+    /// violate them, under `SolverOptions::lazy_bandwidth`
+    pub(crate) pending_rows: Vec<usize>,
+    pub(crate) var_mins: Vec<f64>,
+    pub(crate) var_maxs: Vec<f64>,
+    pub(crate) var_domains: Vec<VarDomain>,
+    pub(crate) ops: Vec<ComparisonOp>,
+    pub(crate) rhs: Vec<f64>,
+    /// When `Some`, a solved coalition writes its optimal value for every
+    /// original column here (zero for columns outside the coalition).
+    pub(crate) primal: Option<Vec<f64>>,
+    /// When `Some`, capacity already taken on each bandwidth row by the flows
+    /// of higher priority classes, subtracted from the row's limit
+    pub(crate) reserved: Option<Vec<f64>>,
 }
 
 impl CoalitionBuffers {
@@ -46,18 +143,22 @@ impl CoalitionBuffers {
             col_remap: vec![usize::MAX; n_cols],
             cost: Vec::with_capacity(n_cols),
             keep_rows: Vec::with_capacity(256),
+            pending_rows: Vec::new(),
             var_mins: Vec::with_capacity(n_cols),
             var_maxs: Vec::with_capacity(n_cols),
             var_domains: Vec::with_capacity(n_cols),
             ops: Vec::with_capacity(1024),
             rhs: Vec::with_capacity(1024),
+            primal: None,
+            reserved: None,
         }
     }
 
-    pub fn reset(&mut self) {
+    pub(crate) fn reset(&mut self) {
         self.col_remap.fill(usize::MAX);
         self.cost.clear();
         self.keep_rows.clear();
+        self.pending_rows.clear();
         self.var_mins.clear();
         self.var_maxs.clear();
         self.var_domains.clear();
@@ -68,9 +169,11 @@ impl CoalitionBuffers {
 
 /// Solver termination status
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(crate) enum SolveStatus {
+pub enum SolveStatus {
     Solved,
     Infeasible,
+    /// Iteration or time limit reached before optimality
+    LimitReached,
 }
 
 /// LP solver wrapper for microlp (used in tests)
@@ -157,9 +260,20 @@ impl LpSolver {
 }
 
 /// Solve result from the coalition solver.
-pub(crate) struct CoalitionResult {
+#[derive(Debug, Clone)]
+pub struct CoalitionResult {
     pub status: SolveStatus,
     pub objective_value: f64,
+    /// Objective split by column group, for LPs solved with groups
+    /// (see `CoalitionLp::col_group`); empty otherwise
+    pub group_objectives: Vec<f64>,
+    /// Objective of each priority class, for LPs routed class by class (see
+    /// `CoalitionLp::residual`); empty otherwise
+    pub class_objectives: Vec<f64>,
+    /// Simplex pivots, summed over priority classes
+    pub iterations: usize,
+    /// Wall time of building and solving the LP, summed over priority classes
+    pub solve_time: Duration,
 }
 
 /// Create and solve an LP for a specific coalition using pre-computed
@@ -177,8 +291,19 @@ pub(crate) fn solve_coalition(
     col_op2_mask: &[u32],
     row_op1_mask: &[u32],
     row_op2_mask: &[u32],
+    options: &SolverOptions,
 ) -> Result<CoalitionResult> {
+    let started = web_time::Instant::now();
     let n_cols = col_op1_mask.len();
+    let scaling = if options.auto_scale {
+        precomputed.scaling
+    } else {
+        LpScaling::default()
+    };
+    let (cost_factor, rhs_factor) = (scaling.cost_factor(), scaling.rhs_factor());
+    // Column j's variable is its flow divided by col_scale(j)
+    let geometric = options.geometric_scaling.then_some(&precomputed.geometric);
+    let col_scale = |col: usize| geometric.map_or(1.0, |g| g.cols[col]);
 
     buffers.reset();
 
@@ -193,7 +318,9 @@ pub(crate) fn solve_coalition(
     for i in 0..n_cols {
         if (col_op1_mask[i] & coalition_mask) != 0 && (col_op2_mask[i] & coalition_mask) != 0 {
             buffers.col_remap[i] = new_col;
-            buffers.cost.push(primitives.cost[i]);
+            buffers
+                .cost
+                .push(primitives.cost[i] * col_scale(i) * cost_factor);
             new_col += 1;
         }
     }
@@ -204,10 +331,15 @@ pub(crate) fn solve_coalition(
         ));
     }
 
-    // Step 2: Compute keep_rows for A_ub
+    // Step 2: Compute keep_rows for A_ub. Under lazy bandwidth constraints
+    // the bandwidth rows wait in pending_rows; multicast rows are always kept.
     for i in 0..row_op1_mask.len() {
         if (row_op1_mask[i] & coalition_mask) != 0 && (row_op2_mask[i] & coalition_mask) != 0 {
-            buffers.keep_rows.push(i);
+            if options.lazy_bandwidth.is_some() && i < primitives.bandwidth_rows {
+                buffers.pending_rows.push(i);
+            } else {
+                buffers.keep_rows.push(i);
+            }
         }
     }
 
@@ -223,30 +355,63 @@ pub(crate) fn solve_coalition(
     let mut triplets = TriMatI::<f64, usize>::new((n_total_rows, n_kept));
     let mut row = 0;
 
+    // Optional row equilibration: divide each row by its largest kept
+    // coefficient, in place of its geometric factor `base`
+    let row_scale = |entries: &[(usize, f64)], base: f64| -> f64 {
+        if !options.equilibrate {
+            return base;
+        }
+        let max_abs = entries
+            .iter()
+            .filter(|&&(old_col, _)| buffers.col_remap[old_col] != usize::MAX)
+            .fold(0.0f64, |acc, &(old_col, val)| {
+                acc.max((val * col_scale(old_col)).abs())
+            });
+        if max_abs > 0.0 { 1.0 / max_abs } else { base }
+    };
+
+    let ub_limit = |row_idx: usize| -> f64 {
+        let limit = primitives.b_ub[row_idx];
+        // A congestion breakpoint's threshold counts its bandwidth row's load
+        let load_row = primitives
+            .congestion_row(row_idx)
+            .map_or(row_idx, |congestion| congestion.group);
+        match buffers.reserved.as_ref().and_then(|r| r.get(load_row)) {
+            // Clamped, so rounding in the fixed flows cannot make the row infeasible
+            Some(&taken) => (limit - taken).max(0.0),
+            None => limit,
+        }
+    };
+
     // Equality constraints — all rows, remap columns
     for (row_idx, entries) in precomputed.eq_rows.iter().enumerate() {
+        let scale = row_scale(entries, geometric.map_or(1.0, |g| g.eq_rows[row_idx]));
         for &(old_col, val) in entries {
             let nc = buffers.col_remap[old_col];
             if nc != usize::MAX {
-                triplets.add_triplet(row, nc, val);
+                triplets.add_triplet(row, nc, val * col_scale(old_col) * scale);
             }
         }
         buffers.ops.push(ComparisonOp::Eq);
-        buffers.rhs.push(primitives.b_eq[row_idx]);
+        buffers
+            .rhs
+            .push(primitives.b_eq[row_idx] * rhs_factor * scale);
         row += 1;
     }
 
     // Inequality constraints — only kept rows, remap columns
     for keep_idx in 0..n_ub_rows {
         let row_idx = buffers.keep_rows[keep_idx];
-        for &(old_col, val) in &precomputed.ub_rows[row_idx] {
+        let entries = &precomputed.ub_rows[row_idx];
+        let scale = row_scale(entries, geometric.map_or(1.0, |g| g.ub_rows[row_idx]));
+        for &(old_col, val) in entries {
             let nc = buffers.col_remap[old_col];
             if nc != usize::MAX {
-                triplets.add_triplet(row, nc, val);
+                triplets.add_triplet(row, nc, val * col_scale(old_col) * scale);
             }
         }
         buffers.ops.push(ComparisonOp::Le);
-        buffers.rhs.push(primitives.b_ub[row_idx]);
+        buffers.rhs.push(ub_limit(row_idx) * rhs_factor * scale);
         row += 1;
     }
 
@@ -266,28 +431,137 @@ pub(crate) fn solve_coalition(
         &buffers.ops,
         &buffers.rhs,
         &buffers.var_domains,
-        None,
-    );
+        options
+            .time_limit
+            .map(|limit| web_time::Instant::now() + limit),
+    )
+    .map(|mut solver| {
+        solver.max_iterations = options.max_iterations;
+        solver
+    });
 
     match solver_result {
-        Ok(mut solver) => match solver.initial_solve() {
-            Ok(StopReason::Finished) => Ok(CoalitionResult {
-                status: SolveStatus::Solved,
-                objective_value: solver.cur_obj_val,
-            }),
-            Ok(StopReason::Limit) => Ok(CoalitionResult {
-                status: SolveStatus::Solved,
-                objective_value: solver.cur_obj_val,
-            }),
-            Err(microlp::Error::Infeasible) => Ok(CoalitionResult {
-                status: SolveStatus::Infeasible,
-                objective_value: 0.0,
-            }),
-            Err(e) => Err(ShapleyError::LpSolver(format!("LP solver error: {e}"))),
-        },
+        Ok(mut solver) => {
+            let mut stop = solver.initial_solve();
+            let mut rounds = 0;
+            while let (Some(lazy), Ok(StopReason::Finished)) = (options.lazy_bandwidth, &stop) {
+                // Pending rows the current flows overrun
+                let violated: Vec<usize> = buffers
+                    .pending_rows
+                    .iter()
+                    .copied()
+                    .filter(|&row_idx| {
+                        let activity: f64 = precomputed.ub_rows[row_idx]
+                            .iter()
+                            .filter(|&&(old_col, _)| buffers.col_remap[old_col] != usize::MAX)
+                            .map(|&(old_col, val)| {
+                                let nc = buffers.col_remap[old_col];
+                                val * *solver.get_value(nc) * col_scale(old_col) / rhs_factor
+                            })
+                            .sum();
+                        let limit = ub_limit(row_idx);
+                        activity > limit + LAZY_TOLERANCE * limit.abs().max(1.0)
+                    })
+                    .collect();
+                if violated.is_empty() {
+                    break;
+                }
+                if rounds == lazy.max_rounds {
+                    // Out of rounds: solve again with every row
+                    let full = SolverOptions {
+                        lazy_bandwidth: None,
+                        ..*options
+                    };
+                    let mut result = solve_coalition(
+                        primitives,
+                        precomputed,
+                        buffers,
+                        coalition_mask,
+                        col_op1_mask,
+                        col_op2_mask,
+                        row_op1_mask,
+                        row_op2_mask,
+                        &full,
+                    )?;
+                    result.iterations += solver.iterations;
+                    result.solve_time = started.elapsed();
+                    return Ok(result);
+                }
+                rounds += 1;
+
+                // Both lists are sorted by row
+                buffers
+                    .pending_rows
+                    .retain(|row_idx| violated.binary_search(row_idx).is_err());
+                for &row_idx in &violated {
+                    let entries = &precomputed.ub_rows[row_idx];
+                    let scale = row_scale(entries, geometric.map_or(1.0, |g| g.ub_rows[row_idx]));
+                    let (indices, data): (Vec<usize>, Vec<f64>) = entries
+                        .iter()
+                        .filter(|&&(old_col, _)| buffers.col_remap[old_col] != usize::MAX)
+                        .map(|&(old_col, val)| {
+                            (buffers.col_remap[old_col], val * col_scale(old_col) * scale)
+                        })
+                        .unzip();
+                    stop = solver.add_constraint(
+                        CsVec::new(n_kept, indices, data),
+                        ComparisonOp::Le,
+                        ub_limit(row_idx) * rhs_factor * scale,
+                    );
+                    if !matches!(stop, Ok(StopReason::Finished)) {
+                        break;
+                    }
+                }
+            }
+            match stop {
+                Ok(StopReason::Finished) => {
+                    if let Some(primal) = &mut buffers.primal {
+                        primal.clear();
+                        primal.extend(buffers.col_remap[..n_cols].iter().enumerate().map(
+                            |(col, &nc)| {
+                                if nc == usize::MAX {
+                                    0.0
+                                } else {
+                                    *solver.get_value(nc) * col_scale(col) / rhs_factor
+                                }
+                            },
+                        ));
+                    }
+                    Ok(CoalitionResult {
+                        status: SolveStatus::Solved,
+                        objective_value: solver.cur_obj_val / (cost_factor * rhs_factor),
+                        group_objectives: Vec::new(),
+                        class_objectives: Vec::new(),
+                        iterations: solver.iterations,
+                        solve_time: started.elapsed(),
+                    })
+                }
+                Ok(StopReason::Limit) => Ok(CoalitionResult {
+                    status: SolveStatus::LimitReached,
+                    objective_value: solver.cur_obj_val / (cost_factor * rhs_factor),
+                    group_objectives: Vec::new(),
+                    class_objectives: Vec::new(),
+                    iterations: solver.iterations,
+                    solve_time: started.elapsed(),
+                }),
+                Err(microlp::Error::Infeasible) => Ok(CoalitionResult {
+                    status: SolveStatus::Infeasible,
+                    objective_value: 0.0,
+                    group_objectives: Vec::new(),
+                    class_objectives: Vec::new(),
+                    iterations: solver.iterations,
+                    solve_time: started.elapsed(),
+                }),
+                Err(e) => Err(ShapleyError::LpSolver(format!("LP solver error: {e}"))),
+            }
+        }
         Err(microlp::Error::Infeasible) => Ok(CoalitionResult {
             status: SolveStatus::Infeasible,
             objective_value: 0.0,
+            group_objectives: Vec::new(),
+            class_objectives: Vec::new(),
+            iterations: 0,
+            solve_time: started.elapsed(),
         }),
         Err(e) => Err(ShapleyError::LpSolver(format!("LP solver error: {e}"))),
     }
@@ -298,7 +572,8 @@ mod tests {
     use super::*;
     use crate::{
         lp_builder::LpBuilderInput,
-        types::{ConsolidatedDemand, ConsolidatedLink},
+        options::LazyConstraints,
+        types::{ConsolidatedDemand, ConsolidatedLink, LinkProvenance},
     };
 
     fn simple_links() -> Vec<ConsolidatedLink> {
@@ -311,6 +586,8 @@ mod tests {
             operator2: "Op1".to_string(),
             shared: 1,
             link_type: 0,
+            provenance: LinkProvenance::OriginalPrivate,
+            multicast_capable: false,
         }]
     }
 
@@ -433,6 +710,7 @@ mod tests {
             &col_masks,
             &row_masks,
             &row_masks,
+            &SolverOptions::default(),
         );
 
         assert!(result.is_err());
@@ -461,6 +739,7 @@ mod tests {
             &col_masks,
             &row_masks,
             &row_masks,
+            &SolverOptions::default(),
         );
 
         assert!(result.is_ok());
@@ -469,4 +748,234 @@ mod tests {
         // Objective should be finite and non-zero for a feasible problem
         assert!(result.objective_value.is_finite());
     }
+
+    #[test]
+    fn test_solve_coalition_iteration_limit() {
+        let links = simple_links();
+        let demands = simple_demands();
+        let primitives = LpBuilderInput::new(&links, &demands)
+            .build()
+            .expect("LP builder should succeed");
+        let precomputed = PrecomputedRows::new(&primitives);
+        let mut buffers = CoalitionBuffers::new(primitives.cost.len());
+
+        let all_bits = u32::MAX;
+        let col_masks = vec![all_bits; primitives.cost.len()];
+        let row_masks = vec![all_bits; primitives.b_ub.len()];
+        let options = SolverOptions {
+            max_iterations: Some(0),
+            ..Default::default()
+        };
+
+        let result = solve_coalition(
+            &primitives,
+            &precomputed,
+            &mut buffers,
+            all_bits,
+            &col_masks,
+            &col_masks,
+            &row_masks,
+            &row_masks,
+            &options,
+        )
+        .expect("hitting a limit is not an error");
+
+        assert_eq!(result.status, SolveStatus::LimitReached);
+    }
+
+    #[test]
+    fn test_solve_coalition_equilibrated_matches() {
+        let links = simple_links();
+        let demands = simple_demands();
+        let primitives = LpBuilderInput::new(&links, &demands)
+            .build()
+            .expect("LP builder should succeed");
+        let precomputed = PrecomputedRows::new(&primitives);
+        let mut buffers = CoalitionBuffers::new(primitives.cost.len());
+
+        let all_bits = u32::MAX;
+        let col_masks = vec![all_bits; primitives.cost.len()];
+        let row_masks = vec![all_bits; primitives.b_ub.len()];
+        let mut solve = |options: SolverOptions| {
+            solve_coalition(
+                &primitives,
+                &precomputed,
+                &mut buffers,
+                all_bits,
+                &col_masks,
+                &col_masks,
+                &row_masks,
+                &row_masks,
+                &options,
+            )
+            .expect("solve should succeed")
+            .objective_value
+        };
+
+        let plain = solve(SolverOptions::default());
+        let scaled = solve(SolverOptions {
+            equilibrate: true,
+            ..Default::default()
+        });
+        assert!((plain - scaled).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_lazy_bandwidth_matches() {
+        // The direct A-B link is faster but too narrow for the demand, so
+        // part of it detours through C
+        let mut links = simple_links();
+        links[0].bandwidth = 2.0;
+        for (device1, device2, shared) in [("A", "C", 2), ("C", "B", 3)] {
+            links.push(ConsolidatedLink {
+                device1: device1.to_string(),
+                device2: device2.to_string(),
+                bandwidth: 10.0,
+                shared,
+                ..links[0].clone()
+            });
+        }
+        let primitives = LpBuilderInput::new(&links, &simple_demands())
+            .build()
+            .expect("LP builder should succeed");
+        let precomputed = PrecomputedRows::new(&primitives);
+        let mut buffers = CoalitionBuffers::new(primitives.cost.len());
+
+        let all_bits = u32::MAX;
+        let col_masks = vec![all_bits; primitives.cost.len()];
+        let row_masks = vec![all_bits; primitives.b_ub.len()];
+        let mut solve = |options: SolverOptions| {
+            let result = solve_coalition(
+                &primitives,
+                &precomputed,
+                &mut buffers,
+                all_bits,
+                &col_masks,
+                &col_masks,
+                &row_masks,
+                &row_masks,
+                &options,
+            )
+            .expect("solve should succeed");
+            assert_eq!(result.status, SolveStatus::Solved);
+            (result.objective_value, buffers.pending_rows.len())
+        };
+
+        let (full, _) = solve(SolverOptions::default());
+        let lazy = |max_rounds| SolverOptions {
+            lazy_bandwidth: Some(LazyConstraints { max_rounds }),
+            ..Default::default()
+        };
+        // Only the A-B row is ever violated; the others are never built
+        let (generated, pending) = solve(lazy(20));
+        assert!((full - generated).abs() < 1e-9);
+        assert_eq!(pending, primitives.bandwidth_rows - 1);
+        // Out of rounds, the coalition is solved again with every row
+        let (fallback, pending) = solve(lazy(0));
+        assert!((full - fallback).abs() < 1e-9);
+        assert_eq!(pending, 0);
+        let (scaled, _) = solve(SolverOptions {
+            equilibrate: true,
+            geometric_scaling: true,
+            ..lazy(20)
+        });
+        assert!((full - scaled).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_auto_scale_matches() {
+        // Latency in nanoseconds, bandwidth in Tbps
+        let mut links = simple_links();
+        links[0].latency = 3e7;
+        links[0].bandwidth = 1e-5;
+        let mut demands = simple_demands();
+        demands[0].traffic = 5e-6;
+        let primitives = LpBuilderInput::new(&links, &demands)
+            .build()
+            .expect("LP builder should succeed");
+        let precomputed = PrecomputedRows::new(&primitives);
+        assert!(precomputed.scaling.cost_exponent < 0);
+        assert!(precomputed.scaling.rhs_exponent > 0);
+        let mut buffers = CoalitionBuffers::new(primitives.cost.len());
+        buffers.primal = Some(Vec::new());
+
+        let all_bits = u32::MAX;
+        let col_masks = vec![all_bits; primitives.cost.len()];
+        let row_masks = vec![all_bits; primitives.b_ub.len()];
+        let mut solve = |options: SolverOptions| {
+            let objective = solve_coalition(
+                &primitives,
+                &precomputed,
+                &mut buffers,
+                all_bits,
+                &col_masks,
+                &col_masks,
+                &row_masks,
+                &row_masks,
+                &options,
+            )
+            .expect("solve should succeed")
+            .objective_value;
+            (objective, buffers.primal.clone().unwrap())
+        };
+
+        let (plain, plain_flows) = solve(SolverOptions::default());
+        let (scaled, scaled_flows) = solve(SolverOptions {
+            auto_scale: true,
+            ..Default::default()
+        });
+        let (geometric, geometric_flows) = solve(SolverOptions {
+            geometric_scaling: true,
+            ..Default::default()
+        });
+        for (objective, flows) in [(scaled, scaled_flows), (geometric, geometric_flows)] {
+            assert!((plain - objective).abs() <= 1e-9 * plain.abs());
+            for (a, b) in plain_flows.iter().zip(&flows) {
+                assert!((a - b).abs() <= 1e-9 * a.abs().max(1e-12));
+            }
+        }
+    }
+
+    #[test]
+    fn test_geometric_scaling_is_powers_of_two() {
+        let mut links = simple_links();
+        links.push(ConsolidatedLink {
+            device1: "B".to_string(),
+            device2: "A".to_string(),
+            latency: 300.0,
+            bandwidth: 0.5,
+            ..links[0].clone()
+        });
+        links[0].latency = 0.1;
+        let primitives = LpBuilderInput::new(&links, &simple_demands())
+            .build()
+            .expect("LP builder should succeed");
+        let geometric = PrecomputedRows::new(&primitives).geometric;
+        let factors = geometric
+            .cols
+            .iter()
+            .chain(&geometric.eq_rows)
+            .chain(&geometric.ub_rows);
+        for &factor in factors {
+            assert!(factor > 0.0 && factor.log2().fract() == 0.0);
+        }
+        // The satellite link's columns are scaled down against the metro link's
+        let cost_of = |col: usize| primitives.cost[col] * geometric.cols[col];
+        let costs: Vec<f64> = (0..primitives.cost.len())
+            .filter(|&col| primitives.cost[col] != 0.0)
+            .map(cost_of)
+            .collect();
+        let spread = |v: &[f64]| {
+            let max = v.iter().fold(0.0f64, |m, c| m.max(c.abs()));
+            let min = v.iter().fold(f64::INFINITY, |m, c| m.min(c.abs()));
+            max / min
+        };
+        let raw: Vec<f64> = primitives
+            .cost
+            .iter()
+            .copied()
+            .filter(|c| *c != 0.0)
+            .collect();
+        assert!(spread(&costs) < spread(&raw));
+    }
 }
@@ -1,9 +1,13 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// Local CSC (Compressed Sparse Column) matrix type, replacing clarabel::algebra::CscMatrix.
 ///
 /// Fields match the Clarabel naming convention used throughout the codebase:
 /// `m` (rows), `n` (cols), `colptr`, `rowval`, `nzval`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
-pub(crate) struct CscMatrix<T = f64> {
+pub struct CscMatrix<T = f64> {
     /// Number of rows.
     pub m: usize,
     /// Number of columns.
@@ -0,0 +1,160 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+};
+
+use crate::{
+    error::{Result, ShapleyError},
+    types::PrivateLinks,
+    utils::SplitMix64,
+};
+
+/// One joint outage state of the shared-risk link groups
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct OutageScenario {
+    pub failed: BTreeSet<u32>,
+    pub probability: f64,
+}
+
+impl OutageScenario {
+    /// Private links that survive this scenario
+    pub fn surviving<'a>(&self, private_links: &'a PrivateLinks) -> Cow<'a, PrivateLinks> {
+        if self.failed.is_empty() {
+            return Cow::Borrowed(private_links);
+        }
+        Cow::Owned(
+            private_links
+                .iter()
+                .filter(|link| !link.srlg.is_some_and(|g| self.failed.contains(&g)))
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+/// Outage scenarios for the SRLGs that appear on a private link and have an
+/// availability below 1. Groups fail independently; all links of a failed group
+/// go down together.
+///
+/// All 2^k scenarios are enumerated when that is at most `max_scenarios`;
+/// otherwise `max_scenarios` scenarios are sampled from `seed` and weighted by
+/// their sample frequency.
+pub(crate) fn outage_scenarios(
+    private_links: &PrivateLinks,
+    availability: &BTreeMap<u32, f64>,
+    max_scenarios: usize,
+    seed: u64,
+) -> Result<Vec<OutageScenario>> {
+    if let Some((group, a)) = availability.iter().find(|&(_, a)| !(0.0..=1.0).contains(a)) {
+        return Err(ShapleyError::Validation(format!(
+            "SRLG {group} availability must be within [0, 1], got {a}"
+        )));
+    }
+
+    let groups: Vec<(u32, f64)> = private_links
+        .iter()
+        .filter_map(|link| link.srlg)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|g| availability.get(&g).map(|&a| (g, a)))
+        .filter(|&(_, a)| a < 1.0)
+        .collect();
+
+    let no_outage = || OutageScenario {
+        failed: BTreeSet::new(),
+        probability: 1.0,
+    };
+    if groups.is_empty() {
+        return Ok(vec![no_outage()]);
+    }
+
+    let k = groups.len();
+    if k < usize::BITS as usize && 1usize << k <= max_scenarios.max(1) {
+        let scenarios = (0..1usize << k)
+            .map(|mask| {
+                let mut scenario = no_outage();
+                for (i, &(group, a)) in groups.iter().enumerate() {
+                    if mask & (1 << i) != 0 {
+                        scenario.failed.insert(group);
+                        scenario.probability *= 1.0 - a;
+                    } else {
+                        scenario.probability *= a;
+                    }
+                }
+                scenario
+            })
+            .filter(|s| s.probability > 0.0)
+            .collect();
+        return Ok(scenarios);
+    }
+
+    // Too many groups to enumerate: sample, merging repeated draws
+    let n_samples = max_scenarios.max(1);
+    let mut rng = SplitMix64::new(seed);
+    let mut counts: BTreeMap<BTreeSet<u32>, usize> = BTreeMap::new();
+    for _ in 0..n_samples {
+        let failed = groups
+            .iter()
+            .filter(|&&(_, a)| rng.next_f64() >= a)
+            .map(|&(group, _)| group)
+            .collect();
+        *counts.entry(failed).or_default() += 1;
+    }
+    Ok(counts
+        .into_iter()
+        .map(|(failed, count)| OutageScenario {
+            failed,
+            probability: count as f64 / n_samples as f64,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PrivateLink;
+
+    fn links() -> PrivateLinks {
+        vec![
+            PrivateLink::new("AAA1".into(), "BBB1".into(), 10.0, 10.0, 1.0, None).with_srlg(1),
+            PrivateLink::new("AAA1".into(), "CCC1".into(), 10.0, 10.0, 1.0, None).with_srlg(1),
+            PrivateLink::new("BBB1".into(), "CCC1".into(), 10.0, 10.0, 1.0, None).with_srlg(2),
+            PrivateLink::new("CCC1".into(), "DDD1".into(), 10.0, 10.0, 1.0, None),
+        ]
+    }
+
+    #[test]
+    fn test_enumerated_scenarios() {
+        let availability = BTreeMap::from([(1, 0.9), (2, 0.8), (3, 0.5)]);
+        let scenarios = outage_scenarios(&links(), &availability, 256, 0).unwrap();
+
+        // Group 3 is on no link, so only groups 1 and 2 are enumerated
+        assert_eq!(scenarios.len(), 4);
+        let total: f64 = scenarios.iter().map(|s| s.probability).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+
+        let both = scenarios.iter().find(|s| s.failed.len() == 2).unwrap();
+        assert!((both.probability - 0.1 * 0.2).abs() < 1e-12);
+        assert_eq!(both.surviving(&links()).len(), 1);
+    }
+
+    #[test]
+    fn test_sampled_scenarios() {
+        let availability = BTreeMap::from([(1, 0.9), (2, 0.8)]);
+        let scenarios = outage_scenarios(&links(), &availability, 2, 42).unwrap();
+
+        let total: f64 = scenarios.iter().map(|s| s.probability).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+        assert_eq!(
+            scenarios,
+            outage_scenarios(&links(), &availability, 2, 42).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_no_srlg_is_single_scenario() {
+        let scenarios = outage_scenarios(&links(), &BTreeMap::new(), 256, 0).unwrap();
+        assert_eq!(scenarios.len(), 1);
+        assert!(matches!(scenarios[0].surviving(&links()), Cow::Borrowed(_)));
+    }
+}
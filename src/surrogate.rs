@@ -0,0 +1,197 @@
+//! Additive surrogate of a coalition game, fit to sampled coalition values.
+//!
+//! The surrogate predicts `v(S) ≈ b + Σ_{k ∈ S} w_k` by least squares on
+//! operator presence, with the standard error of each prediction. Its own
+//! Shapley values are the weights, so the
+//! [`SamplerKind::Surrogate`](crate::options::SamplerKind::Surrogate) sampler
+//! takes them exactly and samples only the marginal contributions of the
+//! residual `v - v̂`, which are small wherever the game is close to additive.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, ShapleyError};
+
+/// Ridge penalty on the operator weights, relative to the sample count. It
+/// only matters for operators whose presence the samples cannot tell apart,
+/// e.g. two operators that always appear together.
+const RIDGE: f64 = 1e-9;
+
+/// A predicted coalition value
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prediction {
+    pub value: f64,
+    /// Standard error of a new observation at the coalition: the residual
+    /// spread plus the uncertainty of the fit there
+    pub standard_error: f64,
+}
+
+/// Least-squares additive model of coalition values (bit k ↔ operator k)
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoalitionSurrogate {
+    pub intercept: f64,
+    /// Value each operator adds, whatever coalition it joins
+    pub weights: Vec<f64>,
+    /// Unbiased variance of the residuals; 0 when the samples leave no
+    /// degrees of freedom
+    pub residual_variance: f64,
+    /// `(XᵀX)⁻¹` over the intercept and the operator columns
+    precision_inverse: Vec<Vec<f64>>,
+}
+
+impl CoalitionSurrogate {
+    /// Fit to `(coalition, value)` samples. Non-finite values, e.g. the
+    /// `-inf` of infeasible coalitions, are left out.
+    pub fn fit(n_operators: usize, samples: &[(u32, f64)]) -> Result<Self> {
+        let samples: Vec<(u32, f64)> = samples
+            .iter()
+            .copied()
+            .filter(|(_, v)| v.is_finite())
+            .collect();
+        if samples.len() < 2 {
+            return Err(ShapleyError::Validation(format!(
+                "A surrogate needs at least two finite coalition values, got {}",
+                samples.len()
+            )));
+        }
+
+        // Normal equations over x = (1, presence of each operator)
+        let dim = n_operators + 1;
+        let features = |coalition: u32| {
+            std::iter::once(1.0).chain((0..n_operators).map(move |k| ((coalition >> k) & 1) as f64))
+        };
+        let mut gram = vec![vec![0.0; dim]; dim];
+        let mut moment = vec![0.0; dim];
+        for &(coalition, value) in &samples {
+            let x: Vec<f64> = features(coalition).collect();
+            for i in 0..dim {
+                moment[i] += x[i] * value;
+                for j in 0..dim {
+                    gram[i][j] += x[i] * x[j];
+                }
+            }
+        }
+        for (k, row) in gram.iter_mut().enumerate().skip(1) {
+            row[k] += RIDGE * samples.len() as f64;
+        }
+        let precision_inverse = invert(gram).ok_or_else(|| {
+            ShapleyError::NumericalError("Surrogate normal equations are singular".to_string())
+        })?;
+        let beta: Vec<f64> = precision_inverse
+            .iter()
+            .map(|row| row.iter().zip(&moment).map(|(a, b)| a * b).sum())
+            .collect();
+
+        let predict =
+            |coalition: u32| -> f64 { features(coalition).zip(&beta).map(|(x, b)| x * b).sum() };
+        let rss: f64 = samples
+            .iter()
+            .map(|&(coalition, value)| (value - predict(coalition)).powi(2))
+            .sum();
+        let dof = samples.len().saturating_sub(dim);
+        let residual_variance = if dof == 0 { 0.0 } else { rss / dof as f64 };
+
+        Ok(Self {
+            intercept: beta[0],
+            weights: beta[1..].to_vec(),
+            residual_variance,
+            precision_inverse,
+        })
+    }
+
+    pub fn n_operators(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn predict(&self, coalition: u32) -> Prediction {
+        let x: Vec<f64> = std::iter::once(1.0)
+            .chain((0..self.n_operators()).map(|k| ((coalition >> k) & 1) as f64))
+            .collect();
+        let value = self.intercept
+            + self
+                .weights
+                .iter()
+                .enumerate()
+                .filter(|(k, _)| (coalition >> k) & 1 == 1)
+                .map(|(_, w)| w)
+                .sum::<f64>();
+        let leverage: f64 = self
+            .precision_inverse
+            .iter()
+            .zip(&x)
+            .map(|(row, xi)| xi * row.iter().zip(&x).map(|(a, xj)| a * xj).sum::<f64>())
+            .sum();
+        Prediction {
+            value,
+            standard_error: (self.residual_variance * (1.0 + leverage)).sqrt(),
+        }
+    }
+
+    /// Shapley values of the surrogate's expected-value game when each
+    /// operator is up with probability `operator_uptime`
+    pub fn shapley_values(&self, operator_uptime: f64) -> Vec<f64> {
+        self.weights.iter().map(|w| operator_uptime * w).collect()
+    }
+}
+
+/// Inverse by Gauss-Jordan elimination with partial pivoting
+fn invert(mut a: Vec<Vec<f64>>) -> Option<Vec<Vec<f64>>> {
+    let n = a.len();
+    let mut inverse: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < f64::EPSILON {
+            return None;
+        }
+        a.swap(col, pivot);
+        inverse.swap(col, pivot);
+        let scale = a[col][col];
+        for j in 0..n {
+            a[col][j] /= scale;
+            inverse[col][j] /= scale;
+        }
+        for row in (0..n).filter(|&r| r != col) {
+            let factor = a[row][col];
+            if factor != 0.0 {
+                for j in 0..n {
+                    a[row][j] -= factor * a[col][j];
+                    inverse[row][j] -= factor * inverse[col][j];
+                }
+            }
+        }
+    }
+    Some(inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_additive_games_are_fit_exactly() {
+        // v(S) = 1 + 2·[0 ∈ S] + 5·[2 ∈ S], sampled on 6 of the 8 coalitions
+        let value = |c: u32| 1.0 + 2.0 * (c & 1) as f64 + 5.0 * ((c >> 2) & 1) as f64;
+        let samples: Vec<(u32, f64)> = [0, 1, 2, 4, 5, 7].map(|c| (c, value(c))).to_vec();
+        let surrogate = CoalitionSurrogate::fit(3, &samples).unwrap();
+        for coalition in 0..8 {
+            let prediction = surrogate.predict(coalition);
+            assert!((prediction.value - value(coalition)).abs() < 1e-6);
+            assert!(prediction.standard_error < 1e-6);
+        }
+        let values = surrogate.shapley_values(0.5);
+        assert!((values[0] - 1.0).abs() < 1e-6 && (values[2] - 2.5).abs() < 1e-6);
+
+        // Synergy the model cannot express shows up as uncertainty
+        let synergy: Vec<(u32, f64)> = (0..8)
+            .map(|c: u32| (c, if c == 7 { 10.0 } else { 0.0 }))
+            .collect();
+        let surrogate = CoalitionSurrogate::fit(3, &synergy).unwrap();
+        assert!(surrogate.predict(7).standard_error > 1.0);
+
+        assert!(CoalitionSurrogate::fit(3, &[(0, 1.0), (1, f64::NEG_INFINITY)]).is_err());
+    }
+}
@@ -0,0 +1,354 @@
+//! Reading the input tables from CSV.
+//!
+//! Rows are decoded as they are read, so a table can come straight from a
+//! socket or a decompressing reader without a temporary file.
+//! With the `gzip` feature, [`read_table_file`] recognizes gzip data by its
+//! magic bytes and decompresses it on the fly, so telemetry dumps such as
+//! `demands.csv.gz` are read as they are.
+//!
+//! [`read_input_table`] reads one of the four input tables strictly: the
+//! header is checked against the table's [`TableSchema`] first, listing
+//! every missing, unexpected and misnamed column, and a row that fails to
+//! decode is reported with its column and what the column holds.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
+
+#[cfg(feature = "gzip")]
+use flate2::read::GzDecoder;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    error::{ErrorContext, InputTable, Result, ShapleyError},
+    types::{Demand, Device, PrivateLink, PublicLink},
+};
+
+/// First two bytes of every gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Every row of a CSV table with a header line, e.g. `Devices` from
+/// `devices.csv`
+pub fn read_table<T: DeserializeOwned>(reader: impl Read) -> Result<Vec<T>> {
+    let mut reader = csv::Reader::from_reader(reader);
+    // Deserializing alone would take a header that fails to read, e.g. from
+    // a truncated gzip stream, for an empty table
+    reader
+        .headers()
+        .map_err(|e| ShapleyError::Io(format!("header: {e}")))?;
+    reader
+        .deserialize()
+        .enumerate()
+        .map(|(row, record)| {
+            record.map_err(|e| {
+                let err = ShapleyError::Io(e.to_string());
+                err.with_context(ErrorContext {
+                    row: Some(row),
+                    ..Default::default()
+                })
+            })
+        })
+        .collect()
+}
+
+/// [`read_table`] from a file, decompressing it if it is gzip data (with the
+/// `gzip` feature; gzip data is an error without it)
+pub fn read_table_file<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<Vec<T>> {
+    read_table(open_table_file(path.as_ref())?)
+}
+
+/// One column of an input table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    pub name: &'static str,
+    /// What the column holds, as error messages describe it
+    pub expected: &'static str,
+    pub required: bool,
+}
+
+impl Column {
+    const fn required(name: &'static str, expected: &'static str) -> Self {
+        Self {
+            name,
+            expected,
+            required: true,
+        }
+    }
+
+    const fn optional(name: &'static str, expected: &'static str) -> Self {
+        Self {
+            name,
+            expected,
+            required: false,
+        }
+    }
+}
+
+/// The columns of an input table, see [`read_input_table`]
+pub trait TableSchema {
+    const TABLE: InputTable;
+    const COLUMNS: &'static [Column];
+}
+
+const NAME: &str = "a name";
+const NUMBER: &str = "a number";
+const COUNT: &str = "a non-negative integer";
+const ID: &str = "a non-negative integer or NA";
+const BOOL: &str = "TRUE or FALSE";
+const UNIT: &str = "a unit: Bps, Kbps, Mbps, Gbps or Tbps";
+
+impl TableSchema for PrivateLink {
+    const TABLE: InputTable = InputTable::PrivateLinks;
+    const COLUMNS: &'static [Column] = &[
+        Column::required("device1", NAME),
+        Column::required("device2", NAME),
+        Column::required("latency", NUMBER),
+        Column::required("bandwidth", NUMBER),
+        Column::required("uptime", "a number between 0 and 1"),
+        Column::required("shared", ID),
+        Column::optional("bundle", ID),
+        Column::optional("srlg", ID),
+        Column::optional("unit", UNIT),
+        Column::optional("measured_bandwidth", NUMBER),
+        Column::optional("latency_p50", NUMBER),
+        Column::optional("latency_p95", NUMBER),
+        Column::optional("latency_p99", NUMBER),
+        Column::optional("multicast_capable", BOOL),
+    ];
+}
+
+impl TableSchema for Device {
+    const TABLE: InputTable = InputTable::Devices;
+    const COLUMNS: &'static [Column] = &[
+        Column::required("device", NAME),
+        Column::required("edge", COUNT),
+        Column::required("operator", NAME),
+        Column::optional("multicast_capable", BOOL),
+    ];
+}
+
+impl TableSchema for Demand {
+    const TABLE: InputTable = InputTable::Demands;
+    const COLUMNS: &'static [Column] = &[
+        Column::required("start", NAME),
+        Column::required("end", NAME),
+        Column::required("receivers", COUNT),
+        Column::required("traffic", NUMBER),
+        Column::required("priority", NUMBER),
+        Column::required("type", COUNT),
+        Column::required("multicast", BOOL),
+        Column::optional("unit", UNIT),
+        Column::optional("class", COUNT),
+        Column::optional("traffic_low", NUMBER),
+        Column::optional("traffic_high", NUMBER),
+    ];
+}
+
+impl TableSchema for PublicLink {
+    const TABLE: InputTable = InputTable::PublicLinks;
+    const COLUMNS: &'static [Column] = &[
+        Column::required("city1", NAME),
+        Column::required("city2", NAME),
+        Column::required("latency", NUMBER),
+    ];
+}
+
+/// Check a header against `T`'s schema, listing every column that is
+/// missing, unexpected, or a misspelling of an expected column such as
+/// `Latency(ms)` for `latency`
+pub fn check_header<T: TableSchema>(header: &[&str]) -> Result<()> {
+    let known = |name: &str| T::COLUMNS.iter().any(|c| c.name == name);
+    let mut problems = Vec::new();
+    let mut misnamed = Vec::new();
+    for &name in header.iter().filter(|name| !known(name)) {
+        match T::COLUMNS
+            .iter()
+            .find(|c| !header.contains(&c.name) && loose(c.name) == loose(name))
+        {
+            Some(column) => {
+                problems.push(format!("column {name:?} should be {:?}", column.name));
+                misnamed.push(column.name);
+            }
+            None => problems.push(format!("unexpected column {name:?}")),
+        }
+    }
+    for column in T::COLUMNS
+        .iter()
+        .filter(|c| c.required && !header.contains(&c.name) && !misnamed.contains(&c.name))
+    {
+        problems.push(format!(
+            "missing column {:?} ({})",
+            column.name, column.expected
+        ));
+    }
+    if problems.is_empty() {
+        return Ok(());
+    }
+    let expected: Vec<&str> = T::COLUMNS.iter().map(|c| c.name).collect();
+    Err(ShapleyError::Validation(format!(
+        "The {} header does not match the table: {}. Expected columns: {}",
+        T::TABLE,
+        problems.join("; "),
+        expected.join(", ")
+    ))
+    .with_context(ErrorContext {
+        table: Some(T::TABLE),
+        ..Default::default()
+    }))
+}
+
+/// A column name without case, punctuation, or a trailing unit such as
+/// `(ms)`, for recognizing misspellings
+fn loose(name: &str) -> String {
+    name.split(['(', '['])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// [`read_table`] of an input table, checking the header first with
+/// [`check_header`]. A row that fails to decode is reported with its column
+/// and what the column holds.
+pub fn read_input_table<T: DeserializeOwned + TableSchema>(reader: impl Read) -> Result<Vec<T>> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let header = reader
+        .headers()
+        .map_err(|e| ShapleyError::Io(format!("{} header: {e}", T::TABLE)))?
+        .clone();
+    let names: Vec<&str> = header.iter().collect();
+    check_header::<T>(&names)?;
+
+    reader
+        .deserialize()
+        .enumerate()
+        .map(|(row, record)| {
+            record.map_err(|e| {
+                let column = match e.kind() {
+                    csv::ErrorKind::Deserialize { err, .. } => err
+                        .field()
+                        .and_then(|i| names.get(i as usize))
+                        .and_then(|name| T::COLUMNS.iter().find(|c| c.name == *name)),
+                    _ => None,
+                };
+                match column {
+                    Some(column) => ShapleyError::Io(format!(
+                        "column {} holds {}: {e}",
+                        column.name, column.expected
+                    ))
+                    .with_context(ErrorContext::cell(
+                        T::TABLE,
+                        row,
+                        column.name,
+                    )),
+                    None => ShapleyError::Io(e.to_string())
+                        .with_context(ErrorContext::row(T::TABLE, row)),
+                }
+            })
+        })
+        .collect()
+}
+
+/// [`read_input_table`] from a file, decompressing gzip data as
+/// [`read_table_file`] does
+pub fn read_input_table_file<T: DeserializeOwned + TableSchema>(
+    path: impl AsRef<Path>,
+) -> Result<Vec<T>> {
+    read_input_table(open_table_file(path.as_ref())?)
+}
+
+fn open_table_file(path: &Path) -> Result<Box<dyn Read>> {
+    let file =
+        File::open(path).map_err(|e| ShapleyError::Io(format!("{}: {e}", path.display())))?;
+    let mut reader = BufReader::new(file);
+    let head = reader
+        .fill_buf()
+        .map_err(|e| ShapleyError::Io(format!("{}: {e}", path.display())))?;
+    if head.starts_with(&GZIP_MAGIC) {
+        #[cfg(feature = "gzip")]
+        return Ok(Box::new(GzDecoder::new(reader)));
+        #[cfg(not(feature = "gzip"))]
+        return Err(ShapleyError::Io(format!(
+            "{}: gzip data needs the gzip feature",
+            path.display()
+        )));
+    }
+    Ok(Box::new(reader))
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "gzip")]
+    use flate2::{Compression, write::GzEncoder};
+
+    use super::*;
+    use crate::types::{Device, PublicLink};
+
+    #[test]
+    fn test_read_tables() {
+        let devices: Vec<Device> = read_table_file("tests/devices.csv").unwrap();
+        assert!(!devices.is_empty());
+
+        let links: Vec<PublicLink> =
+            read_table("city1,city2,latency\nNYC,LON,70\n".as_bytes()).unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!((links[0].city2.as_str(), links[0].latency), ("LON", 70.0));
+
+        let err =
+            read_table::<PublicLink>("city1,city2,latency\nNYC,LON,fast\n".as_bytes()).unwrap_err();
+        assert_eq!(err.context().unwrap().row, Some(0));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_read_gzip_tables() {
+        let devices: Vec<Device> = read_table_file("tests/devices.csv").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let gzip = dir.path().join("devices.csv.gz");
+        let mut encoder = GzEncoder::new(File::create(&gzip).unwrap(), Compression::default());
+        std::io::copy(&mut File::open("tests/devices.csv").unwrap(), &mut encoder).unwrap();
+        encoder.finish().unwrap();
+        let decompressed: Vec<Device> = read_table_file(&gzip).unwrap();
+        assert_eq!(decompressed.len(), devices.len());
+        let decompressed: Vec<Device> = read_input_table_file(&gzip).unwrap();
+        assert_eq!(decompressed.len(), devices.len());
+
+        // A truncated stream fails to decode rather than reading as empty
+        std::fs::write(&gzip, [0x1f, 0x8b, 0x08, 0x00]).unwrap();
+        assert!(read_table_file::<Device>(&gzip).is_err());
+        assert!(read_input_table_file::<Device>(&gzip).is_err());
+    }
+
+    #[test]
+    fn test_read_input_table_checks_the_schema() {
+        let links: Vec<PublicLink> =
+            read_input_table("city1,city2,latency\nNYC,LON,70\n".as_bytes()).unwrap();
+        assert_eq!(links.len(), 1);
+        let devices: Vec<Device> = read_input_table_file("tests/devices.csv").unwrap();
+        assert!(!devices.is_empty());
+
+        let err = read_input_table::<PrivateLink>(
+            "Device1,device2,Latency(ms),bandwidth,shared,color\n".as_bytes(),
+        )
+        .unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains(r#"column "Device1" should be "device1""#));
+        assert!(message.contains(r#"column "Latency(ms)" should be "latency""#));
+        assert!(message.contains(r#"unexpected column "color""#));
+        assert!(message.contains(r#"missing column "uptime""#));
+        assert!(!message.contains(r#"missing column "latency""#));
+        assert_eq!(err.context().unwrap().table, Some(InputTable::PrivateLinks));
+
+        let err = read_input_table::<PublicLink>(
+            "city1,city2,latency\nNYC,LON,70\nNYC,PAR,fast\n".as_bytes(),
+        )
+        .unwrap_err();
+        let context = err.context().unwrap();
+        assert_eq!((context.row, context.column), (Some(1), Some("latency")));
+        assert!(err.to_string().contains("holds a number"));
+    }
+}
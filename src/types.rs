@@ -1,20 +1,49 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 #[cfg(feature = "borsh")]
 use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Deserializer, Serialize};
 
+use crate::utils::city_of;
+
 pub type Demands = Vec<Demand>;
 pub type Devices = Vec<Device>;
 pub type PrivateLinks = Vec<PrivateLink>;
 pub type PublicLinks = Vec<PublicLink>;
+/// Cities of each region, with the weight of each city
+pub type Regions = BTreeMap<String, BTreeMap<String, f64>>;
 
+// Input types. Their borsh encoding is fixed to the original fields: fields
+// added since are skipped when decoding, and encoding fails while one is set
+// rather than dropping it. Snapshots record every field.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "borsh", derive(BorshDeserialize))]
 #[derive(Debug, Clone)]
 pub struct Device {
     pub device: String,
     pub edge: u32,
     pub operator: String,
+    /// Whether the device replicates multicast traffic, so that a multicast
+    /// group's flows into it share link capacity. `None` goes by the name:
+    /// `{city}{n}` devices replicate, `{city}00` public nodes do not.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "borsh", borsh(skip))]
+    pub multicast_capable: Option<bool>,
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for Device {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        unencoded(
+            "Device",
+            "multicast_capable",
+            self.multicast_capable.is_some(),
+        )?;
+        BorshSerialize::serialize(&self.device, writer)?;
+        BorshSerialize::serialize(&self.edge, writer)?;
+        BorshSerialize::serialize(&self.operator, writer)
+    }
 }
 
 impl Device {
@@ -23,12 +52,19 @@ impl Device {
             device,
             edge,
             operator,
+            multicast_capable: None,
         }
     }
+
+    /// Declare whether the device replicates multicast traffic
+    pub fn with_multicast_capable(mut self, capable: bool) -> Self {
+        self.multicast_capable = Some(capable);
+        self
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "borsh", derive(BorshDeserialize))]
 #[derive(Debug, Clone)]
 pub struct PrivateLink {
     pub device1: String,
@@ -38,6 +74,86 @@ pub struct PrivateLink {
     pub uptime: f64,
     #[cfg_attr(feature = "serde", serde(deserialize_with = "deser_shared"))]
     pub shared: Option<u32>,
+    /// Link aggregation group: parallel links between the same two devices with
+    /// the same bundle ID are modeled as one link whose capacity is the sum of
+    /// the members' and whose latency is the lowest member latency.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "deser_shared"))]
+    #[cfg_attr(feature = "borsh", borsh(skip))]
+    pub bundle: Option<u32>,
+    /// Shared-risk link group: links in the same conduit that fail together.
+    /// Availabilities are set per group in `ShapleyOptions::srlg_availability`.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "deser_shared"))]
+    #[cfg_attr(feature = "borsh", borsh(skip))]
+    pub srlg: Option<u32>,
+    /// Unit of `bandwidth`; see [`BandwidthUnit`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "borsh", borsh(skip))]
+    pub unit: Option<BandwidthUnit>,
+    /// Bandwidth the link was measured to deliver, in the unit of `bandwidth`,
+    /// which is then the operator's commitment. Used instead of `bandwidth`
+    /// under `CapacityBasis::Measured`; `None` means the commitment is met.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "borsh", borsh(skip))]
+    pub measured_bandwidth: Option<f64>,
+    /// Median latency. `latency` is the mean; the percentiles are used
+    /// instead under the matching `ShapleyOptions::latency_metric`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "borsh", borsh(skip))]
+    pub latency_p50: Option<f64>,
+    /// 95th percentile latency
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "borsh", borsh(skip))]
+    pub latency_p95: Option<f64>,
+    /// 99th percentile latency
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "borsh", borsh(skip))]
+    pub latency_p99: Option<f64>,
+    /// Whether multicast flows over the link, in either direction, share its
+    /// capacity. `None` follows the receiving device's
+    /// [`Device::multicast_capable`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "borsh", borsh(skip))]
+    pub multicast_capable: Option<bool>,
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for PrivateLink {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        unencoded("PrivateLink", "bundle", self.bundle.is_some())?;
+        unencoded("PrivateLink", "srlg", self.srlg.is_some())?;
+        unencoded("PrivateLink", "unit", self.unit.is_some())?;
+        unencoded(
+            "PrivateLink",
+            "measured_bandwidth",
+            self.measured_bandwidth.is_some(),
+        )?;
+        unencoded("PrivateLink", "latency_p50", self.latency_p50.is_some())?;
+        unencoded("PrivateLink", "latency_p95", self.latency_p95.is_some())?;
+        unencoded("PrivateLink", "latency_p99", self.latency_p99.is_some())?;
+        unencoded(
+            "PrivateLink",
+            "multicast_capable",
+            self.multicast_capable.is_some(),
+        )?;
+        BorshSerialize::serialize(&self.device1, writer)?;
+        BorshSerialize::serialize(&self.device2, writer)?;
+        BorshSerialize::serialize(&self.latency, writer)?;
+        BorshSerialize::serialize(&self.bandwidth, writer)?;
+        BorshSerialize::serialize(&self.uptime, writer)?;
+        BorshSerialize::serialize(&self.shared, writer)
+    }
+}
+
+/// Error for a set field that the borsh encoding has no room for
+#[cfg(feature = "borsh")]
+fn unencoded(table: &str, field: &str, set: bool) -> std::io::Result<()> {
+    if set {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{table}::{field} is not part of the borsh encoding; use a snapshot"),
+        ));
+    }
+    Ok(())
 }
 
 #[cfg(feature = "serde")]
@@ -138,10 +254,120 @@ impl PrivateLink {
             bandwidth,
             uptime,
             shared,
+            bundle: None,
+            srlg: None,
+            unit: None,
+            measured_bandwidth: None,
+            latency_p50: None,
+            latency_p95: None,
+            latency_p99: None,
+            multicast_capable: None,
+        }
+    }
+
+    /// Declare this link a member of link bundle `bundle`
+    pub fn with_bundle(mut self, bundle: u32) -> Self {
+        self.bundle = Some(bundle);
+        self
+    }
+
+    /// Declare this link a member of shared-risk link group `srlg`
+    pub fn with_srlg(mut self, srlg: u32) -> Self {
+        self.srlg = Some(srlg);
+        self
+    }
+
+    /// Declare `bandwidth` to be in `unit`
+    pub fn with_unit(mut self, unit: BandwidthUnit) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+
+    /// Record the bandwidth the link was measured to deliver
+    pub fn with_measured_bandwidth(mut self, measured_bandwidth: f64) -> Self {
+        self.measured_bandwidth = Some(measured_bandwidth);
+        self
+    }
+
+    /// Record the link's latency distribution
+    pub fn with_latency_percentiles(mut self, p50: f64, p95: f64, p99: f64) -> Self {
+        self.latency_p50 = Some(p50);
+        self.latency_p95 = Some(p95);
+        self.latency_p99 = Some(p99);
+        self
+    }
+
+    /// Declare whether multicast flows over the link share its capacity
+    pub fn with_multicast_capable(mut self, capable: bool) -> Self {
+        self.multicast_capable = Some(capable);
+        self
+    }
+}
+
+/// Topology edits on private link tables, chainable for what-if scripts as
+/// `links.remove_operator(&devices, "Beta").scale_bandwidth(&devices, "Alpha", 2.0)`.
+///
+/// A link carries traffic both ways, so links are matched by their devices
+/// in either order. Operators are those of the `devices` table.
+pub trait PrivateLinksExt {
+    /// Links with neither end on a device of `operator`
+    fn remove_operator(self, devices: &[Device], operator: &str) -> PrivateLinks;
+    /// `link`, in place of any link between the same two devices
+    fn add_bidirectional(self, link: PrivateLink) -> PrivateLinks;
+    /// Bandwidth times `factor` on links with an end on a device of
+    /// `operator`, measured bandwidth included
+    fn scale_bandwidth(self, devices: &[Device], operator: &str, factor: f64) -> PrivateLinks;
+    /// Links with both ends in `cities`
+    fn retain_cities(self, cities: &[&str]) -> PrivateLinks;
+}
+
+impl PrivateLinksExt for PrivateLinks {
+    fn remove_operator(mut self, devices: &[Device], operator: &str) -> PrivateLinks {
+        let owned = devices_of(devices, operator);
+        self.retain(|l| !owned.contains(l.device1.as_str()) && !owned.contains(l.device2.as_str()));
+        self
+    }
+
+    fn add_bidirectional(mut self, link: PrivateLink) -> PrivateLinks {
+        self.retain(|l| !same_ends(&l.device1, &l.device2, &link.device1, &link.device2));
+        self.push(link);
+        self
+    }
+
+    fn scale_bandwidth(mut self, devices: &[Device], operator: &str, factor: f64) -> PrivateLinks {
+        let owned = devices_of(devices, operator);
+        for link in self
+            .iter_mut()
+            .filter(|l| owned.contains(l.device1.as_str()) || owned.contains(l.device2.as_str()))
+        {
+            link.bandwidth *= factor;
+            if let Some(measured) = &mut link.measured_bandwidth {
+                *measured *= factor;
+            }
         }
+        self
+    }
+
+    fn retain_cities(mut self, cities: &[&str]) -> PrivateLinks {
+        self.retain(|l| {
+            cities.contains(&city_of(&l.device1)) && cities.contains(&city_of(&l.device2))
+        });
+        self
     }
 }
 
+fn devices_of<'a>(devices: &'a [Device], operator: &str) -> BTreeSet<&'a str> {
+    devices
+        .iter()
+        .filter(|d| d.operator == operator)
+        .map(|d| d.device.as_str())
+        .collect()
+}
+
+fn same_ends(a1: &str, a2: &str, b1: &str, b2: &str) -> bool {
+    (a1 == b1 && a2 == b2) || (a1 == b2 && a2 == b1)
+}
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
 #[derive(Debug, Clone)]
@@ -161,8 +387,29 @@ impl PublicLink {
     }
 }
 
+/// Topology edits on public link tables; see [`PrivateLinksExt`]
+pub trait PublicLinksExt {
+    /// `link`, in place of any link between the same two cities
+    fn add_bidirectional(self, link: PublicLink) -> PublicLinks;
+    /// Links with both ends in `cities`
+    fn retain_cities(self, cities: &[&str]) -> PublicLinks;
+}
+
+impl PublicLinksExt for PublicLinks {
+    fn add_bidirectional(mut self, link: PublicLink) -> PublicLinks {
+        self.retain(|l| !same_ends(&l.city1, &l.city2, &link.city1, &link.city2));
+        self.push(link);
+        self
+    }
+
+    fn retain_cities(mut self, cities: &[&str]) -> PublicLinks {
+        self.retain(|l| cities.contains(&l.city1.as_str()) && cities.contains(&l.city2.as_str()));
+        self
+    }
+}
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "borsh", derive(BorshDeserialize))]
 #[derive(Debug, Clone)]
 pub struct Demand {
     pub start: String,
@@ -174,6 +421,42 @@ pub struct Demand {
     pub kind: u32, // aka type
     #[cfg_attr(feature = "serde", serde(deserialize_with = "deser_multicast"))]
     pub multicast: bool,
+    /// Unit of `traffic`; see [`BandwidthUnit`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "borsh", borsh(skip))]
+    pub unit: Option<BandwidthUnit>,
+    /// Priority class under preemptive routing (`ShapleyOptions::priority_classes`).
+    /// Classes are routed in ascending order, each in the capacity left by the
+    /// ones before it. `None` is class 1.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "borsh", borsh(skip))]
+    pub class: Option<u32>,
+    /// Lowest traffic the demand may carry, in the unit of `traffic`, for
+    /// robust valuation (`ShapleyOptions::robust`). `None` is `traffic`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "borsh", borsh(skip))]
+    pub traffic_low: Option<f64>,
+    /// Highest traffic the demand may carry. `None` is `traffic`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "borsh", borsh(skip))]
+    pub traffic_high: Option<f64>,
+}
+
+#[cfg(feature = "borsh")]
+impl BorshSerialize for Demand {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        unencoded("Demand", "unit", self.unit.is_some())?;
+        unencoded("Demand", "class", self.class.is_some())?;
+        unencoded("Demand", "traffic_low", self.traffic_low.is_some())?;
+        unencoded("Demand", "traffic_high", self.traffic_high.is_some())?;
+        BorshSerialize::serialize(&self.start, writer)?;
+        BorshSerialize::serialize(&self.end, writer)?;
+        BorshSerialize::serialize(&self.receivers, writer)?;
+        BorshSerialize::serialize(&self.traffic, writer)?;
+        BorshSerialize::serialize(&self.priority, writer)?;
+        BorshSerialize::serialize(&self.kind, writer)?;
+        BorshSerialize::serialize(&self.multicast, writer)
+    }
 }
 
 impl Demand {
@@ -194,13 +477,160 @@ impl Demand {
             priority,
             kind,
             multicast,
+            unit: None,
+            class: None,
+            traffic_low: None,
+            traffic_high: None,
         }
     }
+
+    /// Declare `traffic` to be in `unit`
+    pub fn with_unit(mut self, unit: BandwidthUnit) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+
+    /// Put this demand in priority class `class`
+    pub fn with_class(mut self, class: u32) -> Self {
+        self.class = Some(class);
+        self
+    }
+
+    /// Let the traffic vary anywhere in `[low, high]`, which should contain
+    /// `traffic`
+    pub fn with_traffic_range(mut self, low: f64, high: f64) -> Self {
+        self.traffic_low = Some(low);
+        self.traffic_high = Some(high);
+        self
+    }
+
+    /// Priority class, defaulting to 1
+    pub fn priority_class(&self) -> u32 {
+        self.class.unwrap_or(1)
+    }
 }
 
-// Consolidated types for internal processing
-#[derive(Debug, Clone)]
-pub(crate) struct ConsolidatedDemand {
+/// Set algebra over demand tables, chainable as
+/// `demands.filter_type(1).scale(1.2).merge(other)`.
+///
+/// Demands with the same start, end and type are the same entry of the
+/// demand matrix: [`merge`](Self::merge) adds their traffic and
+/// [`difference`](Self::difference) subtracts it. Traffic in another unit is
+/// converted to the unit of the entry it joins; a demand without a unit is
+/// taken to be in that unit.
+pub trait DemandsExt {
+    /// Demands of traffic type `kind`
+    fn filter_type(self, kind: u32) -> Demands;
+    /// Demands starting or ending in `city`
+    fn filter_city(self, city: &str) -> Demands;
+    /// Every demand's traffic times `factor`
+    fn scale(self, factor: f64) -> Demands;
+    /// Both tables, with the traffic of shared entries added
+    fn merge(self, other: Demands) -> Demands;
+    /// The traffic of `other` taken off the shared entries, dropping
+    /// entries left with none
+    fn difference(self, other: &[Demand]) -> Demands;
+}
+
+impl DemandsExt for Demands {
+    fn filter_type(mut self, kind: u32) -> Demands {
+        self.retain(|d| d.kind == kind);
+        self
+    }
+
+    fn filter_city(mut self, city: &str) -> Demands {
+        self.retain(|d| d.start == city || d.end == city);
+        self
+    }
+
+    fn scale(mut self, factor: f64) -> Demands {
+        for demand in &mut self {
+            demand.traffic *= factor;
+        }
+        self
+    }
+
+    fn merge(mut self, other: Demands) -> Demands {
+        for demand in other {
+            match self.iter_mut().find(|d| same_entry(d, &demand)) {
+                Some(entry) => entry.traffic += traffic_in_unit_of(&demand, entry),
+                None => self.push(demand),
+            }
+        }
+        self
+    }
+
+    fn difference(mut self, other: &[Demand]) -> Demands {
+        for demand in other {
+            if let Some(entry) = self.iter_mut().find(|d| same_entry(d, demand)) {
+                entry.traffic -= traffic_in_unit_of(demand, entry);
+            }
+        }
+        self.retain(|d| d.traffic > 0.0);
+        self
+    }
+}
+
+fn same_entry(a: &Demand, b: &Demand) -> bool {
+    a.start == b.start && a.end == b.end && a.kind == b.kind
+}
+
+fn traffic_in_unit_of(demand: &Demand, entry: &Demand) -> f64 {
+    match (demand.unit, entry.unit) {
+        (Some(unit), Some(target)) => unit.convert(demand.traffic, target),
+        _ => demand.traffic,
+    }
+}
+
+/// Unit of a link bandwidth or demand traffic rate.
+///
+/// Units are optional: inputs that give none anywhere are used as they are,
+/// as long as bandwidth and traffic share a unit. Once any row or
+/// `ShapleyOptions::units` names a unit, every row must resolve to one, and
+/// all rates are converted to Gbps before the computation.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BandwidthUnit {
+    #[cfg_attr(feature = "serde", serde(alias = "bps"))]
+    Bps,
+    #[cfg_attr(feature = "serde", serde(alias = "kbps"))]
+    Kbps,
+    #[cfg_attr(feature = "serde", serde(alias = "mbps"))]
+    Mbps,
+    #[cfg_attr(feature = "serde", serde(alias = "gbps"))]
+    Gbps,
+    #[cfg_attr(feature = "serde", serde(alias = "tbps"))]
+    Tbps,
+}
+
+impl BandwidthUnit {
+    pub fn bits_per_second(self) -> f64 {
+        match self {
+            Self::Bps => 1.0,
+            Self::Kbps => 1e3,
+            Self::Mbps => 1e6,
+            Self::Gbps => 1e9,
+            Self::Tbps => 1e12,
+        }
+    }
+
+    /// Express `value` given in this unit in `target`
+    pub fn convert(self, value: f64, target: BandwidthUnit) -> f64 {
+        value * (self.bits_per_second() / target.bits_per_second())
+    }
+}
+
+impl std::fmt::Display for BandwidthUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+// Consolidated types built from the inputs for LP construction
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsolidatedDemand {
     pub start: String,
     pub end: String,
     pub receivers: u32,
@@ -211,8 +641,28 @@ pub(crate) struct ConsolidatedDemand {
     pub original: u32, // Original type before adjustment
 }
 
-#[derive(Debug, Clone)]
-pub(crate) struct ConsolidatedLink {
+/// Where a consolidated link comes from
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkProvenance {
+    /// A private link as given, in its declared direction
+    OriginalPrivate,
+    /// The synthesized opposite direction of a private link
+    Reverse,
+    /// Public internet link between two `{city}00` nodes
+    PublicBackbone,
+    /// Zero-latency link from a demand source into the network
+    OnRamp,
+    /// Zero-latency link from the network to a demand destination
+    OffRamp,
+    /// Transition between a private device and the public internet, costed
+    /// at the contiguity bonus
+    Crossover,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsolidatedLink {
     pub device1: String,
     pub device2: String,
     pub latency: f64,
@@ -221,4 +671,143 @@ pub(crate) struct ConsolidatedLink {
     pub operator2: String,
     pub shared: u32,
     pub link_type: u32, // 0 for all traffic types, specific type otherwise
+    pub provenance: LinkProvenance,
+    /// Multicast flows into `device2` share this link's capacity
+    pub multicast_capable: bool,
+}
+
+/// The network the LP is actually built on, for auditing the synthesized links
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct ConsolidationReport {
+    pub links: Vec<ConsolidatedLink>,
+    pub demands: Vec<ConsolidatedDemand>,
+}
+
+/// One multicast group of a [`ConsolidationReport`]: the multicast demands
+/// of one input type
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MulticastGroup {
+    /// Input demand type (`Demand::kind`)
+    pub group: u32,
+    /// Distinct sources of the group's demands
+    pub sources: Vec<String>,
+    /// Consolidated demands, one per destination and priority
+    pub rows: usize,
+    /// Receivers over all rows
+    pub receivers: u32,
+    /// Traffic times receivers summed over rows, after demand multipliers:
+    /// what the sources inject
+    pub traffic: f64,
+}
+
+impl ConsolidationReport {
+    /// The multicast groups of the consolidated demands, by input type
+    pub fn multicast_groups(&self) -> Vec<MulticastGroup> {
+        let mut groups: BTreeMap<u32, MulticastGroup> = BTreeMap::new();
+        for demand in self.demands.iter().filter(|d| d.multicast) {
+            let group = groups
+                .entry(demand.original)
+                .or_insert_with(|| MulticastGroup {
+                    group: demand.original,
+                    sources: Vec::new(),
+                    rows: 0,
+                    receivers: 0,
+                    traffic: 0.0,
+                });
+            if !group.sources.contains(&demand.start) {
+                group.sources.push(demand.start.clone());
+            }
+            group.rows += 1;
+            group.receivers += demand.receivers;
+            group.traffic += demand.traffic * demand.receivers as f64;
+        }
+        groups.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn demand(start: &str, end: &str, traffic: f64, kind: u32) -> Demand {
+        Demand::new(start.into(), end.into(), 1, traffic, 1.0, kind, false)
+    }
+
+    #[test]
+    fn test_demand_set_algebra() {
+        let base = vec![
+            demand("SIN", "AMS", 10.0, 1),
+            demand("SIN", "FRA", 4.0, 1),
+            demand("NYC", "AMS", 2.0, 2),
+        ];
+        let other = vec![
+            demand("SIN", "AMS", 500.0, 1).with_unit(BandwidthUnit::Mbps),
+            demand("LON", "AMS", 1.0, 1),
+        ];
+        let base: Demands = base
+            .into_iter()
+            .map(|d| d.with_unit(BandwidthUnit::Gbps))
+            .collect();
+
+        let merged = base.clone().filter_type(1).scale(2.0).merge(other.clone());
+        let traffic: Vec<(&str, f64)> =
+            merged.iter().map(|d| (d.end.as_str(), d.traffic)).collect();
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].start, "SIN");
+        assert!((traffic[0].1 - 20.5).abs() < 1e-12);
+        assert_eq!(traffic[1], ("FRA", 8.0));
+        assert_eq!((merged[2].start.as_str(), merged[2].traffic), ("LON", 1.0));
+
+        assert_eq!(base.clone().filter_city("AMS").len(), 2);
+        assert_eq!(base.clone().filter_city("FRA")[0].end, "FRA");
+
+        // Taking off all of an entry's traffic drops it
+        let remaining =
+            merged.difference(&[demand("SIN", "FRA", 8.0, 1), demand("LON", "AMS", 0.5, 1)]);
+        assert_eq!(remaining.len(), 2);
+        assert_eq!(remaining[1].traffic, 0.5);
+    }
+
+    #[test]
+    fn test_link_topology_edits() {
+        let devices = vec![
+            Device::new("SIN1".into(), 1, "Alpha".into()),
+            Device::new("AMS1".into(), 1, "Beta".into()),
+            Device::new("FRA1".into(), 1, "Alpha".into()),
+            Device::new("NYC1".into(), 1, "Gamma".into()),
+        ];
+        let link = |a: &str, b: &str, bandwidth| {
+            PrivateLink::new(a.into(), b.into(), 10.0, bandwidth, 1.0, None)
+        };
+        let links = vec![
+            link("SIN1", "AMS1", 10.0),
+            link("AMS1", "FRA1", 20.0),
+            link("NYC1", "AMS1", 30.0),
+        ];
+
+        let without_alpha = links.clone().remove_operator(&devices, "Alpha");
+        assert_eq!(without_alpha.len(), 1);
+        assert_eq!(without_alpha[0].device1, "NYC1");
+
+        let edited = links
+            .clone()
+            .add_bidirectional(link("FRA1", "AMS1", 40.0))
+            .scale_bandwidth(&devices, "Gamma", 0.5);
+        let bandwidths: Vec<f64> = edited.iter().map(|l| l.bandwidth).collect();
+        assert_eq!(bandwidths, vec![10.0, 15.0, 40.0]);
+
+        let europe = links.retain_cities(&["AMS", "FRA"]);
+        assert_eq!(europe.len(), 1);
+
+        let public = vec![
+            PublicLink::new("SIN".into(), "AMS".into(), 150.0),
+            PublicLink::new("AMS".into(), "FRA".into(), 8.0),
+        ]
+        .add_bidirectional(PublicLink::new("AMS".into(), "SIN".into(), 140.0))
+        .retain_cities(&["SIN", "AMS"]);
+        assert_eq!(public.len(), 1);
+        assert_eq!(public[0].latency, 140.0);
+    }
 }
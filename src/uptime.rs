@@ -0,0 +1,371 @@
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Result, ShapleyError},
+    shapley::{Operator, ShapleyInput, ShapleyOutput},
+    types::Devices,
+};
+
+/// Seconds since the Unix epoch
+pub type Timestamp = u64;
+
+/// What a telemetry availability sample was taken of
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DeviceOrLink {
+    Device(String),
+    /// A private link, in either direction
+    Link(String, String),
+}
+
+impl DeviceOrLink {
+    /// Links are keyed by their devices in sorted order
+    fn key(&self) -> Self {
+        match self {
+            Self::Link(a, b) if b < a => Self::Link(b.clone(), a.clone()),
+            other => other.clone(),
+        }
+    }
+}
+
+/// Period and filtering applied by [`from_samples`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UptimeWindow {
+    /// First second of the window
+    pub start: Timestamp,
+    /// End of the window, exclusive. `None` closes the window at the latest
+    /// sample in it, which then only marks the end.
+    pub end: Option<Timestamp>,
+    /// Components with fewer samples in the window get no estimate
+    pub min_samples: usize,
+    /// Ignore a sample whose state differs from both its neighbours, i.e.
+    /// treat a lone down (or up) reading as a probe glitch
+    pub drop_isolated: bool,
+}
+
+impl Default for UptimeWindow {
+    fn default() -> Self {
+        Self {
+            start: 0,
+            end: None,
+            min_samples: 1,
+            drop_isolated: false,
+        }
+    }
+}
+
+impl UptimeWindow {
+    pub fn new(start: Timestamp, end: Timestamp) -> Self {
+        Self {
+            start,
+            end: Some(end),
+            ..Default::default()
+        }
+    }
+}
+
+/// Fraction of a window each device and private link was up
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct UptimeEstimates {
+    pub devices: BTreeMap<String, f64>,
+    /// Keyed by the link's devices in sorted order
+    pub links: BTreeMap<(String, String), f64>,
+}
+
+/// Estimate uptimes from availability samples `(component, up, time)`.
+///
+/// Each sample's state holds until the component's next sample, the last one
+/// until the end of the window, so irregular sampling is weighted by time.
+/// Time in the window before a component's first sample is not counted.
+/// Samples may come in any order.
+pub fn from_samples(
+    samples: &[(DeviceOrLink, bool, Timestamp)],
+    window: &UptimeWindow,
+) -> Result<UptimeEstimates> {
+    if let Some(end) = window.end
+        && end <= window.start
+    {
+        return Err(ShapleyError::Validation(format!(
+            "Uptime window [{}, {end}) is empty",
+            window.start
+        )));
+    }
+    let in_window = |time: Timestamp| time >= window.start && window.end.is_none_or(|e| time < e);
+    let Some(end) = window.end.or_else(|| {
+        samples
+            .iter()
+            .map(|&(_, _, time)| time)
+            .filter(|&time| in_window(time))
+            .max()
+    }) else {
+        return Ok(UptimeEstimates::default());
+    };
+
+    let mut by_component: BTreeMap<DeviceOrLink, Vec<(Timestamp, bool)>> = BTreeMap::new();
+    for (component, up, time) in samples {
+        if in_window(*time) {
+            by_component
+                .entry(component.key())
+                .or_default()
+                .push((*time, *up));
+        }
+    }
+
+    let mut estimates = UptimeEstimates::default();
+    for (component, mut states) in by_component {
+        states.sort_unstable();
+        if window.drop_isolated && states.len() > 2 {
+            let kept: Vec<(Timestamp, bool)> = (0..states.len())
+                .filter(|&i| {
+                    i == 0
+                        || i == states.len() - 1
+                        || states[i].1 == states[i - 1].1
+                        || states[i].1 == states[i + 1].1
+                })
+                .map(|i| states[i])
+                .collect();
+            states = kept;
+        }
+        // A component first sampled when an open window closes was observed
+        // for no time at all
+        if states.len() < window.min_samples.max(1) || states[0].0 == end {
+            continue;
+        }
+
+        let ends = states.iter().skip(1).map(|&(t, _)| t).chain([end]);
+        let up: u64 = states
+            .iter()
+            .zip(ends)
+            .filter(|((_, up), _)| *up)
+            .map(|(&(t, _), end)| end - t)
+            .sum();
+        let uptime = up as f64 / (end - states[0].0) as f64;
+
+        match component {
+            DeviceOrLink::Device(device) => {
+                estimates.devices.insert(device, uptime);
+            }
+            DeviceOrLink::Link(a, b) => {
+                estimates.links.insert((a, b), uptime);
+            }
+        }
+    }
+    Ok(estimates)
+}
+
+impl UptimeEstimates {
+    /// Mean uptime of each operator's devices that have an estimate
+    pub fn operator_uptime(&self, devices: &Devices) -> BTreeMap<Operator, f64> {
+        let mut totals: BTreeMap<Operator, (f64, usize)> = BTreeMap::new();
+        for device in devices {
+            if let Some(&uptime) = self.devices.get(&device.device) {
+                let (sum, count) = totals.entry(device.operator.clone()).or_default();
+                *sum += uptime;
+                *count += 1;
+            }
+        }
+        totals
+            .into_iter()
+            .map(|(op, (sum, count))| (op, sum / count as f64))
+            .collect()
+    }
+
+    /// Set the uptime of every private link with an estimate and, if any
+    /// operator has one, `operator_uptime` to the mean over operators.
+    /// Everything else is left as it is.
+    pub fn apply(&self, input: &mut ShapleyInput) {
+        for link in &mut input.private_links {
+            let key = if link.device1 <= link.device2 {
+                (link.device1.clone(), link.device2.clone())
+            } else {
+                (link.device2.clone(), link.device1.clone())
+            };
+            if let Some(&uptime) = self.links.get(&key) {
+                link.uptime = uptime;
+            }
+        }
+
+        let operators: HashMap<Operator, f64> = self
+            .operator_uptime(&input.devices)
+            .into_iter()
+            .filter(|(op, _)| op != "Private" && op != "Public")
+            .collect();
+        if !operators.is_empty() {
+            input.operator_uptime = operators.values().sum::<f64>() / operators.len() as f64;
+        }
+    }
+}
+
+/// Shapley values with every operator always up and at the configured
+/// `operator_uptime`, from one set of coalition solves, see
+/// [`ShapleyInput::uptime_report`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct UptimeReport {
+    /// Values at an operator uptime of 1
+    pub gross: ShapleyOutput,
+    /// Values at `ShapleyInput::operator_uptime`, as
+    /// [`ShapleyInput::compute_with`] returns them
+    pub adjusted: ShapleyOutput,
+    /// Adjusted minus gross value; negative for an operator whose value
+    /// drops when operators can be down
+    pub delta: BTreeMap<Operator, f64>,
+}
+
+impl UptimeReport {
+    pub(crate) fn new(gross: ShapleyOutput, adjusted: ShapleyOutput) -> Self {
+        let delta = adjusted
+            .iter()
+            .map(|(op, value)| (op.clone(), value.value - gross[op].value))
+            .collect();
+        Self {
+            gross,
+            adjusted,
+            delta,
+        }
+    }
+}
+
+/// Shapley values at each of several operator uptimes, from one set of
+/// coalition solves; see [`ShapleyInput::compute_for_uptimes`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UptimeSweep {
+    /// Values as [`ShapleyInput::compute_with`] returns them at each uptime,
+    /// by increasing uptime
+    pub values: Vec<(f64, ShapleyOutput)>,
+}
+
+impl UptimeSweep {
+    /// Values at `uptime`, if it was swept
+    pub fn get(&self, uptime: f64) -> Option<&ShapleyOutput> {
+        self.values
+            .iter()
+            .find(|(u, _)| *u == uptime)
+            .map(|(_, output)| output)
+    }
+
+    pub fn uptimes(&self) -> impl Iterator<Item = f64> + '_ {
+        self.values.iter().map(|(u, _)| *u)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Device, PrivateLink};
+
+    fn link(a: &str, b: &str) -> DeviceOrLink {
+        DeviceOrLink::Link(a.into(), b.into())
+    }
+
+    fn device(name: &str) -> DeviceOrLink {
+        DeviceOrLink::Device(name.into())
+    }
+
+    #[test]
+    fn test_from_samples() {
+        let samples = vec![
+            (link("NYC1", "LON1"), true, 0),
+            (link("LON1", "NYC1"), false, 60),
+            (link("NYC1", "LON1"), true, 90),
+            (device("NYC1"), true, 0),
+            (device("NYC1"), true, 30),
+            (device("NYC1"), false, 40),
+            (device("NYC1"), true, 50),
+            (device("NYC1"), true, 80),
+            // Outside the window
+            (device("LON1"), false, 150),
+        ];
+        let window = UptimeWindow::new(0, 120);
+        let estimates = from_samples(&samples, &window).unwrap();
+        assert_eq!(estimates.links[&("LON1".into(), "NYC1".into())], 0.75);
+        assert_eq!(estimates.devices["NYC1"], 110.0 / 120.0);
+        assert!(!estimates.devices.contains_key("LON1"));
+
+        let filtered = UptimeWindow {
+            drop_isolated: true,
+            ..window.clone()
+        };
+        let estimates = from_samples(&samples, &filtered).unwrap();
+        assert_eq!(estimates.devices["NYC1"], 1.0);
+        // The link's outage spans a single sample too
+        assert_eq!(estimates.links[&("LON1".into(), "NYC1".into())], 1.0);
+
+        let sparse = UptimeWindow {
+            min_samples: 4,
+            ..window
+        };
+        let estimates = from_samples(&samples, &sparse).unwrap();
+        assert!(estimates.links.is_empty());
+        assert_eq!(estimates.devices.len(), 1);
+
+        assert!(from_samples(&samples, &UptimeWindow::new(10, 10)).is_err());
+    }
+
+    #[test]
+    fn test_default_window_ends_at_last_sample() {
+        let samples = vec![
+            (device("NYC1"), true, 0),
+            (device("NYC1"), false, 30),
+            (device("NYC1"), true, 60),
+            (device("LON1"), true, 0),
+            (device("LON1"), true, 120),
+            (link("NYC1", "LON1"), true, 0),
+            (link("NYC1", "LON1"), false, 100),
+            (device("PAR1"), false, 120),
+        ];
+        let estimates = from_samples(&samples, &UptimeWindow::default()).unwrap();
+        // NYC1's last state holds until LON1's sample closes the window
+        assert_eq!(estimates.devices["NYC1"], 0.75);
+        assert_eq!(estimates.devices["LON1"], 1.0);
+        assert_eq!(
+            estimates.links[&("LON1".into(), "NYC1".into())],
+            100.0 / 120.0
+        );
+        assert!(!estimates.devices.contains_key("PAR1"));
+
+        let empty = from_samples(&[], &UptimeWindow::default()).unwrap();
+        assert!(empty.devices.is_empty() && empty.links.is_empty());
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut input = ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("NYC1".into(), "LON1".into(), 10.0, 10.0, 1.0, None),
+                PrivateLink::new("LON1".into(), "PAR1".into(), 10.0, 10.0, 0.9, None),
+            ],
+            devices: vec![
+                Device::new("NYC1".into(), 1, "Alpha".into()),
+                Device::new("LON1".into(), 1, "Alpha".into()),
+                Device::new("PAR1".into(), 1, "Beta".into()),
+            ],
+            demands: vec![],
+            public_links: vec![],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        };
+        let estimates = UptimeEstimates {
+            devices: BTreeMap::from([
+                ("NYC1".into(), 1.0),
+                ("LON1".into(), 0.9),
+                ("PAR1".into(), 0.85),
+            ]),
+            links: BTreeMap::from([(("LON1".into(), "NYC1".into()), 0.99)]),
+        };
+        estimates.apply(&mut input);
+        assert_eq!(input.private_links[0].uptime, 0.99);
+        assert_eq!(input.private_links[1].uptime, 0.9);
+        let alpha = estimates.operator_uptime(&input.devices)["Alpha"];
+        assert!((alpha - 0.95).abs() < 1e-12);
+        assert!((input.operator_uptime - 0.9).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,220 @@
+//! Parts of an input nothing uses, which validation lets through but which
+//! usually point at a typo, e.g. a public link to `FRK` meant for `FRA`, and
+//! demands given twice.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{shapley::ShapleyInput, types::Demands, utils::city_of};
+
+/// Devices, public links and cities of an input no demand can use, and
+/// duplicated demands; see
+/// [`ShapleyInput::usage_report`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputUsageReport {
+    /// Devices on no private link
+    pub unused_devices: Vec<String>,
+    /// Public links, as `(city1, city2)`, in a part of the network no demand
+    /// starts or ends in
+    pub unused_public_links: Vec<(String, String)>,
+    /// Cities no demand starts or ends in that link to at most one other
+    /// city, so no route passes through them either
+    pub unused_cities: Vec<String>,
+    /// Demands given in more than one row, whose traffic adds up unless
+    /// [`ShapleyOptions::duplicate_demands`](crate::options::ShapleyOptions::duplicate_demands)
+    /// says otherwise
+    pub duplicate_demands: Vec<DuplicateDemand>,
+}
+
+/// Rows of the demands table with the same start, end and type
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateDemand {
+    pub start: String,
+    pub end: String,
+    pub kind: u32,
+    /// Zero-based rows of the demands table
+    pub rows: Vec<usize>,
+}
+
+impl std::fmt::Display for DuplicateDemand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{} of type {} (rows {})",
+            self.start,
+            self.end,
+            self.kind,
+            self.rows
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// Every demand given in more than one row
+pub(crate) fn duplicate_demands(demands: &Demands) -> Vec<DuplicateDemand> {
+    let mut rows: BTreeMap<(&str, &str, u32), Vec<usize>> = BTreeMap::new();
+    for (row, demand) in demands.iter().enumerate() {
+        rows.entry((&demand.start, &demand.end, demand.kind))
+            .or_default()
+            .push(row);
+    }
+    rows.into_iter()
+        .filter(|(_, rows)| rows.len() > 1)
+        .map(|((start, end, kind), rows)| DuplicateDemand {
+            start: start.to_string(),
+            end: end.to_string(),
+            kind,
+            rows,
+        })
+        .collect()
+}
+
+impl InputUsageReport {
+    /// Whether every device, public link and city can be used and no demand
+    /// is duplicated
+    pub fn is_clean(&self) -> bool {
+        self.unused_devices.is_empty()
+            && self.unused_public_links.is_empty()
+            && self.unused_cities.is_empty()
+            && self.duplicate_demands.is_empty()
+    }
+
+    pub(crate) fn new(input: &ShapleyInput) -> Self {
+        let on_links: BTreeSet<&str> = input
+            .private_links
+            .iter()
+            .flat_map(|l| [l.device1.as_str(), l.device2.as_str()])
+            .collect();
+        let unused_devices = input
+            .devices
+            .iter()
+            .filter(|d| !on_links.contains(d.device.as_str()))
+            .map(|d| d.device.clone())
+            .collect();
+
+        // The network between cities: devices sit in their city, which they
+        // reach the public internet from
+        let edges = input
+            .public_links
+            .iter()
+            .map(|l| (l.city1.as_str(), l.city2.as_str()))
+            .chain(
+                input
+                    .private_links
+                    .iter()
+                    .map(|l| (city_of(&l.device1), city_of(&l.device2))),
+            );
+        let mut neighbours: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for (a, b) in edges {
+            neighbours.entry(a).or_default().insert(b);
+            neighbours.entry(b).or_default().insert(a);
+        }
+        for device in &input.devices {
+            neighbours.entry(city_of(&device.device)).or_default();
+        }
+        let endpoints: BTreeSet<&str> = input
+            .demands
+            .iter()
+            .flat_map(|d| [d.start.as_str(), d.end.as_str()])
+            .collect();
+
+        // Cities connected to a demand endpoint
+        let mut reached: BTreeSet<&str> = BTreeSet::new();
+        let mut stack: Vec<&str> = endpoints.iter().copied().collect();
+        while let Some(city) = stack.pop() {
+            if reached.insert(city) {
+                stack.extend(neighbours.get(city).into_iter().flatten().copied());
+            }
+        }
+        let unused_public_links = input
+            .public_links
+            .iter()
+            .filter(|l| !reached.contains(l.city1.as_str()))
+            .map(|l| (l.city1.clone(), l.city2.clone()))
+            .collect();
+        let unused_cities = neighbours
+            .iter()
+            .filter(|(city, next)| {
+                !endpoints.contains(*city) && next.iter().filter(|n| *n != *city).count() <= 1
+            })
+            .map(|(city, _)| city.to_string())
+            .collect();
+
+        Self {
+            unused_devices,
+            unused_public_links,
+            unused_cities,
+            duplicate_demands: duplicate_demands(&input.demands),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Demand, Device, PrivateLink, PublicLink};
+
+    fn input() -> ShapleyInput {
+        let public = |a: &str, b: &str| PublicLink::new(a.into(), b.into(), 10.0);
+        ShapleyInput {
+            private_links: vec![
+                PrivateLink::new("SIN1".into(), "FRA1".into(), 50.0, 10.0, 1.0, None),
+                PrivateLink::new("FRA1".into(), "AMS1".into(), 3.0, 10.0, 1.0, None),
+            ],
+            devices: vec![
+                Device::new("SIN1".into(), 1, "Alpha".into()),
+                Device::new("FRA1".into(), 1, "Alpha".into()),
+                Device::new("AMS1".into(), 1, "Beta".into()),
+            ],
+            demands: vec![Demand::new(
+                "SIN".into(),
+                "AMS".into(),
+                1,
+                1.0,
+                1.0,
+                1,
+                false,
+            )],
+            public_links: vec![public("SIN", "FRA"), public("FRA", "AMS")],
+            operator_uptime: 1.0,
+            contiguity_bonus: 5.0,
+            demand_multiplier: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_usage_report() {
+        // FRA is on the way from SIN to AMS
+        assert!(input().usage_report().is_clean());
+
+        let mut input = input();
+        input
+            .devices
+            .push(Device::new("LON1".into(), 1, "Beta".into()));
+        input
+            .public_links
+            .push(PublicLink::new("AMS".into(), "FRK".into(), 5.0));
+        input
+            .public_links
+            .push(PublicLink::new("NYC".into(), "CHI".into(), 5.0));
+        let report = input.usage_report();
+        assert_eq!(report.unused_devices, vec!["LON1".to_string()]);
+        assert_eq!(
+            report.unused_public_links,
+            vec![("NYC".to_string(), "CHI".to_string())]
+        );
+        assert_eq!(report.unused_cities, vec!["CHI", "FRK", "LON", "NYC"]);
+
+        input.demands.push(input.demands[0].clone());
+        let duplicates = input.usage_report().duplicate_demands;
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].to_string(), "SIN-AMS of type 1 (rows 0, 1)");
+    }
+}
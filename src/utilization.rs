@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    shapley::{Operator, ShapleyOutput},
+    types::ConsolidatedLink,
+};
+
+/// Traffic a consolidated link carries in the grand coalition's optimal
+/// routing, and the share of Shapley value attributed to it
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct LinkUtilization {
+    pub link: ConsolidatedLink,
+    /// Traffic routed over the link, summed over traffic types
+    pub flow: f64,
+    /// `flow / bandwidth`; `None` for links without a capacity
+    pub utilization: Option<f64>,
+    /// Part of the Shapley values of the link's operators earned by this link
+    pub earned_value: f64,
+}
+
+/// Per-link flows of the grand coalition with Shapley value attributed to links.
+///
+/// Each operator's value is spread over the links it operates in proportion to
+/// their flow; a link between two operators counts half for each. Operators
+/// whose links carry no traffic have no value attributed to any link.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct UtilizationReport {
+    pub links: Vec<LinkUtilization>,
+}
+
+impl UtilizationReport {
+    pub(crate) fn new(links: Vec<ConsolidatedLink>, flows: &[f64], values: &ShapleyOutput) -> Self {
+        let shares = |link: &ConsolidatedLink| -> Vec<(Operator, f64)> {
+            let ops = [&link.operator1, &link.operator2];
+            ops.iter()
+                .filter(|op| values.contains_key(op.as_str()))
+                .map(|&op| (op.clone(), 0.5))
+                .collect()
+        };
+
+        let mut operator_flow: BTreeMap<Operator, f64> = BTreeMap::new();
+        for (link, &flow) in links.iter().zip(flows) {
+            for (op, share) in shares(link) {
+                *operator_flow.entry(op).or_default() += share * flow;
+            }
+        }
+
+        let links = links
+            .into_iter()
+            .zip(flows)
+            .map(|(link, &flow)| {
+                let earned_value = shares(&link)
+                    .into_iter()
+                    .filter(|(op, _)| operator_flow[op] > 0.0)
+                    .map(|(op, share)| values[&op].value * share * flow / operator_flow[&op])
+                    .sum();
+                LinkUtilization {
+                    utilization: (link.bandwidth > 0.0).then(|| flow / link.bandwidth),
+                    link,
+                    flow,
+                    earned_value,
+                }
+            })
+            .collect();
+
+        Self { links }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{shapley::ShapleyValue, types::LinkProvenance};
+
+    fn link(operator1: &str, operator2: &str) -> ConsolidatedLink {
+        ConsolidatedLink {
+            device1: "FRA1".to_string(),
+            device2: "AMS1".to_string(),
+            latency: 1.0,
+            bandwidth: 10.0,
+            operator1: operator1.to_string(),
+            operator2: operator2.to_string(),
+            shared: 0,
+            link_type: 0,
+            provenance: LinkProvenance::OriginalPrivate,
+            multicast_capable: true,
+        }
+    }
+
+    #[test]
+    fn test_value_is_attributed_by_flow() {
+        let values: ShapleyOutput = [("A", 6.0), ("B", 2.0)]
+            .into_iter()
+            .map(|(op, value)| {
+                (
+                    op.to_string(),
+                    ShapleyValue {
+                        value,
+                        proportion: value / 8.0,
+                    },
+                )
+            })
+            .collect();
+        let links = vec![link("A", "A"), link("A", "B"), link("Public", "Public")];
+        let report = UtilizationReport::new(links, &[4.0, 2.0, 5.0], &values);
+
+        // A: 4 + 1 units of flow; B: 1 unit, all on the shared link
+        assert!((report.links[0].earned_value - 4.8).abs() < 1e-9);
+        assert!((report.links[1].earned_value - (1.2 + 2.0)).abs() < 1e-9);
+        assert_eq!(report.links[2].earned_value, 0.0);
+        assert_eq!(report.links[0].utilization, Some(0.4));
+
+        let total: f64 = report.links.iter().map(|l| l.earned_value).sum();
+        assert!((total - 8.0).abs() < 1e-9);
+    }
+}
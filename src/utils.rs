@@ -1,27 +1,84 @@
+/// Owner of infrastructure that is in every coalition and credited to no
+/// operator, e.g. foundation-owned devices
+pub(crate) const PRIVATE_OPERATOR: &str = "Private";
+
+/// Whether links of `operator` are in every coalition: the public internet,
+/// [`PRIVATE_OPERATOR`] infrastructure and links without an owner
+pub(crate) fn is_always_present(operator: &str) -> bool {
+    operator == "Public" || operator == PRIVATE_OPERATOR || operator.is_empty()
+}
+
 /// Check if a string contains any digit
 pub(crate) fn has_digit(s: &str) -> bool {
     s.chars().any(|c| c.is_ascii_digit())
 }
 
-/// Calculate factorial (cached for small values)
-pub(crate) const FACTORIAL_LIMIT: usize = 21;
-pub(crate) const FACTORIALS: [u64; FACTORIAL_LIMIT] = {
-    let mut facts = [1u64; FACTORIAL_LIMIT];
-    let mut i = 1;
-    while i < FACTORIAL_LIMIT {
-        facts[i] = facts[i - 1] * (i as u64);
-        i += 1;
+/// Number of leading characters of a device name that give its city
+const CITY_CODE_LEN: usize = 3;
+
+/// City of a device: its first three characters, as `name[:3]` in the
+/// reference implementation. Names of three characters or fewer are their
+/// own city, so a device named `A1` sits in city `A1` and never meets the
+/// public internet, whose cities contain no digits. Characters are counted,
+/// not bytes, so non-ASCII names are never split inside a character.
+pub(crate) fn city_of(name: &str) -> &str {
+    match name.char_indices().nth(CITY_CODE_LEN) {
+        Some((idx, _)) => &name[..idx],
+        None => name,
     }
-    facts
-};
+}
+
+/// What follows the city in a node name: `1` for device `FRA1`, `00` for
+/// public node `FRA00`, empty for city `FRA`
+pub(crate) fn device_suffix(name: &str) -> &str {
+    &name[city_of(name).len()..]
+}
 
-pub(crate) fn factorial(n: usize) -> f64 {
-    if n < FACTORIAL_LIMIT {
-        FACTORIALS[n] as f64
-    } else {
-        // Use Stirling's approximation for large n
-        let n_f64 = n as f64;
-        (2.0 * std::f64::consts::PI * n_f64).sqrt() * (n_f64 / std::f64::consts::E).powf(n_f64)
+/// Whether a node replicates multicast traffic, going by its name alone:
+/// devices do, cities and public nodes (suffix `00`) do not
+pub(crate) fn multicast_capable_node(name: &str) -> bool {
+    let suffix = device_suffix(name);
+    !suffix.is_empty() && suffix != "00"
+}
+
+/// Name of the node where traffic enters the public internet in `city`
+pub(crate) fn public_node(city: &str) -> String {
+    format!("{city}00")
+}
+
+/// Number of distinct [`letter_code`]s
+pub(crate) const LETTER_CODES: usize = 26 * 26 * 26;
+
+/// Three capital letters, usable as a city code; unique per index below
+/// [`LETTER_CODES`]
+pub(crate) fn letter_code(index: usize) -> String {
+    let letter = |i: usize| char::from(b'A' + (i % 26) as u8);
+    [index / 676, index / 26, index]
+        .into_iter()
+        .map(letter)
+        .collect()
+}
+
+/// SplitMix64 pseudo-random generator: small, seedable and reproducible across platforms
+#[derive(Debug, Clone)]
+pub(crate) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
     }
 }
 
@@ -38,9 +95,36 @@ mod tests {
     }
 
     #[test]
-    fn test_factorial() {
-        assert_eq!(factorial(0), 1.0);
-        assert_eq!(factorial(5), 120.0);
-        assert_eq!(factorial(10), 3628800.0);
+    fn test_city_of() {
+        assert_eq!(city_of("FRA1"), "FRA");
+        assert_eq!(city_of("FRA00"), "FRA");
+        assert_eq!(city_of("FRA"), "FRA");
+        // Short names are their own city
+        assert_eq!(city_of("A1"), "A1");
+        assert_eq!(city_of(""), "");
+        // Multibyte characters count once
+        assert_eq!(city_of("ZÜR1"), "ZÜR");
+        assert_eq!(device_suffix("ZÜR1"), "1");
+        assert_eq!(device_suffix("A1"), "");
+        assert_eq!(device_suffix("FRA00"), "00");
+        assert_eq!(public_node("ZÜR"), "ZÜR00");
+    }
+
+    #[test]
+    fn test_letter_code() {
+        assert_eq!(letter_code(0), "AAA");
+        assert_eq!(letter_code(27), "ABB");
+        assert_eq!(letter_code(LETTER_CODES - 1), "ZZZ");
+    }
+
+    #[test]
+    fn test_splitmix_is_reproducible() {
+        let mut a = SplitMix64::new(7);
+        let mut b = SplitMix64::new(7);
+        for _ in 0..100 {
+            let x = a.next_f64();
+            assert_eq!(x, b.next_f64());
+            assert!((0.0..1.0).contains(&x));
+        }
     }
 }
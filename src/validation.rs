@@ -1,28 +1,35 @@
-use std::collections::HashSet;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+};
 
 use crate::{
-    error::{Result, ShapleyError},
-    types::{Demands, Devices, PrivateLinks, PublicLinks},
+    error::{ErrorContext, InputTable, Result, ShapleyError},
+    options::{
+        CapacityBasis, CapacityReservation, CongestionCurve, DuplicateDemandPolicy,
+        DuplicateLinkPolicy, LabelNormalization, LatencyMetric, ShapleyOptions, Units,
+    },
+    rules::{BuiltinRule, RuleInput, ValidationRules},
+    shapley::ShapleyInput,
+    types::{BandwidthUnit, Demand, Demands, Devices, PrivateLink, PrivateLinks, PublicLinks},
+    usage::duplicate_demands,
     utils::has_digit,
 };
 
-/// Validate all inputs for network shapley computation
+/// Operators addressable by a coalition bitmask (bit 31 is reserved)
+pub(crate) const MAX_SAMPLED_OPERATORS: usize = 31;
+
+/// Validate all inputs for network shapley computation: the operator count,
+/// which is never optional, then every rule of `rules`. With `sampling`, the
+/// operator count is only bounded by [`MAX_SAMPLED_OPERATORS`].
 pub(crate) fn check_inputs(
     private_links: &PrivateLinks,
     devices: &Devices,
     demands: &Demands,
     public_links: &PublicLinks,
-    operator_uptime: f64,
+    sampling: bool,
+    rules: &ValidationRules,
 ) -> Result<()> {
-    // Check for "Public" operator name before filtering
-    for device in devices {
-        if device.operator == "Public" {
-            return Err(ShapleyError::Validation(
-                "Public is a protected keyword for operator names; choose another.".to_string(),
-            ));
-        }
-    }
-
     // Check operator count (excluding "Private" and "Public")
     let operators: HashSet<&str> = devices
         .iter()
@@ -31,11 +38,11 @@ pub(crate) fn check_inputs(
         .collect();
 
     let n_ops = operators.len();
-    if operator_uptime < 1.0 {
-        if n_ops >= 16 {
+    if sampling {
+        if n_ops > MAX_SAMPLED_OPERATORS {
             return Err(ShapleyError::TooManyOperators {
                 count: n_ops,
-                limit: 15,
+                limit: MAX_SAMPLED_OPERATORS,
             });
         }
     } else if n_ops >= 21 {
@@ -45,6 +52,90 @@ pub(crate) fn check_inputs(
         });
     }
 
+    rules.check(&RuleInput {
+        private_links,
+        devices,
+        demands,
+        public_links,
+    })
+}
+
+/// The default rules, in the order they run
+pub(crate) const BUILTIN_RULES: &[BuiltinRule] = &[
+    BuiltinRule {
+        name: "public_operator",
+        check: check_public_operator,
+        disableable: true,
+    },
+    BuiltinRule {
+        name: "private_links_present",
+        check: check_private_links_present,
+        disableable: false,
+    },
+    BuiltinRule {
+        name: "city_labels",
+        check: check_city_labels,
+        disableable: false,
+    },
+    BuiltinRule {
+        name: "demand_types",
+        check: check_demand_types,
+        disableable: false,
+    },
+    BuiltinRule {
+        name: "demand_receivers",
+        check: check_demand_receivers,
+        disableable: true,
+    },
+    BuiltinRule {
+        name: "unique_devices",
+        check: check_unique_devices,
+        disableable: false,
+    },
+    BuiltinRule {
+        name: "device_names",
+        check: check_device_names,
+        disableable: false,
+    },
+    BuiltinRule {
+        name: "link_devices",
+        check: check_link_devices,
+        disableable: false,
+    },
+    BuiltinRule {
+        name: "link_bundles",
+        check: check_link_bundles,
+        disableable: false,
+    },
+    BuiltinRule {
+        name: "reachable_demands",
+        check: check_reachable_demands,
+        disableable: false,
+    },
+];
+
+/// No operator is named `Public`, the owner of public links
+fn check_public_operator(input: &RuleInput<'_>) -> Result<()> {
+    let RuleInput { devices, .. } = *input;
+    // Check for "Public" operator name before filtering
+    for (row, device) in devices.iter().enumerate() {
+        if device.operator == "Public" {
+            return Err(ShapleyError::Validation(
+                "Public is a protected keyword for operator names; choose another.".to_string(),
+            )
+            .with_context(
+                ErrorContext::cell(InputTable::Devices, row, "operator")
+                    .with_device(&device.device),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// There is at least one private link
+fn check_private_links_present(input: &RuleInput<'_>) -> Result<()> {
+    let RuleInput { private_links, .. } = *input;
     // Check that private links table is labeled correctly
     if private_links.is_empty() {
         return Err(ShapleyError::Validation(
@@ -52,102 +143,750 @@ pub(crate) fn check_inputs(
         ));
     }
 
+    Ok(())
+}
+
+/// City codes of public links and demands contain no digit, so they cannot be
+/// mistaken for device names
+fn check_city_labels(input: &RuleInput<'_>) -> Result<()> {
+    let RuleInput {
+        demands,
+        public_links,
+        ..
+    } = *input;
     // Check that public links table is labeled correctly
-    for link in public_links {
+    for (row, link) in public_links.iter().enumerate() {
         if has_digit(&link.city1) {
             return Err(ShapleyError::InvalidCityLabel(format!(
                 "City {} should not contain a digit",
                 link.city1
-            )));
+            ))
+            .with_context(ErrorContext::cell(InputTable::PublicLinks, row, "city1")));
         }
         if has_digit(&link.city2) {
             return Err(ShapleyError::InvalidCityLabel(format!(
                 "City {} should not contain a digit",
                 link.city2
-            )));
+            ))
+            .with_context(ErrorContext::cell(InputTable::PublicLinks, row, "city2")));
         }
     }
 
     // Check that demand points are labeled correctly
-    for demand in demands {
+    for (row, demand) in demands.iter().enumerate() {
         if has_digit(&demand.start) {
             return Err(ShapleyError::InvalidCityLabel(format!(
                 "City {} should not contain a digit",
                 demand.start
-            )));
+            ))
+            .with_context(ErrorContext::cell(InputTable::Demands, row, "start")));
         }
         if has_digit(&demand.end) {
             return Err(ShapleyError::InvalidCityLabel(format!(
                 "City {} should not contain a digit",
                 demand.end
-            )));
+            ))
+            .with_context(ErrorContext::cell(InputTable::Demands, row, "end")));
         }
     }
 
-    // Check that for a given demand type, there is a single origin, size, and multicast flag
-    use std::collections::HashMap;
-    let mut type_info: HashMap<u32, (&str, f64, bool)> = HashMap::new();
+    Ok(())
+}
+
+/// Demands of one type share their origin, traffic, multicast flag and
+/// priority class
+fn check_demand_types(input: &RuleInput<'_>) -> Result<()> {
+    let RuleInput { demands, .. } = *input;
+    // Check that for a given demand type, there is a single origin, size,
+    // multicast flag, and priority class
+    let mut type_info: HashMap<u32, (&str, f64, bool, u32)> = HashMap::new();
 
-    for demand in demands {
+    for (row, demand) in demands.iter().enumerate() {
         match type_info.get(&demand.kind) {
-            Some(&(start, traffic, multicast)) => {
+            Some(&(start, traffic, multicast, class)) => {
                 if start != demand.start.as_str()
                     || traffic != demand.traffic
                     || multicast != demand.multicast
+                    || class != demand.priority_class()
                 {
                     return Err(ShapleyError::DataInconsistency(format!(
                         "Demand type {} has inconsistent properties",
                         demand.kind
-                    )));
+                    ))
+                    .with_context(ErrorContext::row(InputTable::Demands, row)));
                 }
             }
             None => {
                 type_info.insert(
                     demand.kind,
-                    (demand.start.as_str(), demand.traffic, demand.multicast),
+                    (
+                        demand.start.as_str(),
+                        demand.traffic,
+                        demand.multicast,
+                        demand.priority_class(),
+                    ),
                 );
             }
         }
     }
 
+    Ok(())
+}
+
+/// Every demand has a receiver. Traffic is scaled by the receiver count, so
+/// a demand without one would silently ask for nothing, and a multicast
+/// group always has at least as many receivers as rows.
+fn check_demand_receivers(input: &RuleInput<'_>) -> Result<()> {
+    let RuleInput { demands, .. } = *input;
+    for (row, demand) in demands.iter().enumerate() {
+        if demand.receivers == 0 {
+            return Err(ShapleyError::Validation(format!(
+                "Demand {}-{} of type {} has no receivers",
+                demand.start, demand.end, demand.kind
+            ))
+            .with_context(ErrorContext::cell(InputTable::Demands, row, "receivers")));
+        }
+    }
+    Ok(())
+}
+
+/// Every device is listed once
+fn check_unique_devices(input: &RuleInput<'_>) -> Result<()> {
+    let RuleInput { devices, .. } = *input;
     // Check there are no duplicate devices
     let device_names: Vec<&str> = devices.iter().map(|d| d.device.as_str()).collect();
-    let unique_devices: HashSet<&str> = device_names.iter().cloned().collect();
-    if device_names.len() != unique_devices.len() {
-        return Err(ShapleyError::DataInconsistency(
-            "There are duplicated devices in the list.".to_string(),
-        ));
+    let mut unique_devices: HashSet<&str> = HashSet::with_capacity(device_names.len());
+    for (row, &device) in device_names.iter().enumerate() {
+        if !unique_devices.insert(device) {
+            return Err(ShapleyError::DataInconsistency(
+                "There are duplicated devices in the list.".to_string(),
+            )
+            .with_context(
+                ErrorContext::cell(InputTable::Devices, row, "device").with_device(device),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Device names are non-empty and differ from every city code
+fn check_device_names(input: &RuleInput<'_>) -> Result<()> {
+    let RuleInput {
+        devices,
+        demands,
+        public_links,
+        ..
+    } = *input;
+    let device_names: Vec<&str> = devices.iter().map(|d| d.device.as_str()).collect();
+    // Check that device names cannot be mistaken for cities: a device's city
+    // is its first three characters, and a device named exactly like a city
+    // would share that city's node in the routing graph
+    let cities: HashSet<&str> = public_links
+        .iter()
+        .flat_map(|link| [link.city1.as_str(), link.city2.as_str()])
+        .chain(
+            demands
+                .iter()
+                .flat_map(|d| [d.start.as_str(), d.end.as_str()]),
+        )
+        .collect();
+    for (row, device) in device_names.iter().enumerate() {
+        if device.is_empty() {
+            return Err(
+                ShapleyError::Validation("Device names must not be empty".to_string())
+                    .with_context(ErrorContext::cell(InputTable::Devices, row, "device")),
+            );
+        }
+        if cities.contains(device) {
+            return Err(ShapleyError::Validation(format!(
+                "Device {device} has the same name as a city; add an identifier after the city code, e.g. {device}1"
+            ))
+            .with_context(
+                ErrorContext::cell(InputTable::Devices, row, "device").with_device(device),
+            ));
+        }
     }
 
+    Ok(())
+}
+
+/// Every device of a private link is listed in the devices table
+fn check_link_devices(input: &RuleInput<'_>) -> Result<()> {
+    let RuleInput {
+        private_links,
+        devices,
+        ..
+    } = *input;
     // Check that every device in private_links appears in devices
     let device_set: HashSet<&str> = devices.iter().map(|d| d.device.as_str()).collect();
-    for link in private_links {
-        if !device_set.contains(link.device1.as_str()) {
-            return Err(ShapleyError::MissingDevice(link.device1.clone()));
+    for (row, link) in private_links.iter().enumerate() {
+        for (column, device) in [("device1", &link.device1), ("device2", &link.device2)] {
+            if !device_set.contains(device.as_str()) {
+                return Err(ShapleyError::MissingDevice(device.clone()).with_context(
+                    ErrorContext::cell(InputTable::PrivateLinks, row, column).with_device(device),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Members of a link bundle join the same devices and share a shared ID
+fn check_link_bundles(input: &RuleInput<'_>) -> Result<()> {
+    let RuleInput { private_links, .. } = *input;
+    // Check that link bundle members are parallel links sharing capacity settings
+    let mut bundles: HashMap<u32, (&str, &str, Option<u32>)> = HashMap::new();
+    for (row, link) in private_links.iter().enumerate() {
+        let Some(bundle) = link.bundle else { continue };
+        let (a, b) = if link.device1 <= link.device2 {
+            (link.device1.as_str(), link.device2.as_str())
+        } else {
+            (link.device2.as_str(), link.device1.as_str())
+        };
+        let &mut (first_a, first_b, shared) = bundles.entry(bundle).or_insert((a, b, link.shared));
+        if (first_a, first_b) != (a, b) {
+            return Err(ShapleyError::DataInconsistency(format!(
+                "Link bundle {bundle} has members with different endpoints ({first_a}-{first_b} and {a}-{b})"
+            ))
+            .with_context(ErrorContext::cell(InputTable::PrivateLinks, row, "bundle")));
         }
-        if !device_set.contains(link.device2.as_str()) {
-            return Err(ShapleyError::MissingDevice(link.device2.clone()));
+        if shared != link.shared {
+            return Err(ShapleyError::DataInconsistency(format!(
+                "Link bundle {bundle} has members with different shared IDs"
+            ))
+            .with_context(ErrorContext::cell(InputTable::PrivateLinks, row, "shared")));
         }
     }
 
+    Ok(())
+}
+
+/// Every demand endpoint is a city of the public network
+fn check_reachable_demands(input: &RuleInput<'_>) -> Result<()> {
+    let RuleInput {
+        demands,
+        public_links,
+        ..
+    } = *input;
     // Check that all demand nodes are reachable by the public network
     let public_nodes: HashSet<&str> = public_links
         .iter()
         .flat_map(|link| [link.city1.as_str(), link.city2.as_str()])
         .collect();
 
-    for demand in demands {
-        if !public_nodes.contains(demand.start.as_str()) {
-            return Err(ShapleyError::UnreachableDemandNode(demand.start.clone()));
+    for (row, demand) in demands.iter().enumerate() {
+        for (column, city) in [("start", &demand.start), ("end", &demand.end)] {
+            if !public_nodes.contains(city.as_str()) {
+                return Err(ShapleyError::UnreachableDemandNode(city.clone())
+                    .with_context(ErrorContext::cell(InputTable::Demands, row, column)));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Find demands given more than once, i.e. rows with the same start, end and
+/// type, and handle them per `policy`. Merged demands take the place of
+/// their group's first row and keep its other fields, which must agree
+/// across the group.
+pub(crate) fn resolve_duplicate_demands(
+    demands: &Demands,
+    policy: DuplicateDemandPolicy,
+) -> Result<Cow<'_, Demands>> {
+    if policy == DuplicateDemandPolicy::Keep {
+        return Ok(Cow::Borrowed(demands));
+    }
+    let duplicates = duplicate_demands(demands);
+    if duplicates.is_empty() {
+        return Ok(Cow::Borrowed(demands));
+    }
+    if policy == DuplicateDemandPolicy::Error {
+        let groups: Vec<String> = duplicates.iter().map(|d| d.to_string()).collect();
+        return Err(ShapleyError::DataInconsistency(format!(
+            "Demands given more than once: {}",
+            groups.join(", ")
+        )));
+    }
+
+    let mut merged = demands.clone();
+    let mut dropped: HashSet<usize> = HashSet::new();
+    for duplicate in &duplicates {
+        let first = &demands[duplicate.rows[0]];
+        let rows = duplicate.rows.iter().map(|&row| &demands[row]);
+        if rows.clone().any(|d| {
+            (d.receivers, d.priority, d.multicast, d.class)
+                != (
+                    first.receivers,
+                    first.priority,
+                    first.multicast,
+                    first.class,
+                )
+        }) {
+            return Err(ShapleyError::DataInconsistency(format!(
+                "Duplicate demands {duplicate} differ in receivers, priority, multicast or class and cannot be merged"
+            )));
+        }
+        let traffic = rows.map(|d| d.traffic);
+        merged[duplicate.rows[0]].traffic = match policy {
+            DuplicateDemandPolicy::MergeMax => traffic.fold(f64::NEG_INFINITY, f64::max),
+            _ => traffic.sum(),
+        };
+        dropped.extend(&duplicate.rows[1..]);
+    }
+    let merged = merged
+        .into_iter()
+        .enumerate()
+        .filter(|(row, _)| !dropped.contains(row))
+        .map(|(_, demand)| demand)
+        .collect();
+    Ok(Cow::Owned(merged))
+}
+
+/// Find private links given more than once, i.e. unbundled links between the
+/// same pair of devices in either direction, and handle them per `policy`.
+/// Merging turns each group into a link bundle under a fresh bundle ID, so it
+/// is consolidated into one link with the lowest latency and summed bandwidth.
+pub(crate) fn resolve_duplicate_links(
+    private_links: &PrivateLinks,
+    policy: DuplicateLinkPolicy,
+) -> Result<Cow<'_, PrivateLinks>> {
+    if policy == DuplicateLinkPolicy::Keep {
+        return Ok(Cow::Borrowed(private_links));
+    }
+
+    let mut groups: BTreeMap<(&str, &str), Vec<usize>> = BTreeMap::new();
+    for (idx, link) in private_links.iter().enumerate() {
+        if link.bundle.is_some() {
+            continue;
+        }
+        let (a, b) = if link.device1 <= link.device2 {
+            (link.device1.as_str(), link.device2.as_str())
+        } else {
+            (link.device2.as_str(), link.device1.as_str())
+        };
+        groups.entry((a, b)).or_default().push(idx);
+    }
+    let duplicates: Vec<(&(&str, &str), &Vec<usize>)> =
+        groups.iter().filter(|(_, idx)| idx.len() > 1).collect();
+    if duplicates.is_empty() {
+        return Ok(Cow::Borrowed(private_links));
+    }
+
+    if policy == DuplicateLinkPolicy::Error {
+        let pairs: Vec<String> = duplicates
+            .iter()
+            .map(|((a, b), idx)| format!("{a}-{b} ({} rows)", idx.len()))
+            .collect();
+        return Err(ShapleyError::DataInconsistency(format!(
+            "Private links given more than once: {}",
+            pairs.join(", ")
+        )));
+    }
+
+    let mut merged = private_links.clone();
+    let mut next_bundle = private_links
+        .iter()
+        .filter_map(|l| l.bundle)
+        .max()
+        .map_or(0, |b| b + 1);
+    for ((a, b), idx) in duplicates {
+        if idx
+            .iter()
+            .any(|&i| private_links[i].shared != private_links[idx[0]].shared)
+        {
+            return Err(ShapleyError::DataInconsistency(format!(
+                "Duplicate links {a}-{b} have different shared IDs and cannot be merged"
+            )));
+        }
+        for &i in idx {
+            merged[i].bundle = Some(next_bundle);
+        }
+        next_bundle += 1;
+    }
+    Ok(Cow::Owned(merged))
+}
+
+/// Check the measured link capacities and, under [`CapacityBasis::Measured`],
+/// put them in place of the declared ones. Links without a measurement keep
+/// their declared bandwidth.
+pub(crate) fn apply_capacity_basis(
+    private_links: &PrivateLinks,
+    basis: CapacityBasis,
+) -> Result<Cow<'_, PrivateLinks>> {
+    if let Some(link) = private_links.iter().find(|link| {
+        link.measured_bandwidth
+            .is_some_and(|b| !(b.is_finite() && b >= 0.0))
+    }) {
+        return Err(ShapleyError::Validation(format!(
+            "Private link {}-{} needs a finite, non-negative measured bandwidth",
+            link.device1, link.device2
+        )));
+    }
+    if basis == CapacityBasis::Declared
+        || private_links.iter().all(|l| l.measured_bandwidth.is_none())
+    {
+        return Ok(Cow::Borrowed(private_links));
+    }
+    Ok(Cow::Owned(
+        private_links
+            .iter()
+            .map(|link| PrivateLink {
+                bandwidth: link.measured_bandwidth.unwrap_or(link.bandwidth),
+                ..link.clone()
+            })
+            .collect(),
+    ))
+}
+
+/// Check the latency percentiles and put the statistic `metric` asks for in
+/// place of each link's mean latency
+pub(crate) fn apply_latency_metric(
+    private_links: &PrivateLinks,
+    metric: LatencyMetric,
+) -> Result<Cow<'_, PrivateLinks>> {
+    for link in private_links {
+        let given = [link.latency_p50, link.latency_p95, link.latency_p99];
+        if given
+            .iter()
+            .flatten()
+            .any(|l| !(l.is_finite() && *l >= 0.0))
+            || given
+                .iter()
+                .flatten()
+                .zip(given.iter().flatten().skip(1))
+                .any(|(lower, upper)| lower > upper)
+        {
+            return Err(ShapleyError::Validation(format!(
+                "Private link {}-{} needs finite, non-negative, non-decreasing latency percentiles",
+                link.device1, link.device2
+            )));
+        }
+    }
+
+    let pick: fn(&PrivateLink) -> Option<f64> = match metric {
+        LatencyMetric::Mean => return Ok(Cow::Borrowed(private_links)),
+        LatencyMetric::P50 => |l| l.latency_p50,
+        LatencyMetric::P95 => |l| l.latency_p95,
+        LatencyMetric::P99 => |l| l.latency_p99,
+        LatencyMetric::RiskWeighted { risk } => {
+            if !(0.0..=1.0).contains(&risk) {
+                return Err(ShapleyError::Validation(format!(
+                    "Latency risk weight {risk} is outside [0, 1]"
+                )));
+            }
+            return Ok(Cow::Owned(
+                private_links
+                    .iter()
+                    .map(|link| PrivateLink {
+                        latency: (1.0 - risk) * link.latency
+                            + risk * link.latency_p99.unwrap_or(link.latency),
+                        ..link.clone()
+                    })
+                    .collect(),
+            ));
+        }
+    };
+    Ok(Cow::Owned(
+        private_links
+            .iter()
+            .map(|link| PrivateLink {
+                latency: pick(link).unwrap_or(link.latency),
+                ..link.clone()
+            })
+            .collect(),
+    ))
+}
+
+/// Rewrite every city code and device name per `options.labels`, in the
+/// input tables and in the reservations, unrewarded links and regions of
+/// `options`
+pub(crate) fn normalize_labels<'a>(
+    input: &'a ShapleyInput,
+    options: &'a ShapleyOptions,
+) -> (Cow<'a, ShapleyInput>, Cow<'a, ShapleyOptions>) {
+    let labels = options.labels;
+    if labels.is_identity() {
+        return (Cow::Borrowed(input), Cow::Borrowed(options));
+    }
+    let fix = |label: &mut String| {
+        if let Cow::Owned(fixed) = labels.apply(label) {
+            *label = fixed;
+        }
+    };
+
+    let mut input = input.clone();
+    for link in &mut input.private_links {
+        fix(&mut link.device1);
+        fix(&mut link.device2);
+    }
+    for device in &mut input.devices {
+        fix(&mut device.device);
+    }
+    for demand in &mut input.demands {
+        fix(&mut demand.start);
+        fix(&mut demand.end);
+    }
+    for link in &mut input.public_links {
+        fix(&mut link.city1);
+        fix(&mut link.city2);
+    }
+
+    let mut options = options.clone();
+    for reservation in &mut options.reservations {
+        fix(&mut reservation.device1);
+        fix(&mut reservation.device2);
+    }
+    for (device1, device2) in &mut options.unrewarded_links {
+        fix(device1);
+        fix(device2);
+    }
+    for curve in &mut options.congestion {
+        fix(&mut curve.device1);
+        fix(&mut curve.device2);
+    }
+    options.contiguity_bonus_by_city = options
+        .contiguity_bonus_by_city
+        .iter()
+        .map(|(city, bonus)| (normalized(labels, city), *bonus))
+        .collect();
+    options.regions = options
+        .regions
+        .iter()
+        .map(|(region, cities)| {
+            let cities = cities
+                .iter()
+                .map(|(city, weight)| (normalized(labels, city), *weight))
+                .collect();
+            (normalized(labels, region), cities)
+        })
+        .collect();
+    (Cow::Owned(input), Cow::Owned(options))
+}
+
+fn normalized(labels: LabelNormalization, label: &str) -> String {
+    labels.apply(label).into_owned()
+}
+
+/// Check that every reservation names a private link and a valid rate, and
+/// convert the rates to [`CANONICAL_UNIT`] where their link has a unit
+pub(crate) fn normalize_reservations(
+    reservations: &mut [CapacityReservation],
+    private_links: &PrivateLinks,
+    units: &Units,
+) -> Result<()> {
+    for reservation in reservations {
+        let Some(link) = private_links.iter().find(|l| {
+            (l.device1 == reservation.device1 && l.device2 == reservation.device2)
+                || (l.device1 == reservation.device2 && l.device2 == reservation.device1)
+        }) else {
+            return Err(ShapleyError::Validation(format!(
+                "Capacity reservation on {}-{}, which is not a private link",
+                reservation.device1, reservation.device2
+            )));
+        };
+        if !(reservation.bandwidth.is_finite() && reservation.bandwidth >= 0.0) {
+            return Err(ShapleyError::Validation(format!(
+                "Capacity reservation on {}-{} needs a finite, non-negative bandwidth",
+                reservation.device1, reservation.device2
+            )));
+        }
+        if let Some(unit) = link.unit.or(units.bandwidth) {
+            reservation.bandwidth = unit.convert(reservation.bandwidth, CANONICAL_UNIT);
+        }
+    }
+    Ok(())
+}
+
+/// Check that every unrewarded link names a private link
+pub(crate) fn check_unrewarded_links(
+    unrewarded: &[(String, String)],
+    private_links: &PrivateLinks,
+) -> Result<()> {
+    for (device1, device2) in unrewarded {
+        if !private_links.iter().any(|l| {
+            (&l.device1 == device1 && &l.device2 == device2)
+                || (&l.device1 == device2 && &l.device2 == device1)
+        }) {
+            return Err(ShapleyError::Validation(format!(
+                "Unrewarded link {device1}-{device2} is not a private link"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Check that every congestion curve names a private link, at most once, and
+/// has ascending utilizations in `[0, 1)` with non-negative slopes
+pub(crate) fn check_congestion(
+    curves: &[CongestionCurve],
+    private_links: &PrivateLinks,
+) -> Result<()> {
+    for (i, curve) in curves.iter().enumerate() {
+        let (device1, device2) = (&curve.device1, &curve.device2);
+        if !private_links
+            .iter()
+            .any(|l| curve.connects(&l.device1, &l.device2))
+        {
+            return Err(ShapleyError::Validation(format!(
+                "Congestion curve on {device1}-{device2}, which is not a private link"
+            )));
         }
-        if !public_nodes.contains(demand.end.as_str()) {
-            return Err(ShapleyError::UnreachableDemandNode(demand.end.clone()));
+        if curves[..i].iter().any(|c| c.connects(device1, device2)) {
+            return Err(ShapleyError::Validation(format!(
+                "Link {device1}-{device2} has more than one congestion curve"
+            )));
+        }
+        let mut previous = None;
+        for &(utilization, slope) in &curve.breakpoints {
+            let ascending = previous.is_none_or(|p| utilization > p);
+            if !((0.0..1.0).contains(&utilization) && ascending) {
+                return Err(ShapleyError::Validation(format!(
+                    "Congestion curve on {device1}-{device2} needs ascending utilizations in [0, 1), got {utilization}"
+                )));
+            }
+            if !(slope.is_finite() && slope >= 0.0) {
+                return Err(ShapleyError::Validation(format!(
+                    "Congestion curve on {device1}-{device2} needs finite, non-negative slopes, got {slope}"
+                )));
+            }
+            previous = Some(utilization);
         }
     }
+    Ok(())
+}
+
+/// Check that an operator uptime is in `(0, 1]`: an operator that is never up
+/// cannot earn anything
+pub(crate) fn check_uptime(uptime: f64) -> Result<()> {
+    if !(uptime > 0.0 && uptime <= 1.0) {
+        return Err(ShapleyError::Validation(format!(
+            "Operator uptime must be within (0, 1], got {uptime}"
+        )));
+    }
+    Ok(())
+}
 
+/// Check that the input's parameters and their per-type, per-city and
+/// per-operator overrides are in range: uptime in `(0, 1]`, contiguity
+/// bonuses finite and non-negative, demand multipliers finite and positive
+pub(crate) fn check_parameters(input: &ShapleyInput, options: &ShapleyOptions) -> Result<()> {
+    check_uptime(input.operator_uptime)?;
+    let bonuses = std::iter::once(("".to_string(), input.contiguity_bonus))
+        .chain(
+            options
+                .contiguity_bonus_by_type
+                .iter()
+                .map(|(kind, &b)| (format!(" of type {kind}"), b)),
+        )
+        .chain(
+            options
+                .contiguity_bonus_by_city
+                .iter()
+                .map(|(city, &b)| (format!(" of city {city}"), b)),
+        )
+        .chain(
+            options
+                .contiguity_bonus_by_operator
+                .iter()
+                .map(|(op, &b)| (format!(" of operator {op}"), b)),
+        );
+    for (what, bonus) in bonuses {
+        if !(bonus.is_finite() && bonus >= 0.0) {
+            return Err(ShapleyError::Validation(format!(
+                "Contiguity bonus{what} must be finite and non-negative, got {bonus}"
+            )));
+        }
+    }
+    let multipliers = std::iter::once(("".to_string(), input.demand_multiplier)).chain(
+        options
+            .demand_multiplier_by_type
+            .iter()
+            .map(|(kind, &m)| (format!(" of type {kind}"), m)),
+    );
+    for (what, multiplier) in multipliers {
+        if !(multiplier.is_finite() && multiplier > 0.0) {
+            return Err(ShapleyError::Validation(format!(
+                "Demand multiplier{what} must be finite and positive, got {multiplier}"
+            )));
+        }
+    }
     Ok(())
 }
 
+/// Unit every rate is converted to once any unit is given
+pub(crate) const CANONICAL_UNIT: BandwidthUnit = BandwidthUnit::Gbps;
+
+/// Resolve each link's bandwidth unit and each demand's traffic unit, falling
+/// back to `units`, and convert all rates to [`CANONICAL_UNIT`]. Inputs without
+/// any unit are returned unchanged; inputs where only some rows resolve to a
+/// unit are rejected, since their rates cannot be compared.
+pub(crate) fn normalize_units<'a>(
+    private_links: &'a PrivateLinks,
+    demands: &'a Demands,
+    units: &Units,
+) -> Result<(Cow<'a, PrivateLinks>, Cow<'a, Demands>)> {
+    let link_units: Vec<Option<BandwidthUnit>> = private_links
+        .iter()
+        .map(|link| link.unit.or(units.bandwidth))
+        .collect();
+    let demand_units: Vec<Option<BandwidthUnit>> = demands
+        .iter()
+        .map(|demand| demand.unit.or(units.traffic))
+        .collect();
+
+    let any_unit = link_units.iter().chain(&demand_units).any(Option::is_some);
+    if !any_unit {
+        return Ok((Cow::Borrowed(private_links), Cow::Borrowed(demands)));
+    }
+
+    if let Some(link) = private_links
+        .iter()
+        .zip(&link_units)
+        .find_map(|(link, unit)| unit.is_none().then_some(link))
+    {
+        return Err(ShapleyError::Validation(format!(
+            "Private link {}-{} has no bandwidth unit while other rates do; set one on the link or in ShapleyOptions::units",
+            link.device1, link.device2
+        )));
+    }
+    if let Some(demand) = demands
+        .iter()
+        .zip(&demand_units)
+        .find_map(|(demand, unit)| unit.is_none().then_some(demand))
+    {
+        return Err(ShapleyError::Validation(format!(
+            "Demand {}-{} has no traffic unit while other rates do; set one on the demand or in ShapleyOptions::units",
+            demand.start, demand.end
+        )));
+    }
+
+    let links = private_links
+        .iter()
+        .zip(link_units.into_iter().flatten())
+        .map(|(link, unit)| PrivateLink {
+            bandwidth: unit.convert(link.bandwidth, CANONICAL_UNIT),
+            measured_bandwidth: link
+                .measured_bandwidth
+                .map(|b| unit.convert(b, CANONICAL_UNIT)),
+            unit: Some(CANONICAL_UNIT),
+            ..link.clone()
+        })
+        .collect();
+    let demands = demands
+        .iter()
+        .zip(demand_units.into_iter().flatten())
+        .map(|(demand, unit)| Demand {
+            traffic: unit.convert(demand.traffic, CANONICAL_UNIT),
+            unit: Some(CANONICAL_UNIT),
+            ..demand.clone()
+        })
+        .collect();
+    Ok((Cow::Owned(links), Cow::Owned(demands)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,7 +920,17 @@ mod tests {
             false,
         )];
 
-        assert!(check_inputs(&private_links, &devices, &demands, &public_links, 1.0).is_ok());
+        assert!(
+            check_inputs(
+                &private_links,
+                &devices,
+                &demands,
+                &public_links,
+                false,
+                &ValidationRules::default()
+            )
+            .is_ok()
+        );
     }
 
     #[test]
@@ -212,7 +961,273 @@ mod tests {
             false,
         )];
 
-        let result = check_inputs(&private_links, &devices, &demands, &public_links, 1.0);
+        let result = check_inputs(
+            &private_links,
+            &devices,
+            &demands,
+            &public_links,
+            false,
+            &ValidationRules::default(),
+        );
         assert!(matches!(result, Err(ShapleyError::TooManyOperators { .. })));
     }
+
+    #[test]
+    fn test_device_named_like_city() {
+        let private_links = vec![PrivateLink::new(
+            "SIN".into(),
+            "FRA1".into(),
+            50.0,
+            10.0,
+            1.0,
+            None,
+        )];
+        let mut devices = vec![
+            Device::new("SIN".into(), 1, "Alpha".into()),
+            Device::new("FRA1".into(), 1, "Beta".into()),
+        ];
+        let public_links = vec![PublicLink::new("SIN".into(), "FRA".into(), 100.0)];
+        let demands = vec![Demand::new(
+            "SIN".into(),
+            "FRA".into(),
+            1,
+            1.0,
+            1.0,
+            1,
+            false,
+        )];
+
+        let check = |devices: &Devices| {
+            check_inputs(
+                &private_links,
+                devices,
+                &demands,
+                &public_links,
+                false,
+                &ValidationRules::default(),
+            )
+        };
+        match check(&devices).as_ref().map_err(ShapleyError::root) {
+            Err(ShapleyError::Validation(msg)) => assert!(msg.contains("Device SIN")),
+            other => panic!("Expected validation error, got {other:?}"),
+        }
+
+        devices[0].device = String::new();
+        assert!(matches!(
+            check(&devices).unwrap_err().root(),
+            ShapleyError::Validation(_)
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_links() {
+        let private_links = vec![
+            PrivateLink::new("A1".into(), "B1".into(), 50.0, 10.0, 1.0, None),
+            PrivateLink::new("B1".into(), "A1".into(), 52.0, 10.0, 1.0, None),
+            PrivateLink::new("A1".into(), "C1".into(), 50.0, 10.0, 1.0, None),
+        ];
+
+        let kept = resolve_duplicate_links(&private_links, DuplicateLinkPolicy::Keep).unwrap();
+        assert!(matches!(kept, Cow::Borrowed(_)));
+
+        let err = resolve_duplicate_links(&private_links, DuplicateLinkPolicy::Error).unwrap_err();
+        assert!(err.to_string().contains("A1-B1 (2 rows)"));
+
+        let merged = resolve_duplicate_links(&private_links, DuplicateLinkPolicy::Merge).unwrap();
+        assert_eq!(merged[0].bundle, Some(0));
+        assert_eq!(merged[1].bundle, Some(0));
+        assert_eq!(merged[2].bundle, None);
+
+        let mut conflicting = private_links.clone();
+        conflicting[1].shared = Some(7);
+        assert!(resolve_duplicate_links(&conflicting, DuplicateLinkPolicy::Merge).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_demands() {
+        let demand = |traffic| Demand::new("A".into(), "B".into(), 1, traffic, 1.0, 1, false);
+        let demands = vec![
+            demand(2.0),
+            Demand::new("A".into(), "C".into(), 1, 1.0, 1.0, 1, false),
+            demand(3.0),
+        ];
+
+        let kept = resolve_duplicate_demands(&demands, DuplicateDemandPolicy::Keep).unwrap();
+        assert_eq!(kept.len(), 3);
+
+        let err = resolve_duplicate_demands(&demands, DuplicateDemandPolicy::Error).unwrap_err();
+        assert!(err.to_string().contains("A-B of type 1 (rows 0, 2)"));
+
+        let summed = resolve_duplicate_demands(&demands, DuplicateDemandPolicy::MergeSum).unwrap();
+        assert_eq!(summed.len(), 2);
+        assert_eq!((summed[0].traffic, summed[1].end.as_str()), (5.0, "C"));
+        let max = resolve_duplicate_demands(&demands, DuplicateDemandPolicy::MergeMax).unwrap();
+        assert_eq!(max[0].traffic, 3.0);
+
+        let mut conflicting = demands.clone();
+        conflicting[2].priority = 2.0;
+        assert!(resolve_duplicate_demands(&conflicting, DuplicateDemandPolicy::MergeSum).is_err());
+    }
+
+    #[test]
+    fn test_normalize_units() {
+        let private_links = vec![
+            PrivateLink::new("A1".into(), "B1".into(), 50.0, 10.0, 1.0, None)
+                .with_unit(BandwidthUnit::Gbps),
+            PrivateLink::new("B1".into(), "C1".into(), 50.0, 500.0, 1.0, None),
+        ];
+        let demands = vec![
+            Demand::new("A".into(), "B".into(), 1, 200.0, 1.0, 1, false)
+                .with_unit(BandwidthUnit::Mbps),
+        ];
+
+        // Unit-less inputs pass through untouched
+        let bare: PrivateLinks = private_links
+            .iter()
+            .cloned()
+            .map(|link| PrivateLink { unit: None, ..link })
+            .collect();
+        let bare_demands: Demands = demands
+            .iter()
+            .cloned()
+            .map(|demand| Demand {
+                unit: None,
+                ..demand
+            })
+            .collect();
+        let (links, _) = normalize_units(&bare, &bare_demands, &Units::default()).unwrap();
+        assert!(matches!(links, Cow::Borrowed(_)));
+
+        // The second link has no unit of its own
+        let err = normalize_units(&private_links, &demands, &Units::default()).unwrap_err();
+        assert!(err.to_string().contains("B1-C1"));
+
+        let units = Units {
+            bandwidth: Some(BandwidthUnit::Mbps),
+            traffic: None,
+        };
+        let (links, demands) = normalize_units(&private_links, &demands, &units).unwrap();
+        assert_eq!(links[0].bandwidth, 10.0);
+        assert_eq!(links[1].bandwidth, 0.5);
+        assert_eq!(demands[0].traffic, 0.2);
+        assert!(links.iter().all(|link| link.unit == Some(CANONICAL_UNIT)));
+    }
+
+    #[test]
+    fn test_link_bundle_endpoints_must_match() {
+        let private_links = vec![
+            PrivateLink::new("A1".into(), "B1".into(), 50.0, 10.0, 1.0, None).with_bundle(1),
+            PrivateLink::new("B1".into(), "A1".into(), 50.0, 10.0, 1.0, None).with_bundle(1),
+        ];
+        let devices = vec![
+            Device::new("A1".into(), 1, "Op1".into()),
+            Device::new("B1".into(), 1, "Op1".into()),
+            Device::new("C1".into(), 1, "Op1".into()),
+        ];
+        let public_links = vec![PublicLink::new("A".into(), "B".into(), 100.0)];
+        let demands = vec![Demand::new("A".into(), "B".into(), 1, 1.0, 1.0, 1, false)];
+
+        // Reversed orientation is still the same pair of devices
+        assert!(
+            check_inputs(
+                &private_links,
+                &devices,
+                &demands,
+                &public_links,
+                false,
+                &ValidationRules::default()
+            )
+            .is_ok()
+        );
+
+        let mut mismatched = private_links.clone();
+        mismatched
+            .push(PrivateLink::new("A1".into(), "C1".into(), 50.0, 10.0, 1.0, None).with_bundle(1));
+        let err = check_inputs(
+            &mismatched,
+            &devices,
+            &demands,
+            &public_links,
+            false,
+            &ValidationRules::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(err.root(), ShapleyError::DataInconsistency(_)));
+        assert_eq!(
+            err.context(),
+            Some(&ErrorContext::cell(
+                InputTable::PrivateLinks,
+                mismatched.len() - 1,
+                "bundle"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_demands_need_receivers() {
+        let private_links = vec![PrivateLink::new(
+            "A1".into(),
+            "B1".into(),
+            50.0,
+            10.0,
+            1.0,
+            None,
+        )];
+        let devices = vec![
+            Device::new("A1".into(), 1, "Op1".into()),
+            Device::new("B1".into(), 1, "Op1".into()),
+        ];
+        let public_links = vec![PublicLink::new("A".into(), "B".into(), 100.0)];
+        let demands = vec![
+            Demand::new("A".into(), "B".into(), 2, 1.0, 1.0, 1, true),
+            Demand::new("A".into(), "B".into(), 0, 1.0, 1.0, 1, true),
+        ];
+
+        let check = |rules: &ValidationRules| {
+            check_inputs(
+                &private_links,
+                &devices,
+                &demands,
+                &public_links,
+                false,
+                rules,
+            )
+        };
+        let err = check(&ValidationRules::default()).unwrap_err();
+        assert_eq!(
+            err.context(),
+            Some(&ErrorContext::cell(InputTable::Demands, 1, "receivers"))
+        );
+        assert!(check(&ValidationRules::default().disable("demand_receivers")).is_ok());
+    }
+
+    #[test]
+    fn test_apply_latency_metric() {
+        let links = vec![
+            PrivateLink::new("A1".into(), "B1".into(), 10.0, 10.0, 1.0, None)
+                .with_latency_percentiles(9.0, 15.0, 30.0),
+            PrivateLink::new("B1".into(), "C1".into(), 20.0, 10.0, 1.0, None),
+        ];
+        let latency = |metric| -> Vec<f64> {
+            apply_latency_metric(&links, metric)
+                .unwrap()
+                .iter()
+                .map(|l| l.latency)
+                .collect()
+        };
+        assert_eq!(latency(LatencyMetric::Mean), [10.0, 20.0]);
+        assert_eq!(latency(LatencyMetric::P95), [15.0, 20.0]);
+        assert_eq!(
+            latency(LatencyMetric::RiskWeighted { risk: 0.5 }),
+            [20.0, 20.0]
+        );
+        assert!(apply_latency_metric(&links, LatencyMetric::RiskWeighted { risk: 2.0 }).is_err());
+
+        let mut unordered = links.clone();
+        unordered[0].latency_p99 = Some(12.0);
+        assert!(matches!(
+            apply_latency_metric(&unordered, LatencyMetric::Mean),
+            Err(ShapleyError::Validation(_))
+        ));
+    }
 }
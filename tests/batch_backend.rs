@@ -0,0 +1,50 @@
+#![cfg(feature = "unstable-backend")]
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use network_shapley::{
+    batch::{BatchLpBackend, CoalitionBuffers, CoalitionLp, CoalitionResult, SimplexBackend},
+    error::Result,
+    options::{ShapleyOptions, SolverOptions},
+    shapley::testing::{RandomInputConfig, random_input},
+};
+
+/// Counts the coalitions it is handed and solves them with the simplex
+#[derive(Debug, Default)]
+struct CountingBackend {
+    solved: AtomicUsize,
+}
+
+impl BatchLpBackend for CountingBackend {
+    fn solve_batch(
+        &self,
+        lp: CoalitionLp<'_>,
+        masks: &[u32],
+        buffers: &mut CoalitionBuffers,
+        options: &SolverOptions,
+    ) -> Vec<Result<CoalitionResult>> {
+        self.solved.fetch_add(masks.len(), Ordering::Relaxed);
+        SimplexBackend.solve_batch(lp, masks, buffers, options)
+    }
+}
+
+#[test]
+fn test_options_choose_the_backend() {
+    let input = random_input(3, &RandomInputConfig::default());
+    let expected = input.compute().unwrap();
+
+    let backend = Arc::new(CountingBackend::default());
+    let options = ShapleyOptions {
+        backend: Some(backend.clone()),
+        ..Default::default()
+    };
+    let output = input.compute_with(&options).unwrap();
+    assert!(backend.solved.load(Ordering::Relaxed) > 0);
+    assert_eq!(output.len(), expected.len());
+    for (operator, value) in &expected {
+        assert!((output[operator].value - value.value).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "borsh")]
+
+use network_shapley::types::{BandwidthUnit, Demand, Device, PrivateLink, PublicLink};
+
+type Tables = (Vec<PrivateLink>, Vec<Device>, Vec<Demand>, Vec<PublicLink>);
+
+/// Borsh encoding of `tables()` by network-shapley 0.2.0, before any field
+/// was added to the input types
+const BASELINE: &[u8] = &[
+    1, 0, 0, 0, 4, 0, 0, 0, 78, 89, 67, 49, 4, 0, 0, 0, 76, 79, 78, 49, 0, 0, 0, 0, 0, 0, 68, 64,
+    0, 0, 0, 0, 0, 0, 36, 64, 174, 71, 225, 122, 20, 174, 239, 63, 1, 3, 0, 0, 0, 1, 0, 0, 0, 4, 0,
+    0, 0, 78, 89, 67, 49, 10, 0, 0, 0, 5, 0, 0, 0, 65, 108, 112, 104, 97, 1, 0, 0, 0, 3, 0, 0, 0,
+    78, 89, 67, 3, 0, 0, 0, 76, 79, 78, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 20, 64, 0, 0, 0, 0, 0, 0,
+    224, 63, 1, 0, 0, 0, 1, 1, 0, 0, 0, 3, 0, 0, 0, 78, 89, 67, 3, 0, 0, 0, 76, 79, 78, 0, 0, 0, 0,
+    0, 128, 81, 64,
+];
+
+fn tables() -> Tables {
+    (
+        vec![PrivateLink::new(
+            "NYC1".into(),
+            "LON1".into(),
+            40.0,
+            10.0,
+            0.99,
+            Some(3),
+        )],
+        vec![Device::new("NYC1".into(), 10, "Alpha".into())],
+        vec![Demand::new(
+            "NYC".into(),
+            "LON".into(),
+            2,
+            5.0,
+            0.5,
+            1,
+            true,
+        )],
+        vec![PublicLink::new("NYC".into(), "LON".into(), 70.0)],
+    )
+}
+
+#[test]
+fn test_input_types_keep_their_wire_format() {
+    let (private_links, devices, demands, public_links) = tables();
+    assert_eq!(
+        borsh::to_vec(&(&private_links, &devices, &demands, &public_links)).unwrap(),
+        BASELINE
+    );
+
+    let (private_links, devices, demands, public_links): Tables =
+        borsh::from_slice(BASELINE).unwrap();
+    let link = &private_links[0];
+    assert_eq!(
+        (link.device1.as_str(), link.device2.as_str()),
+        ("NYC1", "LON1")
+    );
+    assert_eq!(
+        (link.latency, link.bandwidth, link.uptime),
+        (40.0, 10.0, 0.99)
+    );
+    assert_eq!(link.shared, Some(3));
+    assert_eq!(link.bundle, None);
+    assert_eq!(devices[0].operator, "Alpha");
+    assert_eq!(devices[0].multicast_capable, None);
+    let demand = &demands[0];
+    assert_eq!((demand.receivers, demand.traffic, demand.kind), (2, 5.0, 1));
+    assert!(demand.multicast);
+    assert_eq!(demand.class, None);
+    assert_eq!(public_links[0].latency, 70.0);
+
+    // Fields added since have no place in the encoding, which fails rather
+    // than drop them
+    assert_unencoded(|(private_links, ..)| private_links[0].bundle = Some(7));
+    assert_unencoded(|(private_links, ..)| private_links[0].srlg = Some(1));
+    assert_unencoded(|(private_links, ..)| private_links[0].unit = Some(BandwidthUnit::Mbps));
+    assert_unencoded(|(_, _, demands, _)| demands[0].unit = Some(BandwidthUnit::Mbps));
+    assert_unencoded(|(private_links, ..)| private_links[0].measured_bandwidth = Some(8.0));
+    assert_unencoded(|(private_links, ..)| private_links[0].latency_p95 = Some(55.0));
+    assert_unencoded(|(_, _, demands, _)| demands[0].class = Some(2));
+    assert_unencoded(|(private_links, ..)| private_links[0].multicast_capable = Some(true));
+    assert_unencoded(|(_, devices, ..)| devices[0].multicast_capable = Some(false));
+    assert_unencoded(|(_, _, demands, _)| demands[0].traffic_high = Some(8.0));
+}
+
+fn assert_unencoded(set_field: impl FnOnce(&mut Tables)) {
+    let mut tables = tables();
+    set_field(&mut tables);
+    assert!(borsh::to_vec(&tables).is_err());
+}
@@ -1,6 +1,7 @@
 #![cfg(feature = "serde")]
 
 use network_shapley::{
+    pipeline::{LpProblem, build_lp},
     shapley::ShapleyInput,
     types::{Demand, Device, PrivateLink, PublicLink},
 };
@@ -76,6 +77,34 @@ fn json_round_trip() {
     assert!((deserialized.operator_uptime - 1.0).abs() < f64::EPSILON);
 }
 
+/// Round-trip: the LP built by the pipeline serializes with its matrices,
+/// columns and rows intact.
+#[test]
+fn lp_problem_round_trip() {
+    let input = ShapleyInput {
+        devices: create_basic_devices(),
+        private_links: create_basic_private_links()[..1].to_vec(),
+        public_links: create_basic_public_links(),
+        demands: create_basic_demands()[..1].to_vec(),
+        operator_uptime: 1.0,
+        contiguity_bonus: 0.0,
+        demand_multiplier: 1.0,
+    };
+    let lp = build_lp(&input).unwrap();
+
+    let json = serde_json::to_string(&lp).unwrap();
+    let deserialized: LpProblem = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(deserialized.cost, lp.cost);
+    assert_eq!(deserialized.a_eq.colptr, lp.a_eq.colptr);
+    assert_eq!(deserialized.a_ub.nzval, lp.a_ub.nzval);
+    assert_eq!(deserialized.b_ub, lp.b_ub);
+    assert_eq!(deserialized.columns, lp.columns);
+    assert_eq!(deserialized.inequality_rows, lp.inequality_rows);
+    assert_eq!(deserialized.links, lp.links);
+    assert_eq!(deserialized.demands, lp.demands);
+}
+
 /// Parse a hand-written JSON string with native JSON types (null, integers, booleans).
 #[test]
 fn json_native_types() {
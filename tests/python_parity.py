@@ -4,6 +4,10 @@
 Used by the Rust parity test (python_parity_test.rs) to validate that
 both implementations produce identical results from the same inputs.
 
+With --stdin, reads a JSON list of serialized ShapleyInput cases instead and
+prints one {operator: value} object per case, in order
+(randomized_parity_test.rs).
+
 Requires: pip install pandas scipy
 """
 import json
@@ -57,7 +61,55 @@ def run_scenario(demand_file: str, multiplier: float) -> dict:
     return {row["Operator"]: row["Value"] for _, row in result.iterrows()}
 
 
+def run_case(case: dict) -> dict:
+    devices = pd.DataFrame(
+        [[d["device"], d["edge"], d["operator"]] for d in case["devices"]],
+        columns=["Device", "Edge", "Operator"],
+    )
+    private_links = pd.DataFrame(
+        [
+            [
+                l["device1"],
+                l["device2"],
+                l["latency"],
+                l["bandwidth"],
+                l["uptime"],
+                float("nan") if l["shared"] is None else l["shared"],
+            ]
+            for l in case["private_links"]
+        ],
+        columns=["Device1", "Device2", "Latency", "Bandwidth", "Uptime", "Shared"],
+    )
+    public_links = pd.DataFrame(
+        [[l["city1"], l["city2"], l["latency"]] for l in case["public_links"]],
+        columns=["City1", "City2", "Latency"],
+    )
+    demand = pd.DataFrame(
+        [
+            [d["start"], d["end"], d["receivers"], d["traffic"], d["priority"], d["type"], d["multicast"]]
+            for d in case["demands"]
+        ],
+        columns=["Start", "End", "Receivers", "Traffic", "Priority", "Type", "Multicast"],
+    )
+
+    result = network_shapley(
+        private_links=private_links,
+        devices=devices,
+        demand=demand,
+        public_links=public_links,
+        operator_uptime=case["operator_uptime"],
+        contiguity_bonus=case["contiguity_bonus"],
+        demand_multiplier=case["demand_multiplier"],
+    )
+
+    return {row["Operator"]: row["Value"] for _, row in result.iterrows()}
+
+
 if __name__ == "__main__":
+    if "--stdin" in sys.argv[1:]:
+        print(json.dumps([run_case(case) for case in json.load(sys.stdin)]))
+        sys.exit(0)
+
     output = {
         "demand1_1x": run_scenario("demand1.csv", 1.0),
         "demand1_1.2x": run_scenario("demand1.csv", 1.2),
@@ -0,0 +1,180 @@
+//! Randomized parity test: generates small random networks and checks that
+//! the Rust and Python implementations agree on every one of them.
+//!
+//! Gated twice, since it needs the reference implementation (see
+//! tests/python_parity.py for search paths) plus pandas and scipy:
+//!
+//! Run with: NETWORK_SHAPLEY_PARITY=1 cargo test --features pytest-parity --test randomized_parity_test
+//!
+//! NETWORK_SHAPLEY_PARITY_CASES (default 20) and NETWORK_SHAPLEY_PARITY_SEED
+//! (default 0) choose how many networks are drawn and from which seed.
+#![cfg(feature = "pytest-parity")]
+
+use std::{
+    collections::BTreeMap,
+    env,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use network_shapley::{
+    shapley::ShapleyInput,
+    types::{Demand, Device, PrivateLink, PublicLink},
+};
+
+/// Same tolerance as the fixture-based parity test
+const VALUE_TOLERANCE: f64 = 0.01;
+
+const CITIES: [&str; 5] = ["NYC", "LON", "PAR", "FRA", "AMS"];
+
+/// SplitMix64, so that a failing seed can be replayed without extra crates
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Uniform in [lo, hi), rounded to one decimal as in the fixtures
+    fn range(&mut self, lo: f64, hi: f64) -> f64 {
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        ((lo + unit * (hi - lo)) * 10.0).round() / 10.0
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// A small random network: 2-5 cities joined by a public chain, one device
+/// per city owned by one of up to three operators, and a few private links
+/// and demands between random cities
+fn random_input(rng: &mut Rng) -> ShapleyInput {
+    let n_cities = 2 + rng.below(CITIES.len() - 1);
+    let cities = &CITIES[..n_cities];
+    let n_operators = 1 + rng.below(3);
+
+    let devices = cities
+        .iter()
+        .map(|city| {
+            Device::new(
+                format!("{city}1"),
+                1 + rng.below(10) as u32,
+                format!("Op{}", rng.below(n_operators)),
+            )
+        })
+        .collect();
+
+    let public_links = cities
+        .windows(2)
+        .map(|pair| PublicLink::new(pair[0].into(), pair[1].into(), rng.range(20.0, 120.0)))
+        .collect();
+
+    let n_private = 1 + rng.below(n_cities);
+    let private_links = (0..n_private)
+        .map(|_| {
+            let a = rng.below(n_cities);
+            let b = (a + 1 + rng.below(n_cities - 1)) % n_cities;
+            PrivateLink::new(
+                format!("{}1", cities[a]),
+                format!("{}1", cities[b]),
+                rng.range(5.0, 80.0),
+                rng.range(1.0, 20.0),
+                1.0,
+                None,
+            )
+        })
+        .collect();
+
+    let n_demands = 1 + rng.below(3);
+    let demands = (0..n_demands)
+        .map(|kind| {
+            let start = rng.below(n_cities);
+            let end = (start + 1 + rng.below(n_cities - 1)) % n_cities;
+            Demand::new(
+                cities[start].into(),
+                cities[end].into(),
+                1,
+                rng.range(0.5, 10.0),
+                rng.range(0.5, 2.0),
+                kind as u32 + 1,
+                false,
+            )
+        })
+        .collect();
+
+    ShapleyInput {
+        private_links,
+        devices,
+        demands,
+        public_links,
+        operator_uptime: [1.0, 0.98, 0.9][rng.below(3)],
+        contiguity_bonus: rng.range(0.0, 10.0),
+        demand_multiplier: 1.0,
+    }
+}
+
+fn run_python(cases: &[ShapleyInput]) -> Vec<BTreeMap<String, f64>> {
+    let mut child = Command::new("python3")
+        .args(["tests/python_parity.py", "--stdin"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("NETWORK_SHAPLEY_PARITY is set but python3 could not be started");
+    let input = serde_json::to_vec(cases).unwrap();
+    child.stdin.take().unwrap().write_all(&input).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "Python parity script failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("Failed to parse Python JSON output")
+}
+
+#[test]
+fn test_randomized_python_parity() {
+    if env::var_os("NETWORK_SHAPLEY_PARITY").is_none() {
+        eprintln!("Skipping randomized parity test; set NETWORK_SHAPLEY_PARITY=1 to run it");
+        return;
+    }
+    let n_cases: usize = env_or("NETWORK_SHAPLEY_PARITY_CASES", 20);
+    let seed: u64 = env_or("NETWORK_SHAPLEY_PARITY_SEED", 0);
+
+    let mut rng = Rng(seed);
+    let cases: Vec<ShapleyInput> = (0..n_cases).map(|_| random_input(&mut rng)).collect();
+    let python = run_python(&cases);
+    assert_eq!(python.len(), cases.len());
+
+    for (idx, (case, python)) in cases.iter().zip(&python).enumerate() {
+        let rust = case
+            .compute()
+            .unwrap_or_else(|e| panic!("case {idx} (seed {seed}) failed in Rust: {e}\n{case:?}"));
+        assert_eq!(
+            rust.len(),
+            python.len(),
+            "case {idx} (seed {seed}): operator count mismatch\n{case:?}"
+        );
+        for (op, value) in &rust {
+            let pv = python[op];
+            assert!(
+                (value.value - pv).abs() < VALUE_TOLERANCE,
+                "case {idx} (seed {seed}): value mismatch for {op}: Rust={:.6}, Python={pv:.6}\n{case:?}",
+                value.value
+            );
+        }
+    }
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "borsh")]
+
+use network_shapley::{
+    shapley::ShapleyValue,
+    snapshot::{SNAPSHOT_VERSION, Snapshot, SnapshotContents},
+    types::{BandwidthUnit, Demand, Device, PrivateLink, PublicLink},
+};
+
+/// Encoding of `snapshot()` in format version 1
+const VERSION_1: &[u8] = &[
+    1, 0, 0, 0, 42, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 4, 0, 0, 0, 78, 89, 67, 49, 4, 0, 0, 0, 76,
+    79, 78, 49, 0, 0, 0, 0, 0, 0, 68, 64, 0, 0, 0, 0, 0, 0, 36, 64, 174, 71, 225, 122, 20, 174,
+    239, 63, 1, 3, 0, 0, 0, 1, 7, 0, 0, 0, 1, 2, 0, 0, 0, 1, 2, 1, 0, 0, 0, 0, 0, 0, 32, 64, 1, 0,
+    0, 0, 0, 0, 0, 67, 64, 1, 0, 0, 0, 0, 0, 128, 70, 64, 1, 0, 0, 0, 0, 0, 0, 78, 64, 1, 0, 1, 0,
+    0, 0, 4, 0, 0, 0, 78, 89, 67, 49, 10, 0, 0, 0, 5, 0, 0, 0, 65, 108, 112, 104, 97, 1, 1, 1, 0,
+    0, 0, 3, 0, 0, 0, 78, 89, 67, 3, 0, 0, 0, 76, 79, 78, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 20, 64, 0,
+    0, 0, 0, 0, 0, 224, 63, 1, 0, 0, 0, 1, 1, 3, 1, 2, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 16, 64, 1, 0,
+    0, 0, 0, 0, 0, 24, 64, 1, 0, 0, 0, 3, 0, 0, 0, 78, 89, 67, 3, 0, 0, 0, 76, 79, 78, 0, 0, 0, 0,
+    0, 128, 81, 64, 92, 143, 194, 245, 40, 92, 239, 63, 0, 0, 0, 0, 0, 0, 20, 64, 51, 51, 51, 51,
+    51, 51, 243, 63, 15, 0, 0, 0, 123, 34, 115, 114, 108, 103, 95, 115, 101, 101, 100, 34, 58, 55,
+    125, 1, 0, 0, 0, 5, 0, 0, 0, 65, 108, 112, 104, 97, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 36, 64, 1, 0, 0, 0, 5, 0, 0, 0, 65, 108, 112, 104, 97, 0, 0, 0, 0, 0, 0, 36, 64, 0,
+    0, 0, 0, 0, 0, 240, 63, 100, 243, 46, 242, 71, 184, 214, 4, 198, 176, 27, 40, 91, 152, 183,
+    144, 119, 83, 68, 200, 48, 7, 70, 74, 179, 221, 163, 220, 243, 201, 82, 212,
+];
+
+fn snapshot() -> Snapshot {
+    let mut link = PrivateLink::new("NYC1".into(), "LON1".into(), 40.0, 10.0, 0.99, Some(3))
+        .with_bundle(7)
+        .with_unit(BandwidthUnit::Mbps);
+    link.srlg = Some(2);
+    link.measured_bandwidth = Some(8.0);
+    link.latency_p50 = Some(38.0);
+    link.latency_p95 = Some(45.0);
+    link.latency_p99 = Some(60.0);
+    link.multicast_capable = Some(false);
+    let mut demand = Demand::new("NYC".into(), "LON".into(), 2, 5.0, 0.5, 1, true);
+    demand.unit = Some(BandwidthUnit::Gbps);
+    demand.class = Some(2);
+    demand.traffic_low = Some(4.0);
+    demand.traffic_high = Some(6.0);
+
+    Snapshot::new(SnapshotContents {
+        version: 1,
+        epoch: 42,
+        private_links: vec![link],
+        devices: vec![Device::new("NYC1".into(), 10, "Alpha".into()).with_multicast_capable(true)],
+        demands: vec![demand],
+        public_links: vec![PublicLink::new("NYC".into(), "LON".into(), 70.0)],
+        operator_uptime: 0.98,
+        contiguity_bonus: 5.0,
+        demand_multiplier: 1.2,
+        options: r#"{"srlg_seed":7}"#.into(),
+        operators: vec!["Alpha".into()],
+        coalition_values: vec![0.0, 10.0],
+        results: [(
+            "Alpha".to_string(),
+            ShapleyValue {
+                value: 10.0,
+                proportion: 1.0,
+            },
+        )]
+        .into(),
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_snapshot_keeps_its_format() {
+    assert_eq!(SNAPSHOT_VERSION, 1);
+    let snapshot = snapshot();
+    assert_eq!(snapshot.to_bytes().unwrap(), VERSION_1);
+
+    let decoded = Snapshot::from_bytes(VERSION_1).unwrap();
+    decoded.verify().unwrap();
+    assert_eq!(decoded.hash, snapshot.hash);
+    assert_eq!(decoded.options().unwrap().srlg_seed, 7);
+    let link = &decoded.contents.private_links[0];
+    assert_eq!((link.bundle, link.srlg), (Some(7), Some(2)));
+    assert_eq!(link.latency_p99, Some(60.0));
+    assert_eq!(link.multicast_capable, Some(false));
+    assert_eq!(decoded.contents.devices[0].multicast_capable, Some(true));
+    let demand = &decoded.contents.demands[0];
+    assert_eq!(demand.unit, Some(BandwidthUnit::Gbps));
+    assert_eq!(
+        (demand.traffic_low, demand.traffic_high),
+        (Some(4.0), Some(6.0))
+    );
+}
@@ -1,5 +1,6 @@
 use network_shapley::{
-    error::ShapleyError,
+    error::{InputTable, ShapleyError},
+    options::{DuplicateLinkPolicy, ShapleyOptions},
     shapley::ShapleyInput,
     types::{Demand, Demands, Device, Devices, PrivateLink, PrivateLinks, PublicLink, PublicLinks},
 };
@@ -71,8 +72,8 @@ fn test_public_operator_name_rejected() {
 #[test]
 fn test_too_many_operators_with_low_uptime() {
     let mut devices = vec![];
-    // Create 16 operators (limit is 15 when uptime < 1.0)
-    for i in 1..=16 {
+    // Create 21 operators (limit is 20 with uptime below 1.0 too)
+    for i in 1..=21 {
         devices.push(Device::new(format!("NYC{i}"), 10, format!("Op{i}")));
     }
 
@@ -101,8 +102,8 @@ fn test_too_many_operators_with_low_uptime() {
     assert!(result.is_err());
     match result.unwrap_err() {
         ShapleyError::TooManyOperators { count, limit } => {
-            assert_eq!(count, 16);
-            assert_eq!(limit, 15);
+            assert_eq!(count, 21);
+            assert_eq!(limit, 20);
         }
         _ => panic!("Expected TooManyOperators error"),
     }
@@ -245,6 +246,48 @@ fn test_device_with_00_code_allowed() {
 
 // Note: 2-letter cities are allowed - removing this test
 
+#[test]
+fn test_short_and_non_ascii_device_names() {
+    // Cities are the first three characters, not bytes; a name shorter than
+    // that is its own city and never reaches the public internet
+    let devices = vec![
+        Device::new("ÅÖR1".to_string(), 10, "Alpha".to_string()),
+        Device::new("L1".to_string(), 10, "Beta".to_string()),
+        Device::new("LON1".to_string(), 10, "Beta".to_string()),
+    ];
+    let private_links = vec![
+        PrivateLink::new("ÅÖR1".to_string(), "L1".to_string(), 20.0, 10.0, 1.0, None),
+        PrivateLink::new("L1".to_string(), "LON1".to_string(), 20.0, 10.0, 1.0, None),
+    ];
+    let demands = vec![Demand::new(
+        "ÅÖR".to_string(),
+        "LON".to_string(),
+        1,
+        1.0,
+        1.0,
+        1,
+        false,
+    )];
+    let public_links = vec![PublicLink::new("ÅÖR".to_string(), "LON".to_string(), 100.0)];
+
+    let input = ShapleyInput {
+        private_links,
+        devices,
+        demands,
+        public_links,
+        operator_uptime: 1.0,
+        contiguity_bonus: 0.0,
+        demand_multiplier: 1.0,
+    };
+
+    let result = input
+        .compute()
+        .expect("short and non-ASCII names should be accepted");
+    // The private path beats the 100ms public link, so both operators earn value
+    assert!(result["Alpha"].value > 0.0);
+    assert!(result["Beta"].value > 0.0);
+}
+
 #[test]
 fn test_city_with_digit_rejected() {
     let devices = create_basic_devices();
@@ -321,3 +364,137 @@ fn test_unreachable_demand_node() {
         _ => panic!("Expected UnreachableDemandNode error"),
     }
 }
+
+#[test]
+fn test_validate_locates_offending_row() {
+    let mut demands = create_basic_demands();
+    demands.push(Demand::new(
+        "NYC".to_string(),
+        "PAR".to_string(),
+        1,
+        1.0,
+        1.0,
+        1,
+        false,
+    ));
+    let input = ShapleyInput {
+        private_links: create_basic_private_links(),
+        devices: create_basic_devices(),
+        demands,
+        public_links: create_basic_public_links(),
+        operator_uptime: 1.0,
+        contiguity_bonus: 0.0,
+        demand_multiplier: 1.0,
+    };
+
+    let err = input.validate(&ShapleyOptions::default()).unwrap_err();
+    assert!(matches!(err.root(), ShapleyError::UnreachableDemandNode(city) if city == "PAR"));
+    let context = err.context().unwrap();
+    assert_eq!(context.table, Some(InputTable::Demands));
+    assert_eq!(context.row, Some(create_basic_demands().len()));
+    assert_eq!(err.code().as_str(), "unreachable_demand_node");
+
+    // Computing returns the same error without the context
+    assert!(matches!(
+        input.compute(),
+        Err(ShapleyError::UnreachableDemandNode(city)) if city == "PAR"
+    ));
+}
+
+#[test]
+fn test_duplicate_private_links() {
+    let mut private_links = create_basic_private_links();
+    private_links.push(PrivateLink::new(
+        "LON1".to_string(),
+        "NYC1".to_string(),
+        51.0,
+        10.0,
+        1.0,
+        None,
+    ));
+
+    let input = ShapleyInput {
+        private_links,
+        devices: create_basic_devices(),
+        demands: create_basic_demands(),
+        public_links: create_basic_public_links(),
+        operator_uptime: 1.0,
+        contiguity_bonus: 0.0,
+        demand_multiplier: 1.0,
+    };
+
+    let options = |duplicate_links| ShapleyOptions {
+        duplicate_links,
+        ..Default::default()
+    };
+    match input.compute_with(&options(DuplicateLinkPolicy::Error)) {
+        Err(ShapleyError::DataInconsistency(msg)) => assert!(msg.contains("LON1-NYC1")),
+        other => panic!("Expected DataInconsistency error, got {other:?}"),
+    }
+
+    // Merging bundles the rows into one link instead
+    assert!(
+        input
+            .compute_with(&options(DuplicateLinkPolicy::Merge))
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_parameter_ranges() {
+    let input = ShapleyInput {
+        private_links: create_basic_private_links(),
+        devices: create_basic_devices(),
+        demands: create_basic_demands(),
+        public_links: create_basic_public_links(),
+        operator_uptime: 1.0,
+        contiguity_bonus: 0.0,
+        demand_multiplier: 1.0,
+    };
+    let options = ShapleyOptions::default();
+    assert!(input.check_parameters(&options).is_ok());
+
+    let invalid = [
+        (
+            ShapleyInput {
+                operator_uptime: 1.7,
+                ..input.clone()
+            },
+            "Operator uptime must be within (0, 1], got 1.7",
+        ),
+        (
+            ShapleyInput {
+                operator_uptime: 0.0,
+                ..input.clone()
+            },
+            "Operator uptime",
+        ),
+        (
+            ShapleyInput {
+                contiguity_bonus: -5.0,
+                ..input.clone()
+            },
+            "Contiguity bonus must be finite and non-negative, got -5",
+        ),
+        (
+            ShapleyInput {
+                demand_multiplier: 0.0,
+                ..input.clone()
+            },
+            "Demand multiplier must be finite and positive, got 0",
+        ),
+    ];
+    for (input, expected) in invalid {
+        match input.compute().unwrap_err() {
+            ShapleyError::Validation(msg) => assert!(msg.contains(expected), "{msg}"),
+            other => panic!("Expected validation error, got {other}"),
+        }
+    }
+
+    let options = ShapleyOptions {
+        contiguity_bonus_by_operator: [("Beta".to_string(), -1.0)].into(),
+        ..Default::default()
+    };
+    let err = input.check_parameters(&options).unwrap_err();
+    assert!(err.to_string().contains("of operator Beta"));
+}